@@ -54,7 +54,7 @@ fn benchmark_verification(c: &mut Criterion) {
 
     for size in message_sizes {
         let test_message = vec![0u8; size];
-        let signature = keypair.sign(&test_message).unwrap();
+        let signature = keypair.sign(&test_message).unwrap().to_hex();
 
         group.bench_with_input(BenchmarkId::new("verify_signature", size), &size, |b, _| {
             b.iter(|| keypair.verify(&test_message, &signature));
@@ -136,7 +136,7 @@ fn benchmark_real_world_scenarios(c: &mut Criterion) {
     group.bench_function("complete_transaction_verification", |b| {
         let msg_hash = hash(typical_payload_bytes);
         let hash_bytes = base64url_decode(&msg_hash).unwrap();
-        let signature = keypair.sign(&hash_bytes).unwrap();
+        let signature = keypair.sign(&hash_bytes).unwrap().to_hex();
 
         b.iter(|| keypair.verify(&hash_bytes, &signature));
     });
@@ -147,7 +147,7 @@ fn benchmark_real_world_scenarios(c: &mut Criterion) {
             let msg_hash = hash(typical_payload_bytes);
             let hash_bytes = base64url_decode(&msg_hash).unwrap();
             let signature = keypair.sign(&hash_bytes).unwrap();
-            let verification = keypair.verify(&hash_bytes, &signature).unwrap();
+            let verification = keypair.verify(&hash_bytes, &signature.to_hex()).unwrap();
             assert!(verification);
         });
     });
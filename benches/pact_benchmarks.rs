@@ -61,7 +61,7 @@ fn benchmark_cmd_preparation(c: &mut Criterion) {
     let mut group = c.benchmark_group("Command Preparation");
 
     let keypair = get_test_keypair();
-    let sender = format!("k:{}", keypair.public_key);
+    let sender = format!("k:{}", keypair.public_key());
     let meta = Meta::new("0", &sender);
 
     // Simple command benchmark
@@ -144,7 +144,7 @@ fn benchmark_command_with_varying_caps(c: &mut Criterion) {
     let mut group = c.benchmark_group("Command with Varying Capabilities");
 
     let keypair = get_test_keypair();
-    let sender = format!("k:{}", keypair.public_key);
+    let sender = format!("k:{}", keypair.public_key());
     let meta = Meta::new("0", &sender);
 
     // Benchmark with different numbers of capabilities
@@ -180,7 +180,7 @@ fn benchmark_complex_json_data(c: &mut Criterion) {
     let mut group = c.benchmark_group("Complex JSON Handling");
 
     let keypair = get_test_keypair();
-    let sender = format!("k:{}", keypair.public_key);
+    let sender = format!("k:{}", keypair.public_key());
     let meta = Meta::new("0", &sender);
 
     group.bench_function("complex_env_data", |b| {
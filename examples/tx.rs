@@ -12,7 +12,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let keypair = PactKeypair::generate();
 
     // Format sender account with "k:" prefix
-    let sender_account = format!("k:{}", keypair.public_key);
+    let sender_account = format!("k:{}", keypair.public_key());
 
     let network = "testnet04";
     let chain_id = "0";
@@ -0,0 +1,22 @@
+#![no_main]
+
+use kadena::pact::{Cmd, CommandPayload};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(payload) = CommandPayload::parse(input) {
+        // A parsed payload must serialize and parse back
+        let cmd = serde_json::to_string(&payload).expect("parsed payloads serialize");
+        CommandPayload::parse(&cmd).expect("serialized payloads parse");
+    }
+
+    if let Ok(cmd) = serde_json::from_str::<Cmd>(input) {
+        let _ = cmd.payload();
+        let _ = cmd.summarize();
+        let _ = cmd.request_key();
+    }
+});
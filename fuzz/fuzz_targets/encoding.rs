@@ -0,0 +1,30 @@
+#![no_main]
+
+use kadena::crypto::{
+    base64url_decode, base64url_encode, bin_to_hex, hex_to_bin, verify_signature, PactKeypair,
+    Signature,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(bytes) = hex_to_bin(input) {
+        assert_eq!(hex_to_bin(&bin_to_hex(&bytes)).unwrap(), bytes);
+    }
+    if let Ok(bytes) = base64url_decode(input) {
+        assert_eq!(base64url_encode(&bytes), input);
+    }
+    if let Ok(signature) = Signature::from_hex(input) {
+        assert_eq!(Signature::from_hex(&signature.to_hex()).unwrap(), signature);
+    }
+    let _ = PactKeypair::from_secret_key(input);
+
+    // Split the input into a signature and a public key
+    let (sig, public_key) = data.split_at(data.len() / 2);
+    if let (Ok(sig), Ok(public_key)) = (std::str::from_utf8(sig), std::str::from_utf8(public_key)) {
+        let _ = verify_signature(data, sig, public_key);
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use kadena::pact::RequestKey;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(key) = RequestKey::parse(input) {
+        assert_eq!(RequestKey::parse(key.as_str()).unwrap(), key);
+        assert_eq!(RequestKey::parse(&key.to_hex()).unwrap(), key);
+        assert_eq!(RequestKey::from_bytes(&key.to_bytes()).unwrap(), key);
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use kadena::pact::SigningSession;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(session) = SigningSession::parse(input) {
+        let _ = session.payload();
+        let _ = session.missing_signers();
+        // A parsed session must survive storage
+        let stored = serde_json::to_string(&session).expect("sessions serialize");
+        let restored = SigningSession::parse(&stored).expect("stored sessions parse");
+        assert_eq!(restored, session);
+    }
+});
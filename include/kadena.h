@@ -0,0 +1,48 @@
+/*
+ * C bindings for the kadena crate, built with the `ffi` feature.
+ *
+ * All strings are NUL-terminated UTF-8. Every returned string is owned by the
+ * caller and must be released with kadena_string_free. Functions return NULL
+ * on failure; kadena_last_error then describes the error of the last failed
+ * call on the current thread.
+ */
+
+#ifndef KADENA_H
+#define KADENA_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Generate a keypair as {"publicKey": "...", "secretKey": "..."} */
+char *kadena_keypair_generate(void);
+
+/* Derive the hex public key of a hex secret key */
+char *kadena_public_key(const char *secret_key);
+
+/* Blake2b-256 hash of len bytes, base64url encoded */
+char *kadena_hash(const uint8_t *data, size_t len);
+
+/* Hex signature of len bytes */
+char *kadena_sign(const char *secret_key, const uint8_t *message, size_t len);
+
+/* Hex signature of a base64url command hash */
+char *kadena_sign_hash(const char *secret_key, const char *hash);
+
+/* Prepare and sign an execution command from a JSON request, returning the command JSON */
+char *kadena_prepare_exec(const char *request);
+
+/* Error of the last failed call on this thread, or NULL */
+char *kadena_last_error(void);
+
+/* Release a string returned by this library */
+void kadena_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KADENA_H */
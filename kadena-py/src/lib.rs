@@ -0,0 +1,247 @@
+//! Python bindings for the kadena crate
+//!
+//! Exposes [`PactKeypair`], command preparation and a blocking node client to
+//! Python, so scripts can build, sign and submit Pact commands without
+//! shelling out to pact-js. Structured values, such as commands and results,
+//! cross the boundary as plain dictionaries and lists.
+//!
+//! Build and install the module into the current virtualenv with maturin:
+//!
+//! ```bash
+//! maturin develop -m kadena-py/Cargo.toml
+//! ```
+
+use kadena::{
+    crypto::{hash as blake2b_hash, PactKeypair},
+    fetch::{ApiClient, ApiConfig},
+    pact::{Cap, Cmd, Meta, NetworkId},
+};
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+create_exception!(kadena, KadenaError, PyException);
+
+fn to_py_err(e: impl std::fmt::Display) -> PyErr {
+    KadenaError::new_err(e.to_string())
+}
+
+/// Convert a Python object to JSON through the `json` module
+fn to_value(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let json: String = obj
+        .py()
+        .import("json")?
+        .call_method1("dumps", (obj,))?
+        .extract()?;
+    serde_json::from_str(&json).map_err(to_py_err)
+}
+
+/// Convert a serializable value to a Python object through the `json` module
+fn to_py(py: Python<'_>, value: &impl Serialize) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(to_py_err)?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// An ED25519 keypair
+#[pyclass(name = "PactKeypair", module = "kadena", frozen)]
+struct PyPactKeypair(PactKeypair);
+
+#[pymethods]
+impl PyPactKeypair {
+    /// Generate a new random keypair
+    #[staticmethod]
+    fn generate() -> Self {
+        Self(PactKeypair::generate())
+    }
+
+    /// Restore a keypair from a hex secret key
+    #[staticmethod]
+    fn from_secret_key(secret_key: &str) -> PyResult<Self> {
+        PactKeypair::from_secret_key(secret_key)
+            .map(Self)
+            .map_err(to_py_err)
+    }
+
+    /// The hex public key
+    #[getter]
+    fn public_key(&self) -> &str {
+        self.0.public_key()
+    }
+
+    /// The hex secret key
+    #[getter]
+    fn secret_key(&self) -> &str {
+        self.0.secret_key()
+    }
+
+    /// Sign a message, returning the hex signature
+    fn sign(&self, message: &[u8]) -> PyResult<String> {
+        Ok(self.0.sign(message).map_err(to_py_err)?.to_hex())
+    }
+
+    /// Verify a hex signature of a message
+    fn verify(&self, message: &[u8], signature: &str) -> PyResult<bool> {
+        self.0.verify(message, signature).map_err(to_py_err)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PactKeypair(public_key='{}')", self.0.public_key())
+    }
+}
+
+/// Blake2b-256 hash of `data`, base64url encoded
+#[pyfunction]
+fn hash(data: &[u8]) -> String {
+    blake2b_hash(data)
+}
+
+/// Prepare and sign an execution command
+///
+/// `signers` is a list of `(PactKeypair, caps)` pairs, where `caps` is a list
+/// of `{"name": ..., "args": [...]}` dictionaries. Gas settings default to the
+/// profile of `network_id`. Returns the command as a dictionary.
+#[pyfunction]
+#[pyo3(signature = (
+    code,
+    chain_id,
+    sender,
+    signers,
+    *,
+    data = None,
+    network_id = None,
+    nonce = None,
+    gas_limit = None,
+    gas_price = None,
+    ttl = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn prepare_exec(
+    py: Python<'_>,
+    code: &str,
+    chain_id: &str,
+    sender: &str,
+    signers: Vec<(PyRef<'_, PyPactKeypair>, Bound<'_, PyAny>)>,
+    data: Option<Bound<'_, PyAny>>,
+    network_id: Option<String>,
+    nonce: Option<&str>,
+    gas_limit: Option<u64>,
+    gas_price: Option<f64>,
+    ttl: Option<u64>,
+) -> PyResult<PyObject> {
+    let mut meta = match &network_id {
+        Some(network_id) => {
+            Meta::for_network(NetworkId::from(network_id.as_str()), chain_id, sender)
+        }
+        None => Meta::new(chain_id, sender),
+    };
+    if let Some(gas_limit) = gas_limit {
+        meta = meta.with_gas_limit(gas_limit);
+    }
+    if let Some(gas_price) = gas_price {
+        meta = meta.with_gas_price(gas_price);
+    }
+    if let Some(ttl) = ttl {
+        meta = meta.with_ttl(ttl);
+    }
+
+    let signers = signers
+        .iter()
+        .map(|(keypair, caps)| {
+            let caps: Vec<Cap> = serde_json::from_value(to_value(caps)?).map_err(to_py_err)?;
+            Ok((&keypair.0, caps))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let data = data.as_ref().map(to_value).transpose()?;
+
+    let cmd = Cmd::prepare_exec(&signers, Vec::new(), nonce, code, data, meta, network_id)
+        .map_err(to_py_err)?;
+    to_py(py, &cmd)
+}
+
+/// A blocking client for the Pact API of a chain
+///
+/// Requests release the GIL while they wait for the node.
+#[pyclass(name = "Client", module = "kadena", frozen)]
+struct PyClient {
+    client: ApiClient,
+    runtime: Runtime,
+}
+
+impl PyClient {
+    fn command(cmd: &Bound<'_, PyAny>) -> PyResult<Cmd> {
+        serde_json::from_value(to_value(cmd)?).map_err(to_py_err)
+    }
+
+    fn block_on<T: Serialize + Send>(
+        &self,
+        py: Python<'_>,
+        request: impl std::future::Future<Output = Result<T, kadena::fetch::FetchError>> + Send,
+    ) -> PyResult<PyObject> {
+        let result = py.allow_threads(|| self.runtime.block_on(request));
+        to_py(py, &result.map_err(to_py_err)?)
+    }
+}
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    #[pyo3(signature = (base_url, network_id, chain_id, *, api_key = None, timeout = None))]
+    fn new(
+        base_url: &str,
+        network_id: &str,
+        chain_id: &str,
+        api_key: Option<&str>,
+        timeout: Option<u64>,
+    ) -> PyResult<Self> {
+        let mut config = ApiConfig::new(base_url, network_id, chain_id);
+        if let Some(api_key) = api_key {
+            config = config.with_api_key(api_key);
+        }
+        if let Some(timeout) = timeout {
+            config = config.with_timeout(timeout);
+        }
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(to_py_err)?;
+        Ok(Self {
+            client: ApiClient::new(config),
+            runtime,
+        })
+    }
+
+    /// Execute a command locally, without submitting it
+    fn local(&self, py: Python<'_>, cmd: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let cmd = Self::command(cmd)?;
+        self.block_on(py, self.client.local(&cmd))
+    }
+
+    /// Submit a command, returning the request keys
+    fn send(&self, py: Python<'_>, cmd: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let cmd = Self::command(cmd)?;
+        self.block_on(py, self.client.send(&cmd))
+    }
+
+    /// Get the results of submitted commands, keyed by request key
+    fn poll(&self, py: Python<'_>, request_keys: Vec<String>) -> PyResult<PyObject> {
+        let request_keys: Vec<&str> = request_keys.iter().map(String::as_str).collect();
+        self.block_on(py, self.client.poll(&request_keys))
+    }
+
+    /// Wait for the result of a submitted command
+    fn listen(&self, py: Python<'_>, request_key: &str) -> PyResult<PyObject> {
+        self.block_on(py, self.client.listen(request_key))
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "kadena")]
+fn kadena_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("KadenaError", m.py().get_type::<KadenaError>())?;
+    m.add_class::<PyPactKeypair>()?;
+    m.add_class::<PyClient>()?;
+    m.add_function(wrap_pyfunction!(hash, m)?)?;
+    m.add_function(wrap_pyfunction!(prepare_exec, m)?)?;
+    Ok(())
+}
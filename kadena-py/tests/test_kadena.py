@@ -0,0 +1,41 @@
+import json
+
+import pytest
+
+import kadena
+
+
+def test_keypair_signing():
+    keypair = kadena.PactKeypair.generate()
+    restored = kadena.PactKeypair.from_secret_key(keypair.secret_key)
+    assert restored.public_key == keypair.public_key
+
+    signature = keypair.sign(b"hello")
+    assert restored.verify(b"hello", signature)
+    assert not restored.verify(b"other", signature)
+
+
+def test_invalid_secret_key():
+    with pytest.raises(kadena.KadenaError):
+        kadena.PactKeypair.from_secret_key("not hex")
+
+
+def test_prepare_exec():
+    keypair = kadena.PactKeypair.generate()
+    sender = "k:" + keypair.public_key
+    cmd = kadena.prepare_exec(
+        "(coin.details (read-msg 'account))",
+        "1",
+        sender,
+        [(keypair, [{"name": "coin.GAS", "args": []}])],
+        data={"account": sender},
+        network_id="testnet04",
+        gas_limit=2000,
+    )
+
+    payload = json.loads(cmd["cmd"])
+    assert payload["networkId"] == "testnet04"
+    assert payload["meta"]["gasLimit"] == 2000
+    assert payload["payload"]["exec"]["data"] == {"account": sender}
+    assert cmd["hash"] == kadena.hash(cmd["cmd"].encode())
+    assert len(cmd["sigs"]) == 1
@@ -11,4 +11,53 @@ pub enum CryptoError {
     Ed25519Error(#[from] ed25519_dalek::SignatureError),
     #[error("Invalid seed length")]
     InvalidSeedLength,
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+    #[error("Decryption failed: wrong password or corrupted data")]
+    DecryptionError,
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    #[error("Invalid secret share: {0}")]
+    InvalidShare(String),
+    #[error("HSM error: {0}")]
+    HsmError(String),
+    #[error("KMS error: {0}")]
+    KmsError(String),
+    #[error("Invalid Merkle proof: {0}")]
+    InvalidProof(String),
+    #[error("Unsupported signature scheme: {0}")]
+    UnsupportedScheme(String),
+}
+
+impl CryptoError {
+    /// A stable, machine-readable code for the kind of error
+    ///
+    /// Unlike the display string, codes do not change between releases, so
+    /// they can be used for metrics or to pick a user-facing message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::{hex_to_bin, CryptoError};
+    ///
+    /// let error = hex_to_bin("zz").unwrap_err();
+    /// assert_eq!(error.error_code(), "crypto.invalid_hex");
+    /// assert_eq!(CryptoError::InvalidSeedLength.error_code(), "crypto.invalid_seed_length");
+    /// ```
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::HexError(_) => "crypto.invalid_hex",
+            Self::Base64Error(_) => "crypto.invalid_base64",
+            Self::Ed25519Error(_) => "crypto.ed25519",
+            Self::InvalidSeedLength => "crypto.invalid_seed_length",
+            Self::InvalidFormat(_) => "crypto.invalid_format",
+            Self::DecryptionError => "crypto.decryption_failed",
+            Self::InvalidMnemonic(_) => "crypto.invalid_mnemonic",
+            Self::InvalidShare(_) => "crypto.invalid_share",
+            Self::HsmError(_) => "crypto.hsm",
+            Self::KmsError(_) => "crypto.kms",
+            Self::InvalidProof(_) => "crypto.invalid_proof",
+            Self::UnsupportedScheme(_) => "crypto.unsupported_scheme",
+        }
+    }
 }
@@ -0,0 +1,158 @@
+//! PKCS#11 signer backend
+//!
+//! Signs with ED25519 keys stored on a PKCS#11 token (HSM, smart card,
+//! SoftHSM...) through the `CKM_EDDSA` mechanism, so secret keys never leave
+//! the token.
+
+use std::path::Path;
+
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    mechanism::Mechanism,
+    object::{Attribute, AttributeType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::AuthPin,
+};
+
+use crate::{encoding, CryptoError, Signature, Signer};
+
+/// DER header of an OCTET STRING holding a 32 byte ED25519 point
+const EC_POINT_HEADER: [u8; 2] = [0x04, 0x20];
+
+/// A [`Signer`] backed by an ED25519 key stored on a PKCS#11 token
+#[derive(Debug)]
+pub struct Pkcs11Signer {
+    session: Session,
+    key: ObjectHandle,
+    public_key: String,
+}
+
+impl Pkcs11Signer {
+    /// Create a signer for the key pair labelled `label` in an open session
+    ///
+    /// The session must already be logged in as a user allowed to sign. The
+    /// public key is read from the public key object carrying the same label.
+    pub fn new(session: Session, label: &str) -> Result<Self, CryptoError> {
+        let key = find_object(&session, ObjectClass::PRIVATE_KEY, label)?;
+        let public = find_object(&session, ObjectClass::PUBLIC_KEY, label)?;
+
+        let attributes = session
+            .get_attributes(public, &[AttributeType::EcPoint])
+            .map_err(hsm_error)?;
+        let point = attributes
+            .into_iter()
+            .find_map(|attribute| match attribute {
+                Attribute::EcPoint(point) => Some(point),
+                _ => None,
+            })
+            .ok_or_else(|| CryptoError::HsmError(format!("key {} has no EC point", label)))?;
+
+        Ok(Self {
+            session,
+            key,
+            public_key: encoding::bin_to_hex(decode_ec_point(&point)?),
+        })
+    }
+
+    /// Load a PKCS#11 module, log into the token of `slot_index` and create a signer
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Path to the PKCS#11 library of the token vendor
+    /// * `slot_index` - Index among the slots holding a token
+    /// * `pin` - User PIN of the token
+    /// * `label` - Label of the ED25519 key pair
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use kadena::crypto::Pkcs11Signer;
+    ///
+    /// let signer = Pkcs11Signer::open("/usr/lib/softhsm/libsofthsm2.so", 0, "1234", "treasury")?;
+    /// ```
+    pub fn open(
+        module: impl AsRef<Path>,
+        slot_index: usize,
+        pin: &str,
+        label: &str,
+    ) -> Result<Self, CryptoError> {
+        let context = Pkcs11::new(module).map_err(hsm_error)?;
+        context
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(hsm_error)?;
+
+        let slot = *context
+            .get_slots_with_token()
+            .map_err(hsm_error)?
+            .get(slot_index)
+            .ok_or_else(|| CryptoError::HsmError(format!("no token in slot {}", slot_index)))?;
+
+        let session = context.open_ro_session(slot).map_err(hsm_error)?;
+        session
+            .login(UserType::User, Some(&AuthPin::new(pin.to_string())))
+            .map_err(hsm_error)?;
+
+        Self::new(session, label)
+    }
+}
+
+impl Signer for Pkcs11Signer {
+    fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
+        let signature = self
+            .session
+            .sign(&Mechanism::Eddsa, self.key, msg)
+            .map_err(hsm_error)?;
+        Signature::try_from(signature.as_slice()).map_err(|_| {
+            CryptoError::HsmError(format!(
+                "expected a 64 byte signature, got {}",
+                signature.len()
+            ))
+        })
+    }
+}
+
+fn find_object(
+    session: &Session,
+    class: ObjectClass,
+    label: &str,
+) -> Result<ObjectHandle, CryptoError> {
+    let template = [
+        Attribute::Class(class),
+        Attribute::Label(label.as_bytes().to_vec()),
+    ];
+    let objects = session.find_objects(&template).map_err(hsm_error)?;
+    match objects.as_slice() {
+        [object] => Ok(*object),
+        [] => Err(CryptoError::HsmError(format!(
+            "no {} labelled {}",
+            class, label
+        ))),
+        _ => Err(CryptoError::HsmError(format!(
+            "several objects of class {} labelled {}",
+            class, label
+        ))),
+    }
+}
+
+/// Extract the raw ED25519 public key from a `CKA_EC_POINT` value
+///
+/// Tokens return either the DER-encoded OCTET STRING mandated by PKCS#11 or
+/// the raw 32 byte point.
+fn decode_ec_point(point: &[u8]) -> Result<&[u8], CryptoError> {
+    match point.len() {
+        32 => Ok(point),
+        34 if point[..2] == EC_POINT_HEADER => Ok(&point[2..]),
+        len => Err(CryptoError::HsmError(format!(
+            "unsupported EC point encoding of {} bytes",
+            len
+        ))),
+    }
+}
+
+fn hsm_error(error: cryptoki::error::Error) -> CryptoError {
+    CryptoError::HsmError(error.to_string())
+}
@@ -1,16 +1,23 @@
 use blake2::{digest::consts::U32, Blake2b, Digest};
-use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
-use rand::rngs::OsRng;
+use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 
-use crate::{encoding, CryptoError};
+use crate::{encoding, CryptoError, Signature};
 
 /// Represents a Pact keypair containing a public key and a secret key
+///
+/// The keys are decoded once, when the keypair is created, so signing and
+/// verifying do not parse them again. Their hexadecimal forms are available
+/// through [`public_key`](Self::public_key) and
+/// [`secret_key`](Self::secret_key).
 #[derive(Debug, Clone)]
 pub struct PactKeypair {
     /// The public key as a hexadecimal string
-    pub public_key: String,
+    public_key: String,
     /// The secret key as a hexadecimal string
-    pub secret_key: String,
+    secret_key: String,
+    signing_key: SigningKey,
+    verifying_key: VerifyingKey,
 }
 
 impl PactKeypair {
@@ -25,13 +32,28 @@ impl PactKeypair {
     /// assert_eq!(keypair.public_key().len(), 64); // 32 bytes in hex
     /// ```
     pub fn generate() -> Self {
-        let mut csprng = OsRng;
-        let signing_key: SigningKey = SigningKey::generate(&mut csprng);
-        let verifying_key = signing_key.verifying_key();
-        Self {
-            public_key: encoding::bin_to_hex(verifying_key.as_bytes()),
-            secret_key: encoding::bin_to_hex(&signing_key.to_bytes()),
-        }
+        Self::generate_with_rng(&mut OsRng)
+    }
+
+    /// Generate a new ED25519 keypair from the given random number generator
+    ///
+    /// Lets property tests and simulations generate reproducible keys from a
+    /// seeded generator. Use [`generate`](Self::generate) in production.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::PactKeypair;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let first = PactKeypair::generate_with_rng(&mut StdRng::seed_from_u64(42));
+    /// let second = PactKeypair::generate_with_rng(&mut StdRng::seed_from_u64(42));
+    /// assert_eq!(first.public_key(), second.public_key());
+    /// ```
+    pub fn generate_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        let signing_key: SigningKey = SigningKey::generate(rng);
+        let secret_key = encoding::bin_to_hex(&signing_key.to_bytes());
+        Self::from_signing_key(signing_key, secret_key)
     }
 
     /// Restore a keypair from a secret key
@@ -55,11 +77,17 @@ impl PactKeypair {
             return Err(CryptoError::InvalidSeedLength);
         }
         let signing_key = SigningKey::from_bytes(&secret_bytes.try_into().unwrap());
+        Ok(Self::from_signing_key(signing_key, seed.to_string()))
+    }
+
+    fn from_signing_key(signing_key: SigningKey, secret_key: String) -> Self {
         let verifying_key = signing_key.verifying_key();
-        Ok(Self {
+        Self {
             public_key: encoding::bin_to_hex(verifying_key.as_bytes()),
-            secret_key: seed.to_string(),
-        })
+            secret_key,
+            signing_key,
+            verifying_key,
+        }
     }
 
     /// Get the public key
@@ -86,13 +114,11 @@ impl PactKeypair {
     /// let keypair = PactKeypair::generate();
     /// let msg = b"Hello, world!";
     /// let signature = keypair.sign(msg).unwrap();
-    /// assert_eq!(signature.len(), 128); // 64 bytes in hex
+    /// assert_eq!(signature.to_hex().len(), 128); // 64 bytes in hex
     /// ```
-    pub fn sign(&self, msg: &[u8]) -> Result<String, CryptoError> {
-        let secret_bytes = encoding::hex_to_bin(&self.secret_key)?;
-        let signing_key = SigningKey::from_bytes(&secret_bytes.try_into().unwrap());
-        let signature = signing_key.try_sign(msg)?;
-        Ok(encoding::bin_to_hex(signature.to_bytes().as_ref()))
+    pub fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
+        let signature = self.signing_key.try_sign(msg)?;
+        Ok(Signature::from_bytes(signature.to_bytes()))
     }
 
     /// Verify a signature using this keypair's public key
@@ -110,10 +136,32 @@ impl PactKeypair {
     /// let keypair = PactKeypair::generate();
     /// let msg = b"Hello, world!";
     /// let signature = keypair.sign(msg).unwrap();
-    /// assert!(keypair.verify(msg, &signature).unwrap());
+    /// assert!(keypair.verify(msg, &signature.to_hex()).unwrap());
     /// ```
     pub fn verify(&self, msg: &[u8], signature: &str) -> Result<bool, CryptoError> {
-        verify_signature(msg, signature, &self.public_key)
+        let signature = ed25519_dalek::Signature::from_slice(&encoding::hex_to_bin(signature)?)?;
+        Ok(self.verifying_key.verify(msg, &signature).is_ok())
+    }
+
+    /// Get the raw bytes of the public key
+    pub fn public_key_bytes(&self) -> Result<[u8; 32], CryptoError> {
+        Ok(self.verifying_key.to_bytes())
+    }
+
+    /// Verify a raw signature using this keypair's public key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::PactKeypair;
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let signature = keypair.sign(b"relay").unwrap().to_bytes();
+    /// assert!(keypair.verify_bytes(b"relay", &signature).unwrap());
+    /// ```
+    pub fn verify_bytes(&self, msg: &[u8], signature: &[u8; 64]) -> Result<bool, CryptoError> {
+        let signature = ed25519_dalek::Signature::from_bytes(signature);
+        Ok(self.verifying_key.verify(msg, &signature).is_ok())
     }
 }
 
@@ -159,7 +207,33 @@ pub fn verify_signature(
         return Err(CryptoError::InvalidSeedLength);
     }
 
-    let signature = Signature::from_slice(&sig_bytes)?;
-    let verifying_key = VerifyingKey::from_bytes(&pub_bytes.try_into().unwrap())?;
+    let signature = ed25519_dalek::Signature::from_slice(&sig_bytes)?;
+    verify_signature_bytes(msg, &signature.to_bytes(), &pub_bytes.try_into().unwrap())
+}
+
+/// Verify a raw signature with a raw public key
+///
+/// Same as [`verify_signature`] without the hexadecimal decoding, for hot
+/// paths verifying many signatures that are already held as bytes.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::{verify_signature_bytes, PactKeypair};
+///
+/// let keypair = PactKeypair::generate();
+/// let public_key = keypair.public_key_bytes().unwrap();
+/// let signature = keypair.sign(b"relay").unwrap().to_bytes();
+///
+/// assert!(verify_signature_bytes(b"relay", &signature, &public_key).unwrap());
+/// assert!(!verify_signature_bytes(b"other", &signature, &public_key).unwrap());
+/// ```
+pub fn verify_signature_bytes(
+    msg: &[u8],
+    signature: &[u8; 64],
+    public_key: &[u8; 32],
+) -> Result<bool, CryptoError> {
+    let signature = ed25519_dalek::Signature::from_bytes(signature);
+    let verifying_key = VerifyingKey::from_bytes(public_key)?;
     Ok(verifying_key.verify(msg, &signature).is_ok())
 }
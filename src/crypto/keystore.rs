@@ -0,0 +1,423 @@
+//! Encrypted storage of wallet secrets
+//!
+//! This module reads and writes the password-encrypted blobs produced by the
+//! `kadenaEncrypt` function of `@kadena/hd-wallet`, which wallets built on
+//! that library use to store mnemonics and seeds at rest:
+//!
+//! - a 32 byte key is derived from the password with PBKDF2-HMAC-SHA256
+//!   (1000 iterations, 16 byte random salt)
+//! - the secret is encrypted with AES-256-GCM (12 byte random IV), the
+//!   authentication tag appended to the ciphertext
+//! - the blob is `base64("<base64 salt>.<base64 iv>.<base64 ciphertext>")`
+//!
+//! Keys are derived from mnemonics the same way as those wallets: BIP39 seed
+//! without passphrase, then SLIP-10 ED25519 derivation along `m/44'/626'/<index>'`.
+//!
+//! Chainweaver derives keys differently, so the same recovery phrase yields
+//! other keys there. [`ChainweaverRootKey`] reads and writes its encrypted
+//! root key and derives its keys:
+//!
+//! - the root key is generated from the BIP39 seed as in `cardano-crypto`,
+//!   and keys are derived from it with BIP32-Ed25519 (V2 scheme) along the
+//!   single hardened index `<index>'`
+//! - the root key is stored as the 64 byte extended secret key, encrypted with
+//!   ChaCha20, followed by the 32 byte public key and the 32 byte chain code;
+//!   the ChaCha20 key and 8 byte nonce are derived from the password with
+//!   PBKDF2-HMAC-SHA512 (15000 iterations), an empty password leaves the
+//!   secret key unencrypted
+
+use std::{fmt, str::FromStr};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher},
+    ChaCha20Legacy,
+};
+use ed25519_bip32::{DerivationScheme, XPrv};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{encoding, CryptoError, PactKeypair, Signature, Signer};
+
+const PBKDF2_ITERATIONS: u32 = 1000;
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 12;
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+const CHAINWEAVER_KEY_LEN: usize = 128;
+const CHAINWEAVER_ITERATIONS: u32 = 15000;
+// The salt of `cardano-crypto`, including the terminating NUL of the C string
+const CHAINWEAVER_SALT: &[u8] = b"encrypted wallet salt\0";
+
+/// SLIP-44 coin type registered for Kadena
+pub const KADENA_COIN_TYPE: u32 = 626;
+
+/// Encrypt a secret with a password, in the `@kadena/hd-wallet` blob format
+///
+/// Chainweaver stores its root key instead, see [`ChainweaverRootKey`].
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::{kadena_decrypt, kadena_encrypt};
+///
+/// let blob = kadena_encrypt(b"password", b"secret");
+/// assert_eq!(kadena_decrypt(b"password", &blob).unwrap(), b"secret");
+/// ```
+pub fn kadena_encrypt(password: &[u8], secret: &[u8]) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    let mut iv = [0u8; IV_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut iv);
+
+    let cipher = Aes256Gcm::new(&derive_encryption_key(password, &salt).into());
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&iv), secret)
+        .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+
+    let parts = [
+        STANDARD.encode(salt),
+        STANDARD.encode(iv),
+        STANDARD.encode(ciphertext),
+    ];
+    STANDARD.encode(parts.join("."))
+}
+
+/// Decrypt a blob produced by [`kadena_encrypt`] or `@kadena/hd-wallet`
+///
+/// Fails with [`CryptoError::DecryptionError`] if the password is wrong or the
+/// blob was tampered with.
+pub fn kadena_decrypt(password: &[u8], encrypted: &str) -> Result<Vec<u8>, CryptoError> {
+    let decoded = STANDARD.decode(encrypted.trim())?;
+    let decoded = String::from_utf8(decoded)
+        .map_err(|_| CryptoError::InvalidFormat("blob is not valid UTF-8".to_string()))?;
+
+    let parts: Vec<&str> = decoded.split('.').collect();
+    let [salt, iv, ciphertext] = parts.as_slice() else {
+        return Err(CryptoError::InvalidFormat(
+            "expected salt, iv and ciphertext separated by '.'".to_string(),
+        ));
+    };
+    let salt = STANDARD.decode(salt)?;
+    let iv = STANDARD.decode(iv)?;
+    let ciphertext = STANDARD.decode(ciphertext)?;
+    if iv.len() != IV_LEN {
+        return Err(CryptoError::InvalidFormat(format!(
+            "expected a {} byte IV, got {}",
+            IV_LEN,
+            iv.len()
+        )));
+    }
+
+    let cipher = Aes256Gcm::new(&derive_encryption_key(password, &salt).into());
+    cipher
+        .decrypt(Nonce::from_slice(&iv), ciphertext.as_slice())
+        .map_err(|_| CryptoError::DecryptionError)
+}
+
+fn derive_encryption_key(password: &[u8], salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, PBKDF2_ITERATIONS, &mut key);
+    key
+}
+
+/// A password-encrypted BIP39 mnemonic
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::EncryptedMnemonic;
+///
+/// let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let encrypted = EncryptedMnemonic::encrypt(phrase, "password").unwrap();
+///
+/// // Store `encrypted.to_string()`, then later:
+/// let restored: EncryptedMnemonic = encrypted.to_string().parse().unwrap();
+/// let keypair = restored.keypair("password", 0).unwrap();
+/// assert_eq!(keypair.public_key().len(), 64);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedMnemonic(String);
+
+impl EncryptedMnemonic {
+    /// Validate and encrypt a mnemonic phrase
+    pub fn encrypt(mnemonic: &str, password: &str) -> Result<Self, CryptoError> {
+        let mnemonic = parse_mnemonic(mnemonic)?;
+        Ok(Self(kadena_encrypt(
+            password.as_bytes(),
+            mnemonic.to_string().as_bytes(),
+        )))
+    }
+
+    /// Get the encrypted blob
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Decrypt the mnemonic phrase
+    pub fn decrypt(&self, password: &str) -> Result<String, CryptoError> {
+        let phrase = kadena_decrypt(password.as_bytes(), &self.0)?;
+        String::from_utf8(phrase).map_err(|_| CryptoError::DecryptionError)
+    }
+
+    /// Decrypt the mnemonic and derive the keypair at `index`
+    pub fn keypair(&self, password: &str, index: u32) -> Result<PactKeypair, CryptoError> {
+        keypair_from_mnemonic(&self.decrypt(password)?, index)
+    }
+}
+
+impl fmt::Display for EncryptedMnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for EncryptedMnemonic {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let decoded = STANDARD.decode(s.trim())?;
+        if decoded.iter().filter(|b| **b == b'.').count() != 2 {
+            return Err(CryptoError::InvalidFormat(
+                "expected salt, iv and ciphertext separated by '.'".to_string(),
+            ));
+        }
+        Ok(Self(s.trim().to_string()))
+    }
+}
+
+/// Derive the keypair at `index` from a BIP39 mnemonic
+///
+/// Uses SLIP-10 along the derivation path `m/44'/626'/<index>'`, as
+/// `@kadena/hd-wallet` does. Use [`ChainweaverRootKey`] for Chainweaver
+/// recovery phrases.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::keypair_from_mnemonic;
+///
+/// let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let first = keypair_from_mnemonic(phrase, 0).unwrap();
+/// let second = keypair_from_mnemonic(phrase, 1).unwrap();
+/// assert_ne!(first.public_key(), second.public_key());
+/// ```
+pub fn keypair_from_mnemonic(mnemonic: &str, index: u32) -> Result<PactKeypair, CryptoError> {
+    let seed = parse_mnemonic(mnemonic)?.to_seed("");
+    let secret = slip10_derive(&seed, &[44, KADENA_COIN_TYPE, index]);
+    PactKeypair::from_secret_key(&encoding::bin_to_hex(&secret))
+}
+
+/// Derive an ED25519 secret key from a seed following SLIP-10
+///
+/// Every index of `path` is hardened, as SLIP-10 requires for ED25519.
+pub fn slip10_derive(seed: &[u8], path: &[u32]) -> [u8; 32] {
+    let (mut key, mut chain_code) = hmac_sha512(b"ed25519 seed", &[seed]);
+    for index in path {
+        let hardened = (index | HARDENED_OFFSET).to_be_bytes();
+        (key, chain_code) = hmac_sha512(&chain_code, &[&[0u8], &key, &hardened]);
+    }
+    key
+}
+
+/// A Chainweaver root key, encrypted with the wallet password
+///
+/// Chainweaver keeps the root key derived from the recovery phrase rather than
+/// the phrase itself; see the [module documentation](self) for the format.
+/// Displays and parses as the hexadecimal encoding of its 128 bytes.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::ChainweaverRootKey;
+///
+/// let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+/// let root = ChainweaverRootKey::from_mnemonic(phrase, "password").unwrap();
+///
+/// // Store `root.to_string()`, then later:
+/// let restored: ChainweaverRootKey = root.to_string().parse().unwrap();
+/// let keypair = restored.keypair("password", 0).unwrap();
+/// assert_eq!(keypair.public_key().len(), 64);
+/// assert!(restored.keypair("wrong", 0).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainweaverRootKey([u8; CHAINWEAVER_KEY_LEN]);
+
+impl ChainweaverRootKey {
+    /// Generate the root key of a recovery phrase and encrypt it with a password
+    pub fn from_mnemonic(mnemonic: &str, password: &str) -> Result<Self, CryptoError> {
+        let seed = parse_mnemonic(mnemonic)?.to_seed("");
+        Ok(Self::encrypt(&chainweaver_root(&seed), password))
+    }
+
+    /// Read an encrypted root key from its 128 bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let key = bytes.try_into().map_err(|_| {
+            CryptoError::InvalidFormat(format!(
+                "expected a {} byte root key, got {}",
+                CHAINWEAVER_KEY_LEN,
+                bytes.len()
+            ))
+        })?;
+        Ok(Self(key))
+    }
+
+    /// Get the bytes of the encrypted root key
+    pub fn as_bytes(&self) -> &[u8; CHAINWEAVER_KEY_LEN] {
+        &self.0
+    }
+
+    /// Decrypt the root key and derive the keypair at `index`
+    ///
+    /// Fails with [`CryptoError::DecryptionError`] if the password is wrong.
+    pub fn keypair(&self, password: &str, index: u32) -> Result<ChainweaverKeypair, CryptoError> {
+        let root = self.decrypt(password)?;
+        let xprv = root.derive(DerivationScheme::V2, index | HARDENED_OFFSET);
+        Ok(ChainweaverKeypair::from_xprv(xprv))
+    }
+
+    /// Encrypt the root key with another password
+    pub fn change_password(&self, old: &str, new: &str) -> Result<Self, CryptoError> {
+        Ok(Self::encrypt(&self.decrypt(old)?, new))
+    }
+
+    fn encrypt(root: &XPrv, password: &str) -> Self {
+        let mut key = [0u8; CHAINWEAVER_KEY_LEN];
+        key[..64].copy_from_slice(root.extended_secret_key_bytes());
+        chainweaver_cipher(password.as_bytes(), &mut key[..64]);
+        key[64..96].copy_from_slice(root.public().public_key_bytes());
+        key[96..].copy_from_slice(root.chain_code());
+        Self(key)
+    }
+
+    fn decrypt(&self, password: &str) -> Result<XPrv, CryptoError> {
+        let mut secret = [0u8; 64];
+        let mut chain_code = [0u8; 32];
+        secret.copy_from_slice(&self.0[..64]);
+        chain_code.copy_from_slice(&self.0[96..]);
+        chainweaver_cipher(password.as_bytes(), &mut secret);
+
+        // The cipher is not authenticated, a wrong password shows as an
+        // unclamped secret key or one that does not match the public key
+        if secret[0] & 0b0000_0111 != 0 || secret[31] & 0b1100_0000 != 0b0100_0000 {
+            return Err(CryptoError::DecryptionError);
+        }
+        let root = XPrv::from_extended_and_chaincode(&secret, &chain_code);
+        if root.public().public_key_bytes()[..] != self.0[64..96] {
+            return Err(CryptoError::DecryptionError);
+        }
+        Ok(root)
+    }
+}
+
+impl fmt::Display for ChainweaverRootKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encoding::bin_to_hex(&self.0))
+    }
+}
+
+impl FromStr for ChainweaverRootKey {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&encoding::hex_to_bin(s.trim())?)
+    }
+}
+
+/// A keypair derived from a [`ChainweaverRootKey`]
+///
+/// The secret is a BIP32-Ed25519 extended key, which has no 32 byte ED25519
+/// seed and so cannot be turned into a [`PactKeypair`]. It signs through
+/// [`Signer`], and its signatures are plain ED25519 signatures.
+#[derive(Debug, Clone)]
+pub struct ChainweaverKeypair {
+    public_key: String,
+    xprv: XPrv,
+}
+
+impl ChainweaverKeypair {
+    fn from_xprv(xprv: XPrv) -> Self {
+        Self {
+            public_key: encoding::bin_to_hex(xprv.public().public_key_bytes()),
+            xprv,
+        }
+    }
+
+    /// Get the public key
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// Sign a message using this keypair
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        let signature: ed25519_bip32::Signature<()> = self.xprv.sign(msg);
+        Signature::from_bytes(*signature.to_bytes())
+    }
+}
+
+impl Signer for ChainweaverKeypair {
+    fn public_key(&self) -> &str {
+        ChainweaverKeypair::public_key(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
+        Ok(ChainweaverKeypair::sign(self, msg))
+    }
+}
+
+/// Generate a BIP32-Ed25519 root key from a seed, as `cardano-crypto` does
+fn chainweaver_root(seed: &[u8]) -> XPrv {
+    let mut iteration = 1;
+    loop {
+        let message = format!("Root Seed Chain {}", iteration);
+        let (left, chain_code) = hmac_sha512(seed, &[message.as_bytes()]);
+
+        let mut secret: [u8; 64] = Sha512::digest(left).into();
+        secret[0] &= 0b1111_1000;
+        secret[31] &= 0b0011_1111;
+        secret[31] |= 0b0100_0000;
+        if secret[31] & 0b0010_0000 == 0 {
+            return XPrv::from_extended_and_chaincode(&secret, &chain_code);
+        }
+        iteration += 1;
+    }
+}
+
+/// Encrypt or decrypt a secret key in place, as `cardano-crypto` does
+fn chainweaver_cipher(password: &[u8], data: &mut [u8]) {
+    if password.is_empty() {
+        return;
+    }
+    let mut stretched = [0u8; 40];
+    pbkdf2::pbkdf2_hmac::<Sha512>(
+        password,
+        CHAINWEAVER_SALT,
+        CHAINWEAVER_ITERATIONS,
+        &mut stretched,
+    );
+    let mut cipher = ChaCha20Legacy::new(stretched[..32].into(), stretched[32..].into());
+    cipher.apply_keystream(data);
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac =
+        <Hmac<Sha512> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for chunk in data {
+        mac.update(chunk);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+fn parse_mnemonic(mnemonic: &str) -> Result<bip39::Mnemonic, CryptoError> {
+    bip39::Mnemonic::parse_normalized(mnemonic.trim())
+        .map_err(|e| CryptoError::InvalidMnemonic(e.to_string()))
+}
@@ -0,0 +1,209 @@
+//! Cloud KMS signer backends
+//!
+//! [`KmsSigner`] signs with ED25519 keys held by a key management service.
+//! Services are reached through the [`KmsClient`] trait, which only has to
+//! expose the raw signing and public key APIs; [`KmsSigner`] normalizes their
+//! output into the hexadecimal format [`Cmd`](crate::pact::Cmd) expects.
+//!
+//! An adapter for AWS KMS is available with the `aws-kms` feature.
+
+use std::future::Future;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use crate::{encoding, AsyncSigner, CryptoError, Signature};
+
+/// DER prefix of an ED25519 `SubjectPublicKeyInfo`
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+const SIGNATURE_LEN: usize = 64;
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// Raw access to the ED25519 signing API of a key management service
+pub trait KmsClient {
+    /// Sign a raw message with the key `key_id`
+    ///
+    /// The signature may be returned as raw bytes, hex or base64 text.
+    fn sign(
+        &self,
+        key_id: &str,
+        msg: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>> + Send;
+
+    /// Fetch the public key of `key_id`
+    ///
+    /// The key may be returned as raw bytes, hex text or a DER `SubjectPublicKeyInfo`.
+    fn public_key(&self, key_id: &str)
+        -> impl Future<Output = Result<Vec<u8>, CryptoError>> + Send;
+}
+
+/// An [`AsyncSigner`] backed by a key stored in a key management service
+#[derive(Debug, Clone)]
+pub struct KmsSigner<C> {
+    client: C,
+    key_id: String,
+    public_key: String,
+}
+
+impl<C: KmsClient> KmsSigner<C> {
+    /// Create a signer for `key_id`, fetching its public key from the service
+    pub async fn new(client: C, key_id: &str) -> Result<Self, CryptoError> {
+        let public_key = normalize_public_key(&client.public_key(key_id).await?)?;
+        Ok(Self::with_public_key(client, key_id, &public_key))
+    }
+
+    /// Create a signer for `key_id` whose public key is already known
+    pub fn with_public_key(client: C, key_id: &str, public_key: &str) -> Self {
+        Self {
+            client,
+            key_id: key_id.to_string(),
+            public_key: public_key.to_lowercase(),
+        }
+    }
+
+    /// Get the identifier of the key in the service
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+}
+
+impl<C: KmsClient + Sync> AsyncSigner for KmsSigner<C> {
+    fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
+        normalize_signature(&self.client.sign(&self.key_id, msg).await?)?.parse()
+    }
+}
+
+/// Convert a signature returned by a KMS into lowercase hex
+///
+/// Accepts the 64 raw signature bytes, or their hex or base64 encoding.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::normalize_signature;
+///
+/// let raw = [0xabu8; 64];
+/// assert_eq!(normalize_signature(&raw).unwrap(), "ab".repeat(64));
+/// assert_eq!(normalize_signature("AB".repeat(64).as_bytes()).unwrap(), "ab".repeat(64));
+/// ```
+pub fn normalize_signature(raw: &[u8]) -> Result<String, CryptoError> {
+    decode_fixed(raw, SIGNATURE_LEN)
+        .map(|bytes| encoding::bin_to_hex(&bytes))
+        .ok_or_else(|| {
+            CryptoError::KmsError(format!(
+                "unsupported signature encoding of {} bytes",
+                raw.len()
+            ))
+        })
+}
+
+/// Convert a public key returned by a KMS into lowercase hex
+///
+/// Accepts the 32 raw key bytes, their hex encoding, or a DER-encoded
+/// `SubjectPublicKeyInfo`, as returned by AWS KMS.
+pub fn normalize_public_key(raw: &[u8]) -> Result<String, CryptoError> {
+    let key = raw
+        .strip_prefix(&ED25519_SPKI_PREFIX)
+        .filter(|key| key.len() == PUBLIC_KEY_LEN)
+        .map(<[u8]>::to_vec)
+        .or_else(|| decode_fixed(raw, PUBLIC_KEY_LEN))
+        .ok_or_else(|| {
+            CryptoError::KmsError(format!(
+                "unsupported public key encoding of {} bytes",
+                raw.len()
+            ))
+        })?;
+    Ok(encoding::bin_to_hex(&key))
+}
+
+fn decode_fixed(raw: &[u8], len: usize) -> Option<Vec<u8>> {
+    if raw.len() == len {
+        return Some(raw.to_vec());
+    }
+    let text = std::str::from_utf8(raw).ok()?.trim();
+    if text.len() == len * 2 {
+        return encoding::hex_to_bin(text).ok();
+    }
+    STANDARD
+        .decode(text)
+        .ok()
+        .filter(|bytes| bytes.len() == len)
+}
+
+#[cfg(feature = "aws-kms")]
+mod aws {
+    use aws_sdk_kms::{
+        primitives::Blob,
+        types::{MessageType, SigningAlgorithmSpec},
+        Client,
+    };
+
+    use super::KmsClient;
+    use crate::CryptoError;
+
+    /// [`KmsClient`] adapter for AWS KMS
+    ///
+    /// Requires keys with the `ECC_NIST_EDWARDS25519` key spec.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use kadena::crypto::{AwsKmsClient, KmsSigner};
+    ///
+    /// let config = aws_config::load_from_env().await;
+    /// let client = AwsKmsClient::new(aws_sdk_kms::Client::new(&config));
+    /// let signer = KmsSigner::new(client, "alias/kadena-treasury").await?;
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct AwsKmsClient {
+        client: Client,
+    }
+
+    impl AwsKmsClient {
+        /// Wrap an AWS KMS client
+        pub fn new(client: Client) -> Self {
+            Self { client }
+        }
+    }
+
+    impl KmsClient for AwsKmsClient {
+        async fn sign(&self, key_id: &str, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+            let output = self
+                .client
+                .sign()
+                .key_id(key_id)
+                .message(Blob::new(msg))
+                .message_type(MessageType::Raw)
+                .signing_algorithm(SigningAlgorithmSpec::Ed25519Sha512)
+                .send()
+                .await
+                .map_err(|e| CryptoError::KmsError(e.to_string()))?;
+            output
+                .signature()
+                .map(|signature| signature.as_ref().to_vec())
+                .ok_or_else(|| CryptoError::KmsError("response has no signature".to_string()))
+        }
+
+        async fn public_key(&self, key_id: &str) -> Result<Vec<u8>, CryptoError> {
+            let output = self
+                .client
+                .get_public_key()
+                .key_id(key_id)
+                .send()
+                .await
+                .map_err(|e| CryptoError::KmsError(e.to_string()))?;
+            output
+                .public_key()
+                .map(|key| key.as_ref().to_vec())
+                .ok_or_else(|| CryptoError::KmsError("response has no public key".to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "aws-kms")]
+pub use aws::AwsKmsClient;
@@ -0,0 +1,231 @@
+//! Chainweb Merkle log primitives
+//!
+//! Chainweb authenticates block headers, payloads and transaction outputs with
+//! binary Merkle trees hashed with SHA-512/256:
+//!
+//! - a leaf hashes `0x00 || input`
+//! - an inner node hashes `0x01 || left || right`
+//!
+//! A proof object lists the sibling hashes from a leaf up to a root. Encoded,
+//! it is the number of steps as big-endian `u32` and the position of the leaf
+//! as big-endian `u64`, followed by one byte per step telling on which side
+//! the sibling sits (`0x00` left, `0x01` right) and the 32 byte sibling hash.
+//! Proofs can span nested trees, since the root of a subtree is a node of
+//! the enclosing tree.
+//!
+//! Inputs of chainweb trees are tagged with their type: the leaf input is the
+//! tag as little-endian `u16` followed by the encoded value, see
+//! [`tagged_leaf`]. Trees with a number of leaves that is not a power of two
+//! are left-balanced: the left subtree holds the largest power of two leaves.
+//!
+//! # Examples
+//!
+//! ```
+//! use kadena::crypto::{tagged_leaf, MerkleTree, TRANSACTION_TAG};
+//!
+//! let leaves: Vec<_> = [b"a", b"b", b"c"]
+//!     .iter()
+//!     .map(|tx| tagged_leaf(TRANSACTION_TAG, *tx))
+//!     .collect();
+//! let tree = MerkleTree::new(leaves.clone()).unwrap();
+//!
+//! let proof = tree.proof(2).unwrap();
+//! assert!(proof.verify(leaves[2], &tree.root()));
+//! assert!(!proof.verify(leaves[1], &tree.root()));
+//! ```
+
+use sha2::{Digest, Sha512_256};
+
+use crate::CryptoError;
+
+/// Size of a Merkle hash, in bytes
+pub const MERKLE_HASH_SIZE: usize = 32;
+
+/// A Merkle hash
+pub type MerkleHash = [u8; MERKLE_HASH_SIZE];
+
+/// Size of the step count and leaf position heading an encoded proof object
+const PROOF_HEADER_SIZE: usize = 12;
+
+/// Tag of transactions in block payloads
+pub const TRANSACTION_TAG: u16 = 0x0013;
+
+/// Tag of transaction outputs in block payloads
+pub const TRANSACTION_OUTPUT_TAG: u16 = 0x0014;
+
+/// Hash a leaf of a Merkle tree
+pub fn merkle_leaf(input: &[u8]) -> MerkleHash {
+    Sha512_256::new()
+        .chain_update([0x00])
+        .chain_update(input)
+        .finalize()
+        .into()
+}
+
+/// Hash a leaf holding a tagged chainweb value
+pub fn tagged_leaf(tag: u16, input: &[u8]) -> MerkleHash {
+    Sha512_256::new()
+        .chain_update([0x00])
+        .chain_update(tag.to_le_bytes())
+        .chain_update(input)
+        .finalize()
+        .into()
+}
+
+/// Hash an inner node of a Merkle tree from its children
+pub fn merkle_node(left: &MerkleHash, right: &MerkleHash) -> MerkleHash {
+    Sha512_256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Side of a sibling hash in a proof step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleSide {
+    /// The sibling is the left child
+    Left,
+    /// The sibling is the right child
+    Right,
+}
+
+/// Path from a leaf up to the root of a Merkle tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProofObject {
+    /// Position of the leaf in the tree
+    pub position: u64,
+    /// Sibling hashes from the leaf up to the root
+    pub steps: Vec<(MerkleSide, MerkleHash)>,
+}
+
+impl MerkleProofObject {
+    /// Decode a proof object from its binary encoding
+    pub fn decode(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let invalid = |reason: &str| CryptoError::InvalidProof(reason.to_string());
+        if bytes.len() < PROOF_HEADER_SIZE {
+            return Err(invalid("proof object is too short"));
+        }
+
+        let count = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        let position = u64::from_be_bytes(bytes[4..PROOF_HEADER_SIZE].try_into().unwrap());
+        let body = &bytes[PROOF_HEADER_SIZE..];
+        if body.len() != count * (1 + MERKLE_HASH_SIZE) {
+            return Err(invalid("proof object length does not match its step count"));
+        }
+
+        let steps = body
+            .chunks_exact(1 + MERKLE_HASH_SIZE)
+            .map(|step| {
+                let side = match step[0] {
+                    0x00 => MerkleSide::Left,
+                    0x01 => MerkleSide::Right,
+                    _ => return Err(invalid("invalid proof step side")),
+                };
+                Ok((side, step[1..].try_into().unwrap()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { position, steps })
+    }
+
+    /// Encode the proof object, the inverse of [`MerkleProofObject::decode`]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(PROOF_HEADER_SIZE + self.steps.len() * (1 + MERKLE_HASH_SIZE));
+        bytes.extend_from_slice(&(self.steps.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.position.to_be_bytes());
+        for (side, sibling) in &self.steps {
+            bytes.push(match side {
+                MerkleSide::Left => 0x00,
+                MerkleSide::Right => 0x01,
+            });
+            bytes.extend_from_slice(sibling);
+        }
+        bytes
+    }
+
+    /// Compute the root reached from the hash of the proven leaf
+    pub fn root(&self, leaf: MerkleHash) -> MerkleHash {
+        self.steps
+            .iter()
+            .fold(leaf, |acc, (side, sibling)| match side {
+                MerkleSide::Left => merkle_node(sibling, &acc),
+                MerkleSide::Right => merkle_node(&acc, sibling),
+            })
+    }
+
+    /// Returns true if the proof leads from `leaf` to `root`
+    pub fn verify(&self, leaf: MerkleHash, root: &MerkleHash) -> bool {
+        self.root(leaf) == *root
+    }
+}
+
+/// A Merkle tree over already hashed leaves
+///
+/// Leaves are built with [`merkle_leaf`] or [`tagged_leaf`], or are the roots
+/// of nested trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    leaves: Vec<MerkleHash>,
+}
+
+impl MerkleTree {
+    /// Build a tree over at least one leaf
+    pub fn new(leaves: Vec<MerkleHash>) -> Result<Self, CryptoError> {
+        if leaves.is_empty() {
+            return Err(CryptoError::InvalidFormat(
+                "a Merkle tree needs at least one leaf".to_string(),
+            ));
+        }
+        Ok(Self { leaves })
+    }
+
+    /// Get the leaves of the tree
+    pub fn leaves(&self) -> &[MerkleHash] {
+        &self.leaves
+    }
+
+    /// Compute the root of the tree
+    pub fn root(&self) -> MerkleHash {
+        subtree_root(&self.leaves)
+    }
+
+    /// Build the inclusion proof of the leaf at `index`
+    pub fn proof(&self, index: usize) -> Option<MerkleProofObject> {
+        (index < self.leaves.len()).then(|| MerkleProofObject {
+            position: index as u64,
+            steps: subtree_proof(&self.leaves, index),
+        })
+    }
+}
+
+/// Size of the left subtree of a tree with `len` leaves, `len` > 1
+fn split_point(len: usize) -> usize {
+    len.next_power_of_two() / 2
+}
+
+fn subtree_root(leaves: &[MerkleHash]) -> MerkleHash {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let (left, right) = leaves.split_at(split_point(leaves.len()));
+    merkle_node(&subtree_root(left), &subtree_root(right))
+}
+
+fn subtree_proof(leaves: &[MerkleHash], index: usize) -> Vec<(MerkleSide, MerkleHash)> {
+    if leaves.len() == 1 {
+        return Vec::new();
+    }
+    let split = split_point(leaves.len());
+    let (left, right) = leaves.split_at(split);
+    if index < split {
+        let mut steps = subtree_proof(left, index);
+        steps.push((MerkleSide::Right, subtree_root(right)));
+        steps
+    } else {
+        let mut steps = subtree_proof(right, index - split);
+        steps.push((MerkleSide::Left, subtree_root(left)));
+        steps
+    }
+}
@@ -9,6 +9,12 @@
 //! - Message signing and signature verification
 //! - Blake2b hashing
 //! - Hex and Base64URL encoding utilities
+//! - Password-encrypted mnemonic storage and SLIP-10 key derivation, as in `@kadena/hd-wallet`, and Chainweaver root keys (feature `keystore`)
+//! - Shamir secret sharing backups of secret keys (feature `shamir`)
+//! - A registry of the [`SignatureScheme`]s used to verify command signatures
+//! - Pluggable [`Signer`] backends, including PKCS#11 tokens (feature `hsm`)
+//! - Asynchronous signing with cloud KMS keys (features `kms` and `aws-kms`)
+//! - Chainweb Merkle log primitives for SPV proof verification (feature `merkle`)
 //!
 //! ## Examples
 //!
@@ -25,7 +31,7 @@
 //! let signature = keypair.sign(message).unwrap();
 //!
 //! // Verify the signature
-//! assert!(keypair.verify(message, &signature).unwrap());
+//! assert!(keypair.verify(message, &signature.to_hex()).unwrap());
 //! ```
 //!
 //! ### Working with Existing Keys
@@ -51,8 +57,34 @@
 
 pub mod crypto_error;
 pub mod encoding;
+#[cfg(feature = "hsm")]
+pub mod hsm;
 pub mod keypair;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "kms")]
+pub mod kms;
+#[cfg(feature = "merkle")]
+pub mod merkle;
+pub mod scheme;
+#[cfg(feature = "shamir")]
+pub mod shamir;
+pub mod signature;
+pub mod signer;
 
 pub use crypto_error::*;
 pub use encoding::*;
+#[cfg(feature = "hsm")]
+pub use hsm::*;
 pub use keypair::*;
+#[cfg(feature = "keystore")]
+pub use keystore::*;
+#[cfg(feature = "kms")]
+pub use kms::*;
+#[cfg(feature = "merkle")]
+pub use merkle::*;
+pub use scheme::*;
+#[cfg(feature = "shamir")]
+pub use shamir::*;
+pub use signature::*;
+pub use signer::*;
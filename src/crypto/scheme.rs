@@ -0,0 +1,144 @@
+//! Signature schemes accepted by chainweb
+//!
+//! Every signer of a command names its scheme, which tells how its
+//! signature is verified. [`SchemeRegistry`] maps scheme names to their
+//! [`SignatureScheme`] implementation. [`SchemeRegistry::builtin`] holds the
+//! schemes supported by this crate: `ED25519`, and further schemes behind
+//! their features as chainweb adds them.
+
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{verify_signature, CryptoError};
+
+/// Name of the ED25519 scheme, the default scheme of command signers
+pub const ED25519: &str = "ED25519";
+
+/// A signature algorithm, verifying signatures of command hashes
+pub trait SignatureScheme: Send + Sync {
+    /// Name of the scheme in command signers, e.g. `ED25519`
+    fn name(&self) -> &str;
+
+    /// Verify a signature, as found in a command, against a public key
+    fn verify(&self, msg: &[u8], signature: &str, public_key: &str) -> Result<bool, CryptoError>;
+}
+
+/// The ED25519 scheme, with hexadecimal keys and signatures
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ed25519Scheme;
+
+impl SignatureScheme for Ed25519Scheme {
+    fn name(&self) -> &str {
+        ED25519
+    }
+
+    fn verify(&self, msg: &[u8], signature: &str, public_key: &str) -> Result<bool, CryptoError> {
+        verify_signature(msg, signature, public_key)
+    }
+}
+
+/// Signature schemes by name
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::{CryptoError, PactKeypair, SchemeRegistry, SignatureScheme, ED25519};
+///
+/// let keypair = PactKeypair::generate();
+/// let signature = keypair.sign(b"msg").unwrap().to_hex();
+///
+/// let registry = SchemeRegistry::builtin();
+/// assert!(registry.verify(ED25519, b"msg", &signature, keypair.public_key()).unwrap());
+///
+/// // Schemes not registered are rejected rather than assumed valid
+/// let error = registry.verify("WebAuthn", b"msg", &signature, keypair.public_key()).unwrap_err();
+/// assert_eq!(error.error_code(), "crypto.unsupported_scheme");
+///
+/// // Applications can register schemes of their own
+/// struct AcceptAll;
+/// impl SignatureScheme for AcceptAll {
+///     fn name(&self) -> &str {
+///         "TEST"
+///     }
+///     fn verify(&self, _: &[u8], _: &str, _: &str) -> Result<bool, CryptoError> {
+///         Ok(true)
+///     }
+/// }
+/// let mut registry = SchemeRegistry::default();
+/// registry.register(AcceptAll);
+/// assert_eq!(registry.names(), ["ED25519", "TEST"]);
+/// ```
+#[derive(Clone)]
+pub struct SchemeRegistry {
+    schemes: Vec<Arc<dyn SignatureScheme>>,
+}
+
+impl SchemeRegistry {
+    /// Creates a registry without any scheme
+    pub fn empty() -> Self {
+        Self {
+            schemes: Vec::new(),
+        }
+    }
+
+    /// The schemes supported by this crate, with the enabled features
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<SchemeRegistry> = OnceLock::new();
+        BUILTIN.get_or_init(Self::default)
+    }
+
+    /// Registers a scheme, replacing any scheme of the same name
+    pub fn register(&mut self, scheme: impl SignatureScheme + 'static) -> &mut Self {
+        self.schemes.retain(|s| s.name() != scheme.name());
+        self.schemes.push(Arc::new(scheme));
+        self
+    }
+
+    /// Returns the scheme named `name`
+    pub fn get(&self, name: &str) -> Option<&dyn SignatureScheme> {
+        self.schemes
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.as_ref())
+    }
+
+    /// Returns the names of the registered schemes, in registration order
+    pub fn names(&self) -> Vec<&str> {
+        self.schemes.iter().map(|s| s.name()).collect()
+    }
+
+    /// Verify a signature with the scheme named `scheme`
+    ///
+    /// Fails with [`CryptoError::UnsupportedScheme`] if no such scheme is
+    /// registered.
+    pub fn verify(
+        &self,
+        scheme: &str,
+        msg: &[u8],
+        signature: &str,
+        public_key: &str,
+    ) -> Result<bool, CryptoError> {
+        self.get(scheme)
+            .ok_or_else(|| CryptoError::UnsupportedScheme(scheme.to_string()))?
+            .verify(msg, signature, public_key)
+    }
+}
+
+impl Default for SchemeRegistry {
+    /// The builtin schemes
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Ed25519Scheme);
+        registry
+    }
+}
+
+impl fmt::Debug for SchemeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemeRegistry")
+            .field("schemes", &self.names())
+            .finish()
+    }
+}
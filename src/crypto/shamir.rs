@@ -0,0 +1,157 @@
+//! Shamir secret sharing backups of secret keys
+//!
+//! A secret key is split into `n` shares, any `k` of which recover it, so a key
+//! can be backed up across several custodians without any of them holding it.
+//!
+//! Shares serialize to self-describing strings:
+//!
+//! ```text
+//! kadena-sss-v1:<threshold>:<public key>:<hex share>:<checksum>
+//! ```
+//!
+//! The checksum detects transcription errors in a single share, and the public
+//! key lets [`recover_secret`] check that the recovered key is the expected one.
+
+use std::{fmt, str::FromStr};
+
+use rand::rngs::OsRng;
+use sharks::{Share, Sharks};
+
+use crate::{encoding, hash, CryptoError, PactKeypair};
+
+const SHARE_PREFIX: &str = "kadena-sss-v1";
+const CHECKSUM_LEN: usize = 8;
+
+/// One share of a secret key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretShare {
+    /// Number of shares required to recover the key
+    pub threshold: u8,
+    /// Public key of the shared keypair
+    pub public_key: String,
+    /// Share index followed by the share bytes
+    pub data: Vec<u8>,
+}
+
+impl SecretShare {
+    fn body(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            SHARE_PREFIX,
+            self.threshold,
+            self.public_key,
+            encoding::bin_to_hex(&self.data)
+        )
+    }
+}
+
+impl fmt::Display for SecretShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let body = self.body();
+        write!(f, "{}:{}", body, checksum(&body))
+    }
+}
+
+impl FromStr for SecretShare {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (body, check) = s
+            .rsplit_once(':')
+            .ok_or_else(|| invalid_share("missing checksum"))?;
+        if checksum(body) != check {
+            return Err(invalid_share("checksum mismatch"));
+        }
+
+        let parts: Vec<&str> = body.split(':').collect();
+        let [prefix, threshold, public_key, data] = parts.as_slice() else {
+            return Err(invalid_share("unexpected number of fields"));
+        };
+        if *prefix != SHARE_PREFIX {
+            return Err(invalid_share("unsupported share version"));
+        }
+
+        Ok(Self {
+            threshold: threshold
+                .parse()
+                .map_err(|_| invalid_share("invalid threshold"))?,
+            public_key: public_key.to_string(),
+            data: encoding::hex_to_bin(data)?,
+        })
+    }
+}
+
+/// Split the secret key of `keypair` into `shares` shares, any `threshold` of which recover it
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::{recover_secret, split_secret, PactKeypair};
+///
+/// let keypair = PactKeypair::generate();
+/// let shares = split_secret(&keypair, 5, 3).unwrap();
+///
+/// let recovered = recover_secret(&shares[1..4]).unwrap();
+/// assert_eq!(recovered.public_key(), keypair.public_key());
+/// ```
+pub fn split_secret(
+    keypair: &PactKeypair,
+    shares: u8,
+    threshold: u8,
+) -> Result<Vec<SecretShare>, CryptoError> {
+    if threshold == 0 || threshold > shares {
+        return Err(invalid_share(
+            "threshold must be between 1 and the number of shares",
+        ));
+    }
+
+    let secret = encoding::hex_to_bin(keypair.secret_key())?;
+    Ok(Sharks(threshold)
+        .dealer_rng(&secret, &mut OsRng)
+        .take(shares as usize)
+        .map(|share| SecretShare {
+            threshold,
+            public_key: keypair.public_key().to_string(),
+            data: Vec::from(&share),
+        })
+        .collect())
+}
+
+/// Recover a keypair from shares produced by [`split_secret`]
+///
+/// Fails if the shares belong to different keys, if fewer than the threshold
+/// are provided, or if the recovered key does not match the recorded public key.
+pub fn recover_secret(shares: &[SecretShare]) -> Result<PactKeypair, CryptoError> {
+    let first = shares
+        .first()
+        .ok_or_else(|| invalid_share("no shares provided"))?;
+    if shares
+        .iter()
+        .any(|s| s.public_key != first.public_key || s.threshold != first.threshold)
+    {
+        return Err(invalid_share("shares belong to different keys"));
+    }
+
+    let shares: Vec<Share> = shares
+        .iter()
+        .map(|s| Share::try_from(s.data.as_slice()).map_err(invalid_share))
+        .collect::<Result<_, _>>()?;
+    let secret = Sharks(first.threshold)
+        .recover(&shares)
+        .map_err(invalid_share)?;
+
+    let keypair = PactKeypair::from_secret_key(&encoding::bin_to_hex(&secret))?;
+    if keypair.public_key() != first.public_key {
+        return Err(invalid_share("recovered key does not match the public key"));
+    }
+    Ok(keypair)
+}
+
+fn checksum(body: &str) -> String {
+    hash(body.as_bytes())[..CHECKSUM_LEN].to_string()
+}
+
+fn invalid_share(reason: &str) -> CryptoError {
+    CryptoError::InvalidShare(reason.to_string())
+}
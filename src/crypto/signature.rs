@@ -0,0 +1,125 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{encoding, CryptoError};
+
+/// An ED25519 signature
+///
+/// Returned by [`PactKeypair::sign`](crate::PactKeypair::sign) and the
+/// [`Signer`](crate::Signer) backends. Displays and serializes as the
+/// lowercase hexadecimal string Pact expects in commands.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::{PactKeypair, Signature};
+///
+/// let keypair = PactKeypair::generate();
+/// let signature = keypair.sign(b"Hello, world!").unwrap();
+///
+/// assert_eq!(signature.to_hex().len(), 128);
+/// assert_eq!(signature.to_base64url().len(), 86);
+/// assert!(keypair.verify_bytes(b"Hello, world!", signature.as_bytes()).unwrap());
+///
+/// let parsed: Signature = signature.to_string().parse().unwrap();
+/// assert_eq!(parsed, signature);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; 64]);
+
+impl Signature {
+    /// Create a signature from its raw bytes
+    pub fn from_bytes(bytes: [u8; 64]) -> Self {
+        Self(bytes)
+    }
+
+    /// Parse a signature from its hexadecimal representation
+    pub fn from_hex(hex: &str) -> Result<Self, CryptoError> {
+        Self::try_from(encoding::hex_to_bin(hex)?.as_slice())
+    }
+
+    /// Get the raw bytes of the signature
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+
+    /// Get the raw bytes of the signature
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0
+    }
+
+    /// Get the hexadecimal representation of the signature
+    pub fn to_hex(&self) -> String {
+        encoding::bin_to_hex(&self.0)
+    }
+
+    /// Get the Base64 URL-safe representation of the signature, without padding
+    pub fn to_base64url(&self) -> String {
+        encoding::base64url_encode(&self.0)
+    }
+
+    /// Get the hexadecimal representation of the signature
+    ///
+    /// Signing used to return this string directly.
+    #[deprecated(note = "use `to_hex` or `Display` instead")]
+    pub fn as_string(&self) -> String {
+        self.to_hex()
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = CryptoError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        bytes.try_into().map(Self).map_err(|_| {
+            CryptoError::InvalidFormat(format!("expected a 64 byte signature, got {}", bytes.len()))
+        })
+    }
+}
+
+impl From<[u8; 64]> for Signature {
+    fn from(bytes: [u8; 64]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Signature> for String {
+    fn from(signature: Signature) -> Self {
+        signature.to_hex()
+    }
+}
+
+impl FromStr for Signature {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature({})", self.to_hex())
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Self::from_hex(&hex).map_err(serde::de::Error::custom)
+    }
+}
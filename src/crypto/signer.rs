@@ -0,0 +1,61 @@
+use std::future::Future;
+
+use crate::{CryptoError, PactKeypair, Signature, ED25519};
+
+/// A source of signatures for a single public key
+///
+/// Implementations may keep the secret key outside of process memory, for
+/// instance in a hardware security module, and only expose signing.
+pub trait Signer {
+    /// Get the public key as a hexadecimal string
+    fn public_key(&self) -> &str;
+
+    /// Name of the [`SignatureScheme`](crate::SignatureScheme) of the signatures
+    fn scheme(&self) -> &str {
+        ED25519
+    }
+
+    /// Sign a message
+    fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError>;
+}
+
+impl Signer for PactKeypair {
+    fn public_key(&self) -> &str {
+        PactKeypair::public_key(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, CryptoError> {
+        PactKeypair::sign(self, msg)
+    }
+}
+
+/// A [`Signer`] whose signatures are produced asynchronously
+///
+/// Implemented by remote backends such as cloud KMS services. Every [`Signer`]
+/// is also an `AsyncSigner`, so local keys can be mixed with remote ones.
+pub trait AsyncSigner {
+    /// Get the public key as a hexadecimal string
+    fn public_key(&self) -> &str;
+
+    /// Name of the [`SignatureScheme`](crate::SignatureScheme) of the signatures
+    fn scheme(&self) -> &str {
+        ED25519
+    }
+
+    /// Sign a message, resolving to the signature
+    fn sign(&self, msg: &[u8]) -> impl Future<Output = Result<Signature, CryptoError>> + Send;
+}
+
+impl<T: Signer + Sync> AsyncSigner for T {
+    fn public_key(&self) -> &str {
+        Signer::public_key(self)
+    }
+
+    fn scheme(&self) -> &str {
+        Signer::scheme(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> impl Future<Output = Result<Signature, CryptoError>> + Send {
+        std::future::ready(Signer::sign(self, msg))
+    }
+}
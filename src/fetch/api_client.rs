@@ -3,19 +3,108 @@
 //! This module provides functionality for sending commands to Kadena nodes,
 //! both for local execution and blockchain submission.
 
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
-use crate::{pact::command::Cmd, ApiConfig, FetchError};
-use log::{debug, error};
+use crate::{
+    pact::{command::Cmd, CommandResult, GasReport, PreflightResult, RequestKey},
+    ApiConfig, FetchError, HttpMethod, HttpRequest, HttpResponse, ReqwestTransport, Transport,
+};
+use log::{debug, log_enabled, trace, warn, Level};
 use reqwest::Client;
 use serde::Serialize;
 use serde_json::{json, Value};
 
+/// Options of a `local` request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalOptions {
+    /// Run the command as if it were included in the next block, buying gas
+    pub preflight: bool,
+    /// Verify the command signatures
+    pub signature_verification: bool,
+    /// Ask the node for the gas charged by each operation
+    ///
+    /// Nodes that do not support gas logging ignore this option.
+    pub gas_logs: bool,
+}
+
+impl LocalOptions {
+    /// Options for a preflight run with signature verification
+    pub fn preflight() -> Self {
+        Self {
+            preflight: true,
+            signature_verification: true,
+            gas_logs: false,
+        }
+    }
+
+    /// Enable or disable signature verification
+    pub fn with_signature_verification(mut self, enabled: bool) -> Self {
+        self.signature_verification = enabled;
+        self
+    }
+
+    /// Enable or disable gas logging
+    pub fn with_gas_logs(mut self, enabled: bool) -> Self {
+        self.gas_logs = enabled;
+        self
+    }
+
+    fn query(&self) -> String {
+        let mut query = format!(
+            "preflight={}&signatureVerification={}",
+            self.preflight, self.signature_verification
+        );
+        if self.gas_logs {
+            query.push_str("&gasLogs=true");
+        }
+        query
+    }
+}
+
+/// Counters of the requests sent by a client and its clones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// Requests sent
+    pub requests: u64,
+    /// Requests that failed in transport or with an error status
+    pub failures: u64,
+}
+
 /// API client for interacting with Kadena nodes
-#[derive(Debug)]
+///
+/// Clones are cheap handles sharing the configuration, the transport with its
+/// connection pool, and the request statistics, so a web service can keep one
+/// client in its state and hand out a clone per request.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{ApiClient, ApiConfig};
+///
+/// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+/// let handle = client.clone();
+/// assert_eq!(handle.stats(), client.stats());
+/// ```
+#[derive(Debug, Clone)]
 pub struct ApiClient {
+    inner: Arc<ClientInner>,
+}
+
+#[derive(Debug)]
+struct ClientInner {
     config: ApiConfig,
-    client: Client,
+    transport: Arc<dyn Transport>,
+    requests: AtomicU64,
+    failures: AtomicU64,
+    /// Results of the commands sent in simulation mode, by request key
+    simulated: Mutex<HashMap<String, CommandResult>>,
 }
 
 impl ApiClient {
@@ -32,13 +121,45 @@ impl ApiClient {
     /// let client = ApiClient::new(config);
     /// ```
     pub fn new(config: ApiConfig) -> Self {
-        let client = Client::builder()
+        let mut builder = Client::builder()
             .timeout(Duration::from_secs(config.timeout))
-            .build()
-            .expect("Failed to create HTTP client");
+            .http2_adaptive_window(config.http2_adaptive_window)
+            .tcp_keepalive(config.tcp_keepalive);
+        if config.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        let client = builder.build().expect("Failed to create HTTP client");
 
-        Self { config, client }
+        Self::with_http_client(config, client)
     }
+
+    /// Create an API client sending its requests through a custom transport
+    ///
+    /// The timeout of the configuration is not applied; the transport
+    /// enforces its own.
+    pub fn with_transport(config: ApiConfig, transport: impl Transport + 'static) -> Self {
+        Self::with_shared_transport(config, Arc::new(transport))
+    }
+
+    /// Create an API client sharing a transport with other clients
+    pub(crate) fn with_shared_transport(config: ApiConfig, transport: Arc<dyn Transport>) -> Self {
+        Self {
+            inner: Arc::new(ClientInner {
+                config,
+                transport,
+                requests: AtomicU64::new(0),
+                failures: AtomicU64::new(0),
+                simulated: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Create an API client that reuses an existing HTTP client
+    pub(crate) fn with_http_client(config: ApiConfig, client: Client) -> Self {
+        let transport = ReqwestTransport::new(client).with_max_body_size(config.max_body_size);
+        Self::with_transport(config, transport)
+    }
+
     fn create_payload(&self, cmd: &Cmd) -> Value {
         json!({
             "sigs": cmd.sigs.iter().map(|sig| json!({ "sig": sig.sig })).collect::<Vec<Value>>(),
@@ -67,20 +188,102 @@ impl ApiClient {
     /// # }
     /// ```
     pub async fn local(&self, cmd: &Cmd) -> Result<Value, FetchError> {
-        let url = format!("{}/api/v1/local", self.config.host);
+        let url = format!("{}/api/v1/local", self.config().pact_url()?);
         let payload = self.create_payload(cmd);
+        self.log_request("local", &url, cmd, &payload)?;
+
+        self.execute_request(&url, &payload).await
+    }
 
-        debug!(
-            "Sending local request to {}: {}",
-            url,
-            serde_json::to_string_pretty(&payload)?
+    /// Execute a command locally with explicit options
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - The command to execute
+    /// * `options` - Preflight and signature verification options
+    pub async fn local_with_options(
+        &self,
+        cmd: &Cmd,
+        options: &LocalOptions,
+    ) -> Result<Value, FetchError> {
+        let url = format!(
+            "{}/api/v1/local?{}",
+            self.config().pact_url()?,
+            options.query()
         );
+        let payload = self.create_payload(cmd);
+        self.log_request("local", &url, cmd, &payload)?;
 
         self.execute_request(&url, &payload).await
     }
 
+    /// Run a command with preflight checks and parse its result
+    ///
+    /// The result includes the gas consumed and, when the node reports them,
+    /// the per-operation gas logs.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let cmd = /* ... */;
+    /// let report = client.preflight(&cmd).await?.gas_report();
+    /// println!("Suggested gas limit: {}", report.suggested_gas_limit(10));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn preflight(&self, cmd: &Cmd) -> Result<PreflightResult, FetchError> {
+        let response = self
+            .local_with_options(cmd, &LocalOptions::preflight())
+            .await?;
+        Ok(PreflightResult::from_value(response)?)
+    }
+
+    /// Execute a command locally with gas logging and report its gas consumption
+    ///
+    /// `options` select preflight and signature verification; gas logging is
+    /// always requested. When the node does not report gas logs, the report
+    /// only holds the total, see [`GasReport::has_breakdown`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig, LocalOptions};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("http://localhost:8080", "development", "0"));
+    /// let cmd = /* ... */;
+    /// let report = client.local_gas_report(&cmd, LocalOptions::default()).await?;
+    /// for (operation, gas) in report.by_operation().iter().take(5) {
+    ///     println!("{operation}: {gas}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn local_gas_report(
+        &self,
+        cmd: &Cmd,
+        options: LocalOptions,
+    ) -> Result<GasReport, FetchError> {
+        let response = self
+            .local_with_options(cmd, &options.with_gas_logs(true))
+            .await?;
+        Ok(PreflightResult::from_value(response)?.gas_report())
+    }
+
     /// Send a command to the blockchain
     ///
+    /// Commands exceeding the fee policy of the configuration, if any, are
+    /// rejected before anything is sent.
+    ///
+    /// In simulation mode, see [`ApiConfig::with_simulation`], the command is
+    /// run with preflight checks instead and the response lists its request
+    /// key as if it had been sent. A command failing preflight is reported
+    /// as failed by `poll` and `listen`, like a command failing on chain.
+    ///
     /// # Arguments
     ///
     /// * `cmd` - The command to send
@@ -99,44 +302,237 @@ impl ApiClient {
     /// # }
     /// ```
     pub async fn send(&self, cmd: &Cmd) -> Result<Value, FetchError> {
-        let url = format!("{}/api/v1/send", self.config.host);
+        if let Some(policy) = &self.config().fee_policy {
+            policy.check_cmd(cmd)?;
+        }
+        if self.config().simulate {
+            return self.simulate_send(cmd).await;
+        }
+        let url = format!("{}/api/v1/send", self.config().pact_url()?);
         let payload = json!({
             "cmds": [self.create_payload(cmd)]
         });
 
-        debug!(
-            "Sending transaction to {}: {}",
-            url,
-            serde_json::to_string_pretty(&payload)?
-        );
+        self.log_request("send", &url, cmd, &payload)?;
 
         self.execute_request(&url, &payload).await
     }
 
-    async fn execute_request(
+    /// Poll the results of commands sent to the chain
+    ///
+    /// Request keys may be given in base64url or hex form. Only commands that
+    /// have been included in a block are part of the returned map, keyed by
+    /// base64url request key.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let results = client.poll(&["request-key"]).await?;
+    /// if let Some(result) = results.get("request-key") {
+    ///     println!("Included at height {:?}", result.block_height());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn poll(
+        &self,
+        request_keys: &[&str],
+    ) -> Result<HashMap<String, CommandResult>, FetchError> {
+        let url = format!("{}/api/v1/poll", self.config().pact_url()?);
+        let mut request_keys = request_keys
+            .iter()
+            .map(|key| RequestKey::parse(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut results = HashMap::new();
+        if self.config().simulate {
+            request_keys.retain(|key| match self.simulated(key.as_ref()) {
+                Some(result) => {
+                    results.insert(key.to_string(), result);
+                    false
+                }
+                None => true,
+            });
+            if request_keys.is_empty() {
+                return Ok(results);
+            }
+        }
+        let payload = json!({ "requestKeys": request_keys });
+
+        let response = self.execute_request(&url, &payload).await?;
+        let mined: HashMap<String, CommandResult> = serde_json::from_value(response)?;
+        results.extend(mined);
+        Ok(results)
+    }
+
+    /// Look up the result of a command, if it was mined on the client's chain
+    ///
+    /// Returns `None` for commands never sent, still pending or expired. With
+    /// a [`derive_nonce`](crate::pact::derive_nonce) nonce, checking before
+    /// resending a payout makes it idempotent.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.chainweb.com", "mainnet01", "0"));
+    /// let cmd = /* the payout, rebuilt from stored values */;
+    /// if client.find_mined(&cmd.hash).await?.is_none() {
+    ///     client.send(&cmd).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn find_mined(&self, request_key: &str) -> Result<Option<CommandResult>, FetchError> {
+        let key = RequestKey::parse(request_key)?.to_string();
+        Ok(self.poll(&[&key]).await?.remove(&key))
+    }
+
+    /// Wait for the result of a command sent to the chain
+    ///
+    /// The node holds the request until the command is included in a block or
+    /// the request times out.
+    pub async fn listen(&self, request_key: &str) -> Result<CommandResult, FetchError> {
+        let url = format!("{}/api/v1/listen", self.config().pact_url()?);
+        let request_key = RequestKey::parse(request_key)?;
+        if let Some(result) = self.simulated(request_key.as_ref()) {
+            return Ok(result);
+        }
+        let payload = json!({ "listen": request_key });
+
+        let response = self.execute_request(&url, &payload).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Get the configuration of the client
+    pub fn config(&self) -> &ApiConfig {
+        &self.inner.config
+    }
+
+    /// Get the request counters, shared by all clones of the client
+    pub fn stats(&self) -> ClientStats {
+        ClientStats {
+            requests: self.inner.requests.load(Ordering::Relaxed),
+            failures: self.inner.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Run a command with preflight checks in place of sending it
+    async fn simulate_send(&self, cmd: &Cmd) -> Result<Value, FetchError> {
+        let preflight = self.preflight(cmd).await?;
+        for warning in &preflight.warnings {
+            warn!("Preflight warning for {}: {}", cmd.hash, warning);
+        }
+        debug!(hash = cmd.hash.as_str(); "Simulated send request");
+        self.inner
+            .simulated
+            .lock()
+            .unwrap()
+            .insert(cmd.hash.clone(), preflight.result);
+        Ok(json!({ "requestKeys": [cmd.hash] }))
+    }
+
+    fn simulated(&self, request_key: &str) -> Option<CommandResult> {
+        self.inner
+            .simulated
+            .lock()
+            .unwrap()
+            .get(request_key)
+            .cloned()
+    }
+
+    pub(crate) async fn execute_request(
         &self,
         url: &str,
         payload: &impl Serialize,
     ) -> Result<Value, FetchError> {
-        let mut request = self.client.post(url).json(payload);
-
-        if let Some(api_key) = &self.config.api_key {
-            request = request.header("X-API-Key", api_key);
+        let response = self.post(url, payload).await?;
+        let json_response = response.json_body()?;
+        debug!(url = url, status = response.status; "Received response");
+        if log_enabled!(Level::Trace) {
+            trace!(
+                "Response from {}: {}",
+                url,
+                serde_json::to_string_pretty(&json_response)?
+            );
         }
+        Ok(json_response)
+    }
 
-        let response = request.send().await?;
+    /// Post a JSON payload, counting the request in the client statistics
+    pub(crate) async fn post(
+        &self,
+        url: &str,
+        payload: &impl Serialize,
+    ) -> Result<HttpResponse, FetchError> {
+        let mut headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+        headers.extend(self.config().auth_headers());
+        self.execute(HttpRequest {
+            method: HttpMethod::Post,
+            url: url.to_string(),
+            headers,
+            body: serde_json::to_vec(payload)?,
+        })
+        .await
+    }
 
-        if response.status().is_success() {
-            let json_response = response.json().await?;
-            debug!(
-                "Received response: {}",
-                serde_json::to_string_pretty(&json_response)?
+    /// Get a JSON document, counting the request in the client statistics
+    pub(crate) async fn get_json(&self, url: &str, accept: &str) -> Result<Value, FetchError> {
+        self.get(url, accept).await?.json_body()
+    }
+
+    /// Get a document in any encoding, counting the request in the client statistics
+    pub(crate) async fn get(&self, url: &str, accept: &str) -> Result<HttpResponse, FetchError> {
+        let mut headers = vec![("Accept".to_string(), accept.to_string())];
+        headers.extend(self.config().auth_headers());
+        let response = self
+            .execute(HttpRequest {
+                method: HttpMethod::Get,
+                url: url.to_string(),
+                headers,
+                body: Vec::new(),
+            })
+            .await?;
+        debug!(url = url, status = response.status; "Received response");
+        Ok(response)
+    }
+
+    async fn execute(&self, request: HttpRequest) -> Result<HttpResponse, FetchError> {
+        self.inner.requests.fetch_add(1, Ordering::Relaxed);
+        let limit = self.config().max_body_size;
+        let response = match self.inner.transport.execute(request).await {
+            Ok(response) if response.body.len() > limit => Err(FetchError::BodyTooLarge { limit }),
+            Ok(response) if response.is_success() => Ok(response),
+            result => Err(result.map_or_else(|e| e, |response| response.error())),
+        };
+        if response.is_err() {
+            self.inner.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        response
+    }
+
+    /// Log a command request, with its payload at the `trace` level
+    fn log_request(
+        &self,
+        endpoint: &str,
+        url: &str,
+        cmd: &Cmd,
+        payload: &Value,
+    ) -> Result<(), FetchError> {
+        debug!(endpoint = endpoint, url = url, hash = cmd.hash.as_str(); "Sending {} request", endpoint);
+        if log_enabled!(Level::Trace) {
+            trace!(
+                "Payload of {} request to {}: {}",
+                endpoint,
+                url,
+                serde_json::to_string_pretty(&self.config().payload_logging.apply(payload))?
             );
-            Ok(json_response)
-        } else {
-            let error_text = response.text().await?;
-            error!("API error: {}", error_text);
-            Err(FetchError::ApiError(error_text))
         }
+        Ok(())
     }
 }
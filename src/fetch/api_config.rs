@@ -1,12 +1,147 @@
+use std::{fmt, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use crate::{pact::FeePolicy, FetchError, PayloadLogging, DEFAULT_MAX_BODY_SIZE, REDACTED};
+
+/// Credentials of an authenticated gateway
+///
+/// Secrets are redacted from the `Debug` output, so configurations can be
+/// logged safely.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// HTTP basic authentication
+    Basic {
+        /// The user name
+        username: String,
+        /// The password, if any
+        password: Option<String>,
+    },
+    /// A bearer token, sent in the `Authorization` header
+    Bearer(String),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Basic { username, password } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &password.as_ref().map(|_| REDACTED))
+                .finish(),
+            Auth::Bearer(_) => f.debug_tuple("Bearer").field(&REDACTED).finish(),
+        }
+    }
+}
+
+/// Kind of Chainweb API a host exposes
+///
+/// Chainweb nodes serve two APIs on different ports. The service API (usually
+/// port 1848) serves the Pact API and the stream of header updates, while the
+/// P2P API (usually port 443 or 1789) serves the routes nodes use to talk to
+/// each other, the mempool and peer lists included. Both serve the cut, headers, payloads and
+/// node information. Public gateways such as `api.chainweb.com` expose both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EndpointKind {
+    /// A gateway exposing both APIs
+    #[default]
+    Gateway,
+    /// The service API of a node
+    Service,
+    /// The P2P API of a node
+    P2p,
+    /// A standalone Pact server (`pact -s`), serving the Pact API at its root
+    PactServer,
+}
+
+/// Routes of the Chainweb APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiRoute {
+    /// The Pact API of a chain
+    Pact,
+    /// The current cut of the network
+    Cut,
+    /// The peers of a node
+    Peer,
+    /// Block headers of a chain
+    Header,
+    /// The stream of new block headers of all chains
+    HeaderUpdates,
+    /// The mempool of a chain
+    Mempool,
+    /// Block payloads of a chain
+    Payload,
+    /// Information about the node and its network
+    Info,
+}
+
+impl EndpointKind {
+    /// Returns true if endpoints of this kind serve `route`
+    pub fn serves(&self, route: ApiRoute) -> bool {
+        match self {
+            EndpointKind::Gateway => true,
+            EndpointKind::Service => !matches!(route, ApiRoute::Mempool | ApiRoute::Peer),
+            EndpointKind::P2p => !matches!(route, ApiRoute::Pact | ApiRoute::HeaderUpdates),
+            EndpointKind::PactServer => route == ApiRoute::Pact,
+        }
+    }
+}
+
 /// Configuration for API client
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ApiConfig {
     /// Base URL for the API
     pub host: String,
+    /// Base URL of the node
+    pub base_url: String,
+    /// Kadena network
+    pub network: String,
+    /// Kadena chain id
+    pub chain_id: String,
+    /// Kind of API exposed by the node
+    pub endpoint: EndpointKind,
     /// Timeout for requests in seconds
     pub timeout: u64,
-    /// Optional API key
+    /// Maximum size of response bodies in bytes
+    pub max_body_size: usize,
+    /// Optional API key, sent in the `X-API-Key` header
     pub api_key: Option<String>,
+    /// Optional credentials, sent in the `Authorization` header
+    pub auth: Option<Auth>,
+    /// Speak HTTP/2 without negotiating it first, for nodes known to support it
+    pub http2_prior_knowledge: bool,
+    /// Size HTTP/2 flow control windows from the measured bandwidth and latency
+    pub http2_adaptive_window: bool,
+    /// Interval of TCP keep-alive probes on idle connections, if enabled
+    pub tcp_keepalive: Option<Duration>,
+    /// How request payloads are logged at the `trace` level
+    pub payload_logging: PayloadLogging,
+    /// Budget the gas fees of sent commands are checked against, if any
+    pub fee_policy: Option<FeePolicy>,
+    /// Run sent commands as preflight `local` requests instead of sending them
+    pub simulate: bool,
+}
+
+impl fmt::Debug for ApiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiConfig")
+            .field("host", &self.host)
+            .field("base_url", &self.base_url)
+            .field("network", &self.network)
+            .field("chain_id", &self.chain_id)
+            .field("endpoint", &self.endpoint)
+            .field("timeout", &self.timeout)
+            .field("max_body_size", &self.max_body_size)
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .field("auth", &self.auth)
+            .field("http2_prior_knowledge", &self.http2_prior_knowledge)
+            .field("http2_adaptive_window", &self.http2_adaptive_window)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field("payload_logging", &self.payload_logging)
+            .field("fee_policy", &self.fee_policy)
+            .field("simulate", &self.simulate)
+            .finish()
+    }
 }
 
 impl ApiConfig {
@@ -31,9 +166,68 @@ impl ApiConfig {
                 "{}/chainweb/0.0/{}/chain/{}/pact",
                 base_url, network, chain_id
             ),
+            base_url: base_url.to_string(),
+            network: network.to_string(),
+            chain_id: chain_id.to_string(),
+            endpoint: EndpointKind::default(),
             timeout: 30,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
             api_key: None,
+            auth: None,
+            http2_prior_knowledge: false,
+            http2_adaptive_window: false,
+            tcp_keepalive: None,
+            payload_logging: PayloadLogging::default(),
+            fee_policy: None,
+            simulate: false,
+        }
+    }
+
+    /// Create a configuration for a standalone Pact server (`pact -s`)
+    ///
+    /// The Pact server serves the Pact API at its root, without the chainweb
+    /// prefix, and knows of no network or chain: commands are built without a
+    /// network id, and routes other than the Pact API are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{ApiConfig, EndpointKind};
+    ///
+    /// let config = ApiConfig::pact_server("http://localhost:8080");
+    /// assert_eq!(config.endpoint, EndpointKind::PactServer);
+    /// assert_eq!(config.pact_url().unwrap(), "http://localhost:8080");
+    /// assert_eq!(config.network_id(), None);
+    /// assert!(config.cut_url().is_err());
+    /// ```
+    pub fn pact_server(base_url: &str) -> Self {
+        Self::new(base_url.trim_end_matches('/'), "", "0").with_endpoint(EndpointKind::PactServer)
+    }
+
+    /// Set the kind of API exposed by the node
+    ///
+    /// [`EndpointKind::PactServer`] also moves the Pact API to the root of the
+    /// base URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{ApiConfig, EndpointKind};
+    ///
+    /// let config = ApiConfig::new("https://node.example.com:1789", "mainnet01", "0")
+    ///     .with_endpoint(EndpointKind::P2p);
+    /// assert!(config.pact_url().is_err());
+    /// assert_eq!(
+    ///     config.header_url().unwrap(),
+    ///     "https://node.example.com:1789/chainweb/0.0/mainnet01/chain/0/header"
+    /// );
+    /// ```
+    pub fn with_endpoint(mut self, endpoint: EndpointKind) -> Self {
+        if endpoint == EndpointKind::PactServer {
+            self.host = self.base_url.clone();
         }
+        self.endpoint = endpoint;
+        self
     }
 
     /// Set a custom timeout
@@ -42,9 +236,221 @@ impl ApiConfig {
         self
     }
 
+    /// Set the maximum size of response bodies, such as block payloads
+    ///
+    /// Larger bodies fail with [`FetchError::BodyTooLarge`]. The default
+    /// transport stops reading once the limit is exceeded; the bodies of
+    /// custom transports are checked once received.
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
     /// Set an API key
     pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
         self.api_key = Some(api_key.into());
         self
     }
+
+    /// Speak HTTP/2 from the first request, skipping the protocol negotiation
+    ///
+    /// Only use with nodes or gateways known to support HTTP/2, including
+    /// cleartext HTTP/2 for `http://` URLs.
+    pub fn with_http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Enable or disable adaptive HTTP/2 flow control windows
+    pub fn with_http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = enabled;
+        self
+    }
+
+    /// Send TCP keep-alive probes on idle connections
+    ///
+    /// Keeps pooled connections to the node alive between the requests of
+    /// pollers, saving a TCP and TLS handshake per request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let config = ApiConfig::new("https://api.chainweb.com", "mainnet01", "0")
+    ///     .with_tcp_keepalive(Duration::from_secs(30))
+    ///     .with_http2_adaptive_window(true);
+    /// let client = ApiClient::new(config);
+    /// ```
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Log request payloads verbatim, without redacting signatures and env data
+    ///
+    /// Only meant for troubleshooting: logs at the `trace` level will then
+    /// contain any secrets passed in environment data.
+    pub fn with_unsafe_payload_logging(mut self) -> Self {
+        self.payload_logging = PayloadLogging::Unsafe;
+        self
+    }
+
+    /// Check the gas fees of commands against `policy` before sending them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::ApiConfig;
+    /// use kadena::pact::FeePolicy;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let config = ApiConfig::new("https://api.chainweb.com", "mainnet01", "0")
+    ///     .with_fee_policy(FeePolicy::new().with_max_fee(Decimal::new(1, 2)));
+    /// ```
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = Some(policy);
+        self
+    }
+
+    /// Simulate sending commands, without spending gas
+    ///
+    /// [`ApiClient::send`](crate::ApiClient::send) then runs commands with
+    /// preflight checks and keeps their results, which `poll` and `listen`
+    /// report as if the commands were mined. Business flows, trackers
+    /// included, can be exercised against a real node this way.
+    pub fn with_simulation(mut self) -> Self {
+        self.simulate = true;
+        self
+    }
+
+    /// Authenticate with HTTP basic authentication
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::ApiConfig;
+    ///
+    /// let config = ApiConfig::new("https://node.example.com", "mainnet01", "0")
+    ///     .with_basic_auth("ops", Some("hunter2"));
+    /// assert!(!format!("{:?}", config).contains("hunter2"));
+    /// ```
+    pub fn with_basic_auth(mut self, username: &str, password: Option<&str>) -> Self {
+        self.auth = Some(Auth::Basic {
+            username: username.to_string(),
+            password: password.map(ToString::to_string),
+        });
+        self
+    }
+
+    /// Authenticate with a bearer token
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Bearer(token.into()));
+        self
+    }
+
+    /// The headers carrying the API key and credentials
+    pub(crate) fn auth_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(api_key) = &self.api_key {
+            headers.push(("X-API-Key".to_string(), api_key.clone()));
+        }
+        match &self.auth {
+            Some(Auth::Basic { username, password }) => {
+                let credentials = format!("{}:{}", username, password.as_deref().unwrap_or(""));
+                let value = format!("Basic {}", STANDARD.encode(credentials));
+                headers.push(("Authorization".to_string(), value));
+            }
+            Some(Auth::Bearer(token)) => {
+                headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+            }
+            None => {}
+        }
+        headers
+    }
+
+    /// Get the network id of commands sent to the node
+    ///
+    /// `None` for a Pact server, which has no network.
+    pub fn network_id(&self) -> Option<String> {
+        (self.endpoint != EndpointKind::PactServer).then(|| self.network.clone())
+    }
+
+    /// Get the base URL of the chain's Pact API
+    pub fn pact_url(&self) -> Result<String, FetchError> {
+        self.check(ApiRoute::Pact)?;
+        Ok(self.host.clone())
+    }
+
+    /// Get the URL of the network's current cut
+    pub fn cut_url(&self) -> Result<String, FetchError> {
+        self.check(ApiRoute::Cut)?;
+        Ok(format!("{}/cut", self.network_url()))
+    }
+
+    /// Get the URL listing up to `limit` peers of the node
+    pub fn peer_url(&self, limit: usize) -> Result<String, FetchError> {
+        self.check(ApiRoute::Peer)?;
+        Ok(format!("{}/cut/peer?limit={}", self.network_url(), limit))
+    }
+
+    /// Get the URL of the stream of new block headers of all chains
+    pub fn header_updates_url(&self) -> Result<String, FetchError> {
+        self.check(ApiRoute::HeaderUpdates)?;
+        Ok(format!("{}/header/updates", self.network_url()))
+    }
+
+    /// Get the URL of the node information, including the chain graph history
+    pub fn info_url(&self) -> Result<String, FetchError> {
+        self.check(ApiRoute::Info)?;
+        Ok(format!("{}/info", self.base_url))
+    }
+
+    /// Get the URL of the chain's block headers
+    pub fn header_url(&self) -> Result<String, FetchError> {
+        self.check(ApiRoute::Header)?;
+        Ok(format!(
+            "{}/chain/{}/header",
+            self.network_url(),
+            self.chain_id
+        ))
+    }
+
+    /// Get the URL of a block payload with its transaction outputs
+    pub fn payload_outputs_url(&self, payload_hash: &str) -> Result<String, FetchError> {
+        self.check(ApiRoute::Payload)?;
+        Ok(format!(
+            "{}/chain/{}/payload/{}/outputs",
+            self.network_url(),
+            self.chain_id,
+            payload_hash
+        ))
+    }
+
+    /// Get the URL of a mempool operation, e.g. `getPending`
+    pub fn mempool_url(&self, operation: &str) -> Result<String, FetchError> {
+        self.check(ApiRoute::Mempool)?;
+        Ok(format!(
+            "{}/chain/{}/mempool/{}",
+            self.network_url(),
+            self.chain_id,
+            operation
+        ))
+    }
+
+    fn network_url(&self) -> String {
+        format!("{}/chainweb/0.0/{}", self.base_url, self.network)
+    }
+
+    fn check(&self, route: ApiRoute) -> Result<(), FetchError> {
+        if self.endpoint.serves(route) {
+            Ok(())
+        } else {
+            Err(FetchError::UnsupportedRoute {
+                route,
+                endpoint: self.endpoint,
+            })
+        }
+    }
 }
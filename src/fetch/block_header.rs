@@ -0,0 +1,409 @@
+//! Chainweb block headers and their light validation
+//!
+//! Nodes serve headers either as base64url encoded binary headers or, when
+//! requested with `blockheader-encoding=object`, as JSON objects. A single
+//! header is also served as raw bytes to `Accept: application/octet-stream`.
+//! All decode into [`BlockHeader`], which can check the proof of work of a
+//! header and its links to its parent and to the headers of adjacent chains.
+//!
+//! The binary encoding is 318 bytes long for headers with three adjacent
+//! chains, all integers little-endian:
+//!
+//! ```text
+//! feature flags (u64) | creation time (u64) | parent hash (32)
+//! | adjacent count (u16) | (chain id (u32) | hash (32)) per adjacent
+//! | target (32) | payload hash (32) | chain id (u32) | weight (32)
+//! | height (u64) | chainweb version (u32) | epoch start (u64) | nonce (u64)
+//! | hash (32)
+//! ```
+//!
+//! The proof of work hash is the Blake2s-256 hash of every field but the
+//! block hash, read as a little-endian 256 bit integer that must not exceed
+//! the target. The block hash is not covered by the proof of work: it is the
+//! root of a chainweb Merkle tree whose leaves are the tagged fields in the
+//! order of the encoding, then the adjacent hashes by chain id. A header is
+//! only valid if its `hash` is that root.
+
+use std::collections::BTreeMap;
+
+use blake2::{Blake2s256, Digest};
+use serde_json::Value;
+
+use crate::{base64url_decode, base64url_encode, tagged_leaf, FetchError, MerkleTree};
+
+const HASH_SIZE: usize = 32;
+
+/// Merkle tags of the header fields
+const CHAIN_ID_TAG: u16 = 0x0002;
+const BLOCK_HEIGHT_TAG: u16 = 0x0003;
+const BLOCK_WEIGHT_TAG: u16 = 0x0004;
+const BLOCK_PAYLOAD_HASH_TAG: u16 = 0x0005;
+const FEATURE_FLAGS_TAG: u16 = 0x0006;
+const BLOCK_CREATION_TIME_TAG: u16 = 0x0007;
+const CHAINWEB_VERSION_TAG: u16 = 0x0008;
+const BLOCK_HASH_TAG: u16 = 0x0010;
+const HASH_TARGET_TAG: u16 = 0x0011;
+const EPOCH_START_TIME_TAG: u16 = 0x0019;
+const BLOCK_NONCE_TAG: u16 = 0x0020;
+
+/// Chainweb version codes of the binary encoding, by network name
+const VERSIONS: [(&str, u32); 3] = [
+    ("development", 0x01),
+    ("mainnet01", 0x05),
+    ("testnet04", 0x07),
+];
+
+/// A Chainweb block header
+///
+/// Hashes, the target and the weight are kept base64url encoded, as the
+/// Chainweb API reports them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Feature flags, currently always zero
+    pub feature_flags: u64,
+    /// Creation time, in microseconds since the Unix epoch
+    pub creation_time: u64,
+    /// Hash of the parent header on the same chain
+    pub parent: String,
+    /// Hashes of the parent headers on the adjacent chains, by chain id
+    pub adjacents: BTreeMap<u32, String>,
+    /// Proof of work target
+    pub target: String,
+    /// Hash of the block payload
+    pub payload_hash: String,
+    /// Chain of the block
+    pub chain_id: u32,
+    /// Accumulated work of the chain
+    pub weight: String,
+    /// Height of the block
+    pub height: u64,
+    /// Chainweb version code
+    pub chainweb_version: u32,
+    /// Start of the difficulty adjustment epoch, in microseconds
+    pub epoch_start: u64,
+    /// Proof of work nonce
+    pub nonce: u64,
+    /// Hash of the block
+    pub hash: String,
+}
+
+impl BlockHeader {
+    /// Decode a binary header
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FetchError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let feature_flags = reader.u64()?;
+        let creation_time = reader.u64()?;
+        let parent = reader.hash()?;
+        let adjacent_count = u16::from_le_bytes(reader.take()?);
+        let mut adjacents = BTreeMap::new();
+        for _ in 0..adjacent_count {
+            let chain = reader.u32()?;
+            adjacents.insert(chain, reader.hash()?);
+        }
+
+        let header = Self {
+            feature_flags,
+            creation_time,
+            parent,
+            adjacents,
+            target: reader.hash()?,
+            payload_hash: reader.hash()?,
+            chain_id: reader.u32()?,
+            weight: reader.hash()?,
+            height: reader.u64()?,
+            chainweb_version: reader.u32()?,
+            epoch_start: reader.u64()?,
+            nonce: reader.u64()?,
+            hash: reader.hash()?,
+        };
+        if reader.offset != bytes.len() {
+            return Err(invalid("trailing bytes after the header"));
+        }
+        Ok(header)
+    }
+
+    /// Decode a base64url binary header, the default encoding of the API
+    pub fn from_base64(encoded: &str) -> Result<Self, FetchError> {
+        Self::from_bytes(&decode(encoded.trim())?)
+    }
+
+    /// Decode a header in the JSON object encoding
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::BlockHeader;
+    /// use serde_json::json;
+    ///
+    /// let hash = "A".repeat(43);
+    /// let mut header = BlockHeader::from_json(&json!({
+    ///     "featureFlags": 0,
+    ///     "creationTime": 1_700_000_000_000_000u64,
+    ///     "parent": hash,
+    ///     "adjacents": {"5": hash, "10": hash, "15": hash},
+    ///     "target": format!("{}8", "_".repeat(42)),
+    ///     "payloadHash": hash,
+    ///     "chainId": 0,
+    ///     "weight": hash,
+    ///     "height": 100,
+    ///     "chainwebVersion": "mainnet01",
+    ///     "epochStart": 1_700_000_000_000_000u64,
+    ///     "nonce": "42",
+    ///     "hash": hash
+    /// }))
+    /// .unwrap();
+    ///
+    /// assert_eq!(header.to_bytes().unwrap().len(), 318);
+    /// assert!(header.validate_pow().is_err());
+    ///
+    /// header.hash = header.compute_hash().unwrap();
+    /// assert!(header.validate_pow().is_ok());
+    /// ```
+    pub fn from_json(value: &Value) -> Result<Self, FetchError> {
+        let u64_field = |field: &str| {
+            let value = &value[field];
+            value
+                .as_u64()
+                .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                .ok_or_else(|| invalid(&format!("missing or invalid {}", field)))
+        };
+        let hash_field = |field: &str| {
+            value[field]
+                .as_str()
+                .map(ToString::to_string)
+                .ok_or_else(|| invalid(&format!("missing or invalid {}", field)))
+        };
+
+        let adjacents = value["adjacents"]
+            .as_object()
+            .ok_or_else(|| invalid("missing or invalid adjacents"))?
+            .iter()
+            .map(|(chain, hash)| {
+                Ok((
+                    chain
+                        .parse()
+                        .map_err(|_| invalid("invalid adjacent chain"))?,
+                    hash.as_str()
+                        .ok_or_else(|| invalid("invalid adjacent hash"))?
+                        .to_string(),
+                ))
+            })
+            .collect::<Result<_, FetchError>>()?;
+        let chainweb_version = match &value["chainwebVersion"] {
+            Value::String(name) => VERSIONS
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, code)| *code)
+                .ok_or_else(|| invalid(&format!("unknown chainweb version {}", name)))?,
+            _ => u64_field("chainwebVersion")? as u32,
+        };
+
+        Ok(Self {
+            feature_flags: u64_field("featureFlags")?,
+            creation_time: u64_field("creationTime")?,
+            parent: hash_field("parent")?,
+            adjacents,
+            target: hash_field("target")?,
+            payload_hash: hash_field("payloadHash")?,
+            chain_id: u64_field("chainId")? as u32,
+            weight: hash_field("weight")?,
+            height: u64_field("height")?,
+            chainweb_version,
+            epoch_start: u64_field("epochStart")?,
+            nonce: u64_field("nonce")?,
+            hash: hash_field("hash")?,
+        })
+    }
+
+    /// Decode a header as returned by the API, in either encoding
+    pub fn from_value(value: &Value) -> Result<Self, FetchError> {
+        match value {
+            Value::String(encoded) => Self::from_base64(encoded),
+            value => Self::from_json(value),
+        }
+    }
+
+    /// Encode the header in the binary encoding
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FetchError> {
+        let mut bytes = Vec::with_capacity(318);
+        bytes.extend_from_slice(&self.feature_flags.to_le_bytes());
+        bytes.extend_from_slice(&self.creation_time.to_le_bytes());
+        bytes.extend_from_slice(&decode_hash(&self.parent)?);
+        bytes.extend_from_slice(&(self.adjacents.len() as u16).to_le_bytes());
+        for (chain, hash) in &self.adjacents {
+            bytes.extend_from_slice(&chain.to_le_bytes());
+            bytes.extend_from_slice(&decode_hash(hash)?);
+        }
+        bytes.extend_from_slice(&decode_hash(&self.target)?);
+        bytes.extend_from_slice(&decode_hash(&self.payload_hash)?);
+        bytes.extend_from_slice(&self.chain_id.to_le_bytes());
+        bytes.extend_from_slice(&decode_hash(&self.weight)?);
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.chainweb_version.to_le_bytes());
+        bytes.extend_from_slice(&self.epoch_start.to_le_bytes());
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        bytes.extend_from_slice(&decode_hash(&self.hash)?);
+        Ok(bytes)
+    }
+
+    /// Encode the header in the base64url binary encoding of the API
+    pub fn to_base64(&self) -> Result<String, FetchError> {
+        Ok(base64url_encode(&self.to_bytes()?))
+    }
+
+    /// Compute the proof of work hash of the header
+    pub fn pow_hash(&self) -> Result<[u8; HASH_SIZE], FetchError> {
+        let bytes = self.to_bytes()?;
+        Ok(Blake2s256::digest(&bytes[..bytes.len() - HASH_SIZE]).into())
+    }
+
+    /// Compute the block hash from the contents of the header
+    pub fn compute_hash(&self) -> Result<String, FetchError> {
+        let mut leaves = vec![
+            tagged_leaf(FEATURE_FLAGS_TAG, &self.feature_flags.to_le_bytes()),
+            tagged_leaf(BLOCK_CREATION_TIME_TAG, &self.creation_time.to_le_bytes()),
+            tagged_leaf(BLOCK_HASH_TAG, &decode_hash(&self.parent)?),
+            tagged_leaf(HASH_TARGET_TAG, &decode_hash(&self.target)?),
+            tagged_leaf(BLOCK_PAYLOAD_HASH_TAG, &decode_hash(&self.payload_hash)?),
+            tagged_leaf(CHAIN_ID_TAG, &self.chain_id.to_le_bytes()),
+            tagged_leaf(BLOCK_WEIGHT_TAG, &decode_hash(&self.weight)?),
+            tagged_leaf(BLOCK_HEIGHT_TAG, &self.height.to_le_bytes()),
+            tagged_leaf(CHAINWEB_VERSION_TAG, &self.chainweb_version.to_le_bytes()),
+            tagged_leaf(EPOCH_START_TIME_TAG, &self.epoch_start.to_le_bytes()),
+            tagged_leaf(BLOCK_NONCE_TAG, &self.nonce.to_le_bytes()),
+        ];
+        for hash in self.adjacents.values() {
+            leaves.push(tagged_leaf(BLOCK_HASH_TAG, &decode_hash(hash)?));
+        }
+        let tree = MerkleTree::new(leaves).map_err(|e| invalid(&e.to_string()))?;
+        Ok(base64url_encode(&tree.root()))
+    }
+
+    /// Check that the `hash` of the header is the hash of its contents
+    ///
+    /// Decoding does not check the hash, so a header received from a node
+    /// must be validated before its hash is relied upon.
+    pub fn validate_hash(&self) -> Result<(), FetchError> {
+        let hash = self.compute_hash()?;
+        if hash != self.hash {
+            return Err(invalid(&format!(
+                "block {} has the contents of block {}",
+                self.hash, hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check the hash of the header and that its proof of work meets the target
+    ///
+    /// The proof of work does not cover the block hash, so the hash is
+    /// checked first, see [`validate_hash`](Self::validate_hash).
+    pub fn validate_pow(&self) -> Result<(), FetchError> {
+        self.validate_hash()?;
+        if !self.pow_target()?.is_met_by(&self.pow_hash()?) {
+            return Err(invalid(&format!(
+                "proof of work of block {} does not meet its target",
+                self.hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that `parent` is the parent of this header on the same chain
+    pub fn validate_parent(&self, parent: &BlockHeader) -> Result<(), FetchError> {
+        if self.parent != parent.hash {
+            return Err(invalid(&format!(
+                "block {} does not descend from {}",
+                self.hash, parent.hash
+            )));
+        }
+        if self.chain_id != parent.chain_id || self.chainweb_version != parent.chainweb_version {
+            return Err(invalid("parent belongs to another chain"));
+        }
+        if self.height != parent.height + 1 {
+            return Err(invalid(&format!(
+                "block at height {} follows parent at height {}",
+                self.height, parent.height
+            )));
+        }
+        if self.creation_time <= parent.creation_time {
+            return Err(invalid("block is not newer than its parent"));
+        }
+        Ok(())
+    }
+
+    /// Check that the headers of adjacent chains are the ones this header links to
+    ///
+    /// Each adjacent header must be referenced by this header and sit at the
+    /// height of its parent.
+    pub fn validate_adjacents(&self, adjacents: &[BlockHeader]) -> Result<(), FetchError> {
+        for adjacent in adjacents {
+            match self.adjacents.get(&adjacent.chain_id) {
+                Some(hash) if *hash == adjacent.hash => {}
+                Some(_) => {
+                    return Err(invalid(&format!(
+                        "block {} does not link to {} on chain {}",
+                        self.hash, adjacent.hash, adjacent.chain_id
+                    )))
+                }
+                None => {
+                    return Err(invalid(&format!(
+                        "chain {} is not adjacent to chain {}",
+                        adjacent.chain_id, self.chain_id
+                    )))
+                }
+            }
+            if adjacent.height + 1 != self.height {
+                return Err(invalid(&format!(
+                    "adjacent block on chain {} is at height {} instead of {}",
+                    adjacent.chain_id,
+                    adjacent.height,
+                    self.height.saturating_sub(1)
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], FetchError> {
+        let chunk = self
+            .bytes
+            .get(self.offset..self.offset + N)
+            .ok_or_else(|| invalid("header is truncated"))?;
+        self.offset += N;
+        Ok(chunk.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, FetchError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, FetchError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn hash(&mut self) -> Result<String, FetchError> {
+        Ok(base64url_encode(&self.take::<HASH_SIZE>()?))
+    }
+}
+
+fn decode(encoded: &str) -> Result<Vec<u8>, FetchError> {
+    base64url_decode(encoded).map_err(|e| invalid(&e.to_string()))
+}
+
+fn decode_hash(encoded: &str) -> Result<[u8; HASH_SIZE], FetchError> {
+    decode(encoded)?
+        .try_into()
+        .map_err(|_| invalid("hashes must be 32 bytes long"))
+}
+
+fn invalid(reason: &str) -> FetchError {
+    FetchError::InvalidHeader(reason.to_string())
+}
@@ -0,0 +1,151 @@
+//! Cancellation of long-running operations
+//!
+//! The crate spawns no background tasks: polling loops and subscriptions run
+//! inside the futures and streams they return, and dropping those stops them.
+//! A [`CancellationToken`] stops them from elsewhere, e.g. from a shutdown
+//! handler, without having to own the future. Loops such as
+//! [`ApiClient::await_spv`](crate::ApiClient::await_spv) and
+//! [`TxTracker::run`](crate::TxTracker::run) accept a token directly; any other
+//! future or stream can be wrapped with [`CancellationToken::run`] and
+//! [`CancellationToken::stream`].
+
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures::{
+    channel::oneshot,
+    future::{select, Either, FutureExt, Shared},
+    Stream, StreamExt,
+};
+
+use crate::FetchError;
+
+/// A signal shared by every clone, stopping the operations watching it
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{sleep, CancellationToken, FetchError};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let token = CancellationToken::new();
+/// let shutdown = token.clone();
+///
+/// let (result, _) = futures::join!(
+///     token.run(sleep(Duration::from_secs(60))),
+///     async { shutdown.cancel() },
+/// );
+/// assert!(matches!(result, Err(FetchError::Cancelled)));
+/// assert!(token.is_cancelled());
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    cancelled: AtomicBool,
+    sender: Mutex<Option<oneshot::Sender<()>>>,
+    receiver: Shared<oneshot::Receiver<()>>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        let (sender, receiver) = oneshot::channel();
+        Self {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                sender: Mutex::new(Some(sender)),
+                receiver: receiver.shared(),
+            }),
+        }
+    }
+
+    /// Cancel the operations watching this token or any of its clones
+    ///
+    /// Cancelling again has no effect.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        if let Some(sender) = self.inner.sender.lock().unwrap().take() {
+            let _ = sender.send(());
+        }
+    }
+
+    /// Returns true once the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled
+    ///
+    /// Never resolves if every clone of the token is dropped uncancelled.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        let receiver = self.inner.receiver.clone();
+        async move {
+            if receiver.await.is_err() {
+                futures::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Run `future` until it completes or the token is cancelled
+    ///
+    /// On cancellation the future is dropped, which aborts its pending
+    /// requests, and [`FetchError::Cancelled`] is returned.
+    pub async fn run<F: Future>(&self, future: F) -> Result<F::Output, FetchError> {
+        if self.is_cancelled() {
+            return Err(FetchError::Cancelled);
+        }
+        let future = std::pin::pin!(future);
+        let cancelled = std::pin::pin!(self.cancelled());
+        match select(future, cancelled).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(_) => Err(FetchError::Cancelled),
+        }
+    }
+
+    /// End `stream` once the token is cancelled
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use kadena::fetch::CancellationToken;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let token = CancellationToken::new();
+    /// let mut numbers = Box::pin(token.stream(futures::stream::iter(0..)));
+    /// assert_eq!(numbers.next().await, Some(0));
+    /// token.cancel();
+    /// assert_eq!(numbers.next().await, None);
+    /// # }
+    /// ```
+    pub fn stream<S: Stream>(&self, stream: S) -> impl Stream<Item = S::Item> {
+        stream.take_until(self.cancelled())
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
@@ -0,0 +1,271 @@
+//! Chain graph of a Chainweb network
+//!
+//! The chains of a network are linked in a graph: each block header references
+//! the parent headers of the adjacent chains. A cross-chain transfer can only
+//! be completed on a chain of the graph, and its SPV proof takes one block per
+//! hop between the chains to become available. The graph changed over the
+//! life of mainnet, so nodes report its history in their `/info` route.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde_json::Value;
+
+use crate::{ChainwebClient, FetchError};
+
+/// Adjacency of the chains of a network
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::ChainGraph;
+///
+/// // A ring of four chains
+/// let graph = ChainGraph::new([(0, vec![1, 3]), (1, vec![0, 2]), (2, vec![1, 3]), (3, vec![2, 0])]);
+/// assert!(graph.is_adjacent(0, 1));
+/// assert_eq!(graph.path(0, 2), Some(vec![0, 1, 2]));
+/// assert_eq!(graph.distance(0, 2), Some(2));
+///
+/// let error = graph.check_cross_chain(0, 7).unwrap_err();
+/// assert_eq!(error.error_code(), "fetch.unreachable_chain");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainGraph {
+    adjacency: BTreeMap<u32, BTreeSet<u32>>,
+}
+
+impl ChainGraph {
+    /// Create a graph from the adjacent chains of every chain
+    pub fn new<I, A>(adjacency: I) -> Self
+    where
+        I: IntoIterator<Item = (u32, A)>,
+        A: IntoIterator<Item = u32>,
+    {
+        Self {
+            adjacency: adjacency
+                .into_iter()
+                .map(|(chain, adjacents)| (chain, adjacents.into_iter().collect()))
+                .collect(),
+        }
+    }
+
+    /// Parse an adjacency list, e.g. `[[0, [5, 10, 15]], [1, [6, 11, 16]], ...]`
+    pub fn from_value(value: &Value) -> Result<Self, FetchError> {
+        let invalid = || FetchError::ApiError(format!("Invalid chain graph: {}", value));
+        let chain = |value: &Value| value.as_u64().and_then(|c| u32::try_from(c).ok());
+        let adjacency = value
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|entry| {
+                let adjacents = entry[1]
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|c| chain(c).ok_or_else(invalid))
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+                Ok((chain(&entry[0]).ok_or_else(invalid)?, adjacents))
+            })
+            .collect::<Result<_, FetchError>>()?;
+        Ok(Self { adjacency })
+    }
+
+    /// Get the chains of the graph, in order
+    pub fn chains(&self) -> impl Iterator<Item = u32> + '_ {
+        self.adjacency.keys().copied()
+    }
+
+    /// Returns true if `chain` is part of the graph
+    pub fn contains(&self, chain: u32) -> bool {
+        self.adjacency.contains_key(&chain)
+    }
+
+    /// Get the chains adjacent to `chain`
+    pub fn adjacents(&self, chain: u32) -> impl Iterator<Item = u32> + '_ {
+        self.adjacency.get(&chain).into_iter().flatten().copied()
+    }
+
+    /// Returns true if the headers of `a` reference the headers of `b`
+    pub fn is_adjacent(&self, a: u32, b: u32) -> bool {
+        self.adjacency.get(&a).is_some_and(|adj| adj.contains(&b))
+    }
+
+    /// Find a shortest path between two chains, both included
+    ///
+    /// Returns `None` if either chain is not part of the graph, or if no path
+    /// links them.
+    pub fn path(&self, from: u32, to: u32) -> Option<Vec<u32>> {
+        if !self.contains(from) || !self.contains(to) {
+            return None;
+        }
+        // Chain each visited chain was reached from
+        let mut previous = BTreeMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(chain) = queue.pop_front() {
+            if chain == to {
+                let mut path = vec![to];
+                let mut chain = to;
+                while chain != from {
+                    chain = previous[&chain];
+                    path.push(chain);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.adjacents(chain) {
+                if next != from && !previous.contains_key(&next) {
+                    previous.insert(next, chain);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Number of hops between two chains, if they are linked
+    ///
+    /// An SPV proof for the target chain is available once the target chain
+    /// has mined at least this many blocks after the source transaction.
+    pub fn distance(&self, from: u32, to: u32) -> Option<usize> {
+        self.path(from, to).map(|path| path.len() - 1)
+    }
+
+    /// Check that a cross-chain transfer from `source` can complete on `target`
+    ///
+    /// Returns the path of the SPV proof, or [`FetchError::UnreachableChain`]
+    /// if the target is not part of the graph or not reachable from the
+    /// source.
+    pub fn check_cross_chain(&self, source: u32, target: u32) -> Result<Vec<u32>, FetchError> {
+        if source == target {
+            return Err(FetchError::UnreachableChain(format!(
+                "source and target are both chain {}",
+                source
+            )));
+        }
+        for chain in [source, target] {
+            if !self.contains(chain) {
+                return Err(FetchError::UnreachableChain(format!(
+                    "chain {} is not part of the chain graph",
+                    chain
+                )));
+            }
+        }
+        self.path(source, target).ok_or_else(|| {
+            FetchError::UnreachableChain(format!(
+                "chain {} is not reachable from chain {}",
+                target, source
+            ))
+        })
+    }
+}
+
+/// The chain graphs of a network, by the height they took effect at
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::ChainGraphHistory;
+/// use serde_json::json;
+///
+/// let info = json!({
+///     "nodeGraphHistory": [
+///         [100, [[0, [1, 2]], [1, [0, 2]], [2, [0, 1]]]],
+///         [0, [[0, [1]], [1, [0]]]]
+///     ]
+/// });
+/// let history = ChainGraphHistory::from_info(&info).unwrap();
+/// assert!(!history.at(99).unwrap().contains(2));
+/// assert!(history.at(100).unwrap().contains(2));
+/// assert_eq!(history.latest().unwrap().chains().count(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainGraphHistory {
+    graphs: Vec<(u64, ChainGraph)>,
+}
+
+impl ChainGraphHistory {
+    /// Parse the `nodeGraphHistory` of a node's `/info` response
+    pub fn from_info(info: &Value) -> Result<Self, FetchError> {
+        let history = &info["nodeGraphHistory"];
+        let invalid = || FetchError::ApiError(format!("Invalid chain graph history: {}", history));
+        let mut graphs = history
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|entry| {
+                let height = entry[0].as_u64().ok_or_else(invalid)?;
+                Ok((height, ChainGraph::from_value(&entry[1])?))
+            })
+            .collect::<Result<Vec<_>, FetchError>>()?;
+        graphs.sort_by_key(|(height, _)| *height);
+        Ok(Self { graphs })
+    }
+
+    /// Get the graph in effect at a block height
+    pub fn at(&self, height: u64) -> Option<&ChainGraph> {
+        self.graphs
+            .iter()
+            .rev()
+            .find(|(since, _)| *since <= height)
+            .map(|(_, graph)| graph)
+    }
+
+    /// Get the most recent graph, including one scheduled for a future height
+    pub fn latest(&self) -> Option<&ChainGraph> {
+        self.graphs.last().map(|(_, graph)| graph)
+    }
+}
+
+impl ChainwebClient {
+    /// Get the history of the chain graph from the node's `/info` route
+    pub async fn chain_graph_history(&self) -> Result<ChainGraphHistory, FetchError> {
+        let config = self.config("0");
+        let url = config.info_url()?;
+        let info = self.chain("0").get_json(&url, "application/json").await?;
+        ChainGraphHistory::from_info(&info)
+    }
+
+    /// Get the chain graph in effect at the current cut of the network
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+    /// let graph = client.chain_graph().await?;
+    /// let path = graph.check_cross_chain(0, 7)?;
+    /// println!("SPV proof needs {} blocks on chain 7", path.len() - 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn chain_graph(&self) -> Result<ChainGraph, FetchError> {
+        let history = self.chain_graph_history().await?;
+        let cut = self.cut().await?;
+        let height = cut["hashes"]
+            .as_object()
+            .and_then(|hashes| hashes.values().filter_map(|h| h["height"].as_u64()).max())
+            .ok_or_else(|| FetchError::ApiError(format!("Unexpected cut: {}", cut)))?;
+        history.at(height).cloned().ok_or_else(|| {
+            FetchError::ApiError(format!("No chain graph in effect at height {}", height))
+        })
+    }
+
+    /// Check that a cross-chain transfer can complete on the target chain
+    ///
+    /// Fetches the current chain graph and returns the path of the SPV proof,
+    /// see [`ChainGraph::check_cross_chain`].
+    pub async fn check_cross_chain(
+        &self,
+        source_chain: &str,
+        target_chain: &str,
+    ) -> Result<Vec<u32>, FetchError> {
+        let chain = |id: &str| {
+            id.parse::<u32>()
+                .map_err(|_| FetchError::UnreachableChain(format!("invalid chain id {:?}", id)))
+        };
+        self.chain_graph()
+            .await?
+            .check_cross_chain(chain(source_chain)?, chain(target_chain)?)
+    }
+}
@@ -0,0 +1,230 @@
+//! Target chain selection for new transactions
+//!
+//! Accounts on Kadena hold independent balances on each chain, and chains can be
+//! unevenly loaded. A [`ChainSelector`] picks the chain a new transaction should
+//! be sent to, according to a configurable [`SelectionStrategy`].
+//!
+//! A client configured with [`ChainwebClient::with_chain_selector`] picks the
+//! chain of the commands sent with [`ChainwebClient::send_transfer`] and
+//! [`ChainwebClient::send_command`]: the selected chain goes into the
+//! [`Meta`] of the command, which is then sent to that chain.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::Value;
+
+use crate::{
+    pact::{Cmd, CommandError, Meta, Transfer},
+    ChainwebClient, FetchError, PactKeypair,
+};
+
+/// Criteria used to pick a target chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionStrategy {
+    /// Cycle through the client's chains
+    RoundRobin,
+    /// Prefer the chain on which the account holds the largest balance
+    HighestBalance,
+    /// Prefer the chain with the fewest transactions pending in the mempool
+    LeastCongested,
+}
+
+/// Picks a target chain for new transactions
+#[derive(Debug)]
+pub struct ChainSelector {
+    strategy: SelectionStrategy,
+    next: AtomicUsize,
+}
+
+impl ChainSelector {
+    /// Create a new selector using the given strategy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{ChainSelector, SelectionStrategy};
+    ///
+    /// let selector = ChainSelector::new(SelectionStrategy::RoundRobin);
+    /// assert_eq!(selector.strategy(), SelectionStrategy::RoundRobin);
+    /// ```
+    pub fn new(strategy: SelectionStrategy) -> Self {
+        Self {
+            strategy,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the selection strategy
+    pub fn strategy(&self) -> SelectionStrategy {
+        self.strategy
+    }
+
+    /// Select a target chain among the chains of `client`
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client whose chains are candidates
+    /// * `account` - The sending account, used by balance-based strategies
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::crypto::PactKeypair;
+    /// use kadena::fetch::{ChainSelector, ChainwebClient, SelectionStrategy};
+    /// use kadena::pact::{Meta, Transfer};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let keypair = PactKeypair::from_secret_key("...")?;
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+    /// let selector = ChainSelector::new(SelectionStrategy::HighestBalance);
+    /// let chain_id = selector.select(&client, "k:abc123").await?;
+    ///
+    /// let meta = Meta::new(&chain_id, "k:abc123");
+    /// let cmd = Transfer::new("k:abc123", "k:def456", Decimal::ONE).prepare(&[&keypair], meta, None)?;
+    /// client.chain(&chain_id).send(&cmd).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn select(
+        &self,
+        client: &ChainwebClient,
+        account: &str,
+    ) -> Result<String, FetchError> {
+        let chain_ids = client.chain_ids();
+        if chain_ids.is_empty() {
+            return Err(FetchError::NoChainAvailable);
+        }
+
+        match self.strategy {
+            SelectionStrategy::RoundRobin => {
+                let index = self.next.fetch_add(1, Ordering::Relaxed) % chain_ids.len();
+                Ok(chain_ids[index].clone())
+            }
+            SelectionStrategy::HighestBalance => {
+                let balances = client.total_balance(account).await?;
+                balances
+                    .balances
+                    .into_iter()
+                    .rev()
+                    .max_by_key(|b| b.balance)
+                    .map(|b| b.chain_id)
+                    .ok_or(FetchError::NoChainAvailable)
+            }
+            SelectionStrategy::LeastCongested => {
+                let mut pending: Vec<(usize, usize)> = stream::iter(chain_ids.iter().enumerate())
+                    .map(|(index, chain_id)| async move {
+                        let hashes = client.mempool_pending(chain_id).await?;
+                        Ok::<_, FetchError>((index, hashes.len()))
+                    })
+                    .buffer_unordered(client.max_concurrency())
+                    .try_collect()
+                    .await?;
+                pending.sort();
+                pending
+                    .into_iter()
+                    .min_by_key(|(_, count)| *count)
+                    .map(|(index, _)| chain_ids[index].clone())
+                    .ok_or(FetchError::NoChainAvailable)
+            }
+        }
+    }
+}
+
+/// A command sent to the chain picked for it
+#[derive(Debug, Clone)]
+pub struct SentCommand {
+    /// The chain the command was sent to
+    pub chain_id: String,
+    /// The command, whose hash is its request key
+    pub cmd: Cmd,
+    /// The response of the node
+    pub response: Value,
+}
+
+impl ChainwebClient {
+    /// Prepare a command for the selected chain and send it there
+    ///
+    /// The chain is picked by the client's [`ChainSelector`] for `account`,
+    /// or taken from `meta` without a selector. `prepare` receives `meta`
+    /// with the chain set, and the network id of the client.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::crypto::PactKeypair;
+    /// use kadena::fetch::{ChainSelector, ChainwebClient, SelectionStrategy};
+    /// use kadena::pact::{Cap, Cmd, Meta};
+    ///
+    /// let keypair = PactKeypair::from_secret_key("...")?;
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04")
+    ///     .with_chain_selector(ChainSelector::new(SelectionStrategy::LeastCongested));
+    /// let sent = client
+    ///     .send_command("k:abc123", Meta::new("0", "k:abc123"), |meta, network_id| {
+    ///         let signers = [(&keypair, vec![Cap::new("coin.GAS")])];
+    ///         Cmd::prepare_exec(&signers, Vec::new(), None, "(+ 1 2)", None, meta, network_id)
+    ///     })
+    ///     .await?;
+    /// println!("sent {} to chain {}", sent.cmd.hash, sent.chain_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_command<F>(
+        &self,
+        account: &str,
+        mut meta: Meta,
+        prepare: F,
+    ) -> Result<SentCommand, FetchError>
+    where
+        F: FnOnce(Meta, Option<String>) -> Result<Cmd, CommandError>,
+    {
+        if let Some(selector) = self.chain_selector() {
+            meta.chain_id = selector.select(self, account).await?;
+        }
+        let chain_id = meta.chain_id.clone();
+        let client = self.chain(&chain_id);
+        let cmd = prepare(meta, client.config().network_id())?;
+        let response = client.send(&cmd).await?;
+        Ok(SentCommand {
+            chain_id,
+            cmd,
+            response,
+        })
+    }
+
+    /// Send a transfer from the chain selected for its sender
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::crypto::PactKeypair;
+    /// use kadena::fetch::{ChainSelector, ChainwebClient, SelectionStrategy};
+    /// use kadena::pact::{Meta, Transfer};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let keypair = PactKeypair::from_secret_key("...")?;
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04")
+    ///     .with_chain_selector(ChainSelector::new(SelectionStrategy::HighestBalance));
+    /// let transfer = Transfer::new("k:abc123", "k:def456", Decimal::ONE);
+    /// let sent = client
+    ///     .send_transfer(&transfer, &[&keypair], Meta::new("0", "k:abc123"))
+    ///     .await?;
+    /// client.chain(&sent.chain_id).listen(&sent.cmd.hash).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_transfer(
+        &self,
+        transfer: &Transfer,
+        signers: &[&PactKeypair],
+        meta: Meta,
+    ) -> Result<SentCommand, FetchError> {
+        self.send_command(&transfer.sender, meta, |meta, network_id| {
+            transfer.prepare(signers, meta, network_id)
+        })
+        .await
+    }
+}
@@ -0,0 +1,693 @@
+//! Multi-chain access to a Chainweb network
+//!
+//! A Chainweb network is made of several parallel chains, each exposing its own
+//! Pact API. This module provides a client spanning all chains of a network.
+
+use std::{sync::Arc, time::Duration};
+
+use futures::{future, stream, stream::BoxStream, StreamExt, TryStreamExt};
+use log::{debug, error, trace};
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::{
+    pact::{command::Cmd, meta::Meta},
+    ApiClient, ApiConfig, Auth, BlockHeader, BlockPayload, ChainSelector, EndpointKind, FetchError,
+    ReqwestTransport, Transport, DEFAULT_MAX_BODY_SIZE,
+};
+
+/// Number of chains on the current mainnet and testnet graphs
+pub const DEFAULT_CHAIN_COUNT: u16 = 20;
+
+/// Default number of chains queried concurrently
+pub const DEFAULT_MAX_CONCURRENCY: usize = 5;
+
+/// Balance of an account on a single chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainBalance {
+    /// The chain id
+    pub chain_id: String,
+    /// The account balance on this chain
+    pub balance: Decimal,
+}
+
+/// Balances of an account across all chains of a network
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountBalances {
+    /// The account name
+    pub account: String,
+    /// Balances on the chains where the account exists, in chain order
+    pub balances: Vec<ChainBalance>,
+    /// Sum of all per-chain balances
+    pub total: Decimal,
+}
+
+/// Status of a transaction in a chain's mempool
+#[derive(Debug, Clone)]
+pub enum MempoolStatus {
+    /// The mempool does not hold the transaction
+    Missing,
+    /// The transaction waits to be included in a block
+    Pending(Cmd),
+}
+
+impl MempoolStatus {
+    /// Parse an entry of a mempool lookup, e.g. `{"tag": "Pending", "contents": ...}`
+    ///
+    /// The transaction may be encoded as an object or as a JSON string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::MempoolStatus;
+    /// use serde_json::json;
+    ///
+    /// let status = MempoolStatus::from_value(&json!({"tag": "Missing"})).unwrap();
+    /// assert!(status.into_pending().is_none());
+    ///
+    /// let tx = r#"{"hash": "h1", "sigs": [{"sig": "s1"}], "cmd": "{}"}"#;
+    /// let status = MempoolStatus::from_value(&json!({"tag": "Pending", "contents": tx})).unwrap();
+    /// assert_eq!(status.into_pending().unwrap().hash, "h1");
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Self, FetchError> {
+        match value["tag"].as_str() {
+            Some("Missing") => Ok(Self::Missing),
+            Some("Pending") => {
+                let cmd = match &value["contents"] {
+                    Value::String(tx) => serde_json::from_str(tx)?,
+                    tx => serde_json::from_value(tx.clone())?,
+                };
+                Ok(Self::Pending(cmd))
+            }
+            _ => Err(FetchError::ApiError(format!(
+                "Unexpected mempool entry: {}",
+                value
+            ))),
+        }
+    }
+
+    /// Returns the command, if the transaction is pending
+    pub fn into_pending(self) -> Option<Cmd> {
+        match self {
+            Self::Pending(cmd) => Some(cmd),
+            Self::Missing => None,
+        }
+    }
+}
+
+/// Client for interacting with every chain of a Chainweb network
+///
+/// Requests go through the same [`Transport`] as those of [`ApiClient`], by
+/// default HTTP over TCP. Every API of the client, and the light client,
+/// header streams and watchers built on it, can run over another transport
+/// set with [`ChainwebClient::with_transport`].
+#[derive(Debug, Clone)]
+pub struct ChainwebClient {
+    base_url: String,
+    network: String,
+    chain_ids: Vec<String>,
+    endpoint: EndpointKind,
+    timeout: u64,
+    api_key: Option<String>,
+    auth: Option<Auth>,
+    max_concurrency: usize,
+    max_body_size: usize,
+    client: Client,
+    /// Client of long-lived streams, without a request timeout
+    stream_client: Client,
+    /// Transport replacing the default HTTP transport, if any
+    transport: Option<Arc<dyn Transport>>,
+    /// Selector of the chains of sent commands, if any
+    selector: Option<Arc<ChainSelector>>,
+}
+
+impl ChainwebClient {
+    /// Create a new client for all chains of a network
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the node
+    /// * `network` - Kadena network
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04")
+    ///     .with_max_concurrency(10);
+    /// assert_eq!(client.chain_ids().len(), 20);
+    /// ```
+    pub fn new(base_url: &str, network: &str) -> Self {
+        let timeout = 30;
+        Self {
+            base_url: base_url.to_string(),
+            network: network.to_string(),
+            chain_ids: (0..DEFAULT_CHAIN_COUNT).map(|c| c.to_string()).collect(),
+            endpoint: EndpointKind::default(),
+            timeout,
+            api_key: None,
+            auth: None,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            client: build_http_client(timeout),
+            stream_client: build_stream_client(timeout),
+            transport: None,
+            selector: None,
+        }
+    }
+
+    /// Set the chains this client operates on
+    pub fn with_chain_ids<I, S>(mut self, chain_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.chain_ids = chain_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the kind of API exposed by the node
+    pub fn with_endpoint(mut self, endpoint: EndpointKind) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Set a custom timeout
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.timeout = seconds;
+        self.client = build_http_client(seconds);
+        self.stream_client = build_stream_client(seconds);
+        self
+    }
+
+    /// Set an API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Authenticate with HTTP basic authentication
+    ///
+    /// The credentials are sent with every request, streams included.
+    pub fn with_basic_auth(mut self, username: &str, password: Option<&str>) -> Self {
+        self.auth = Some(Auth::Basic {
+            username: username.to_string(),
+            password: password.map(ToString::to_string),
+        });
+        self
+    }
+
+    /// Authenticate with a bearer token
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Bearer(token.into()));
+        self
+    }
+
+    /// Set the maximum number of chains queried concurrently
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Set the maximum size of response bodies, such as block payloads
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Send requests through a custom transport, e.g. a Unix socket or a replay
+    ///
+    /// The timeout is not applied; the transport enforces its own. Streams,
+    /// such as [`ChainwebClient::header_updates`], are read from the complete
+    /// response before their first event is reported. This suits in-process
+    /// and replay transports, but a stream of a live node only ends when the
+    /// node closes it, so transports waiting for the end of the response,
+    /// [`UnixSocketTransport`](crate::UnixSocketTransport) included, do not
+    /// support streams.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{ChainwebClient, UnixSocketTransport};
+    ///
+    /// let client = ChainwebClient::new("http://localhost", "mainnet01")
+    ///     .with_transport(UnixSocketTransport::new("/var/run/chainweb-node.sock"));
+    /// ```
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Pick the chain of the commands sent with [`ChainwebClient::send_transfer`]
+    /// and [`ChainwebClient::send_command`] with `selector`
+    ///
+    /// Without a selector, commands are sent to the chain of their metadata.
+    pub fn with_chain_selector(mut self, selector: ChainSelector) -> Self {
+        self.selector = Some(Arc::new(selector));
+        self
+    }
+
+    /// Get the chain selector, if any
+    pub fn chain_selector(&self) -> Option<&ChainSelector> {
+        self.selector.as_deref()
+    }
+
+    /// Get the chains this client operates on
+    pub fn chain_ids(&self) -> &[String] {
+        &self.chain_ids
+    }
+
+    /// Get the network this client operates on
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    /// Get the maximum number of chains queried concurrently
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Create an API client for a single chain, sharing this client's transport
+    pub fn chain(&self, chain_id: &str) -> ApiClient {
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => Arc::new(
+                ReqwestTransport::new(self.client.clone()).with_max_body_size(self.max_body_size),
+            ),
+        };
+        ApiClient::with_shared_transport(self.config(chain_id), transport)
+    }
+
+    pub(crate) fn config(&self, chain_id: &str) -> ApiConfig {
+        let mut config = ApiConfig::new(&self.base_url, &self.network, chain_id)
+            .with_endpoint(self.endpoint)
+            .with_timeout(self.timeout)
+            .with_max_body_size(self.max_body_size);
+        if let Some(api_key) = &self.api_key {
+            config = config.with_api_key(api_key.clone());
+        }
+        config.auth = self.auth.clone();
+        config
+    }
+
+    /// Query the `coin` balance of an account on all chains
+    ///
+    /// Chains are queried concurrently, bounded by the configured maximum concurrency.
+    /// Chains on which the account does not exist are left out of the result.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+    /// let balances = client.total_balance("k:abc123").await?;
+    /// println!("Total: {} KDA", balances.total);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn total_balance(&self, account: &str) -> Result<AccountBalances, FetchError> {
+        let code = format!("(coin.details {})", serde_json::to_string(account)?);
+
+        let mut results: Vec<(usize, Option<Decimal>)> =
+            stream::iter(self.chain_ids.iter().enumerate())
+                .map(|(index, chain_id)| {
+                    let code = &code;
+                    async move {
+                        let balance = self.chain_balance(chain_id, code).await?;
+                        Ok::<_, FetchError>((index, balance))
+                    }
+                })
+                .buffer_unordered(self.max_concurrency)
+                .try_collect()
+                .await?;
+        results.sort_by_key(|(index, _)| *index);
+
+        let balances: Vec<ChainBalance> = results
+            .into_iter()
+            .filter_map(|(index, balance)| {
+                balance.map(|balance| ChainBalance {
+                    chain_id: self.chain_ids[index].clone(),
+                    balance,
+                })
+            })
+            .collect();
+        let total = balances.iter().map(|b| b.balance).sum();
+
+        Ok(AccountBalances {
+            account: account.to_string(),
+            balances,
+            total,
+        })
+    }
+
+    async fn chain_balance(
+        &self,
+        chain_id: &str,
+        code: &str,
+    ) -> Result<Option<Decimal>, FetchError> {
+        let cmd = Cmd::prepare_exec(
+            &[],
+            Vec::new(),
+            None,
+            code,
+            None,
+            Meta::new(chain_id, ""),
+            Some(self.network.clone()),
+        )?;
+
+        let response = self.chain(chain_id).local(&cmd).await?;
+        parse_balance(&response)
+    }
+
+    /// List the hashes of transactions pending in a chain's mempool
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+    /// let pending = client.mempool_pending("0").await?;
+    /// println!("{} pending transactions", pending.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn mempool_pending(&self, chain_id: &str) -> Result<Vec<String>, FetchError> {
+        let client = self.chain(chain_id);
+        let url = client.config().mempool_url("getPending")?;
+        let body = client.post(&url, &json!({})).await?.json_body()?;
+        trace!("Received mempool response from {}: {}", url, body);
+        Ok(body["hashes"]
+            .as_array()
+            .map(|hashes| {
+                hashes
+                    .iter()
+                    .filter_map(|h| h.as_str().map(ToString::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Look up transactions in a chain's mempool by hash
+    ///
+    /// Returns one status per hash, in order. Pending transactions are parsed
+    /// into commands, as the node will include them in a block.
+    pub async fn mempool_lookup(
+        &self,
+        chain_id: &str,
+        hashes: &[&str],
+    ) -> Result<Vec<MempoolStatus>, FetchError> {
+        let client = self.chain(chain_id);
+        let url = client.config().mempool_url("lookup")?;
+        let body = client.post(&url, &hashes).await?.json_body()?;
+        trace!("Received mempool lookup from {}: {}", url, body);
+        body.as_array()
+            .ok_or_else(|| FetchError::ApiError(format!("Unexpected response: {}", body)))?
+            .iter()
+            .map(MempoolStatus::from_value)
+            .collect()
+    }
+
+    /// List the commands pending in a chain's mempool
+    ///
+    /// Transactions leaving the mempool between listing and lookup are
+    /// skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+    /// for cmd in client.mempool_pending_commands("0").await? {
+    ///     let payload = cmd.payload()?;
+    ///     if payload.meta.sender == "k:abc123" {
+    ///         println!("{} pays {} per gas", cmd.hash, payload.meta.gas_price);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn mempool_pending_commands(&self, chain_id: &str) -> Result<Vec<Cmd>, FetchError> {
+        let hashes = self.mempool_pending(chain_id).await?;
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let hashes: Vec<&str> = hashes.iter().map(String::as_str).collect();
+        Ok(self
+            .mempool_lookup(chain_id, &hashes)
+            .await?
+            .into_iter()
+            .filter_map(MempoolStatus::into_pending)
+            .collect())
+    }
+
+    /// Get the payload of a block with the outputs of its transactions
+    ///
+    /// Fails with [`FetchError::BodyTooLarge`] if the response exceeds the
+    /// configured maximum body size.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04")
+    ///     .with_max_body_size(64 * 1024 * 1024);
+    /// let payload = client.payload_with_outputs("0", "payload-hash").await?;
+    /// for result in payload.outputs() {
+    ///     let result = result?;
+    ///     println!("{} succeeded: {}", result.req_key, result.is_success());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn payload_with_outputs(
+        &self,
+        chain_id: &str,
+        payload_hash: &str,
+    ) -> Result<BlockPayload, FetchError> {
+        let client = self.chain(chain_id);
+        let url = client.config().payload_outputs_url(payload_hash)?;
+        let body = client.get(&url, "application/json").await?.body;
+        debug!("Received {} byte payload from {}", body.len(), url);
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Get a block header of a chain by its hash
+    pub async fn header(&self, chain_id: &str, block_hash: &str) -> Result<Value, FetchError> {
+        let client = self.chain(chain_id);
+        let url = format!("{}/{}", client.config().header_url()?, block_hash);
+        let body = client
+            .get_json(&url, "application/json;blockheader-encoding=object")
+            .await?;
+        trace!("Received header from {}: {}", url, body);
+        Ok(body)
+    }
+
+    /// Get a block header of a chain by its hash, decoded
+    ///
+    /// The header is requested in the binary encoding, which decodes without
+    /// parsing JSON. Nodes answering with JSON are supported as well.
+    pub async fn block_header(
+        &self,
+        chain_id: &str,
+        block_hash: &str,
+    ) -> Result<BlockHeader, FetchError> {
+        let client = self.chain(chain_id);
+        let url = format!("{}/{}", client.config().header_url()?, block_hash);
+        let response = client.get(&url, "application/octet-stream").await?;
+        trace!("Received {} byte header from {}", response.body.len(), url);
+        if response
+            .header("Content-Type")
+            .is_some_and(|ct| ct.contains("json"))
+        {
+            BlockHeader::from_value(&response.json_body()?)
+        } else {
+            BlockHeader::from_bytes(&response.body)
+        }
+    }
+
+    /// Get the block headers of a chain between two heights, both included
+    ///
+    /// Headers are requested in the default encoding of header pages, base64url
+    /// binary headers, and pages are followed until the range is complete.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+    /// for header in client.block_headers("0", 4_000_000, 4_000_099).await? {
+    ///     header.validate_pow()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn block_headers(
+        &self,
+        chain_id: &str,
+        min_height: u64,
+        max_height: u64,
+    ) -> Result<Vec<BlockHeader>, FetchError> {
+        let client = self.chain(chain_id);
+        let base_url = format!(
+            "{}?minheight={}&maxheight={}",
+            client.config().header_url()?,
+            min_height,
+            max_height
+        );
+        let mut headers = Vec::new();
+        let mut next: Option<String> = None;
+        loop {
+            let url = match &next {
+                Some(cursor) => format!("{}&next={}", base_url, cursor),
+                None => base_url.clone(),
+            };
+            let page = client.get_json(&url, "application/json").await?;
+            let items = page["items"]
+                .as_array()
+                .ok_or_else(|| FetchError::ApiError(format!("Unexpected header page: {}", page)))?;
+            for item in items {
+                headers.push(BlockHeader::from_value(item)?);
+            }
+            match page["next"].as_str() {
+                Some(cursor) if !items.is_empty() => next = Some(cursor.to_string()),
+                _ => return Ok(headers),
+            }
+        }
+    }
+
+    /// Open a long-lived GET request, not subject to the request timeout
+    ///
+    /// Returns the chunks of the body as they arrive. Custom transports
+    /// return the complete body as a single chunk.
+    pub(crate) async fn open_stream(
+        &self,
+        url: &str,
+    ) -> Result<BoxStream<'static, Result<Vec<u8>, FetchError>>, FetchError> {
+        if self.transport.is_some() {
+            let response = self.chain("0").get(url, "text/event-stream").await?;
+            return Ok(stream::once(future::ready(Ok(response.body))).boxed());
+        }
+
+        let mut request = self
+            .stream_client
+            .get(url)
+            .header("Accept", "text/event-stream");
+        for (name, value) in self.config("0").auth_headers() {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("API error: {}", error_text);
+            return Err(FetchError::ApiError(error_text));
+        }
+
+        Ok(stream::unfold(Some(response), |response| async move {
+            let mut response = response?;
+            match response.chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk.to_vec()), Some(response))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e.into()), None)),
+            }
+        })
+        .boxed())
+    }
+
+    /// Get the current cut of the network
+    ///
+    /// The cut lists the latest block hash and height of every chain.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+    /// let cut = client.cut().await?;
+    /// println!("cut height {}", cut["height"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn cut(&self) -> Result<Value, FetchError> {
+        let client = self.chain("0");
+        let url = client.config().cut_url()?;
+        let body = client.get_json(&url, "application/json").await?;
+        trace!("Received cut from {}: {}", url, body);
+        Ok(body)
+    }
+}
+
+fn build_http_client(timeout: u64) -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+fn build_stream_client(connect_timeout: u64) -> Client {
+    Client::builder()
+        .connect_timeout(Duration::from_secs(connect_timeout))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// Extract the balance from a `coin.details` local response
+///
+/// Returns `None` when the account does not exist on the queried chain.
+fn parse_balance(response: &Value) -> Result<Option<Decimal>, FetchError> {
+    let result = &response["result"];
+    match result["status"].as_str() {
+        Some("success") => parse_decimal(&result["data"]["balance"])
+            .map(Some)
+            .ok_or_else(|| {
+                FetchError::ApiError(format!("Invalid balance in response: {}", result))
+            }),
+        Some("failure") => {
+            let message = result["error"]["message"].as_str().unwrap_or_default();
+            if is_row_not_found(message) {
+                Ok(None)
+            } else {
+                Err(FetchError::ApiError(message.to_string()))
+            }
+        }
+        _ => Err(FetchError::ApiError(format!(
+            "Unexpected response: {}",
+            response
+        ))),
+    }
+}
+
+/// Returns true if a Pact error message reports a missing table row, e.g. an unknown account
+pub(crate) fn is_row_not_found(message: &str) -> bool {
+    message.contains("row not found") || message.contains("No value found")
+}
+
+/// Parse a Pact decimal, encoded as a JSON number, a string or `{"decimal": "..."}`
+pub(crate) fn parse_decimal(value: &Value) -> Option<Decimal> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(obj) => match obj.get("decimal").or_else(|| obj.get("int"))? {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    text.parse()
+        .ok()
+        .or_else(|| Decimal::from_scientific(&text).ok())
+}
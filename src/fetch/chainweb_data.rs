@@ -0,0 +1,258 @@
+//! Indexed account history from chainweb-data
+//!
+//! Chainweb nodes do not index transactions by account. This module queries a
+//! [chainweb-data](https://github.com/kadena-io/chainweb-data) indexer for the
+//! transfers of an account and follows its pagination cursors.
+
+use std::time::Duration;
+
+use log::{error, trace};
+use reqwest::{Client, Url};
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::{chainweb_client::parse_decimal, FetchError};
+
+/// Response header carrying the cursor of the next page
+const NEXT_HEADER: &str = "Chainweb-Next";
+
+/// Default number of entries requested per page
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Direction of a transfer relative to the queried account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    /// Funds received by the account
+    Incoming,
+    /// Funds sent by the account
+    Outgoing,
+}
+
+/// A transfer involving an account
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// Whether the account sent or received the funds
+    pub direction: TransferDirection,
+    /// The other account of the transfer, empty for coinbase and burns
+    pub counterparty: String,
+    /// The amount transferred
+    pub amount: Decimal,
+    /// The token transferred, e.g. `coin`
+    pub token: String,
+    /// Request key of the transaction
+    pub request_key: String,
+    /// The chain the transfer happened on
+    pub chain_id: String,
+    /// Height of the block including the transaction
+    pub height: u64,
+    /// Creation time of the block, as reported by the indexer (ISO 8601)
+    pub block_time: String,
+    /// Target chain of a cross-chain transfer
+    pub cross_chain_id: Option<String>,
+}
+
+/// A page of account history
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryPage {
+    /// Entries of the page, most recent first
+    pub entries: Vec<HistoryEntry>,
+    /// Cursor of the next page, `None` on the last page
+    pub next: Option<String>,
+}
+
+/// Client for a chainweb-data indexer
+#[derive(Debug, Clone)]
+pub struct ChainwebDataClient {
+    base_url: String,
+    token: String,
+    page_size: u32,
+    client: Client,
+}
+
+impl ChainwebDataClient {
+    /// Create a client for a chainweb-data instance
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::ChainwebDataClient;
+    ///
+    /// let client = ChainwebDataClient::new("https://estats.testnet.chainweb.com")
+    ///     .with_page_size(100);
+    /// ```
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: "coin".to_string(),
+            page_size: DEFAULT_PAGE_SIZE,
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to create HTTP client"),
+        }
+    }
+
+    /// Set the token whose transfers are listed, `coin` by default
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = token.to_string();
+        self
+    }
+
+    /// Set the number of entries requested per page
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Fetch a page of the transfer history of an account
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account name
+    /// * `chain_filter` - Optional chain to restrict the history to
+    /// * `cursor` - Cursor returned with the previous page, `None` for the first page
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::ChainwebDataClient;
+    ///
+    /// let client = ChainwebDataClient::new("https://estats.testnet.chainweb.com");
+    /// let mut page = client.history("k:abc123", None, None).await?;
+    /// while let Some(cursor) = page.next.clone() {
+    ///     page = client.history("k:abc123", None, Some(&cursor)).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn history(
+        &self,
+        account: &str,
+        chain_filter: Option<&str>,
+        cursor: Option<&str>,
+    ) -> Result<HistoryPage, FetchError> {
+        let mut url = Url::parse(&self.base_url)
+            .map_err(|e| FetchError::ApiError(format!("Invalid base URL: {}", e)))?;
+        url.path_segments_mut()
+            .map_err(|_| FetchError::ApiError("Invalid base URL".to_string()))?
+            .pop_if_empty()
+            .extend(["txs", "account", account]);
+
+        let mut query = vec![
+            ("token", self.token.clone()),
+            ("limit", self.page_size.to_string()),
+        ];
+        if let Some(chain_id) = chain_filter {
+            query.push(("chain", chain_id.to_string()));
+        }
+        if let Some(cursor) = cursor {
+            query.push(("next", cursor.to_string()));
+        }
+
+        let response = self.client.get(url).query(&query).send().await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("API error: {}", error_text);
+            return Err(FetchError::ApiError(error_text));
+        }
+
+        let next = response
+            .headers()
+            .get(NEXT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        let body: Value = response.json().await?;
+        trace!("Received {} history response: {}", account, body);
+
+        let entries = body
+            .as_array()
+            .ok_or_else(|| FetchError::ApiError(format!("Unexpected response: {}", body)))?
+            .iter()
+            .map(|entry| parse_entry(account, entry))
+            .collect::<Result<_, _>>()?;
+        Ok(HistoryPage { entries, next })
+    }
+
+    /// Returns true if the indexer knows a transaction with this request key
+    ///
+    /// Unlike [`ApiClient::find_mined`](crate::ApiClient::find_mined), the
+    /// indexer covers all chains and the whole history of the network.
+    pub async fn is_mined(&self, request_key: &str) -> Result<bool, FetchError> {
+        let url = format!("{}/txs/tx", self.base_url);
+        let response = self
+            .client
+            .get(url)
+            .query(&[("requestkey", request_key)])
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            error!("API error: {}", error_text);
+            return Err(FetchError::ApiError(error_text));
+        }
+
+        let body: Value = response.json().await?;
+        trace!("Received {} transaction response: {}", request_key, body);
+        Ok(match &body {
+            Value::Array(txs) => !txs.is_empty(),
+            Value::Null => false,
+            _ => true,
+        })
+    }
+
+    /// Fetch the complete transfer history of an account, following all pages
+    pub async fn history_all(
+        &self,
+        account: &str,
+        chain_filter: Option<&str>,
+    ) -> Result<Vec<HistoryEntry>, FetchError> {
+        let mut page = self.history(account, chain_filter, None).await?;
+        let mut entries = std::mem::take(&mut page.entries);
+        while let Some(cursor) = page.next.take() {
+            page = self.history(account, chain_filter, Some(&cursor)).await?;
+            entries.append(&mut page.entries);
+        }
+        Ok(entries)
+    }
+}
+
+fn parse_entry(account: &str, entry: &Value) -> Result<HistoryEntry, FetchError> {
+    let invalid = |field: &str| {
+        FetchError::ApiError(format!("Invalid {} in history entry: {}", field, entry))
+    };
+    let text = |field: &str| entry[field].as_str().unwrap_or_default().to_string();
+    let chain = |value: &Value| match value {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s.clone()),
+        _ => None,
+    };
+
+    let from = text("fromAccount");
+    let to = text("toAccount");
+    let (direction, counterparty) = if to == account && from != account {
+        (TransferDirection::Incoming, from)
+    } else {
+        (TransferDirection::Outgoing, to)
+    };
+    let counterparty = entry["crossChainAccount"]
+        .as_str()
+        .map_or(counterparty, ToString::to_string);
+
+    Ok(HistoryEntry {
+        direction,
+        counterparty,
+        amount: parse_decimal(&entry["amount"]).ok_or_else(|| invalid("amount"))?,
+        token: text("token"),
+        request_key: text("requestKey"),
+        chain_id: chain(&entry["chain"])
+            .or_else(|| chain(&entry["chainid"]))
+            .ok_or_else(|| invalid("chain"))?,
+        height: entry["height"].as_u64().ok_or_else(|| invalid("height"))?,
+        block_time: text("blockTime"),
+        cross_chain_id: chain(&entry["crossChainId"]),
+    })
+}
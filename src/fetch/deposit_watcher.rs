@@ -0,0 +1,333 @@
+//! Detection of confirmed deposits
+//!
+//! A [`DepositWatcher`] subscribes to the events of new blocks on all chains,
+//! keeps the transfers crediting a set of watched accounts pending until
+//! enough blocks were mined on top of them, and then reports them as
+//! [`Deposit`]s. A deposit is only reported if its block is an ancestor of
+//! the latest block of its chain. Blocks of a fork that the header stream
+//! skipped are fetched and scanned as well, so deposits in blocks replaced by
+//! a fork are dropped, and reported again if the new fork includes them.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    sync::Arc,
+};
+
+use futures::{stream, Stream, StreamExt};
+use rust_decimal::Decimal;
+
+use crate::{
+    chainweb_client::parse_decimal,
+    light_client::check_requested,
+    pact::{validate_principal, Guard},
+    BlockHeader, CancellationToken, ChainwebClient, EventFilter, FetchError, HeaderUpdate,
+    ObservedEvent, MAX_BACKFILL,
+};
+
+/// Default number of blocks mined on top of a deposit before it is reported
+pub const DEFAULT_CONFIRMATION_DEPTH: u64 = 6;
+
+/// A confirmed transfer to a watched account
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deposit {
+    /// The watched account credited
+    pub account: String,
+    /// The sending account, empty for the finish of a cross-chain transfer
+    pub sender: String,
+    /// The amount credited
+    pub amount: Decimal,
+    /// The token transferred, e.g. `coin`
+    pub token: String,
+    /// Request key of the transaction
+    pub request_key: String,
+    /// The chain the deposit happened on
+    pub chain_id: u32,
+    /// Height of the block including the transaction
+    pub height: u64,
+    /// Hash of the block including the transaction
+    pub block_hash: String,
+}
+
+/// Watches accounts on all chains for confirmed deposits
+///
+/// # Examples
+///
+/// ```ignore
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use futures::StreamExt;
+/// use kadena::fetch::{ChainwebClient, DepositWatcher};
+/// use kadena::pact::KeySet;
+///
+/// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+/// let mut watcher = DepositWatcher::new(&client).with_confirmations(10);
+/// watcher.watch("k:abc123", KeySet::keys_all(["abc123"]))?;
+///
+/// let mut deposits = Box::pin(watcher.deposits().await?);
+/// while let Some(deposit) = deposits.next().await {
+///     let deposit = deposit?;
+///     println!("{} received {} on chain {}", deposit.account, deposit.amount, deposit.chain_id);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct DepositWatcher<'a> {
+    client: &'a ChainwebClient,
+    token: String,
+    confirmations: u64,
+    cancellation: Option<CancellationToken>,
+    accounts: HashSet<String>,
+}
+
+impl<'a> DepositWatcher<'a> {
+    /// Create a watcher of `coin` deposits following the blocks of `client`
+    pub fn new(client: &'a ChainwebClient) -> Self {
+        Self {
+            client,
+            token: "coin".to_string(),
+            confirmations: DEFAULT_CONFIRMATION_DEPTH,
+            cancellation: None,
+            accounts: HashSet::new(),
+        }
+    }
+
+    /// Watch deposits of a `fungible-v2` token other than `coin`
+    pub fn with_token(mut self, module: &str) -> Self {
+        self.token = module.to_string();
+        self
+    }
+
+    /// Set the number of blocks mined on top of a deposit before it is reported
+    ///
+    /// Zero reports deposits as soon as their block is seen.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// End the [`deposits`](Self::deposits) stream once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Watch a principal account, checking first that it belongs to `guard`
+    ///
+    /// A deposit address with a typo would otherwise be watched, and funds
+    /// sent to it lost. Fails with the error of
+    /// [`validate_principal`].
+    pub fn watch(&mut self, account: &str, guard: impl Into<Guard>) -> Result<(), FetchError> {
+        validate_principal(account, &guard.into())?;
+        self.accounts.insert(account.to_string());
+        Ok(())
+    }
+
+    /// Watch an account without checking its name against a guard
+    ///
+    /// For vanity accounts, whose names are not derived from their guards.
+    pub fn watch_unchecked(&mut self, account: &str) {
+        self.accounts.insert(account.to_string());
+    }
+
+    /// Stop watching an account
+    ///
+    /// Returns false if the account was not watched.
+    pub fn unwatch(&mut self, account: &str) -> bool {
+        self.accounts.remove(account)
+    }
+
+    /// Returns true if deposits to `account` are watched
+    pub fn is_watched(&self, account: &str) -> bool {
+        self.accounts.contains(account)
+    }
+
+    /// Turn the watcher into a stream of confirmed deposits
+    ///
+    /// Deposits of a chain are reported in block order. The stream ends when
+    /// the node closes the header stream, or once the cancellation token is
+    /// cancelled; deposits still pending confirmation are then not reported.
+    pub async fn deposits(
+        self,
+    ) -> Result<impl Stream<Item = Result<Deposit, FetchError>> + 'a, FetchError> {
+        let updates = Box::pin(self.client.header_updates().await?);
+        let token = self.cancellation.clone().unwrap_or_default();
+        let accounts = Arc::new(self.accounts.clone());
+        let filter = EventFilter::new()
+            .with_module(self.token.clone())
+            .with_name("TRANSFER")
+            .with_predicate(move |event| {
+                event
+                    .param(1)
+                    .and_then(|account| account.as_str())
+                    .is_some_and(|account| accounts.contains(account))
+            });
+        let state = (self, filter, updates, Tracked::default(), VecDeque::new());
+
+        Ok(token.stream(stream::unfold(
+            state,
+            |(watcher, filter, mut updates, mut tracked, mut ready)| async move {
+                loop {
+                    if let Some(deposit) = ready.pop_front() {
+                        return Some((Ok(deposit), (watcher, filter, updates, tracked, ready)));
+                    }
+                    let update = match updates.next().await? {
+                        Ok(update) => update,
+                        Err(e) => {
+                            return Some((Err(e), (watcher, filter, updates, tracked, ready)))
+                        }
+                    };
+                    match watcher.observe(update, &filter, &mut tracked).await {
+                        Ok(confirmed) => ready.extend(confirmed),
+                        Err(e) => {
+                            return Some((Err(e), (watcher, filter, updates, tracked, ready)))
+                        }
+                    }
+                }
+            },
+        )))
+    }
+
+    /// Track the deposits of a new block, returning those it confirms
+    ///
+    /// Ancestors of the block not seen yet, e.g. the blocks of a fork
+    /// replacing seen ones, are fetched and scanned first, up to
+    /// [`MAX_BACKFILL`] blocks.
+    async fn observe(
+        &self,
+        update: HeaderUpdate,
+        filter: &EventFilter,
+        tracked: &mut Tracked,
+    ) -> Result<Vec<Deposit>, FetchError> {
+        let tip = update.header.clone();
+        let chain_id = tip.chain_id;
+        if tracked.headers.contains_key(&tip.hash) {
+            return Ok(Vec::new());
+        }
+
+        let mut missing = Vec::new();
+        if let Some(lowest) = tracked.lowest_height(chain_id) {
+            let mut parent = tip.parent.clone();
+            let mut height = tip.height;
+            while height > lowest && !tracked.headers.contains_key(&parent) {
+                if missing.len() == MAX_BACKFILL {
+                    return Err(FetchError::InvalidHeader(format!(
+                        "block {} forks more than {} blocks below the watched ones",
+                        tip.hash, MAX_BACKFILL
+                    )));
+                }
+                let ancestor = self
+                    .client
+                    .block_header(&chain_id.to_string(), &parent)
+                    .await?;
+                check_requested(&ancestor, &parent)?;
+                parent = ancestor.parent.clone();
+                height = ancestor.height;
+                missing.push(ancestor);
+            }
+        }
+
+        // The transaction count of fetched headers is unknown, so their
+        // payloads are always fetched
+        let updates = missing
+            .into_iter()
+            .rev()
+            .map(|header| HeaderUpdate {
+                header,
+                tx_count: 1,
+            })
+            .chain([update]);
+        for update in updates {
+            let header = update.header.clone();
+            for observed in self.client.block_events(update, filter).await? {
+                tracked.pending.push(self.deposit(observed)?);
+            }
+            tracked.headers.insert(header.hash.clone(), header);
+        }
+
+        // Deposits deep enough are reported if their block is on the branch
+        // of the tip, and dropped otherwise
+        let branch = tracked.branch(&tip);
+        let (mut deep, rest): (Vec<Deposit>, _) = std::mem::take(&mut tracked.pending)
+            .into_iter()
+            .partition(|deposit| {
+                deposit.chain_id == chain_id && deposit.height + self.confirmations <= tip.height
+            });
+        tracked.pending = rest;
+        tracked.headers.retain(|_, header| {
+            header.chain_id != chain_id || header.height + self.confirmations >= tip.height
+        });
+        deep.sort_by_key(|deposit| deposit.height);
+        Ok(deep
+            .into_iter()
+            .filter(|deposit| branch.contains(&deposit.block_hash))
+            .collect())
+    }
+
+    fn deposit(&self, observed: ObservedEvent) -> Result<Deposit, FetchError> {
+        let event = &observed.event;
+        let text = |index| {
+            event
+                .param(index)
+                .and_then(|value| value.as_str())
+                .map(ToString::to_string)
+        };
+        let invalid = || {
+            FetchError::InvalidPayload(format!(
+                "invalid {} event in {}",
+                event.qualified_name(),
+                observed.request_key
+            ))
+        };
+        Ok(Deposit {
+            account: text(1).ok_or_else(invalid)?,
+            sender: text(0).ok_or_else(invalid)?,
+            amount: event.param(2).and_then(parse_decimal).ok_or_else(invalid)?,
+            token: self.token.clone(),
+            request_key: observed.request_key,
+            chain_id: observed.chain_id,
+            height: observed.height,
+            block_hash: observed.block_hash,
+        })
+    }
+}
+
+/// Deposits pending confirmation and the recent blocks of all chains
+#[derive(Default)]
+struct Tracked {
+    pending: Vec<Deposit>,
+    headers: HashMap<String, BlockHeader>,
+}
+
+impl Tracked {
+    /// Height of the lowest block of a chain kept
+    fn lowest_height(&self, chain_id: u32) -> Option<u64> {
+        self.headers
+            .values()
+            .filter(|header| header.chain_id == chain_id)
+            .map(|header| header.height)
+            .min()
+    }
+
+    /// Hashes of `tip` and its ancestors among the kept blocks
+    fn branch(&self, tip: &BlockHeader) -> HashSet<String> {
+        let mut branch = HashSet::from([tip.hash.clone()]);
+        let mut parent = &tip.parent;
+        while let Some(header) = self.headers.get(parent) {
+            if !branch.insert(header.hash.clone()) {
+                break;
+            }
+            parent = &header.parent;
+        }
+        branch
+    }
+}
+
+impl fmt::Debug for DepositWatcher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DepositWatcher")
+            .field("token", &self.token)
+            .field("confirmations", &self.confirmations)
+            .field("accounts", &self.accounts)
+            .finish()
+    }
+}
@@ -0,0 +1,187 @@
+//! Typed queries of deployed modules and registered keysets
+//!
+//! `describe-module` and `describe-keyset` are run as local calls and their
+//! results parsed into [`ModuleDescription`] and [`KeySet`], e.g. to verify a
+//! deployment or to pin a [`ModuleUpgrade`](crate::pact::ModuleUpgrade) to the
+//! deployed module hash. [`ApiClient::verify_deployed_module`] checks that
+//! the modules an application calls are the versions it was written against,
+//! and [`ApiClient::check_interface`] that they implement the interfaces
+//! called on them.
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    pact::{
+        command::Cmd, describe_keyset_code, meta::Meta, KeySet, ModuleDescription,
+        ModuleExpectation, TokenTransfer,
+    },
+    ApiClient, FetchError,
+};
+
+impl ApiClient {
+    /// Describe a deployed module
+    ///
+    /// Returns `None` when no module of that name is deployed on the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    /// use kadena::pact::ModuleUpgrade;
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let deployed = client.describe_module("free.my-module").await?.expect("deployed");
+    /// let upgrade = ModuleUpgrade::pinned_to(&deployed, "(module my-module GOV ...)");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn describe_module(
+        &self,
+        module: &str,
+    ) -> Result<Option<ModuleDescription>, FetchError> {
+        self.local_read(&ModuleDescription::query_code(module), |message| {
+            message.contains("Cannot find module") || message.contains("not found")
+        })
+        .await
+    }
+
+    /// Check that the deployed version of a module is the expected one
+    ///
+    /// `expected` is a module hash or the source of the module, see
+    /// [`ModuleExpectation`]. Returns the description of the deployed module,
+    /// or [`FetchError::UnexpectedModule`] if the module is not deployed or its
+    /// hash differs.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.chainweb.com", "mainnet01", "0"));
+    /// // Refuse to call a dependency that was upgraded since it was audited
+    /// client
+    ///     .verify_deployed_module("free.dex", "ZHbRj0x9yAv1yBQAlSt5WzMb6nwLcbdPjCkmbhXFhYI")
+    ///     .await?;
+    /// client
+    ///     .verify_deployed_module("free.oracle", include_str!("oracle.pact"))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn verify_deployed_module(
+        &self,
+        module: &str,
+        expected: impl Into<ModuleExpectation>,
+    ) -> Result<ModuleDescription, FetchError> {
+        let expected = expected.into();
+        let deployed = self.deployed_module(module).await?;
+        if !deployed.matches(&expected) {
+            return Err(FetchError::UnexpectedModule(format!(
+                "{} is deployed with hash {}, expected {}",
+                module,
+                deployed.hash,
+                expected.expected_hash()
+            )));
+        }
+        Ok(deployed)
+    }
+
+    /// Check that a deployed module implements `interface`
+    ///
+    /// Returns the description of the module. Fails with
+    /// [`CommandError::MissingInterface`](crate::pact::CommandError::MissingInterface)
+    /// if it does not implement the interface, and with
+    /// [`FetchError::UnexpectedModule`] if it is not deployed.
+    pub async fn check_interface(
+        &self,
+        module: &str,
+        interface: &str,
+    ) -> Result<ModuleDescription, FetchError> {
+        let deployed = self.deployed_module(module).await?;
+        deployed.require_interface(interface)?;
+        Ok(deployed)
+    }
+
+    /// Create a transfer builder for a token, once its module is checked to
+    /// implement `fungible-v2`
+    ///
+    /// See [`TokenTransfer::checked`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.chainweb.com", "mainnet01", "0"));
+    /// let token = client.token_transfer("free.some-token").await?;
+    /// let transfer = token.transfer("k:alice", "k:bob", Decimal::new(25, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn token_transfer(&self, module: &str) -> Result<TokenTransfer, FetchError> {
+        let deployed = self.deployed_module(module).await?;
+        Ok(TokenTransfer::checked(&deployed)?)
+    }
+
+    /// Describe a keyset registered with `define-keyset`
+    ///
+    /// Returns `None` when no keyset of that name is registered on the chain.
+    pub async fn describe_keyset(&self, name: &str) -> Result<Option<KeySet>, FetchError> {
+        self.local_read(&describe_keyset_code(name), |message| {
+            message.contains("not found") || message.contains("No such keyset")
+        })
+        .await
+    }
+
+    /// Describe a module, failing with [`FetchError::UnexpectedModule`] if it is not deployed
+    async fn deployed_module(&self, module: &str) -> Result<ModuleDescription, FetchError> {
+        self.describe_module(module).await?.ok_or_else(|| {
+            FetchError::UnexpectedModule(format!(
+                "{} is not deployed on chain {}",
+                module,
+                self.config().chain_id
+            ))
+        })
+    }
+
+    /// Runs `code` locally and parses its result
+    ///
+    /// Returns `None` when the failure message is one `not_found` recognizes.
+    pub(crate) async fn local_read<T: DeserializeOwned>(
+        &self,
+        code: &str,
+        not_found: fn(&str) -> bool,
+    ) -> Result<Option<T>, FetchError> {
+        let cmd = Cmd::prepare_exec(
+            &[],
+            Vec::new(),
+            None,
+            code,
+            None,
+            Meta::new(&self.config().chain_id, ""),
+            self.config().network_id(),
+        )?;
+
+        let response = self.local(&cmd).await?;
+        let result = &response["result"];
+        match result["status"].as_str() {
+            Some("success") => Ok(Some(serde_json::from_value(result["data"].clone())?)),
+            Some("failure") => {
+                let message = result["error"]["message"].as_str().unwrap_or_default();
+                if not_found(message) {
+                    Ok(None)
+                } else {
+                    Err(FetchError::ApiError(message.to_string()))
+                }
+            }
+            _ => Err(FetchError::ApiError(format!(
+                "Unexpected response: {}",
+                response
+            ))),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! On-demand mining of development networks
+//!
+//! Development networks such as `kadena/devnet` mine blocks only when asked
+//! to, through their `make-blocks` endpoint. Integration tests request blocks
+//! right after sending their commands instead of waiting for a block cadence.
+
+use serde_json::json;
+
+use crate::{pact::NetworkId, ApiClient, FetchError};
+
+/// Path of the on-demand mining endpoint, relative to the node's base URL
+pub const MAKE_BLOCKS_PATH: &str = "/make-blocks";
+
+impl ApiClient {
+    /// Ask a development network to mine `count` blocks on the client's chain
+    ///
+    /// Fails with [`FetchError::MiningUnavailable`] on mainnet and testnet,
+    /// which only mine at their block cadence.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("http://localhost:8080", "development", "0"));
+    /// let response = client.send(&cmd).await?;
+    /// client.request_blocks(1).await?;
+    /// let result = client.listen("request-key").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request_blocks(&self, count: u32) -> Result<(), FetchError> {
+        let config = self.config();
+        if matches!(
+            NetworkId::from(config.network.as_str()),
+            NetworkId::Mainnet | NetworkId::Testnet
+        ) {
+            return Err(FetchError::MiningUnavailable(config.network.clone()));
+        }
+        let url = format!("{}{}", config.base_url, MAKE_BLOCKS_PATH);
+        let payload = json!({ config.chain_id.as_str(): count });
+        self.post(&url, &payload).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Subscription to Pact events as blocks are mined
+//!
+//! [`ChainwebClient::subscribe_events`] follows the header stream, fetches the
+//! payload of every block containing transactions and delivers the events of
+//! its transaction outputs that match an [`EventFilter`]. Coinbase outputs are
+//! not inspected.
+
+use std::{fmt, sync::Arc};
+
+use futures::{stream, Stream, StreamExt};
+use serde_json::Value;
+
+use crate::{pact::PactEvent, BlockPayload, ChainwebClient, FetchError, HeaderUpdate};
+
+type EventPredicate = Arc<dyn Fn(&PactEvent) -> bool + Send + Sync>;
+
+/// Selects events by module, name and parameters
+///
+/// An empty filter matches every event.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::EventFilter;
+/// use kadena::pact::PactEvent;
+/// use serde_json::json;
+///
+/// let filter = EventFilter::transfers_to("k:bob");
+/// let event: PactEvent = serde_json::from_value(json!({
+///     "name": "TRANSFER",
+///     "module": {"namespace": null, "name": "coin"},
+///     "params": ["k:alice", "k:bob", 1.5]
+/// }))
+/// .unwrap();
+/// assert!(filter.matches(&event));
+/// ```
+#[derive(Clone, Default)]
+pub struct EventFilter {
+    module: Option<String>,
+    name: Option<String>,
+    params: Vec<(usize, Value)>,
+    predicates: Vec<EventPredicate>,
+}
+
+impl fmt::Debug for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventFilter")
+            .field("module", &self.module)
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+impl EventFilter {
+    /// Create a filter matching every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select `coin.TRANSFER` events crediting `account`
+    pub fn transfers_to(account: &str) -> Self {
+        Self::new()
+            .with_module("coin")
+            .with_name("TRANSFER")
+            .with_param(1, Value::from(account))
+    }
+
+    /// Only match events of a module, e.g. `coin` or `free.my-module`
+    pub fn with_module(mut self, module: impl Into<String>) -> Self {
+        self.module = Some(module.into());
+        self
+    }
+
+    /// Only match events with a name, e.g. `TRANSFER`
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only match events whose parameter at `index` equals `value`
+    pub fn with_param(mut self, index: usize, value: Value) -> Self {
+        self.params.push((index, value));
+        self
+    }
+
+    /// Only match events satisfying a predicate
+    pub fn with_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&PactEvent) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Returns true if the event passes every criterion of the filter
+    pub fn matches(&self, event: &PactEvent) -> bool {
+        self.module
+            .as_ref()
+            .is_none_or(|module| event.module.qualified() == *module)
+            && self.name.as_ref().is_none_or(|name| event.name == *name)
+            && self
+                .params
+                .iter()
+                .all(|(index, value)| event.param(*index) == Some(value))
+            && self.predicates.iter().all(|predicate| predicate(event))
+    }
+}
+
+/// An event observed in a mined block
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservedEvent {
+    /// The event
+    pub event: PactEvent,
+    /// Request key of the command that emitted the event
+    pub request_key: String,
+    /// Chain of the block
+    pub chain_id: u32,
+    /// Height of the block
+    pub height: u64,
+    /// Hash of the block
+    pub block_hash: String,
+}
+
+impl ChainwebClient {
+    /// Subscribe to the events of new blocks matching a filter
+    ///
+    /// The stream ends when the node closes the header stream.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use futures::StreamExt;
+    /// use kadena::fetch::{ChainwebClient, EventFilter};
+    ///
+    /// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+    /// let mut deposits = Box::pin(client.subscribe_events(EventFilter::transfers_to("k:bob")).await?);
+    /// while let Some(deposit) = deposits.next().await {
+    ///     let deposit = deposit?;
+    ///     println!("{:?} received on chain {}", deposit.event.param(2), deposit.chain_id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_events(
+        &self,
+        filter: EventFilter,
+    ) -> Result<impl Stream<Item = Result<ObservedEvent, FetchError>> + '_, FetchError> {
+        let updates = self.header_updates().await?;
+        Ok(updates
+            .then(move |update| {
+                let filter = filter.clone();
+                async move { self.block_events(update?, &filter).await }
+            })
+            .flat_map(|events| match events {
+                Ok(events) => stream::iter(events.into_iter().map(Ok).collect::<Vec<_>>()),
+                Err(e) => stream::iter(vec![Err(e)]),
+            }))
+    }
+
+    /// Events of a block matching a filter
+    pub(crate) async fn block_events(
+        &self,
+        update: HeaderUpdate,
+        filter: &EventFilter,
+    ) -> Result<Vec<ObservedEvent>, FetchError> {
+        if update.tx_count == 0 {
+            return Ok(Vec::new());
+        }
+        let header = update.header;
+        let payload = self
+            .payload_with_outputs(&header.chain_id.to_string(), &header.payload_hash)
+            .await?;
+        Ok(payload_events(&payload)?
+            .into_iter()
+            .filter(|(_, event)| filter.matches(event))
+            .map(|(request_key, event)| ObservedEvent {
+                event,
+                request_key,
+                chain_id: header.chain_id,
+                height: header.height,
+                block_hash: header.hash.clone(),
+            })
+            .collect())
+    }
+}
+
+/// Events of the transaction outputs of a payload, with their request keys
+fn payload_events(payload: &BlockPayload) -> Result<Vec<(String, PactEvent)>, FetchError> {
+    let mut events = Vec::new();
+    for output in payload.outputs() {
+        let output = output?;
+        let request_key = output.req_key;
+        events.extend(
+            output
+                .events
+                .into_iter()
+                .map(|event| (request_key.clone(), event)),
+        );
+    }
+    Ok(events)
+}
@@ -0,0 +1,173 @@
+//! Funding accounts from the faucets of test networks
+//!
+//! Integration tests and demos need funded accounts. On testnet, the
+//! `user.coin-faucet` contract pays out coins and the gas of the request. On
+//! development networks, such as `kadena/devnet`, the genesis account
+//! `sender00`, whose key is public, transfers the coins.
+//!
+//! Accounts that do not exist yet are created when their guard can be derived
+//! from their name, i.e. for `k:` accounts.
+
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::{
+    crypto::PactKeypair,
+    pact::{command::Cmd, Amount, CommandError, GasStation, KeySet, Meta, NetworkId, Transfer},
+    ApiClient, FetchError,
+};
+
+/// Module of the testnet faucet contract
+pub const TESTNET_FAUCET_MODULE: &str = "user.coin-faucet";
+/// Account of the testnet faucet, paying the coins and the gas
+pub const TESTNET_FAUCET_ACCOUNT: &str = "coin-faucet";
+/// Genesis account funding development networks
+pub const DEVNET_FAUCET_ACCOUNT: &str = "sender00";
+/// Well-known secret key of [`DEVNET_FAUCET_ACCOUNT`], never to be used on a public network
+pub const DEVNET_FAUCET_SECRET_KEY: &str =
+    "251a920c403ae8c8f65f59142316af3c82b631fba46ddea92ee8c95035bd2898";
+
+/// A faucet funding accounts on a test network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faucet {
+    /// The `user.coin-faucet` contract of testnet
+    Testnet,
+    /// The `sender00` genesis account of development networks
+    Devnet,
+}
+
+impl Faucet {
+    /// Returns the faucet of a network, if it has one
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::Faucet;
+    /// use kadena::pact::NetworkId;
+    ///
+    /// assert_eq!(Faucet::for_network(&NetworkId::Testnet), Some(Faucet::Testnet));
+    /// assert_eq!(Faucet::for_network(&NetworkId::Mainnet), None);
+    /// ```
+    pub fn for_network(network: &NetworkId) -> Option<Self> {
+        match network {
+            NetworkId::Testnet => Some(Faucet::Testnet),
+            NetworkId::Development => Some(Faucet::Devnet),
+            NetworkId::Mainnet | NetworkId::Custom(_) => None,
+        }
+    }
+
+    /// Prepares the command funding `account` with `amount` KDA
+    ///
+    /// The testnet faucet is signed with a throwaway key, as its capabilities
+    /// are all the guard of the faucet account requires.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::Faucet;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let cmd = Faucet::Testnet
+    ///     .prepare("k:abc123", Decimal::new(20, 0), "0", "testnet04")
+    ///     .unwrap();
+    /// let payload = cmd.payload().unwrap();
+    /// assert_eq!(payload.meta.sender, "coin-faucet");
+    /// assert_eq!(
+    ///     payload.payload.code(),
+    ///     Some("(user.coin-faucet.create-and-request-coin \"k:abc123\" (read-keyset \"ks\") 20.0)")
+    /// );
+    /// ```
+    pub fn prepare(
+        &self,
+        account: &str,
+        amount: Decimal,
+        chain_id: &str,
+        network_id: &str,
+    ) -> Result<Cmd, CommandError> {
+        let amount = Amount::new(amount)?;
+        let guard = account
+            .strip_prefix("k:")
+            .map(|key| KeySet::keys_all([key]));
+        let network = NetworkId::from(network_id);
+        let meta = |sender| Meta::for_network(network.clone(), chain_id, sender);
+
+        match self {
+            Faucet::Testnet => {
+                let mut env_data = json!({});
+                let code = match &guard {
+                    Some(guard) => format!(
+                        "({}.create-and-request-coin {} {} {})",
+                        TESTNET_FAUCET_MODULE,
+                        Value::from(account),
+                        guard.read_keyset("ks", &mut env_data),
+                        amount.to_pact()
+                    ),
+                    None => format!(
+                        "({}.request-coin {} {})",
+                        TESTNET_FAUCET_MODULE,
+                        Value::from(account),
+                        amount.to_pact()
+                    ),
+                };
+
+                let payout =
+                    Transfer::new(TESTNET_FAUCET_ACCOUNT, account, amount.as_decimal()).cap();
+                let signer = PactKeypair::generate();
+                GasStation::new(TESTNET_FAUCET_MODULE, TESTNET_FAUCET_ACCOUNT).prepare_exec(
+                    account,
+                    &[(&signer, vec![payout])],
+                    &code,
+                    Some(env_data),
+                    meta(TESTNET_FAUCET_ACCOUNT),
+                    Some(network_id.to_string()),
+                )
+            }
+            Faucet::Devnet => {
+                let faucet = PactKeypair::from_secret_key(DEVNET_FAUCET_SECRET_KEY)?;
+                let mut transfer =
+                    Transfer::new(DEVNET_FAUCET_ACCOUNT, account, amount.as_decimal());
+                if let Some(guard) = guard {
+                    transfer = transfer.with_receiver_guard(guard);
+                }
+                transfer.prepare(
+                    &[&faucet],
+                    meta(DEVNET_FAUCET_ACCOUNT),
+                    Some(network_id.to_string()),
+                )
+            }
+        }
+    }
+}
+
+impl ApiClient {
+    /// Fund `account` with `amount` KDA from the faucet of the client's network
+    ///
+    /// Submits the request on the client's chain and returns the response of
+    /// `send`. The testnet faucet pays out at most 20 KDA per request.
+    /// Fails with [`FetchError::FaucetUnavailable`] on networks without a
+    /// faucet, such as mainnet.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("http://localhost:8080", "development", "0"));
+    /// let response = client.fund_from_faucet("k:abc123", Decimal::new(100, 0)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fund_from_faucet(
+        &self,
+        account: &str,
+        amount: Decimal,
+    ) -> Result<Value, FetchError> {
+        let config = self.config();
+        let faucet = Faucet::for_network(&NetworkId::from(config.network.as_str()))
+            .ok_or_else(|| FetchError::FaucetUnavailable(config.network.clone()))?;
+        let cmd = faucet.prepare(account, amount, &config.chain_id, &config.network)?;
+        self.send(&cmd).await
+    }
+}
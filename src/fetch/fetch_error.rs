@@ -9,7 +9,105 @@ pub enum FetchError {
     /// JSON serialization/deserialization errors
     #[error("JSON serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+    /// Command preparation errors
+    #[error("Command error: {0}")]
+    CommandError(#[from] crate::pact::CommandError),
+    /// Local storage errors
+    #[error("Storage error: {0}")]
+    StorageError(#[from] std::io::Error),
     /// API-specific errors
     #[error("API error: {0}")]
     ApiError(String),
+    /// The server answered with something other than JSON, e.g. the HTML error
+    /// page of a load balancer
+    #[error("Unexpected {content_type:?} response with status {status}: {body}")]
+    UnexpectedResponse {
+        status: u16,
+        content_type: Option<String>,
+        /// The beginning of the body
+        body: String,
+    },
+    /// A custom transport failed to carry a request
+    #[error("Transport error: {0}")]
+    TransportError(String),
+    /// An operation did not complete before its deadline
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    /// The command was already submitted recently
+    #[error("Command {0} was already submitted")]
+    DuplicateSubmission(String),
+    /// The configured endpoint does not serve the requested route
+    #[error("{endpoint:?} endpoints do not serve the {route:?} API")]
+    UnsupportedRoute {
+        route: crate::fetch::ApiRoute,
+        endpoint: crate::fetch::EndpointKind,
+    },
+    /// A response body exceeded the configured maximum size
+    #[error("Response body exceeds {limit} bytes")]
+    BodyTooLarge { limit: usize },
+    /// A block payload could not be decoded
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+    /// A block header is malformed or fails validation
+    #[error("Invalid block header: {0}")]
+    InvalidHeader(String),
+    /// An SPV proof is malformed or does not verify
+    #[error("Invalid SPV proof: {0}")]
+    InvalidProof(String),
+    /// No healthy node serves the requested route
+    #[error("No healthy node available")]
+    NoNodeAvailable,
+    /// No chain satisfies the selection criteria
+    #[error("No chain available for selection")]
+    NoChainAvailable,
+    /// The network has no faucet to fund accounts from
+    #[error("No faucet available on network {0}")]
+    FaucetUnavailable(String),
+    /// The network does not mine blocks on demand
+    #[error("On-demand mining is not available on network {0}")]
+    MiningUnavailable(String),
+    /// A cross-chain operation targets a chain it cannot reach
+    #[error("Unreachable chain: {0}")]
+    UnreachableChain(String),
+    /// A replayed request is missing from the recording
+    #[error("No recorded response for {0}")]
+    NotRecorded(String),
+    /// The operation was stopped through its cancellation token
+    #[error("Operation cancelled")]
+    Cancelled,
+    /// A deployed module is missing or differs from the expected version
+    #[error("Unexpected module: {0}")]
+    UnexpectedModule(String),
+}
+
+impl FetchError {
+    /// A stable, machine-readable code for the kind of error
+    ///
+    /// Wrapped command errors report the code of the underlying error.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::NetworkError(_) => "fetch.network",
+            Self::SerializationError(_) => "fetch.serialization",
+            Self::CommandError(e) => e.error_code(),
+            Self::StorageError(_) => "fetch.storage",
+            Self::ApiError(_) => "fetch.api",
+            Self::UnexpectedResponse { .. } => "fetch.unexpected_response",
+            Self::TransportError(_) => "fetch.transport",
+            Self::Timeout(_) => "fetch.timeout",
+            Self::DuplicateSubmission(_) => "fetch.duplicate_submission",
+            Self::UnsupportedRoute { .. } => "fetch.unsupported_route",
+            Self::BodyTooLarge { .. } => "fetch.body_too_large",
+            Self::InvalidPayload(_) => "fetch.invalid_payload",
+            Self::InvalidHeader(_) => "fetch.invalid_header",
+            Self::InvalidProof(_) => "fetch.invalid_proof",
+            Self::NoNodeAvailable => "fetch.no_node_available",
+            Self::NoChainAvailable => "fetch.no_chain_available",
+            Self::FaucetUnavailable(_) => "fetch.faucet_unavailable",
+            Self::MiningUnavailable(_) => "fetch.mining_unavailable",
+            Self::NotRecorded(_) => "fetch.not_recorded",
+            Self::UnreachableChain(_) => "fetch.unreachable_chain",
+            Self::Cancelled => "fetch.cancelled",
+            Self::UnexpectedModule(_) => "fetch.unexpected_module",
+        }
+    }
 }
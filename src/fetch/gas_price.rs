@@ -0,0 +1,168 @@
+//! Gas price suggestions from recent blocks
+//!
+//! Miners fill blocks with the best paying transactions first, so the gas
+//! prices paid in recent blocks tell what it takes to be mined when blocks are
+//! congested. [`ApiClient::suggest_gas_price`] samples them and suggests prices
+//! at a few percentiles.
+
+use futures::future::try_join_all;
+use serde_json::Value;
+
+use crate::{
+    pact::{CommandPayload, Meta},
+    ApiClient, BlockPayload, FetchError,
+};
+
+/// Lowest gas price accepted by chainweb nodes
+pub const MIN_GAS_PRICE: f64 = 0.00000001;
+/// Number of recent blocks sampled by [`ApiClient::suggest_gas_price`]
+pub const DEFAULT_GAS_PRICE_BLOCKS: u64 = 20;
+
+/// Gas prices suggested from the prices paid by recent transactions
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{GasPriceSuggestion, MIN_GAS_PRICE};
+/// use kadena::pact::Meta;
+///
+/// let prices = [0.00000001, 0.00000001, 0.0000001, 0.000001];
+/// let suggestion = GasPriceSuggestion::from_prices(&prices);
+/// assert_eq!(suggestion.low, 0.00000001);
+/// assert_eq!(suggestion.median, 0.00000001);
+/// assert_eq!(suggestion.high, 0.000001);
+///
+/// let meta = suggestion.apply(Meta::new("0", "k:abc123"));
+/// assert_eq!(meta.gas_price, suggestion.median);
+///
+/// // Without samples, the minimum price is suggested
+/// assert_eq!(GasPriceSuggestion::from_prices(&[]).high, MIN_GAS_PRICE);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasPriceSuggestion {
+    /// The 25th percentile, for transactions that can wait
+    pub low: f64,
+    /// The median, for transactions mined in the next blocks
+    pub median: f64,
+    /// The 90th percentile, for transactions that must not wait
+    pub high: f64,
+    /// Number of transactions sampled
+    pub samples: usize,
+}
+
+impl GasPriceSuggestion {
+    /// Suggests prices from sampled gas prices, never below [`MIN_GAS_PRICE`]
+    pub fn from_prices(prices: &[f64]) -> Self {
+        let mut prices: Vec<f64> = prices.iter().copied().filter(|p| p.is_finite()).collect();
+        prices.sort_by(f64::total_cmp);
+        let percentile = |p: usize| {
+            if prices.is_empty() {
+                return MIN_GAS_PRICE;
+            }
+            // Nearest rank
+            let rank = (p * prices.len()).div_ceil(100).max(1);
+            prices[rank - 1].max(MIN_GAS_PRICE)
+        };
+        Self {
+            low: percentile(25),
+            median: percentile(50),
+            high: percentile(90),
+            samples: prices.len(),
+        }
+    }
+
+    /// Sets the median price as the gas price of `meta`
+    pub fn apply(&self, meta: Meta) -> Meta {
+        meta.with_gas_price(self.median)
+    }
+}
+
+impl ApiClient {
+    /// Suggest gas prices from the last [`DEFAULT_GAS_PRICE_BLOCKS`] blocks of the chain
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    /// use kadena::pact::Meta;
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.chainweb.com", "mainnet01", "0"));
+    /// let suggestion = client.suggest_gas_price().await?;
+    /// let meta = suggestion.apply(Meta::new("0", "k:abc123"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn suggest_gas_price(&self) -> Result<GasPriceSuggestion, FetchError> {
+        self.suggest_gas_price_over(DEFAULT_GAS_PRICE_BLOCKS).await
+    }
+
+    /// Suggest gas prices from the last `blocks` blocks of the chain
+    pub async fn suggest_gas_price_over(
+        &self,
+        blocks: u64,
+    ) -> Result<GasPriceSuggestion, FetchError> {
+        let prices = self.recent_gas_prices(blocks).await?;
+        Ok(GasPriceSuggestion::from_prices(&prices))
+    }
+
+    /// Get the gas prices of the transactions in the last `blocks` blocks of the chain
+    ///
+    /// Needs a node serving the cut, header and payload routes, see
+    /// [`EndpointKind`](crate::EndpointKind).
+    pub async fn recent_gas_prices(&self, blocks: u64) -> Result<Vec<f64>, FetchError> {
+        if blocks == 0 {
+            return Ok(Vec::new());
+        }
+        let config = self.config();
+        let cut = self
+            .get_json(&config.cut_url()?, "application/json")
+            .await?;
+        let height = cut["hashes"][&config.chain_id]["height"]
+            .as_u64()
+            .ok_or_else(|| unexpected("cut", &cut))?;
+
+        let url = format!(
+            "{}?minheight={}&limit={}",
+            config.header_url()?,
+            (height + 1).saturating_sub(blocks),
+            blocks
+        );
+        let page = self
+            .get_json(&url, "application/json;blockheader-encoding=object")
+            .await?;
+        let payload_hashes = page["items"]
+            .as_array()
+            .ok_or_else(|| unexpected("header page", &page))?
+            .iter()
+            .map(|header| {
+                header["payloadHash"]
+                    .as_str()
+                    .ok_or_else(|| unexpected("header", header))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let payloads = try_join_all(payload_hashes.into_iter().map(|hash| async move {
+            let url = config.payload_outputs_url(hash)?;
+            let payload = self.get_json(&url, "application/json").await?;
+            Ok::<BlockPayload, FetchError>(serde_json::from_value(payload)?)
+        }))
+        .await?;
+
+        let mut prices = Vec::new();
+        for payload in &payloads {
+            for transaction in payload.transactions() {
+                let (tx, _) = transaction?;
+                let cmd = tx["cmd"]
+                    .as_str()
+                    .ok_or_else(|| unexpected("transaction", &tx))?;
+                prices.push(CommandPayload::parse(cmd)?.meta.gas_price);
+            }
+        }
+        Ok(prices)
+    }
+}
+
+fn unexpected(what: &str, value: &Value) -> FetchError {
+    FetchError::ApiError(format!("Unexpected {}: {}", what, value))
+}
@@ -0,0 +1,139 @@
+//! Streaming of new block headers
+//!
+//! Chainweb nodes publish every new block header of every chain on the
+//! `header/updates` endpoint, as server-sent events named `BlockHeader`.
+
+use std::collections::VecDeque;
+
+use futures::{stream, stream::BoxStream, Stream, StreamExt};
+use log::debug;
+use serde_json::Value;
+
+use crate::{BlockHeader, ChainwebClient, FetchError};
+
+/// A new block header announced by a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderUpdate {
+    /// The new header
+    pub header: BlockHeader,
+    /// Number of transactions in the block
+    pub tx_count: u64,
+}
+
+impl HeaderUpdate {
+    /// Parses the data of a `BlockHeader` event
+    pub fn from_value(value: &Value) -> Result<Self, FetchError> {
+        Ok(Self {
+            header: BlockHeader::from_value(&value["header"])?,
+            tx_count: value["txCount"].as_u64().unwrap_or_default(),
+        })
+    }
+}
+
+impl ChainwebClient {
+    /// Subscribe to the block headers of all chains as they are mined
+    ///
+    /// The stream ends when the node closes the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use futures::StreamExt;
+    /// use kadena::fetch::ChainwebClient;
+    ///
+    /// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+    /// let mut updates = Box::pin(client.header_updates().await?);
+    /// while let Some(update) = updates.next().await {
+    ///     let update = update?;
+    ///     println!("chain {} at height {}", update.header.chain_id, update.header.height);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn header_updates(
+        &self,
+    ) -> Result<impl Stream<Item = Result<HeaderUpdate, FetchError>>, FetchError> {
+        let url = self.config("0").header_updates_url()?;
+        let chunks = self.open_stream(&url).await?;
+        debug!("Subscribed to header updates at {}", url);
+
+        Ok(event_stream(chunks, "BlockHeader")
+            .map(|data| HeaderUpdate::from_value(&serde_json::from_str(&data?)?)))
+    }
+}
+
+/// An event of a server-sent event stream
+#[derive(Debug, Default)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Incremental parser of server-sent events
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    current: SseEvent,
+}
+
+impl SseParser {
+    /// Feeds a chunk of the stream, returning the events it completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                let event = std::mem::take(&mut self.current);
+                if !event.data.is_empty() {
+                    events.push(event);
+                }
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.current.event = value.to_string(),
+                "data" => {
+                    if !self.current.data.is_empty() {
+                        self.current.data.push('\n');
+                    }
+                    self.current.data.push_str(value);
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Streams the data of the events named `name`
+pub(crate) fn event_stream(
+    chunks: BoxStream<'static, Result<Vec<u8>, FetchError>>,
+    name: &'static str,
+) -> impl Stream<Item = Result<String, FetchError>> {
+    let state = (chunks, SseParser::default(), VecDeque::new());
+    stream::unfold(Some(state), move |state| async move {
+        let (mut chunks, mut parser, mut pending) = state?;
+        loop {
+            if let Some(event) = pending.pop_front() {
+                return Some((Ok(event), Some((chunks, parser, pending))));
+            }
+            match chunks.next().await {
+                Some(Ok(chunk)) => pending.extend(
+                    parser
+                        .push(&chunk)
+                        .into_iter()
+                        .filter(|e: &SseEvent| e.event == name)
+                        .map(|e| e.data),
+                ),
+                None => return None,
+                Some(Err(e)) => return Some((Err(e), None)),
+            }
+        }
+    })
+}
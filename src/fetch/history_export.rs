@@ -0,0 +1,172 @@
+//! Account history export for accounting
+//!
+//! [`HistoryExport`] turns the entries of
+//! [`ChainwebDataClient::history_all`](crate::ChainwebDataClient::history_all)
+//! into CSV or JSON for tax and audit workflows. Amounts and fees are written
+//! as exact decimal strings, never through floating point.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::{HistoryEntry, TransferDirection};
+
+/// Columns of the CSV export, in order
+pub const HISTORY_CSV_COLUMNS: [&str; 10] = [
+    "timestamp",
+    "direction",
+    "counterparty",
+    "amount",
+    "fee",
+    "token",
+    "request_key",
+    "chain_id",
+    "height",
+    "cross_chain_id",
+];
+
+/// Exporter of account history to CSV or JSON
+///
+/// The indexer does not report gas fees with transfers; fees known to the
+/// caller, e.g. from the results of its own commands, are added by request
+/// key and left empty otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{HistoryEntry, HistoryExport, TransferDirection};
+/// use rust_decimal::Decimal;
+///
+/// let entries = [HistoryEntry {
+///     direction: TransferDirection::Outgoing,
+///     counterparty: "k:bob".to_string(),
+///     amount: Decimal::new(1_000_000_000_001, 12),
+///     token: "coin".to_string(),
+///     request_key: "rk1".to_string(),
+///     chain_id: "0".to_string(),
+///     height: 42,
+///     block_time: "2024-01-01T00:00:00Z".to_string(),
+///     cross_chain_id: None,
+/// }];
+///
+/// let export = HistoryExport::new(&entries).with_fee("rk1", Decimal::new(25, 6));
+/// assert_eq!(
+///     export.to_csv().lines().nth(1).unwrap(),
+///     "2024-01-01T00:00:00Z,outgoing,k:bob,1.000000000001,0.000025,coin,rk1,0,42,"
+/// );
+/// assert_eq!(export.to_json()[0]["amount"], "1.000000000001");
+/// ```
+#[derive(Debug, Clone)]
+pub struct HistoryExport<'a> {
+    entries: &'a [HistoryEntry],
+    fees: HashMap<String, Decimal>,
+}
+
+impl<'a> HistoryExport<'a> {
+    /// Create an exporter of `entries`, in the given order
+    pub fn new(entries: &'a [HistoryEntry]) -> Self {
+        Self {
+            entries,
+            fees: HashMap::new(),
+        }
+    }
+
+    /// Set the gas fee paid by the transaction with `request_key`
+    pub fn with_fee(mut self, request_key: &str, fee: Decimal) -> Self {
+        self.fees.insert(request_key.to_string(), fee);
+        self
+    }
+
+    /// Set the gas fees of several transactions, by request key
+    pub fn with_fees<I, S>(mut self, fees: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Decimal)>,
+        S: Into<String>,
+    {
+        self.fees
+            .extend(fees.into_iter().map(|(key, fee)| (key.into(), fee)));
+        self
+    }
+
+    /// Export as CSV, with a header row of [`HISTORY_CSV_COLUMNS`]
+    ///
+    /// Text fields are quoted when needed, and those a spreadsheet would read
+    /// as a formula are prefixed with `'`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = HISTORY_CSV_COLUMNS.join(",");
+        csv.push('\n');
+        for entry in self.entries {
+            let row = [
+                text_cell(&entry.block_time),
+                direction(entry.direction).to_string(),
+                text_cell(&entry.counterparty),
+                entry.amount.to_string(),
+                self.fee(entry)
+                    .map(|fee| fee.to_string())
+                    .unwrap_or_default(),
+                text_cell(&entry.token),
+                text_cell(&entry.request_key),
+                text_cell(&entry.chain_id),
+                entry.height.to_string(),
+                entry
+                    .cross_chain_id
+                    .as_deref()
+                    .map(text_cell)
+                    .unwrap_or_default(),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Export as a JSON array of objects keyed like [`HISTORY_CSV_COLUMNS`]
+    ///
+    /// Amounts and fees are strings, so no precision is lost by JSON parsers
+    /// reading numbers as floats.
+    pub fn to_json(&self) -> Value {
+        self.entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "timestamp": entry.block_time,
+                    "direction": direction(entry.direction),
+                    "counterparty": entry.counterparty,
+                    "amount": entry.amount.to_string(),
+                    "fee": self.fee(entry).map(|fee| fee.to_string()),
+                    "token": entry.token,
+                    "request_key": entry.request_key,
+                    "chain_id": entry.chain_id,
+                    "height": entry.height,
+                    "cross_chain_id": entry.cross_chain_id,
+                })
+            })
+            .collect()
+    }
+
+    fn fee(&self, entry: &HistoryEntry) -> Option<Decimal> {
+        self.fees.get(&entry.request_key).copied()
+    }
+}
+
+fn direction(direction: TransferDirection) -> &'static str {
+    match direction {
+        TransferDirection::Incoming => "incoming",
+        TransferDirection::Outgoing => "outgoing",
+    }
+}
+
+/// Escape a text field for CSV
+fn text_cell(text: &str) -> String {
+    let text = if text.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
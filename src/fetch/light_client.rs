@@ -0,0 +1,282 @@
+//! Verified view of the heads of all chains
+//!
+//! [`LightClient`] starts from the current cut of a node, then follows the
+//! header stream and only accepts headers whose hash matches their contents,
+//! with a valid proof of work, that extend the headers it already holds. The
+//! resulting view of chain heights and recent block hashes is the trusted
+//! source for counting confirmations and for checking the roots of SPV proofs.
+//!
+//! The initial cut is trusted as served, so the client should be synced from
+//! a node the caller trusts; every later header is verified.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use futures::StreamExt;
+use log::{debug, warn};
+
+use crate::{BlockHeader, ChainwebClient, FetchError};
+
+/// Default number of blocks of history kept per chain
+pub const DEFAULT_HISTORY_DEPTH: u64 = 240;
+
+/// Maximum number of missing ancestors fetched to fill a gap in the stream
+pub const MAX_BACKFILL: usize = 64;
+
+#[derive(Debug, Default)]
+struct LightState {
+    heads: BTreeMap<u32, BlockHeader>,
+    headers: HashMap<String, BlockHeader>,
+}
+
+/// A client maintaining a verified view of the heads of all chains
+#[derive(Debug)]
+pub struct LightClient {
+    client: ChainwebClient,
+    history_depth: u64,
+    state: Mutex<LightState>,
+}
+
+impl LightClient {
+    /// Create a light client fetching headers through `client`
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ChainwebClient, LightClient};
+    ///
+    /// let light = LightClient::new(ChainwebClient::new("https://api.chainweb.com", "mainnet01"));
+    /// light.sync().await?;
+    /// println!("chain 0 at height {:?}", light.height(0));
+    ///
+    /// // Verify and apply new headers until the node closes the stream
+    /// light.follow().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(client: ChainwebClient) -> Self {
+        Self {
+            client,
+            history_depth: DEFAULT_HISTORY_DEPTH,
+            state: Mutex::new(LightState::default()),
+        }
+    }
+
+    /// Set the number of blocks of history kept per chain
+    pub fn with_history_depth(mut self, depth: u64) -> Self {
+        self.history_depth = depth;
+        self
+    }
+
+    /// Load the heads of all chains from the current cut of the node
+    ///
+    /// Headers received since the last sync are kept, so resyncing after a
+    /// dropped stream only moves heads forward.
+    pub async fn sync(&self) -> Result<(), FetchError> {
+        let cut = self.client.cut().await?;
+        let hashes = cut["hashes"]
+            .as_object()
+            .ok_or_else(|| FetchError::ApiError("cut without hashes".to_string()))?;
+
+        for (chain_id, head) in hashes {
+            let hash = head["hash"].as_str().ok_or_else(|| {
+                FetchError::ApiError(format!("cut without hash for chain {}", chain_id))
+            })?;
+            if self.is_known_block(chain_id.parse().unwrap_or(u32::MAX), hash) {
+                continue;
+            }
+
+            let header = self.client.block_header(chain_id, hash).await?;
+            check_requested(&header, hash)?;
+            header.validate_pow()?;
+            self.insert(header);
+        }
+        Ok(())
+    }
+
+    /// Verify a header and add it to the view
+    ///
+    /// The hash of the header must match its contents, its proof of work must
+    /// be valid and it must extend a known header of its chain. Missing
+    /// ancestors, e.g. after a dropped connection, are fetched from the node
+    /// and verified as well, up to [`MAX_BACKFILL`]. Returns true if the
+    /// header became the head of its chain.
+    pub async fn apply(&self, header: BlockHeader) -> Result<bool, FetchError> {
+        header.validate_pow()?;
+        if self.is_known_block(header.chain_id, &header.hash) {
+            return Ok(false);
+        }
+
+        let mut missing = Vec::new();
+        let mut parent = header.parent.clone();
+        while !self.is_known_block(header.chain_id, &parent) {
+            if self.height(header.chain_id).is_none() || missing.len() == MAX_BACKFILL {
+                return Err(FetchError::InvalidHeader(format!(
+                    "block {} does not extend a known header of chain {}",
+                    header.hash, header.chain_id
+                )));
+            }
+            let ancestor = self
+                .client
+                .block_header(&header.chain_id.to_string(), &parent)
+                .await?;
+            check_requested(&ancestor, &parent)?;
+            ancestor.validate_pow()?;
+            parent = ancestor.parent.clone();
+            missing.push(ancestor);
+        }
+
+        for ancestor in missing.into_iter().rev() {
+            self.accept(ancestor)?;
+        }
+        self.accept(header)
+    }
+
+    /// Follow the header stream of the node, applying every new header
+    ///
+    /// Headers that fail verification are logged and skipped. Returns when
+    /// the node closes the stream.
+    pub async fn follow(&self) -> Result<(), FetchError> {
+        let mut updates = Box::pin(self.client.header_updates().await?);
+        while let Some(update) = updates.next().await {
+            let header = update?.header;
+            let hash = header.hash.clone();
+            match self.apply(header).await {
+                Ok(_) => debug!("Applied header {}", hash),
+                Err(e) => warn!("Rejected header {}: {}", hash, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the verified height of a chain
+    pub fn height(&self, chain_id: u32) -> Option<u64> {
+        self.state
+            .lock()
+            .unwrap()
+            .heads
+            .get(&chain_id)
+            .map(|head| head.height)
+    }
+
+    /// Get the verified heights of all known chains
+    pub fn heights(&self) -> BTreeMap<u32, u64> {
+        self.state
+            .lock()
+            .unwrap()
+            .heads
+            .iter()
+            .map(|(chain, head)| (*chain, head.height))
+            .collect()
+    }
+
+    /// Get the verified head of a chain
+    pub fn head(&self, chain_id: u32) -> Option<BlockHeader> {
+        self.state.lock().unwrap().heads.get(&chain_id).cloned()
+    }
+
+    /// Number of confirmations of a block at `block_height` on a chain
+    pub fn confirmations(&self, chain_id: u32, block_height: u64) -> Option<u64> {
+        let height = self.height(chain_id)?;
+        Some(
+            height
+                .checked_sub(block_height)
+                .map_or(0, |depth| depth + 1),
+        )
+    }
+
+    /// Get the hash of the block at `height` on the verified branch of a chain
+    ///
+    /// Follows the parents of the head down to `height`. Returns `None` if the
+    /// chain is unknown, if `height` is above its head, or if the block is no
+    /// longer part of the kept history.
+    pub fn canonical_hash(&self, chain_id: u32, height: u64) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        let mut header = state.heads.get(&chain_id)?;
+        while header.height > height {
+            header = state.headers.get(&header.parent)?;
+        }
+        (header.height == height).then(|| header.hash.clone())
+    }
+
+    /// Returns true if a block is part of the verified recent history of a chain
+    pub fn is_known_block(&self, chain_id: u32, block_hash: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .headers
+            .get(block_hash)
+            .is_some_and(|header| header.chain_id == chain_id)
+    }
+
+    /// Check that an SPV proof leads to a verified block of its target chain
+    #[cfg(feature = "merkle")]
+    pub fn verify_spv(&self, proof: &crate::SpvProof) -> Result<(), FetchError> {
+        let block_hash = proof.block_hash();
+        if !self.is_known_block(proof.chain, &block_hash) {
+            return Err(FetchError::InvalidProof(format!(
+                "block {} is not a verified block of chain {}",
+                block_hash, proof.chain
+            )));
+        }
+        Ok(())
+    }
+
+    /// Adds a header whose parent is known, checking its hash and links
+    fn accept(&self, header: BlockHeader) -> Result<bool, FetchError> {
+        // Headers are stored under their hash, which SPV roots are checked against
+        header.validate_hash()?;
+        let state = self.state.lock().unwrap();
+        let parent = state.headers.get(&header.parent).ok_or_else(|| {
+            FetchError::InvalidHeader(format!("unknown parent {}", header.parent))
+        })?;
+        header.validate_parent(parent)?;
+
+        let adjacents: Vec<BlockHeader> = header
+            .adjacents
+            .iter()
+            .filter_map(|(chain, hash)| state.headers.get(hash).filter(|h| h.chain_id == *chain))
+            .cloned()
+            .collect();
+        header.validate_adjacents(&adjacents)?;
+
+        drop(state);
+        Ok(self.insert(header))
+    }
+
+    /// Stores a verified header, moving the head of its chain forward
+    fn insert(&self, header: BlockHeader) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let chain_id = header.chain_id;
+        let is_head = state
+            .heads
+            .get(&chain_id)
+            .is_none_or(|head| header.height > head.height);
+        if is_head {
+            state.heads.insert(chain_id, header.clone());
+        }
+        state.headers.insert(header.hash.clone(), header);
+
+        let floor = state.heads[&chain_id]
+            .height
+            .saturating_sub(self.history_depth);
+        state
+            .headers
+            .retain(|_, h| h.chain_id != chain_id || h.height >= floor);
+        is_head
+    }
+}
+
+/// Check that the node answered with the header that was asked for
+pub(crate) fn check_requested(header: &BlockHeader, hash: &str) -> Result<(), FetchError> {
+    if header.hash != hash {
+        return Err(FetchError::InvalidHeader(format!(
+            "asked for block {} but received {}",
+            hash, header.hash
+        )));
+    }
+    Ok(())
+}
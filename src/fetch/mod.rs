@@ -1,7 +1,68 @@
 pub mod api_client;
 pub mod api_config;
+pub mod block_header;
+pub mod cancel;
+pub mod chain_graph;
+pub mod chain_selector;
+pub mod chainweb_client;
+pub mod chainweb_data;
+pub mod deposit_watcher;
+pub mod describe;
+pub mod devnet;
+pub mod event_stream;
+pub mod faucet;
 pub mod fetch_error;
+pub mod gas_price;
+pub mod header_stream;
+pub mod history_export;
+pub mod light_client;
+pub mod node_pool;
+pub mod payload;
+pub mod pow;
+pub mod recording;
+pub mod redaction;
+pub mod reorg;
+pub mod rotation;
+pub mod signature_check;
+pub mod spv;
+#[cfg(feature = "merkle")]
+pub mod spv_proof;
+pub mod submit_guard;
+pub mod timer;
+pub mod transport;
+pub mod tx_queue;
+pub mod tx_tracker;
+pub mod withdrawal;
 
 pub use api_client::*;
 pub use api_config::*;
+pub use block_header::*;
+pub use cancel::*;
+pub use chain_graph::*;
+pub use chain_selector::*;
+pub use chainweb_client::*;
+pub use chainweb_data::*;
+pub use deposit_watcher::*;
+pub use devnet::*;
+pub use event_stream::*;
+pub use faucet::*;
 pub use fetch_error::*;
+pub use gas_price::*;
+pub use header_stream::*;
+pub use history_export::*;
+pub use light_client::*;
+pub use node_pool::*;
+pub use payload::*;
+pub use pow::*;
+pub use recording::*;
+pub use redaction::*;
+pub use reorg::*;
+pub use spv::*;
+#[cfg(feature = "merkle")]
+pub use spv_proof::*;
+pub use submit_guard::*;
+pub use timer::*;
+pub use transport::*;
+pub use tx_queue::*;
+pub use tx_tracker::*;
+pub use withdrawal::*;
@@ -0,0 +1,531 @@
+//! Failover across several Chainweb nodes
+//!
+//! Relying on a single node, typically the public gateway, makes every request
+//! fail while that node is down. [`NodePool`] keeps a list of nodes, screens
+//! them with a health probe, and runs requests against the first healthy node
+//! serving the needed route, failing over to the next one on network errors.
+//!
+//! The pool measures the latency and error rate of every node on each request
+//! and health check, and prefers the fastest healthy node. A preferred node is
+//! only replaced once another node is clearly faster, so that small latency
+//! variations do not make the pool flap between nodes.
+
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures::future::join_all;
+use log::{debug, warn};
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::{ApiClient, ApiConfig, ApiRoute, Auth, EndpointKind, FetchError, HttpResponse};
+
+/// Maximum number of peers requested from a node
+pub const DEFAULT_PEER_LIMIT: usize = 64;
+
+/// Default fraction by which a node must beat the preferred node to replace it
+pub const DEFAULT_HYSTERESIS: f64 = 0.2;
+
+/// Weight of the latest sample in the moving averages of latency and error rate
+const SMOOTHING: f64 = 0.3;
+
+/// Factor by which a node's error rate inflates its latency score
+const ERROR_PENALTY: f64 = 4.0;
+
+/// A node of a [`NodePool`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolNode {
+    /// Base URL of the node
+    pub base_url: String,
+    /// Kind of API exposed by the node
+    pub endpoint: EndpointKind,
+    /// Whether the node passed its last health check
+    pub healthy: bool,
+}
+
+/// Measurements of a node of a [`NodePool`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStats {
+    /// Base URL of the node
+    pub base_url: String,
+    /// Kind of API exposed by the node
+    pub endpoint: EndpointKind,
+    /// Whether the node passed its last health check or request
+    pub healthy: bool,
+    /// Whether the pool currently prefers this node
+    pub preferred: bool,
+    /// Moving average of the response time, if the node was measured
+    pub latency: Option<Duration>,
+    /// Moving average of the share of failed requests, between 0 and 1
+    pub error_rate: f64,
+    /// Number of requests and health checks sent to the node
+    pub requests: u64,
+    /// Number of those that failed
+    pub errors: u64,
+}
+
+#[derive(Debug)]
+struct NodeEntry {
+    node: PoolNode,
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    requests: u64,
+    errors: u64,
+}
+
+impl NodeEntry {
+    fn record(&mut self, latency: Option<Duration>) {
+        self.requests += 1;
+        match latency {
+            Some(latency) => {
+                let ms = latency.as_secs_f64() * 1000.0;
+                self.latency_ms = Some(match self.latency_ms {
+                    Some(avg) => SMOOTHING * ms + (1.0 - SMOOTHING) * avg,
+                    None => ms,
+                });
+                self.error_rate *= 1.0 - SMOOTHING;
+                self.node.healthy = true;
+            }
+            None => {
+                self.errors += 1;
+                self.error_rate = SMOOTHING + (1.0 - SMOOTHING) * self.error_rate;
+                self.node.healthy = false;
+            }
+        }
+    }
+
+    /// Lower is better; unmeasured nodes rank last
+    fn score(&self) -> f64 {
+        self.latency_ms.map_or(f64::INFINITY, |ms| {
+            ms * (1.0 + ERROR_PENALTY * self.error_rate)
+        })
+    }
+}
+
+/// A pool of nodes of one network, with failover between them
+#[derive(Debug)]
+pub struct NodePool {
+    network: String,
+    timeout: u64,
+    api_key: Option<String>,
+    auth: Option<Auth>,
+    peer_limit: usize,
+    hysteresis: f64,
+    nodes: Mutex<Vec<NodeEntry>>,
+    preferred: Mutex<Option<String>>,
+    client: Client,
+}
+
+impl NodePool {
+    /// Create an empty pool for a network
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{EndpointKind, NodePool};
+    ///
+    /// let pool = NodePool::new("mainnet01")
+    ///     .with_node("https://api.chainweb.com", EndpointKind::Gateway)
+    ///     .with_node("https://node.example.com:1848", EndpointKind::Service);
+    /// assert_eq!(pool.nodes().len(), 2);
+    /// ```
+    pub fn new(network: &str) -> Self {
+        let timeout = 30;
+        Self {
+            network: network.to_string(),
+            timeout,
+            api_key: None,
+            auth: None,
+            peer_limit: DEFAULT_PEER_LIMIT,
+            hysteresis: DEFAULT_HYSTERESIS,
+            nodes: Mutex::new(Vec::new()),
+            preferred: Mutex::new(None),
+            client: build_http_client(timeout),
+        }
+    }
+
+    /// Add a node to the pool
+    pub fn with_node(self, base_url: &str, endpoint: EndpointKind) -> Self {
+        self.add_node(base_url, endpoint);
+        self
+    }
+
+    /// Set a custom timeout
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.timeout = seconds;
+        self.client = build_http_client(seconds);
+        self
+    }
+
+    /// Set an API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Authenticate to the nodes with HTTP basic authentication
+    pub fn with_basic_auth(mut self, username: &str, password: Option<&str>) -> Self {
+        self.auth = Some(Auth::Basic {
+            username: username.to_string(),
+            password: password.map(ToString::to_string),
+        });
+        self
+    }
+
+    /// Authenticate to the nodes with a bearer token
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Bearer(token.into()));
+        self
+    }
+
+    /// Set the maximum number of peers requested by [`NodePool::discover_peers`]
+    pub fn with_peer_limit(mut self, limit: usize) -> Self {
+        self.peer_limit = limit;
+        self
+    }
+
+    /// Set the fraction by which a node must beat the preferred node to replace it
+    ///
+    /// With the default of `0.2`, a node must score at least 20% better than
+    /// the preferred node before the pool switches to it.
+    pub fn with_hysteresis(mut self, hysteresis: f64) -> Self {
+        self.hysteresis = hysteresis.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Add a node to the pool, unless it is already part of it
+    ///
+    /// New nodes are considered healthy until a check or request fails.
+    /// Returns true if the node was added.
+    pub fn add_node(&self, base_url: &str, endpoint: EndpointKind) -> bool {
+        let base_url = base_url.trim_end_matches('/');
+        let mut nodes = self.nodes.lock().unwrap();
+        if nodes.iter().any(|n| n.node.base_url == base_url) {
+            return false;
+        }
+        nodes.push(NodeEntry {
+            node: PoolNode {
+                base_url: base_url.to_string(),
+                endpoint,
+                healthy: true,
+            },
+            latency_ms: None,
+            error_rate: 0.0,
+            requests: 0,
+            errors: 0,
+        });
+        true
+    }
+
+    /// Get a snapshot of the nodes of the pool
+    pub fn nodes(&self) -> Vec<PoolNode> {
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|n| n.node.clone())
+            .collect()
+    }
+
+    /// Get the measurements of every node of the pool
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{EndpointKind, NodePool};
+    ///
+    /// let pool = NodePool::new("mainnet01").with_node("https://api.chainweb.com", EndpointKind::Gateway);
+    /// let stats = pool.pool_stats();
+    /// assert_eq!(stats[0].requests, 0);
+    /// assert!(stats[0].latency.is_none());
+    /// ```
+    pub fn pool_stats(&self) -> Vec<NodeStats> {
+        let preferred = self.preferred.lock().unwrap().clone();
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|n| NodeStats {
+                base_url: n.node.base_url.clone(),
+                endpoint: n.node.endpoint,
+                healthy: n.node.healthy,
+                preferred: preferred.as_deref() == Some(n.node.base_url.as_str()),
+                latency: n.latency_ms.map(|ms| Duration::from_secs_f64(ms / 1000.0)),
+                error_rate: n.error_rate,
+                requests: n.requests,
+                errors: n.errors,
+            })
+            .collect()
+    }
+
+    /// Get the network this pool operates on
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    /// Probe every node of the pool and record whether it is healthy
+    ///
+    /// A node is healthy if it returns the current cut or, for a Pact server,
+    /// which serves no cut, its version. The response time of the probe is
+    /// recorded in the node's latency. Returns the number of healthy nodes.
+    pub async fn check_health(&self) -> usize {
+        let nodes = self.nodes();
+        let results = join_all(nodes.iter().map(|node| self.probe(node))).await;
+
+        for (node, latency) in nodes.iter().zip(results) {
+            self.record(&node.base_url, latency);
+        }
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|n| n.node.healthy)
+            .count()
+    }
+
+    /// Discover peers of the nodes of the pool and add the healthy ones
+    ///
+    /// Peers are listed by the `cut/peer` route of the P2P API, so only nodes
+    /// serving [`ApiRoute::Peer`], P2P and gateway nodes, are asked. Peers advertise their P2P address and are
+    /// added as [`EndpointKind::P2p`] nodes once they pass a health check.
+    /// Returns the base URLs of the added nodes.
+    ///
+    /// Discovery only ever yields P2P nodes. They serve cuts, headers and
+    /// payloads, but not the Pact API, so they add no failover for `send`,
+    /// `local` or `poll`: requests for [`ApiRoute::Pact`] keep going to the
+    /// service and gateway nodes added to the pool.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{EndpointKind, NodePool};
+    ///
+    /// let pool = NodePool::new("mainnet01").with_node("https://api.chainweb.com", EndpointKind::Gateway);
+    /// let added = pool.discover_peers().await?;
+    /// println!("discovered {} peers", added.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn discover_peers(&self) -> Result<Vec<String>, FetchError> {
+        let sources: Vec<PoolNode> = self
+            .nodes()
+            .into_iter()
+            .filter(|n| n.healthy && n.endpoint.serves(ApiRoute::Peer))
+            .collect();
+
+        let mut candidates = Vec::new();
+        let mut last_error = None;
+        for source in &sources {
+            match self.peers_of(source).await {
+                Ok(peers) => candidates.extend(peers),
+                Err(e) => {
+                    warn!("Failed to list peers of {}: {}", source.base_url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        if candidates.is_empty() {
+            if let Some(e) = last_error {
+                return Err(e);
+            }
+        }
+
+        let known = self.nodes();
+        candidates.sort();
+        candidates.dedup();
+        candidates.retain(|c| !known.iter().any(|n| &n.base_url == c));
+
+        let candidates: Vec<PoolNode> = candidates
+            .into_iter()
+            .map(|base_url| PoolNode {
+                base_url,
+                endpoint: EndpointKind::P2p,
+                healthy: true,
+            })
+            .collect();
+        let results = join_all(candidates.iter().map(|node| self.probe(node))).await;
+
+        let mut added = Vec::new();
+        for (node, latency) in candidates.into_iter().zip(results) {
+            if latency.is_some() && self.add_node(&node.base_url, node.endpoint) {
+                self.record(&node.base_url, latency);
+                added.push(node.base_url);
+            }
+        }
+        Ok(added)
+    }
+
+    /// Run a request against the preferred healthy node serving `route`
+    ///
+    /// Nodes are tried from the fastest to the slowest, the preferred node
+    /// first unless another node beats it by more than the hysteresis. Nodes
+    /// failing with a network error are marked unhealthy and the request is
+    /// retried on the next node. Other errors are returned as is, since the
+    /// node did answer.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example(cmd: kadena::pact::Cmd) -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiRoute, EndpointKind, NodePool};
+    ///
+    /// let pool = NodePool::new("mainnet01").with_node("https://api.chainweb.com", EndpointKind::Gateway);
+    /// let result = pool
+    ///     .execute("0", ApiRoute::Pact, |client| async move { client.local(&cmd).await })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute<T, F, Fut>(
+        &self,
+        chain_id: &str,
+        route: ApiRoute,
+        request: F,
+    ) -> Result<T, FetchError>
+    where
+        F: Fn(ApiClient) -> Fut,
+        Fut: Future<Output = Result<T, FetchError>>,
+    {
+        let mut last_error = None;
+        for node in self.candidates(route) {
+            let started = Instant::now();
+            match request(self.api_client(&node, chain_id)).await {
+                Err(FetchError::NetworkError(e)) => {
+                    warn!("Node {} failed, trying next node: {}", node.base_url, e);
+                    self.record(&node.base_url, None);
+                    last_error = Some(FetchError::NetworkError(e));
+                }
+                result => {
+                    self.record(&node.base_url, Some(started.elapsed()));
+                    *self.preferred.lock().unwrap() = Some(node.base_url);
+                    return result;
+                }
+            }
+        }
+        Err(last_error.unwrap_or(FetchError::NoNodeAvailable))
+    }
+
+    /// Healthy nodes serving `route`, in the order requests should try them
+    fn candidates(&self, route: ApiRoute) -> Vec<PoolNode> {
+        let nodes = self.nodes.lock().unwrap();
+        let mut candidates: Vec<&NodeEntry> = nodes
+            .iter()
+            .filter(|n| n.node.healthy && n.node.endpoint.serves(route))
+            .collect();
+        candidates.sort_by(|a, b| a.score().total_cmp(&b.score()));
+
+        let preferred = self.preferred.lock().unwrap();
+        if let Some(position) = candidates
+            .iter()
+            .position(|n| preferred.as_deref() == Some(n.node.base_url.as_str()))
+        {
+            let best = candidates[0].score();
+            let current = candidates[position].score();
+            if best >= current * (1.0 - self.hysteresis) {
+                let node = candidates.remove(position);
+                candidates.insert(0, node);
+            }
+        }
+        candidates.into_iter().map(|n| n.node.clone()).collect()
+    }
+
+    /// Create an API client for a chain on a node of the pool
+    pub fn api_client(&self, node: &PoolNode, chain_id: &str) -> ApiClient {
+        ApiClient::with_http_client(self.config(node, chain_id), self.client.clone())
+    }
+
+    fn config(&self, node: &PoolNode, chain_id: &str) -> ApiConfig {
+        let mut config = ApiConfig::new(&node.base_url, &self.network, chain_id)
+            .with_endpoint(node.endpoint)
+            .with_timeout(self.timeout);
+        if let Some(api_key) = &self.api_key {
+            config = config.with_api_key(api_key.clone());
+        }
+        config.auth = self.auth.clone();
+        config
+    }
+
+    fn record(&self, base_url: &str, latency: Option<Duration>) {
+        if let Some(entry) = self
+            .nodes
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|n| n.node.base_url == base_url)
+        {
+            entry.record(latency);
+        }
+    }
+
+    /// Returns the response time of the node, or `None` if it is unhealthy
+    async fn probe(&self, node: &PoolNode) -> Option<Duration> {
+        let config = self.config(node, "0");
+        let url = probe_url(&config).ok()?;
+        let started = Instant::now();
+        match self.get(&config, &url).await {
+            Ok(_) => Some(started.elapsed()),
+            Err(e) => {
+                debug!("Health check of {} failed: {}", node.base_url, e);
+                None
+            }
+        }
+    }
+
+    async fn peers_of(&self, node: &PoolNode) -> Result<Vec<String>, FetchError> {
+        let config = self.config(node, "0");
+        let url = config.peer_url(self.peer_limit)?;
+        let body = self.get(&config, &url).await?.json_body()?;
+        Ok(parse_peers(&body))
+    }
+
+    /// Get a document with the credentials of `config`
+    async fn get(&self, config: &ApiConfig, url: &str) -> Result<HttpResponse, FetchError> {
+        let mut request = self.client.get(url);
+        for (name, value) in config.auth_headers() {
+            request = request.header(name, value);
+        }
+
+        let response =
+            HttpResponse::from_reqwest(request.send().await?, config.max_body_size).await?;
+        if !response.is_success() {
+            return Err(response.error());
+        }
+        Ok(response)
+    }
+}
+
+/// URL of the health checks of a node, on a route its kind serves
+fn probe_url(config: &ApiConfig) -> Result<String, FetchError> {
+    match config.endpoint {
+        EndpointKind::PactServer => Ok(format!("{}/version", config.pact_url()?)),
+        _ => config.cut_url(),
+    }
+}
+
+fn parse_peers(body: &Value) -> Vec<String> {
+    body["items"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let address = &item["address"];
+                    let hostname = address["hostname"].as_str()?;
+                    let port = address["port"].as_u64()?;
+                    Some(format!("https://{}:{}", hostname, port))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn build_http_client(timeout: u64) -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()
+        .expect("Failed to create HTTP client")
+}
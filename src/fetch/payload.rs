@@ -0,0 +1,160 @@
+//! Block payloads with transaction outputs
+//!
+//! The payload of a block lists its transactions and their outputs, each
+//! encoded as base64url JSON. Payloads of busy blocks can be large, so response
+//! bodies are read chunk by chunk up to a configurable size, and transactions
+//! are only decoded when iterated. Outputs decode into the [`CommandResult`]
+//! returned by `poll`, so block scanners and request key lookups share one
+//! result model.
+
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{base64url_decode, pact::CommandResult, FetchError};
+
+/// Default maximum size of a response body, in bytes
+pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Payload of a block, with the outputs of its transactions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockPayload {
+    /// Pairs of encoded transactions and their outputs
+    pub transactions: Vec<(String, String)>,
+    /// Encoded miner data
+    pub miner_data: String,
+    /// Encoded coinbase output
+    pub coinbase: String,
+    /// Hash of the payload
+    pub payload_hash: String,
+    /// Hash of the transactions
+    pub transactions_hash: String,
+    /// Hash of the outputs
+    pub outputs_hash: String,
+}
+
+impl BlockPayload {
+    /// Number of transactions in the block
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Returns true if the block contains no transactions
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Decode the transactions and their outputs, one at a time
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::BlockPayload;
+    ///
+    /// // base64url of `{"hash":"h"}` and `{"reqKey":"h"}`
+    /// let payload = BlockPayload {
+    ///     transactions: vec![("eyJoYXNoIjoiaCJ9".to_string(), "eyJyZXFLZXkiOiJoIn0".to_string())],
+    ///     miner_data: String::new(),
+    ///     coinbase: String::new(),
+    ///     payload_hash: String::new(),
+    ///     transactions_hash: String::new(),
+    ///     outputs_hash: String::new(),
+    /// };
+    ///
+    /// let (tx, output) = payload.transactions().next().unwrap().unwrap();
+    /// assert_eq!(tx["hash"], "h");
+    /// assert_eq!(output["reqKey"], "h");
+    /// ```
+    pub fn transactions(&self) -> impl Iterator<Item = Result<(Value, Value), FetchError>> + '_ {
+        self.transactions
+            .iter()
+            .map(|(tx, output)| Ok((decode(tx)?, decode(output)?)))
+    }
+
+    /// Decode the coinbase output
+    pub fn coinbase(&self) -> Result<Value, FetchError> {
+        decode(&self.coinbase)
+    }
+
+    /// Decode the transaction outputs into command results, one at a time
+    ///
+    /// Block outputs carry no `metaData`; the block is the one the payload
+    /// was fetched for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::base64url_encode;
+    /// use kadena::fetch::BlockPayload;
+    /// use serde_json::json;
+    ///
+    /// let output = json!({
+    ///     "reqKey": "h",
+    ///     "txId": 7,
+    ///     "result": {"status": "failure", "error": {"message": "Insufficient funds"}},
+    ///     "gas": 380,
+    ///     "events": [{
+    ///         "name": "TRANSFER",
+    ///         "module": {"namespace": null, "name": "coin"},
+    ///         "params": ["k:alice", "k:miner", 0.0000038]
+    ///     }]
+    /// });
+    /// let payload = BlockPayload {
+    ///     transactions: vec![("e30".to_string(), base64url_encode(output.to_string().as_bytes()))],
+    ///     miner_data: String::new(),
+    ///     coinbase: String::new(),
+    ///     payload_hash: String::new(),
+    ///     transactions_hash: String::new(),
+    ///     outputs_hash: String::new(),
+    /// };
+    ///
+    /// let result = payload.outputs().next().unwrap().unwrap();
+    /// assert_eq!(result.req_key, "h");
+    /// assert_eq!(result.failure().unwrap().error_code(), "pact.insufficient_funds");
+    /// assert_eq!(result.events[0].qualified_name(), "coin.TRANSFER");
+    /// ```
+    pub fn outputs(&self) -> impl Iterator<Item = Result<CommandResult, FetchError>> + '_ {
+        self.transactions
+            .iter()
+            .map(|(_, output)| decode_result(output))
+    }
+
+    /// Decode the coinbase output into a command result
+    ///
+    /// The request key of the coinbase is the hash of the parent block.
+    pub fn coinbase_result(&self) -> Result<CommandResult, FetchError> {
+        decode_result(&self.coinbase)
+    }
+}
+
+fn decode_result(encoded: &str) -> Result<CommandResult, FetchError> {
+    Ok(CommandResult::from_value(decode(encoded)?)?)
+}
+
+fn decode(encoded: &str) -> Result<Value, FetchError> {
+    let bytes = base64url_decode(encoded).map_err(|e| FetchError::InvalidPayload(e.to_string()))?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Read a response body, failing once it exceeds `limit` bytes
+///
+/// The declared content length is checked first, then the body is read chunk
+/// by chunk so that an oversized body is never buffered in full.
+pub(crate) async fn read_body(mut response: Response, limit: usize) -> Result<Vec<u8>, FetchError> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(FetchError::BodyTooLarge { limit });
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(FetchError::BodyTooLarge { limit });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
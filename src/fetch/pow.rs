@@ -0,0 +1,231 @@
+//! Proof of work targets and difficulties
+//!
+//! The target of a header is a 256 bit little-endian integer that the proof
+//! of work hash of the header must not exceed. Its difficulty, as Chainweb
+//! defines it, is `(2^256 - 1) / target`: the expected number of hashes needed
+//! to meet the target. Summing the difficulties of consecutive blocks over the
+//! time they took estimates the hash rate of a chain.
+
+use std::cmp::Ordering;
+
+use crate::{base64url_decode, base64url_encode, BlockHeader, FetchError};
+
+/// Unsigned 256 bit integer, as little-endian 64 bit limbs
+type U256 = [u64; 4];
+
+const U256_MAX: U256 = [u64::MAX; 4];
+
+/// The proof of work target of a block header
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::PowTarget;
+///
+/// let target = PowTarget::from_difficulty(1 << 40);
+/// assert_eq!(target.difficulty(), 1 << 40);
+/// assert_eq!(target.leading_zeros(), 40);
+///
+/// let mut pow_hash = [0xff; 32];
+/// assert!(!target.is_met_by(&pow_hash));
+/// pow_hash[27..].fill(0);
+/// assert!(target.is_met_by(&pow_hash));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PowTarget([u8; 32]);
+
+impl PowTarget {
+    /// The easiest target, met by every hash
+    pub const MAX: PowTarget = PowTarget([0xff; 32]);
+
+    /// Create a target from its little-endian bytes
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Decode a target in the base64url encoding of headers
+    pub fn from_base64(encoded: &str) -> Result<Self, FetchError> {
+        base64url_decode(encoded)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(Self)
+            .ok_or_else(|| FetchError::InvalidHeader(format!("invalid target {}", encoded)))
+    }
+
+    /// Create the target of a difficulty, rounding towards the easier target
+    ///
+    /// A difficulty of zero gives [`PowTarget::MAX`].
+    pub fn from_difficulty(difficulty: u128) -> Self {
+        if difficulty == 0 {
+            return Self::MAX;
+        }
+        let divisor = [difficulty as u64, (difficulty >> 64) as u64, 0, 0];
+        Self(to_bytes(div(U256_MAX, divisor)))
+    }
+
+    /// Get the little-endian bytes of the target
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Encode the target in the base64url encoding of headers
+    pub fn to_base64(&self) -> String {
+        base64url_encode(&self.0)
+    }
+
+    /// Returns true if a proof of work hash does not exceed the target
+    pub fn is_met_by(&self, pow_hash: &[u8; 32]) -> bool {
+        cmp(from_bytes(pow_hash), from_bytes(&self.0)).is_le()
+    }
+
+    /// Get the difficulty of the target
+    ///
+    /// Saturates at `u128::MAX` for targets below `2^128`, far beyond the
+    /// difficulty of any Chainweb network.
+    pub fn difficulty(&self) -> u128 {
+        let target = from_bytes(&self.0);
+        if target == [0; 4] {
+            return u128::MAX;
+        }
+        match div(U256_MAX, target) {
+            [low, high, 0, 0] => (u128::from(high) << 64) | u128::from(low),
+            _ => u128::MAX,
+        }
+    }
+
+    /// Number of leading zero bits of the target
+    ///
+    /// Roughly the base 2 logarithm of the difficulty.
+    pub fn leading_zeros(&self) -> u32 {
+        let target = from_bytes(&self.0);
+        let mut zeros = 0;
+        for limb in target.iter().rev() {
+            zeros += limb.leading_zeros();
+            if *limb != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+}
+
+impl PartialOrd for PowTarget {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PowTarget {
+    /// Orders targets as integers: harder targets are smaller
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp(from_bytes(&self.0), from_bytes(&other.0))
+    }
+}
+
+impl BlockHeader {
+    /// Decode the proof of work target of the header
+    pub fn pow_target(&self) -> Result<PowTarget, FetchError> {
+        PowTarget::from_base64(&self.target)
+    }
+
+    /// Get the difficulty of the header's target
+    pub fn difficulty(&self) -> Result<u128, FetchError> {
+        Ok(self.pow_target()?.difficulty())
+    }
+}
+
+/// Estimate the hash rate of a chain from consecutive headers, in hashes per second
+///
+/// The difficulties of every header but the first are divided by the time
+/// elapsed since the first header. Returns `None` for fewer than two headers,
+/// headers that are not in order, or undecodable targets.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{estimate_hashrate, BlockHeader, PowTarget};
+/// use serde_json::json;
+///
+/// let hash = "A".repeat(43);
+/// let target = PowTarget::from_difficulty(3_000_000).to_base64();
+/// let headers: Vec<BlockHeader> = (0..3u64)
+///     .map(|n| {
+///         BlockHeader::from_json(&json!({
+///             "featureFlags": 0,
+///             "creationTime": 1_700_000_000_000_000u64 + n * 30_000_000,
+///             "parent": hash,
+///             "adjacents": {"5": hash, "10": hash, "15": hash},
+///             "target": target,
+///             "payloadHash": hash,
+///             "chainId": 0,
+///             "weight": hash,
+///             "height": 100 + n,
+///             "chainwebVersion": "mainnet01",
+///             "epochStart": 1_700_000_000_000_000u64,
+///             "nonce": "0",
+///             "hash": hash
+///         }))
+///         .unwrap()
+///     })
+///     .collect();
+///
+/// // Two blocks of difficulty 3,000,000 in 60 seconds
+/// assert_eq!(estimate_hashrate(&headers), Some(100_000.0));
+/// ```
+pub fn estimate_hashrate(headers: &[BlockHeader]) -> Option<f64> {
+    let (first, rest) = headers.split_first()?;
+    let last = rest.last()?;
+    let elapsed = last.creation_time.checked_sub(first.creation_time)?;
+    if elapsed == 0 {
+        return None;
+    }
+    let mut work = 0.0;
+    for header in rest {
+        work += header.difficulty().ok()? as f64;
+    }
+    Some(work / (elapsed as f64 / 1_000_000.0))
+}
+
+fn from_bytes(bytes: &[u8; 32]) -> U256 {
+    let mut limbs = [0; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    limbs
+}
+
+fn to_bytes(limbs: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    bytes
+}
+
+fn cmp(a: U256, b: U256) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+/// Long division, one bit at a time; `divisor` must not be zero
+fn div(dividend: U256, divisor: U256) -> U256 {
+    let mut quotient = [0; 4];
+    let mut remainder = [0; 4];
+    for bit in (0..256).rev() {
+        // remainder = remainder << 1 | next bit of the dividend
+        for i in (1..4).rev() {
+            remainder[i] = (remainder[i] << 1) | (remainder[i - 1] >> 63);
+        }
+        remainder[0] = (remainder[0] << 1) | ((dividend[bit / 64] >> (bit % 64)) & 1);
+        if cmp(remainder, divisor).is_ge() {
+            let mut borrow = false;
+            for i in 0..4 {
+                let (diff, b1) = remainder[i].overflowing_sub(divisor[i]);
+                let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+                remainder[i] = diff;
+                borrow = b1 || b2;
+            }
+            quotient[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+    quotient
+}
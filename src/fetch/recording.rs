@@ -0,0 +1,220 @@
+//! Recording and replay of node interactions
+//!
+//! A [`RecordingTransport`] wraps another [`Transport`] and writes every
+//! request and response it carries to a JSON file. A [`ReplayTransport`]
+//! answers requests from such a file, so a bug report or a test reproduces
+//! the exact behavior of a node without network access. Both transports work
+//! with [`ApiClient`](crate::ApiClient) and
+//! [`ChainwebClient`](crate::ChainwebClient).
+
+use std::{fmt, fs, path::PathBuf, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{FetchError, HttpMethod, HttpRequest, HttpResponse, Transport, TransportFuture};
+
+/// A request and the response of the node, as written to recordings
+///
+/// Request headers are not recorded, so credentials never end up in a
+/// recording. Bodies holding a JSON object or array are stored as JSON, other
+/// bodies as text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    /// Method of the request
+    pub method: HttpMethod,
+    /// Path and query of the request URL
+    pub path: String,
+    /// Body of the request
+    pub request_body: Value,
+    /// Status code of the response
+    pub status: u16,
+    /// Header names and values of the response
+    pub headers: Vec<(String, String)>,
+    /// Body of the response
+    pub response_body: Value,
+}
+
+impl RecordedExchange {
+    /// Record a request and its response
+    pub fn new(request: &HttpRequest, response: &HttpResponse) -> Self {
+        Self {
+            method: request.method,
+            path: request.path().to_string(),
+            request_body: body_value(&request.body),
+            status: response.status,
+            headers: response.headers.clone(),
+            response_body: body_value(&response.body),
+        }
+    }
+
+    /// Returns true if `request` is a repetition of the recorded request
+    pub fn matches(&self, request: &HttpRequest) -> bool {
+        self.method == request.method
+            && self.path == request.path()
+            && self.request_body == body_value(&request.body)
+    }
+
+    /// Rebuild the recorded response
+    pub fn response(&self) -> HttpResponse {
+        let body = match &self.response_body {
+            Value::String(text) => text.clone().into_bytes(),
+            json => json.to_string().into_bytes(),
+        };
+        HttpResponse {
+            status: self.status,
+            headers: self.headers.clone(),
+            body,
+        }
+    }
+}
+
+/// Records the requests sent through another transport
+///
+/// The recording is rewritten after every request, so it is complete even if
+/// the process is stopped abruptly. Request bodies are recorded verbatim,
+/// signatures and environment data included.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kadena::fetch::{ApiClient, ApiConfig, RecordingTransport, ReqwestTransport};
+///
+/// let transport = RecordingTransport::new(ReqwestTransport::default(), "session.json");
+/// let client = ApiClient::with_transport(
+///     ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"),
+///     transport,
+/// );
+/// ```
+pub struct RecordingTransport<T> {
+    inner: T,
+    path: PathBuf,
+    exchanges: Mutex<Vec<RecordedExchange>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Record the requests sent through `inner` to the file at `path`
+    pub fn new(inner: T, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            exchanges: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get the exchanges recorded so far
+    pub fn exchanges(&self) -> Vec<RecordedExchange> {
+        self.exchanges
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn record(&self, exchange: RecordedExchange) -> Result<(), FetchError> {
+        let mut exchanges = self.exchanges.lock().unwrap_or_else(|e| e.into_inner());
+        exchanges.push(exchange);
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&*exchanges)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl<T> fmt::Debug for RecordingTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingTransport")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn execute(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let response = self.inner.execute(request.clone()).await?;
+            self.record(RecordedExchange::new(&request, &response))?;
+            Ok(response)
+        })
+    }
+}
+
+/// Answers requests with the responses of a recording
+///
+/// Each request is answered with the first unused exchange of the same
+/// method, path and body, so a request repeated while polling gets the
+/// recorded responses in order. Requests without a recorded response fail
+/// with [`FetchError::NotRecorded`].
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{HttpMethod, HttpRequest, HttpResponse, RecordedExchange, ReplayTransport};
+///
+/// let request = HttpRequest {
+///     method: HttpMethod::Get,
+///     url: "http://node/info".to_string(),
+///     headers: Vec::new(),
+///     body: Vec::new(),
+/// };
+/// let exchange = RecordedExchange::new(&request, &HttpResponse::new(200, "ok"));
+/// let transport = ReplayTransport::new(vec![exchange.clone()]);
+/// assert!(exchange.matches(&request));
+/// assert_eq!(transport.remaining(), 1);
+/// ```
+#[derive(Debug)]
+pub struct ReplayTransport {
+    exchanges: Vec<RecordedExchange>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    /// Replay the given exchanges
+    pub fn new(exchanges: Vec<RecordedExchange>) -> Self {
+        let used = Mutex::new(vec![false; exchanges.len()]);
+        Self { exchanges, used }
+    }
+
+    /// Replay the recording written to `path` by a [`RecordingTransport`]
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, FetchError> {
+        let json = fs::read_to_string(path.into())?;
+        Ok(Self::new(serde_json::from_str(&json)?))
+    }
+
+    /// Number of recorded exchanges not replayed yet
+    pub fn remaining(&self) -> usize {
+        self.used
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|used| !**used)
+            .count()
+    }
+
+    fn replay(&self, request: &HttpRequest) -> Result<HttpResponse, FetchError> {
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let index = self
+            .exchanges
+            .iter()
+            .enumerate()
+            .position(|(n, exchange)| !used[n] && exchange.matches(request))
+            .ok_or_else(|| {
+                FetchError::NotRecorded(format!("{} {}", request.method.as_str(), request.path()))
+            })?;
+        used[index] = true;
+        Ok(self.exchanges[index].response())
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn execute(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(std::future::ready(self.replay(&request)))
+    }
+}
+
+/// Keep JSON documents readable in recordings, and anything else as text
+fn body_value(body: &[u8]) -> Value {
+    match serde_json::from_slice(body) {
+        Ok(json @ (Value::Object(_) | Value::Array(_))) => json,
+        _ => Value::String(String::from_utf8_lossy(body).into_owned()),
+    }
+}
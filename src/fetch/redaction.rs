@@ -0,0 +1,97 @@
+//! Redaction of command payloads in logs
+//!
+//! [`ApiClient`](crate::ApiClient) logs a summary of each request at the
+//! `debug` level and the payload itself at the `trace` level. Payloads are
+//! redacted before they are logged: signatures and the values of environment
+//! data, which may carry secrets such as passwords or keys, are replaced with
+//! [`REDACTED`]. Full payloads can be logged for troubleshooting with
+//! [`ApiConfig::with_unsafe_payload_logging`](crate::ApiConfig::with_unsafe_payload_logging).
+
+use serde_json::{Map, Value};
+
+/// Placeholder logged in place of secret values
+pub const REDACTED: &str = "<redacted>";
+
+/// How request payloads are logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadLogging {
+    /// Log payloads with signatures and environment data redacted
+    #[default]
+    Redacted,
+    /// Log payloads verbatim, including signatures and environment data
+    ///
+    /// Logs may then contain secrets; only use this while troubleshooting.
+    Unsafe,
+}
+
+impl PayloadLogging {
+    /// Prepare a payload for logging
+    pub fn apply(&self, payload: &Value) -> Value {
+        match self {
+            PayloadLogging::Redacted => redact_payload(payload),
+            PayloadLogging::Unsafe => payload.clone(),
+        }
+    }
+}
+
+/// Redact the signatures and environment data of a request payload
+///
+/// Accepts a single command, as sent to `/local`, or a batch of commands
+/// under `cmds`, as sent to `/send`. The serialized `cmd` of each command is
+/// expanded into an object, so the logged payload stays readable.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{redact_payload, REDACTED};
+/// use serde_json::json;
+///
+/// let cmd = json!({"payload": {"exec": {"code": "(+ 1 2)", "data": {"password": "hunter2"}}}});
+/// let payload = json!({
+///     "hash": "abc",
+///     "sigs": [{"sig": "deadbeef"}],
+///     "cmd": cmd.to_string(),
+/// });
+///
+/// let redacted = redact_payload(&payload);
+/// assert_eq!(redacted["sigs"][0]["sig"], REDACTED);
+/// assert_eq!(redacted["cmd"]["payload"]["exec"]["data"]["password"], REDACTED);
+/// assert_eq!(redacted["cmd"]["payload"]["exec"]["code"], "(+ 1 2)");
+/// ```
+pub fn redact_payload(payload: &Value) -> Value {
+    let mut payload = payload.clone();
+    match payload.get_mut("cmds").and_then(Value::as_array_mut) {
+        Some(cmds) => cmds.iter_mut().for_each(redact_command),
+        None => redact_command(&mut payload),
+    }
+    payload
+}
+
+fn redact_command(command: &mut Value) {
+    if let Some(sigs) = command.get_mut("sigs").and_then(Value::as_array_mut) {
+        for sig in sigs {
+            if let Some(value) = sig.get_mut("sig").filter(|value| !value.is_null()) {
+                *value = Value::from(REDACTED);
+            }
+        }
+    }
+
+    if let Some(cmd) = command.get_mut("cmd") {
+        // An unparsable command is logged in full; it cannot carry env data
+        if let Some(mut parsed) = cmd.as_str().and_then(|s| serde_json::from_str(s).ok()) {
+            redact_env_data(&mut parsed);
+            *cmd = parsed;
+        }
+    }
+}
+
+fn redact_env_data(cmd: &mut Value) {
+    for kind in ["exec", "cont"] {
+        if let Some(Value::Object(data)) = cmd.pointer_mut(&format!("/payload/{}/data", kind)) {
+            *data = data
+                .keys()
+                .map(|key| (key.clone(), Value::from(REDACTED)))
+                .collect::<Map<_, _>>();
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Detection of reorganizations below confirmed transactions
+//!
+//! A transaction reported in a block can still be dropped if another branch
+//! of its chain overtakes that block. A [`ReorgMonitor`] remembers the block
+//! each confirmed transaction was observed in and compares it with the
+//! verified branch of a [`LightClient`]. When the block at that height
+//! changes, a [`ReorgEvent`] tells the caller to confirm the transaction
+//! again, e.g. before a deposit is credited.
+
+use std::collections::BTreeMap;
+
+use log::warn;
+
+use crate::{pact::CommandResult, LightClient};
+
+/// Default number of confirmations after which observations are no longer tracked
+pub const DEFAULT_FINALITY_DEPTH: u64 = 64;
+
+/// A block observed for a confirmed transaction that left the verified branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReorgEvent {
+    /// Request key of the transaction
+    pub request_key: String,
+    /// Chain of the transaction
+    pub chain_id: u32,
+    /// Height of the block the transaction was observed in
+    pub height: u64,
+    /// Hash of the block the transaction was observed in
+    pub observed_hash: String,
+    /// Hash of the block now at that height
+    pub canonical_hash: String,
+}
+
+#[derive(Debug, Clone)]
+struct Observation {
+    chain_id: u32,
+    height: u64,
+    block_hash: String,
+}
+
+/// Tracks the blocks of confirmed transactions and reports reorgs below them
+///
+/// # Examples
+///
+/// ```ignore
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use kadena::fetch::{ChainwebClient, LightClient, ReorgMonitor};
+///
+/// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+/// let light = LightClient::new(client.clone());
+/// light.sync().await?;
+///
+/// let mut reorgs = ReorgMonitor::new();
+/// let result = client.chain("0").listen("request-key").await?;
+/// reorgs.observe_result(0, &result);
+///
+/// // After applying new headers to the light client
+/// for reorg in reorgs.check(&light) {
+///     println!("{} must be confirmed again", reorg.request_key);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReorgMonitor {
+    observations: BTreeMap<String, Observation>,
+    finality_depth: u64,
+}
+
+impl Default for ReorgMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReorgMonitor {
+    /// Create a monitor tracking observations for [`DEFAULT_FINALITY_DEPTH`] confirmations
+    pub fn new() -> Self {
+        Self {
+            observations: BTreeMap::new(),
+            finality_depth: DEFAULT_FINALITY_DEPTH,
+        }
+    }
+
+    /// Stop tracking observations once they have `depth` confirmations
+    ///
+    /// The depth should stay below the history depth of the light client,
+    /// which cannot tell the block at a height that left its history.
+    pub fn with_finality_depth(mut self, depth: u64) -> Self {
+        self.finality_depth = depth;
+        self
+    }
+
+    /// Remember the block a transaction was observed in
+    ///
+    /// Observing a transaction again replaces its previous observation.
+    pub fn observe(&mut self, request_key: &str, chain_id: u32, height: u64, block_hash: &str) {
+        self.observations.insert(
+            request_key.to_string(),
+            Observation {
+                chain_id,
+                height,
+                block_hash: block_hash.to_string(),
+            },
+        );
+    }
+
+    /// Remember the block of a command result returned by `poll` or `listen`
+    ///
+    /// Returns false if the result carries no block metadata.
+    pub fn observe_result(&mut self, chain_id: u32, result: &CommandResult) -> bool {
+        match &result.meta_data {
+            Some(meta) => {
+                self.observe(
+                    &result.req_key,
+                    chain_id,
+                    meta.block_height,
+                    &meta.block_hash,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop tracking a transaction, returning true if it was tracked
+    pub fn forget(&mut self, request_key: &str) -> bool {
+        self.observations.remove(request_key).is_some()
+    }
+
+    /// Returns true if a transaction is tracked
+    pub fn is_tracked(&self, request_key: &str) -> bool {
+        self.observations.contains_key(request_key)
+    }
+
+    /// Request keys of the tracked transactions
+    pub fn tracked(&self) -> Vec<&str> {
+        self.observations.keys().map(String::as_str).collect()
+    }
+
+    /// Compare the observations with the verified branches of `light`
+    ///
+    /// Returns a [`ReorgEvent`] for every observed block that was replaced,
+    /// and stops tracking those transactions until they are observed again.
+    /// Observations with at least the finality depth of confirmations are no
+    /// longer tracked. Observations above the verified heads are kept.
+    pub fn check(&mut self, light: &LightClient) -> Vec<ReorgEvent> {
+        let mut events = Vec::new();
+        let finality_depth = self.finality_depth;
+        self.observations.retain(|request_key, observation| {
+            let Some(canonical) = light.canonical_hash(observation.chain_id, observation.height)
+            else {
+                return true;
+            };
+            if canonical != observation.block_hash {
+                warn!(
+                    "Block {} of {} at height {} of chain {} was replaced by {}",
+                    observation.block_hash,
+                    request_key,
+                    observation.height,
+                    observation.chain_id,
+                    canonical
+                );
+                events.push(ReorgEvent {
+                    request_key: request_key.clone(),
+                    chain_id: observation.chain_id,
+                    height: observation.height,
+                    observed_hash: observation.block_hash.clone(),
+                    canonical_hash: canonical,
+                });
+                return false;
+            }
+            light
+                .confirmations(observation.chain_id, observation.height)
+                .is_none_or(|confirmations| confirmations < finality_depth)
+        });
+        events
+    }
+}
@@ -0,0 +1,45 @@
+//! Submission of treasury key rotations
+//!
+//! [`ApiClient::submit_rotation`] sends an approved [`RotationCeremony`]
+//! after checking its approvals against the guards on chain, and waits for
+//! the transfer to the new account to be mined.
+
+use crate::{
+    pact::{CommandResult, RotationCeremony},
+    ApiClient, FetchError,
+};
+
+impl ApiClient {
+    /// Submit an approved key rotation and wait for its result
+    ///
+    /// The approvals are checked with [`ApiClient::check_signatures`] first:
+    /// the old approvers must satisfy the guard of the old account on chain,
+    /// and the new account, if it already exists, must be guarded by the new
+    /// keyset. A rotation that failed on chain is returned as is; see
+    /// [`CommandResult::failure`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.chainweb.com", "mainnet01", "0"));
+    /// let ceremony = /* approved by the old and new key holders */;
+    /// let result = client.submit_rotation(ceremony).await?;
+    /// if let Some(failure) = result.failure() {
+    ///     eprintln!("rotation failed: {}", failure);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn submit_rotation(
+        &self,
+        ceremony: RotationCeremony,
+    ) -> Result<CommandResult, FetchError> {
+        let cmd = ceremony.into_cmd()?;
+        self.check_signatures(&cmd).await?;
+        self.send(&cmd).await?;
+        self.listen(&cmd.hash).await
+    }
+}
@@ -0,0 +1,138 @@
+//! Checking signatures against on-chain guards before submission
+//!
+//! A command whose signatures do not satisfy the guard of the gas payer or
+//! of the sending account fails on chain, and the gas is still charged when
+//! the gas payer's guard is satisfied. [`ApiClient::check_signatures`] reads
+//! the guards of the accounts involved with local calls and checks them
+//! against the signatures of the command before it is sent.
+
+use serde_json::Value;
+
+use crate::{
+    fetch::chainweb_client::is_row_not_found,
+    pact::{
+        command::Cmd, AccountDetails, CommandError, CommandIntent, CommandSigner, Guard,
+        SigningSession, TransferKind,
+    },
+    ApiClient, FetchError,
+};
+
+impl ApiClient {
+    /// Read the details of an account of a `fungible-v2` token, e.g. `coin`
+    ///
+    /// Returns `None` when the account does not exist on the chain.
+    pub async fn account_details(
+        &self,
+        token: &str,
+        account: &str,
+    ) -> Result<Option<AccountDetails>, FetchError> {
+        let code = format!("({}.details {})", token, Value::from(account));
+        self.local_read(&code, is_row_not_found).await
+    }
+
+    /// Check that the signatures of a command satisfy the on-chain guards
+    ///
+    /// The signatures must be valid, and the keys that signed must satisfy
+    /// the keyset guard of the gas payer and, for transfers, of the sending
+    /// account. For `transfer-create` to an existing account, the receiver
+    /// guard of the command must be the guard on chain. Only signers scoped
+    /// to the relevant capability, or not scoped at all, count.
+    ///
+    /// Guards that cannot be evaluated offline, such as capability guards or
+    /// keysets with custom predicates, are assumed to be satisfied.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let cmd = /* ... */;
+    /// client.check_signatures(&cmd).await?;
+    /// client.send(&cmd).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn check_signatures(&self, cmd: &Cmd) -> Result<(), FetchError> {
+        let session = SigningSession::from_cmd(cmd)?;
+        let missing = session.missing_signers();
+        if !missing.is_empty() {
+            return Err(CommandError::UnsatisfiedKeyset(format!(
+                "missing signatures of {}",
+                missing.join(", ")
+            ))
+            .into());
+        }
+        let payload = session.payload()?;
+
+        let sender = &payload.meta.sender;
+        let gas_keys = signing_keys(&payload.signers, &["coin.GAS"]);
+        self.check_guard("coin", sender, &gas_keys).await?;
+
+        if let CommandIntent::Transfer(transfer) = payload.intent() {
+            let cap = match transfer.kind {
+                TransferKind::CrossChain => format!("{}.TRANSFER_XCHAIN", transfer.token),
+                _ => format!("{}.TRANSFER", transfer.token),
+            };
+            let keys = signing_keys(&payload.signers, &[&cap]);
+            self.check_guard(&transfer.token, &transfer.from, &keys)
+                .await?;
+
+            if let (TransferKind::TransferCreate, Some(guard)) =
+                (transfer.kind, &transfer.receiver_guard)
+            {
+                let receiver = self.account_details(&transfer.token, &transfer.to).await?;
+                if let Some(receiver) = receiver.filter(|receiver| receiver.guard != *guard) {
+                    return Err(CommandError::InvalidGuard(format!(
+                        "guard of {} does not match the guard of the existing account",
+                        receiver.account
+                    ))
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `keys` satisfy the guard of `account`
+    async fn check_guard(
+        &self,
+        token: &str,
+        account: &str,
+        keys: &[&str],
+    ) -> Result<(), FetchError> {
+        let details = self.account_details(token, account).await?.ok_or_else(|| {
+            CommandError::UnsatisfiedKeyset(format!(
+                "account {} does not exist on chain {}",
+                account,
+                self.config().chain_id
+            ))
+        })?;
+        match &details.guard {
+            Guard::KeySet(keyset) if keyset.is_satisfied_by(keys) == Some(false) => {
+                Err(CommandError::UnsatisfiedKeyset(format!(
+                    "signatures of {:?} do not satisfy the {} guard of {}",
+                    keys, keyset.pred, account
+                ))
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Keys of the signers scoped to one of `caps`, or not scoped at all
+fn signing_keys<'a>(signers: &'a [CommandSigner], caps: &[&str]) -> Vec<&'a str> {
+    signers
+        .iter()
+        .filter(|signer| {
+            signer.clist.is_empty()
+                || signer
+                    .clist
+                    .iter()
+                    .any(|cap| caps.contains(&cap.name.as_str()))
+        })
+        .map(|signer| signer.pub_key.as_str())
+        .collect()
+}
@@ -0,0 +1,163 @@
+//! SPV proof retrieval for cross-chain transactions
+//!
+//! A cross-chain transfer is completed on the target chain with an SPV proof of
+//! the source transaction. Nodes can only produce that proof once the target
+//! chain has seen enough blocks after the source transaction was mined, so
+//! requesting a proof too early fails with a "not reachable" error.
+
+use std::time::{Duration, Instant};
+
+use crate::{pact::RequestKey, sleep, ApiClient, CancellationToken, FetchError};
+use log::debug;
+use serde_json::json;
+
+/// Error fragments returned by nodes while an SPV proof is not yet available
+const NOT_READY_MESSAGES: [&str; 3] = [
+    "SPV target not reachable",
+    "Transaction hash not found",
+    "not yet available",
+];
+
+/// Options controlling how long [`ApiClient::await_spv`] waits for a proof
+#[derive(Debug, Clone)]
+pub struct SpvOptions {
+    /// Maximum time to wait for the proof
+    pub timeout: Duration,
+    /// Delay between two attempts
+    pub interval: Duration,
+    /// Token stopping the wait early with [`FetchError::Cancelled`]
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for SpvOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(600),
+            interval: Duration::from_secs(10),
+            cancellation: None,
+        }
+    }
+}
+
+impl SpvOptions {
+    /// Set the maximum time to wait for the proof
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the delay between two attempts
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Stop waiting once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+impl ApiClient {
+    /// Request an SPV proof for a transaction, to be used on a target chain
+    ///
+    /// # Arguments
+    ///
+    /// * `request_key` - Request key of the source transaction, in base64url or hex form
+    /// * `target_chain` - Chain id on which the proof will be used
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let proof = client.spv("TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c", "1").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn spv(&self, request_key: &str, target_chain: &str) -> Result<String, FetchError> {
+        let url = format!("{}/spv", self.config().pact_url()?);
+        let payload = json!({
+            "requestKey": RequestKey::parse(request_key)?,
+            "targetChainId": target_chain,
+        });
+
+        debug!("Requesting SPV proof from {}: {}", url, payload);
+
+        let response = self.execute_request(&url, &payload).await?;
+        response
+            .as_str()
+            .map(ToString::to_string)
+            .ok_or_else(|| FetchError::ApiError(format!("Unexpected SPV response: {}", response)))
+    }
+
+    /// Request an SPV proof, retrying until it becomes available
+    ///
+    /// Errors indicating that the proof is not available yet are retried every
+    /// `options.interval` until `options.timeout` elapses. Any other error is
+    /// returned immediately, as is [`FetchError::Cancelled`] once the
+    /// cancellation token of `options` is cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ApiClient, ApiConfig, SpvOptions};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let options = SpvOptions::default().with_timeout(Duration::from_secs(300));
+    /// let proof = client.await_spv("request-key", "1", &options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn await_spv(
+        &self,
+        request_key: &str,
+        target_chain: &str,
+        options: &SpvOptions,
+    ) -> Result<String, FetchError> {
+        match &options.cancellation {
+            Some(token) => {
+                token
+                    .run(self.poll_spv(request_key, target_chain, options))
+                    .await?
+            }
+            None => self.poll_spv(request_key, target_chain, options).await,
+        }
+    }
+
+    async fn poll_spv(
+        &self,
+        request_key: &str,
+        target_chain: &str,
+        options: &SpvOptions,
+    ) -> Result<String, FetchError> {
+        let deadline = Instant::now() + options.timeout;
+
+        loop {
+            match self.spv(request_key, target_chain).await {
+                Err(FetchError::ApiError(message)) if is_not_ready(&message) => {
+                    if Instant::now() + options.interval > deadline {
+                        return Err(FetchError::Timeout(format!(
+                            "SPV proof for {} not available: {}",
+                            request_key, message
+                        )));
+                    }
+                    debug!("SPV proof for {} not ready yet: {}", request_key, message);
+                    sleep(options.interval).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+fn is_not_ready(message: &str) -> bool {
+    NOT_READY_MESSAGES
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
@@ -0,0 +1,133 @@
+//! Client-side verification of SPV proofs
+//!
+//! An SPV proof returned by the `spv` endpoint is a base64url encoded JSON
+//! object holding the proven transaction output (the subject), the Merkle path
+//! from that output up to a block hash of the target chain (the object), and
+//! the id of the target chain. The path climbs through the outputs tree of the
+//! block payload, the block header and the header chain, so the computed root
+//! is the hash of a block on the target chain.
+//!
+//! Checking that root against a block hash obtained independently, e.g. from
+//! a header chain the relayer tracks itself or from another node, removes the
+//! need to trust the node that produced the proof.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    base64url_decode, base64url_encode, tagged_leaf, ChainwebClient, FetchError, MerkleHash,
+    MerkleProofObject, TRANSACTION_OUTPUT_TAG,
+};
+
+#[derive(Deserialize)]
+struct RawProof {
+    chain: u32,
+    object: String,
+    subject: RawSubject,
+}
+
+#[derive(Deserialize)]
+struct RawSubject {
+    tag: String,
+    input: String,
+}
+
+/// A decoded SPV output proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpvProof {
+    /// Chain on which the proof can be used
+    pub chain: u32,
+    /// Encoded transaction output the proof is about
+    pub subject: Vec<u8>,
+    /// Merkle path from the output to a block hash of the target chain
+    pub object: MerkleProofObject,
+}
+
+impl SpvProof {
+    /// Decode a proof as returned by the `spv` endpoint
+    pub fn parse(proof: &str) -> Result<Self, FetchError> {
+        let raw: RawProof = serde_json::from_slice(&decode(proof.trim())?)?;
+        if raw.subject.tag != "TransactionOutput" {
+            return Err(FetchError::InvalidProof(format!(
+                "unsupported subject {}",
+                raw.subject.tag
+            )));
+        }
+
+        Ok(Self {
+            chain: raw.chain,
+            subject: decode(&raw.subject.input)?,
+            object: MerkleProofObject::decode(&decode(&raw.object)?)
+                .map_err(|e| FetchError::InvalidProof(e.to_string()))?,
+        })
+    }
+
+    /// Decode the proven transaction output
+    pub fn output(&self) -> Result<Value, FetchError> {
+        Ok(serde_json::from_slice(&self.subject)?)
+    }
+
+    /// Compute the root of the proof, the hash of a block on the target chain
+    pub fn root(&self) -> MerkleHash {
+        self.object
+            .root(tagged_leaf(TRANSACTION_OUTPUT_TAG, &self.subject))
+    }
+
+    /// Compute the root of the proof as a base64url block hash
+    pub fn block_hash(&self) -> String {
+        base64url_encode(&self.root())
+    }
+
+    /// Check that the proof leads to a trusted block hash of the target chain
+    ///
+    /// # Arguments
+    ///
+    /// * `trusted_block_hash` - Base64url hash of a block the caller trusts
+    pub fn verify(&self, trusted_block_hash: &str) -> Result<(), FetchError> {
+        let block_hash = self.block_hash();
+        if block_hash != trusted_block_hash {
+            return Err(FetchError::InvalidProof(format!(
+                "proof leads to block {} instead of {}",
+                block_hash, trusted_block_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl ChainwebClient {
+    /// Check that a proof leads to a block of this client's header chain
+    ///
+    /// The header is fetched by hash from the node this client points at, so
+    /// using a different node than the one that produced the proof removes the
+    /// need to trust the latter. Returns the header of the block.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example(proof: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// use kadena::fetch::{ChainwebClient, SpvProof};
+    ///
+    /// let proof = SpvProof::parse(proof)?;
+    /// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+    /// let header = client.verify_spv(&proof).await?;
+    /// println!("proof anchored at height {}", header["height"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn verify_spv(&self, proof: &SpvProof) -> Result<Value, FetchError> {
+        let block_hash = proof.block_hash();
+        let header = self.header(&proof.chain.to_string(), &block_hash).await?;
+        if header["hash"].as_str() != Some(block_hash.as_str()) {
+            return Err(FetchError::InvalidProof(format!(
+                "block {} is not part of the header chain",
+                block_hash
+            )));
+        }
+        Ok(header)
+    }
+}
+
+fn decode(input: &str) -> Result<Vec<u8>, FetchError> {
+    base64url_decode(input).map_err(|e| FetchError::InvalidProof(e.to_string()))
+}
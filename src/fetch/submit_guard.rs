@@ -0,0 +1,178 @@
+//! Protection against submitting the same command twice
+//!
+//! Retry logic that resends a command after a timeout can submit it twice if
+//! the first attempt actually reached the node. [`SubmitGuard`] remembers the
+//! hashes of recently submitted commands and refuses to send them again.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::Value;
+
+use crate::{pact::command::Cmd, ApiClient, FetchError};
+
+/// Remembers recently submitted commands to reject duplicate submissions
+///
+/// Entries expire after the configured TTL, which should cover the command's
+/// time-to-live on chain. With persistence enabled, entries are written to a
+/// JSON file on every change, so they survive a process restart.
+#[derive(Debug)]
+pub struct SubmitGuard {
+    ttl: Duration,
+    path: Option<PathBuf>,
+    submitted: Mutex<HashMap<String, u64>>,
+}
+
+impl SubmitGuard {
+    /// Create an in-memory guard
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use kadena::fetch::SubmitGuard;
+    ///
+    /// let guard = SubmitGuard::new(Duration::from_secs(28_800));
+    /// assert!(guard.record("command-hash").is_ok());
+    /// assert!(guard.record("command-hash").is_err());
+    /// ```
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            path: None,
+            submitted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a guard persisted to a JSON file, loading its existing entries
+    pub fn with_persistence(ttl: Duration, path: impl AsRef<Path>) -> Result<Self, FetchError> {
+        let path = path.as_ref().to_path_buf();
+        let submitted = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(FetchError::ApiError(format!(
+                    "Cannot read {:?}: {}",
+                    path, e
+                )))
+            }
+        };
+
+        let guard = Self {
+            ttl,
+            path: Some(path),
+            submitted: Mutex::new(submitted),
+        };
+        guard.prune()?;
+        Ok(guard)
+    }
+
+    /// Returns true if a command with this hash was submitted within the TTL
+    pub fn is_duplicate(&self, hash: &str) -> bool {
+        let now = now_secs();
+        self.lock()
+            .get(hash)
+            .is_some_and(|submitted| !self.is_expired(*submitted, now))
+    }
+
+    /// Record the submission of a command
+    ///
+    /// Fails with [`FetchError::DuplicateSubmission`] if the command was already
+    /// submitted within the TTL.
+    pub fn record(&self, hash: &str) -> Result<(), FetchError> {
+        let now = now_secs();
+        let mut submitted = self.lock();
+        if let Some(at) = submitted.get(hash) {
+            if !self.is_expired(*at, now) {
+                return Err(FetchError::DuplicateSubmission(hash.to_string()));
+            }
+        }
+        submitted.insert(hash.to_string(), now);
+        self.persist(&submitted)
+    }
+
+    /// Forget a command, allowing it to be submitted again
+    ///
+    /// Use it only once the node is known to have rejected the command.
+    pub fn forget(&self, hash: &str) -> Result<(), FetchError> {
+        let mut submitted = self.lock();
+        if submitted.remove(hash).is_some() {
+            self.persist(&submitted)?;
+        }
+        Ok(())
+    }
+
+    /// Drop expired entries
+    pub fn prune(&self) -> Result<(), FetchError> {
+        let now = now_secs();
+        let mut submitted = self.lock();
+        let before = submitted.len();
+        submitted.retain(|_, at| !self.is_expired(*at, now));
+        if submitted.len() != before {
+            self.persist(&submitted)?;
+        }
+        Ok(())
+    }
+
+    /// Number of commands currently remembered
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if no command is remembered
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn is_expired(&self, submitted: u64, now: u64) -> bool {
+        now.saturating_sub(submitted) >= self.ttl.as_secs()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, u64>> {
+        self.submitted.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn persist(&self, submitted: &HashMap<String, u64>) -> Result<(), FetchError> {
+        if let Some(path) = &self.path {
+            fs::write(path, serde_json::to_vec(submitted)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl ApiClient {
+    /// Send a command unless it was already submitted through `guard`
+    ///
+    /// The command is recorded before it is sent, so a retry after a network
+    /// error is refused as well: the first attempt may have reached the node.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::time::Duration;
+    /// use kadena::fetch::{ApiClient, ApiConfig, SubmitGuard};
+    ///
+    /// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+    /// let guard = SubmitGuard::with_persistence(Duration::from_secs(28_800), "submitted.json")?;
+    /// let cmd = /* ... */;
+    /// client.send_guarded(&cmd, &guard).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_guarded(&self, cmd: &Cmd, guard: &SubmitGuard) -> Result<Value, FetchError> {
+        guard.record(&cmd.hash)?;
+        self.send(cmd).await
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,87 @@
+//! Runtime-agnostic timers
+//!
+//! The helpers in this crate that wait, such as [`ApiClient::await_spv`](crate::ApiClient::await_spv),
+//! go through [`sleep`] and [`timeout`] instead of a specific async runtime.
+//! With the `runtime-tokio` feature they use tokio's timer, which requires a
+//! tokio runtime. With `runtime-agnostic` they use `futures-timer`, which runs
+//! its own timer thread and works under any executor, e.g. async-std or smol.
+//! If both features are enabled, `runtime-agnostic` takes precedence.
+//!
+//! Note that [`ReqwestTransport`](crate::ReqwestTransport) always needs a
+//! tokio runtime; use [`ApiClient::with_transport`](crate::ApiClient::with_transport)
+//! with a transport built on your runtime's HTTP client otherwise.
+
+use std::{future::Future, time::Duration};
+
+use crate::FetchError;
+
+#[cfg(not(any(feature = "runtime-tokio", feature = "runtime-agnostic")))]
+compile_error!("the `fetch` feature requires either `runtime-tokio` or `runtime-agnostic`");
+
+/// Wait for `duration` to elapse
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use kadena::fetch::sleep;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// sleep(Duration::from_millis(10)).await;
+/// # }
+/// ```
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "runtime-agnostic")]
+    futures_timer::Delay::new(duration).await;
+
+    #[cfg(all(feature = "runtime-tokio", not(feature = "runtime-agnostic")))]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(not(any(feature = "runtime-tokio", feature = "runtime-agnostic")))]
+    let _ = duration;
+}
+
+/// Run `future` to completion, failing with [`FetchError::Timeout`] if it
+/// takes longer than `duration`
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use kadena::fetch::{sleep, timeout, FetchError};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let result = timeout(Duration::from_millis(10), sleep(Duration::from_secs(5))).await;
+/// assert!(matches!(result, Err(FetchError::Timeout(_))));
+/// # }
+/// ```
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, FetchError> {
+    let elapsed = || FetchError::Timeout(format!("operation exceeded {:?}", duration));
+
+    #[cfg(feature = "runtime-agnostic")]
+    {
+        use futures::future::{select, Either};
+
+        let future = std::pin::pin!(future);
+        match select(future, futures_timer::Delay::new(duration)).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(_) => Err(elapsed()),
+        }
+    }
+
+    #[cfg(all(feature = "runtime-tokio", not(feature = "runtime-agnostic")))]
+    {
+        tokio::time::timeout(duration, future)
+            .await
+            .map_err(|_| elapsed())
+    }
+
+    // Without a runtime only the `compile_error!` above should be reported
+    #[cfg(not(any(feature = "runtime-tokio", feature = "runtime-agnostic")))]
+    {
+        drop(future);
+        Err(elapsed())
+    }
+}
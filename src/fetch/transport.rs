@@ -0,0 +1,463 @@
+//! Pluggable HTTP transports
+//!
+//! [`ApiClient`](crate::ApiClient) and [`ChainwebClient`](crate::ChainwebClient)
+//! send their requests through a [`Transport`].
+//! The default [`ReqwestTransport`] speaks HTTP over TCP. [`UnixSocketTransport`]
+//! reaches a node listening on a Unix domain socket, e.g. a sidecar sharing
+//! the pod of the application, and [`InProcessTransport`] answers requests
+//! with a function, so integration tests run without any server.
+//! [`RecordingTransport`](crate::RecordingTransport) and
+//! [`ReplayTransport`](crate::ReplayTransport) capture node interactions and
+//! serve them back.
+
+use std::{fmt, future::Future, path::PathBuf, pin::Pin, time::Duration};
+
+use log::error;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{fetch::payload::read_body, timeout, FetchError, DEFAULT_MAX_BODY_SIZE};
+
+/// Method of an HTTP request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+impl HttpMethod {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+        }
+    }
+}
+
+/// An HTTP request sent through a [`Transport`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// The method
+    pub method: HttpMethod,
+    /// The absolute URL, e.g. `http://localhost/chainweb/0.0/...`
+    pub url: String,
+    /// Header names and values
+    pub headers: Vec<(String, String)>,
+    /// The body, empty for `GET` requests
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Get the path and query of the URL
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{HttpMethod, HttpRequest};
+    ///
+    /// let request = HttpRequest {
+    ///     method: HttpMethod::Get,
+    ///     url: "http://localhost:1848/chainweb/0.0/testnet04/cut?limit=1".to_string(),
+    ///     headers: Vec::new(),
+    ///     body: Vec::new(),
+    /// };
+    /// assert_eq!(request.path(), "/chainweb/0.0/testnet04/cut?limit=1");
+    /// ```
+    pub fn path(&self) -> &str {
+        let rest = self
+            .url
+            .split_once("://")
+            .map_or(&*self.url, |(_, rest)| rest);
+        rest.find('/').map_or("/", |n| &rest[n..])
+    }
+
+    /// Get the value of a header, ignoring the case of its name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    #[cfg_attr(not(all(unix, feature = "runtime-tokio")), allow(dead_code))]
+    fn authority(&self) -> &str {
+        let rest = self
+            .url
+            .split_once("://")
+            .map_or(&*self.url, |(_, rest)| rest);
+        rest.split('/').next().unwrap_or_default()
+    }
+}
+
+/// An HTTP response returned by a [`Transport`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// The status code
+    pub status: u16,
+    /// Header names and values
+    pub headers: Vec<(String, String)>,
+    /// The body
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Create a response without headers
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Create a JSON response
+    pub fn json(status: u16, body: &Value) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    /// Returns true for 2xx status codes
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Get the value of a header, ignoring the case of its name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Returns true if the body is an HTML page rather than an API response
+    pub fn is_html(&self) -> bool {
+        let declared = self
+            .header("Content-Type")
+            .is_some_and(|ct| ct.to_ascii_lowercase().contains("html"));
+        let text = String::from_utf8_lossy(&self.body[..self.body.len().min(64)]).to_lowercase();
+        let text = text.trim_start();
+        declared || text.starts_with("<!doctype html") || text.starts_with("<html")
+    }
+
+    /// Parse the body as JSON
+    ///
+    /// Fails with [`FetchError::UnexpectedResponse`], holding the status and
+    /// the beginning of the body, if the body is not JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::fetch::{FetchError, HttpResponse};
+    ///
+    /// let page = HttpResponse::new(200, "<html><body>502 Bad Gateway</body></html>");
+    /// assert!(matches!(page.json_body(), Err(FetchError::UnexpectedResponse { status: 200, .. })));
+    /// ```
+    pub fn json_body(&self) -> Result<Value, FetchError> {
+        if self.is_html() {
+            return Err(self.unexpected());
+        }
+        serde_json::from_slice(&self.body).map_err(|_| self.unexpected())
+    }
+
+    /// The error reported for a response with an unsuccessful status
+    ///
+    /// Node errors are plain text and become [`FetchError::ApiError`]; HTML
+    /// pages become [`FetchError::UnexpectedResponse`].
+    pub fn error(&self) -> FetchError {
+        if self.is_html() {
+            return self.unexpected();
+        }
+        let error_text = String::from_utf8_lossy(&self.body).into_owned();
+        error!("API error: {}", error_text);
+        FetchError::ApiError(error_text)
+    }
+
+    fn unexpected(&self) -> FetchError {
+        let body = String::from_utf8_lossy(&self.body);
+        let mut excerpt: String = body.chars().take(UNEXPECTED_BODY_EXCERPT).collect();
+        if excerpt.len() < body.len() {
+            excerpt.push_str("...");
+        }
+        error!(
+            "Unexpected response with status {}: {}",
+            self.status, excerpt
+        );
+        FetchError::UnexpectedResponse {
+            status: self.status,
+            content_type: self.header("Content-Type").map(ToString::to_string),
+            body: excerpt,
+        }
+    }
+
+    /// Read a response, failing if its body exceeds `max_body_size` bytes
+    pub(crate) async fn from_reqwest(
+        response: reqwest::Response,
+        max_body_size: usize,
+    ) -> Result<Self, FetchError> {
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = read_body(response, max_body_size).await?;
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Number of characters of an unexpected body kept in the error
+const UNEXPECTED_BODY_EXCERPT: usize = 256;
+
+/// The future returned by [`Transport::execute`]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<HttpResponse, FetchError>> + Send + 'a>>;
+
+/// Carries HTTP requests to a node
+pub trait Transport: Send + Sync + fmt::Debug {
+    /// Send a request and read the complete response
+    fn execute(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+/// HTTP over TCP, with `reqwest`
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: Client,
+    max_body_size: usize,
+}
+
+impl ReqwestTransport {
+    /// Create a transport sending requests with an existing HTTP client
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Set the maximum size of response bodies read
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self::new(Client::default())
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn execute(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut builder = match request.method {
+                HttpMethod::Get => self.client.get(&request.url),
+                HttpMethod::Post => self.client.post(&request.url).body(request.body),
+            };
+            for (name, value) in &request.headers {
+                builder = builder.header(name, value);
+            }
+
+            HttpResponse::from_reqwest(builder.send().await?, self.max_body_size).await
+        })
+    }
+}
+
+/// HTTP/1.1 over a Unix domain socket
+///
+/// The scheme and host of request URLs are ignored, apart from the `Host`
+/// header; every request opens a new connection.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use kadena::fetch::{ApiClient, ApiConfig, UnixSocketTransport};
+///
+/// let transport = UnixSocketTransport::new("/var/run/chainweb-node.sock")
+///     .with_timeout(Duration::from_secs(10));
+/// let client = ApiClient::with_transport(
+///     ApiConfig::new("http://localhost", "mainnet01", "0"),
+///     transport,
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct UnixSocketTransport {
+    path: PathBuf,
+    timeout: Duration,
+}
+
+impl UnixSocketTransport {
+    /// Create a transport connecting to the socket at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Set the timeout of a request, including connecting
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    #[cfg(all(unix, feature = "runtime-tokio"))]
+    async fn round_trip(&self, request: &HttpRequest) -> Result<Vec<u8>, std::io::Error> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+            request.method.as_str(),
+            request.path(),
+            request.authority(),
+            request.body.len()
+        );
+        for (name, value) in &request.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        let mut message = head.into_bytes();
+        message.extend_from_slice(&request.body);
+
+        let mut stream = tokio::net::UnixStream::connect(&self.path).await?;
+        stream.write_all(&message).await?;
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).await?;
+        Ok(raw)
+    }
+
+    #[cfg(not(all(unix, feature = "runtime-tokio")))]
+    async fn round_trip(&self, _request: &HttpRequest) -> Result<Vec<u8>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Unix domain sockets require a Unix platform and the runtime-tokio feature",
+        ))
+    }
+}
+
+impl Transport for UnixSocketTransport {
+    fn execute(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let raw = timeout(self.timeout, self.round_trip(&request))
+                .await
+                .map_err(|_| {
+                    FetchError::Timeout(format!("no response from {}", self.path.display()))
+                })?
+                .map_err(|e| {
+                    FetchError::TransportError(format!("{}: {}", self.path.display(), e))
+                })?;
+            parse_response(&raw)
+        })
+    }
+}
+
+/// Answers requests with a function, without any I/O
+///
+/// # Examples
+///
+/// ```
+/// use kadena::fetch::{ApiClient, ApiConfig, HttpResponse, InProcessTransport};
+/// use serde_json::json;
+///
+/// let transport = InProcessTransport::new(|request| {
+///     if request.path().ends_with("/api/v1/local") {
+///         HttpResponse::json(200, &json!({"result": {"status": "success", "data": 3}}))
+///     } else {
+///         HttpResponse::new(404, "not found")
+///     }
+/// });
+/// let client = ApiClient::with_transport(
+///     ApiConfig::new("http://node", "development", "0"),
+///     transport,
+/// );
+/// ```
+pub struct InProcessTransport<F> {
+    handler: F,
+}
+
+impl<F> InProcessTransport<F>
+where
+    F: Fn(HttpRequest) -> HttpResponse + Send + Sync,
+{
+    /// Create a transport answering every request with `handler`
+    pub fn new(handler: F) -> Self {
+        Self { handler }
+    }
+}
+
+impl<F> fmt::Debug for InProcessTransport<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InProcessTransport").finish_non_exhaustive()
+    }
+}
+
+impl<F> Transport for InProcessTransport<F>
+where
+    F: Fn(HttpRequest) -> HttpResponse + Send + Sync,
+{
+    fn execute(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(std::future::ready(Ok((self.handler)(request))))
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Parses a complete HTTP/1.1 response, read until the connection closed
+fn parse_response(raw: &[u8]) -> Result<HttpResponse, FetchError> {
+    let invalid =
+        |reason: &str| FetchError::TransportError(format!("invalid response: {}", reason));
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("incomplete head"))?;
+    let head = std::str::from_utf8(&raw[..split]).map_err(|_| invalid("head is not UTF-8"))?;
+    let mut lines = head.split("\r\n");
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| invalid("malformed status line"))?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let mut body = raw[split + 4..].to_vec();
+    if find_header(&headers, "Transfer-Encoding").is_some_and(|te| te.contains("chunked")) {
+        body = dechunk(&body).ok_or_else(|| invalid("malformed chunked body"))?;
+    } else if let Some(length) = find_header(&headers, "Content-Length") {
+        let length: usize = length.parse().map_err(|_| invalid("bad Content-Length"))?;
+        body.truncate(length);
+    }
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Decodes a body sent with chunked transfer encoding
+fn dechunk(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = raw.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&raw[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(raw.get(..size)?);
+        raw = raw.get(size + 2..)?;
+    }
+}
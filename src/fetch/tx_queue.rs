@@ -0,0 +1,360 @@
+//! Durable queue of outbound transactions
+//!
+//! Prepared commands are enqueued, submitted with retries and polled until
+//! they are included in a block. The queue is written to a [`QueueStorage`]
+//! after every change, so a restarted process resumes where it stopped.
+//!
+//! A failed submission may still have reached the mempool, so a command is
+//! polled before it is retried or marked failed, and a node rejecting it as
+//! a duplicate counts as a submission. Submitted commands that are not mined
+//! before their TTL runs out are marked failed.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pact::{command::Cmd, CommandResult},
+    ChainwebClient, FetchError,
+};
+
+/// Default number of submission attempts before an entry is marked failed
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Seconds past its TTL a submitted command is still polled before it expires
+///
+/// Nodes check the TTL against the creation time of the parent block, which
+/// lags behind the clock.
+pub const EXPIRY_MARGIN: u64 = 120;
+
+/// Progress of a queued command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum QueueStatus {
+    /// Waiting to be submitted
+    Pending,
+    /// Accepted by a node, waiting to be included in a block
+    Submitted,
+    /// Included in a block, successfully or not
+    Confirmed { result: Box<CommandResult> },
+    /// Submission failed on every attempt, or the command expired unmined
+    Failed { error: String },
+}
+
+/// A command in the queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueEntry {
+    /// The signed command
+    pub cmd: Cmd,
+    /// The chain the command is sent to
+    pub chain_id: String,
+    /// Progress of the command
+    pub status: QueueStatus,
+    /// Number of submission attempts so far
+    pub attempts: u32,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+}
+
+impl QueueEntry {
+    /// Returns true once the entry is confirmed or failed
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self.status,
+            QueueStatus::Confirmed { .. } | QueueStatus::Failed { .. }
+        )
+    }
+}
+
+/// Storage backend of a [`TxQueue`]
+pub trait QueueStorage {
+    /// Load all entries
+    fn load(&self) -> Result<Vec<QueueEntry>, FetchError>;
+
+    /// Replace the stored entries
+    fn save(&self, entries: &[QueueEntry]) -> Result<(), FetchError>;
+}
+
+/// Storage keeping entries in memory, lost when the process exits
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    entries: Mutex<Vec<QueueEntry>>,
+}
+
+impl QueueStorage for MemoryStorage {
+    fn load(&self) -> Result<Vec<QueueEntry>, FetchError> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    }
+
+    fn save(&self, entries: &[QueueEntry]) -> Result<(), FetchError> {
+        *self.entries.lock().unwrap_or_else(|e| e.into_inner()) = entries.to_vec();
+        Ok(())
+    }
+}
+
+/// Storage writing entries to a JSON file
+///
+/// The file is replaced atomically, so a crash while saving leaves the
+/// previous state intact.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    path: PathBuf,
+}
+
+impl FileStorage {
+    /// Create a storage writing to `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl QueueStorage for FileStorage {
+    fn load(&self) -> Result<Vec<QueueEntry>, FetchError> {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, entries: &[QueueEntry]) -> Result<(), FetchError> {
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(entries)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Counts of the entries changed by [`TxQueue::process`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueReport {
+    /// Entries accepted by a node
+    pub submitted: usize,
+    /// Entries whose submission attempt failed and will be retried
+    pub retried: usize,
+    /// Entries included in a block
+    pub confirmed: usize,
+    /// Entries that ran out of submission attempts or expired
+    pub failed: usize,
+}
+
+/// A durable queue of outbound transactions
+///
+/// # Examples
+///
+/// ```ignore
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use kadena::fetch::{ChainwebClient, FileStorage, TxQueue};
+///
+/// let client = ChainwebClient::new("https://api.testnet.chainweb.com", "testnet04");
+/// let mut queue = TxQueue::open(FileStorage::new("payouts.json"))?;
+/// let cmd = /* ... */;
+/// queue.enqueue(cmd, "0")?;
+///
+/// while !queue.is_settled() {
+///     queue.process(&client).await?;
+///     tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TxQueue<S: QueueStorage> {
+    storage: S,
+    entries: Vec<QueueEntry>,
+    max_attempts: u32,
+}
+
+impl<S: QueueStorage> TxQueue<S> {
+    /// Open a queue, resuming the entries found in `storage`
+    pub fn open(storage: S) -> Result<Self, FetchError> {
+        let entries = storage.load()?;
+        Ok(Self {
+            storage,
+            entries,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        })
+    }
+
+    /// Set the number of submission attempts before an entry is marked failed
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Add a command to the queue
+    ///
+    /// Enqueuing a command that is already queued has no effect.
+    pub fn enqueue(&mut self, cmd: Cmd, chain_id: &str) -> Result<(), FetchError> {
+        if self.get(&cmd.hash).is_some() {
+            return Ok(());
+        }
+        self.entries.push(QueueEntry {
+            cmd,
+            chain_id: chain_id.to_string(),
+            status: QueueStatus::Pending,
+            attempts: 0,
+            last_error: None,
+        });
+        self.storage.save(&self.entries)
+    }
+
+    /// Get the entry of a command by hash
+    pub fn get(&self, hash: &str) -> Option<&QueueEntry> {
+        self.entries.iter().find(|entry| entry.cmd.hash == hash)
+    }
+
+    /// Get all entries, in enqueuing order
+    pub fn entries(&self) -> &[QueueEntry] {
+        &self.entries
+    }
+
+    /// Returns true once every entry is confirmed or failed
+    pub fn is_settled(&self) -> bool {
+        self.entries.iter().all(QueueEntry::is_settled)
+    }
+
+    /// Remove the settled entries, returning them
+    pub fn drain_settled(&mut self) -> Result<Vec<QueueEntry>, FetchError> {
+        let (settled, remaining) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(QueueEntry::is_settled);
+        self.entries = remaining;
+        self.storage.save(&self.entries)?;
+        Ok(settled)
+    }
+
+    /// Submit pending entries and poll submitted ones
+    ///
+    /// Each call makes one submission attempt per pending entry, then one poll
+    /// request per chain for the submitted entries and those whose submission
+    /// failed. An entry is only marked failed if it is not mined once its
+    /// attempts are used up, or once its TTL ran out. The queue is saved after
+    /// every change.
+    pub async fn process(&mut self, client: &ChainwebClient) -> Result<QueueReport, FetchError> {
+        let mut report = QueueReport::default();
+
+        for index in 0..self.entries.len() {
+            if self.entries[index].status != QueueStatus::Pending {
+                continue;
+            }
+            let entry = &mut self.entries[index];
+            entry.attempts += 1;
+            match client.chain(&entry.chain_id).send(&entry.cmd).await {
+                Ok(_) => {
+                    debug!("Submitted {}", entry.cmd.hash);
+                    entry.status = QueueStatus::Submitted;
+                    entry.last_error = None;
+                    report.submitted += 1;
+                }
+                Err(e) if is_duplicate(&e) => {
+                    debug!("{} was submitted before: {}", entry.cmd.hash, e);
+                    entry.status = QueueStatus::Submitted;
+                    entry.last_error = None;
+                    report.submitted += 1;
+                }
+                Err(e) => {
+                    warn!("Submitting {} failed: {}", entry.cmd.hash, e);
+                    entry.last_error = Some(e.to_string());
+                }
+            }
+            self.storage.save(&self.entries)?;
+        }
+
+        let mut chains: Vec<&str> = self
+            .entries
+            .iter()
+            .filter(|entry| is_unconfirmed(entry))
+            .map(|entry| entry.chain_id.as_str())
+            .collect();
+        chains.sort_unstable();
+        chains.dedup();
+        let chains: Vec<String> = chains.into_iter().map(ToString::to_string).collect();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        for chain_id in chains {
+            let keys: Vec<&str> = self
+                .entries
+                .iter()
+                .filter(|entry| is_unconfirmed(entry) && entry.chain_id == chain_id)
+                .map(|entry| entry.cmd.hash.as_str())
+                .collect();
+            let mut results = client.chain(&chain_id).poll(&keys).await?;
+
+            let mut changed = false;
+            for entry in self.entries.iter_mut() {
+                if !is_unconfirmed(entry) || entry.chain_id != chain_id {
+                    continue;
+                }
+                if let Some(result) = results.remove(&entry.cmd.hash) {
+                    debug!("Confirmed {}", entry.cmd.hash);
+                    entry.status = QueueStatus::Confirmed {
+                        result: Box::new(result),
+                    };
+                    entry.last_error = None;
+                    report.confirmed += 1;
+                    changed = true;
+                } else if let Some(error) = give_up_reason(entry, self.max_attempts, now) {
+                    warn!("Giving up on {}: {}", entry.cmd.hash, error);
+                    entry.status = QueueStatus::Failed { error };
+                    report.failed += 1;
+                    changed = true;
+                }
+            }
+            if changed {
+                self.storage.save(&self.entries)?;
+            }
+        }
+
+        // Every pending entry was attempted, so those left failed this time
+        report.retried = self
+            .entries
+            .iter()
+            .filter(|entry| entry.status == QueueStatus::Pending)
+            .count();
+        Ok(report)
+    }
+}
+
+/// Returns true for entries submitted, or whose submission failed, but not mined
+fn is_unconfirmed(entry: &QueueEntry) -> bool {
+    match entry.status {
+        QueueStatus::Submitted => true,
+        QueueStatus::Pending => entry.attempts > 0,
+        _ => false,
+    }
+}
+
+/// Why an unmined entry is given up on, if it is
+fn give_up_reason(entry: &QueueEntry, max_attempts: u32, now: u64) -> Option<String> {
+    if entry.status == QueueStatus::Pending {
+        return (entry.attempts >= max_attempts)
+            .then(|| entry.last_error.clone().unwrap_or_default());
+    }
+    let meta = entry.cmd.payload().ok()?.meta;
+    let expiry = meta.creation_time.saturating_add(meta.ttl);
+    (expiry.saturating_add(EXPIRY_MARGIN) < now).then(|| format!("expired unmined at {}", expiry))
+}
+
+/// Returns true if a node rejected a command it has seen before
+fn is_duplicate(error: &FetchError) -> bool {
+    match error {
+        FetchError::ApiError(message) => {
+            let message = message.to_lowercase();
+            message.contains("already exists") || message.contains("duplicate")
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,271 @@
+//! Push-style notifications for submitted transactions
+//!
+//! A [`TxTracker`] watches a list of request keys and polls their results in
+//! a single request per round. When a command is mined, its callback runs and
+//! the key leaves the watch list; [`TxTracker::updates`] delivers the same
+//! updates as a stream instead.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use futures::{stream, Stream};
+
+use crate::{
+    pact::{CommandResult, RequestKey},
+    sleep, ApiClient, CancellationToken, FetchError,
+};
+
+/// Default delay between two polls of the watched request keys
+pub const DEFAULT_TRACKER_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The outcome of a watched command
+#[derive(Debug, Clone)]
+pub enum TxStatus {
+    /// The command was mined and succeeded
+    Succeeded(CommandResult),
+    /// The command was mined and failed; the gas is still charged
+    Failed(CommandResult),
+    /// The command was not mined within the tracker's timeout, e.g. because
+    /// its time-to-live expired
+    TimedOut,
+}
+
+/// A status update of a watched command
+#[derive(Debug, Clone)]
+pub struct TxUpdate {
+    /// Request key of the command, in base64url form
+    pub request_key: String,
+    /// What became of the command
+    pub status: TxStatus,
+}
+
+type Callback = Box<dyn FnMut(&TxUpdate) + Send>;
+
+struct Watch {
+    request_key: String,
+    since: Instant,
+    callback: Option<Callback>,
+}
+
+/// Watches request keys until their commands are mined
+///
+/// # Examples
+///
+/// ```ignore
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use kadena::fetch::{ApiClient, ApiConfig, TxStatus, TxTracker};
+///
+/// let client = ApiClient::new(ApiConfig::new("https://api.testnet.chainweb.com", "testnet04", "0"));
+/// let mut tracker = TxTracker::new(&client);
+/// tracker.watch("request-key", |update| {
+///     if let TxStatus::Succeeded(result) = &update.status {
+///         println!("{} mined at height {:?}", update.request_key, result.block_height());
+///     }
+/// })?;
+/// tracker.run().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TxTracker<'a> {
+    client: &'a ApiClient,
+    interval: Duration,
+    timeout: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+    watched: Vec<Watch>,
+}
+
+impl<'a> TxTracker<'a> {
+    /// Create a tracker polling the chain of `client`
+    pub fn new(client: &'a ApiClient) -> Self {
+        Self {
+            client,
+            interval: DEFAULT_TRACKER_INTERVAL,
+            timeout: None,
+            cancellation: None,
+            watched: Vec::new(),
+        }
+    }
+
+    /// Set the delay between two polls
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Give up on commands not mined within `timeout` of being watched
+    ///
+    /// They are reported as [`TxStatus::TimedOut`]. Without a timeout,
+    /// commands are watched until they are mined.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop [`run`](Self::run) and [`updates`](Self::updates) once `token` is cancelled
+    ///
+    /// The request keys not settled yet stay watched.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Watch a request key, calling `callback` once its command is mined
+    ///
+    /// Request keys may be given in base64url or hex form.
+    pub fn watch<F>(&mut self, request_key: &str, callback: F) -> Result<(), FetchError>
+    where
+        F: FnMut(&TxUpdate) + Send + 'static,
+    {
+        self.add(request_key, Some(Box::new(callback)))
+    }
+
+    /// Watch a request key without a callback, for use with [`updates`](Self::updates)
+    pub fn track(&mut self, request_key: &str) -> Result<(), FetchError> {
+        self.add(request_key, None)
+    }
+
+    /// Stop watching a request key
+    ///
+    /// Returns false if the key was not watched.
+    pub fn unwatch(&mut self, request_key: &str) -> bool {
+        let Ok(key) = RequestKey::parse(request_key) else {
+            return false;
+        };
+        let key = key.to_string();
+        let watched = self.watched.len();
+        self.watched.retain(|watch| watch.request_key != key);
+        self.watched.len() < watched
+    }
+
+    /// Request keys still watched, in base64url form
+    pub fn pending(&self) -> Vec<&str> {
+        self.watched
+            .iter()
+            .map(|watch| watch.request_key.as_str())
+            .collect()
+    }
+
+    /// Returns true when no request key is watched
+    pub fn is_empty(&self) -> bool {
+        self.watched.is_empty()
+    }
+
+    /// Poll the watched request keys once
+    ///
+    /// Mined and timed out commands are reported to their callbacks, returned
+    /// and no longer watched.
+    pub async fn poll_once(&mut self) -> Result<Vec<TxUpdate>, FetchError> {
+        if self.watched.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<&str> = self.pending();
+        let mut results = self.client.poll(&keys).await?;
+
+        let mut updates = Vec::new();
+        let mut pending = Vec::new();
+        for mut watch in std::mem::take(&mut self.watched) {
+            let status = match results.remove(&watch.request_key) {
+                Some(result) if result.is_success() => TxStatus::Succeeded(result),
+                Some(result) => TxStatus::Failed(result),
+                None if self
+                    .timeout
+                    .is_some_and(|timeout| watch.since.elapsed() >= timeout) =>
+                {
+                    TxStatus::TimedOut
+                }
+                None => {
+                    pending.push(watch);
+                    continue;
+                }
+            };
+            let update = TxUpdate {
+                request_key: watch.request_key,
+                status,
+            };
+            if let Some(callback) = watch.callback.as_mut() {
+                callback(&update);
+            }
+            updates.push(update);
+        }
+        self.watched = pending;
+        Ok(updates)
+    }
+
+    /// Poll until every watched command is mined or timed out
+    ///
+    /// Returns early, without error, once the cancellation token is cancelled.
+    pub async fn run(&mut self) -> Result<(), FetchError> {
+        let token = self.cancellation.clone().unwrap_or_default();
+        loop {
+            match token.run(self.poll_once()).await {
+                Ok(updates) => {
+                    updates?;
+                }
+                Err(_) => return Ok(()),
+            }
+            if self.watched.is_empty() {
+                return Ok(());
+            }
+            if token.run(sleep(self.interval)).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Turn the tracker into a stream of updates
+    ///
+    /// Callbacks still run. The stream ends once no request key is watched,
+    /// after the first error, or once the cancellation token is cancelled.
+    pub fn updates(self) -> impl Stream<Item = Result<TxUpdate, FetchError>> + 'a {
+        let token = self.cancellation.clone().unwrap_or_default();
+        let state = (self, Vec::<TxUpdate>::new(), true, false);
+        token.stream(stream::unfold(
+            state,
+            |(mut tracker, mut ready, mut first, failed)| async move {
+                loop {
+                    if let Some(update) = ready.pop() {
+                        return Some((Ok(update), (tracker, ready, first, failed)));
+                    }
+                    if failed || tracker.watched.is_empty() {
+                        return None;
+                    }
+                    if !first {
+                        sleep(tracker.interval).await;
+                    }
+                    first = false;
+                    match tracker.poll_once().await {
+                        Ok(mut updates) => {
+                            updates.reverse();
+                            ready = updates;
+                        }
+                        Err(e) => return Some((Err(e), (tracker, ready, first, true))),
+                    }
+                }
+            },
+        ))
+    }
+
+    fn add(&mut self, request_key: &str, callback: Option<Callback>) -> Result<(), FetchError> {
+        let request_key = RequestKey::parse(request_key)?.to_string();
+        self.watched
+            .retain(|watch| watch.request_key != request_key);
+        self.watched.push(Watch {
+            request_key,
+            since: Instant::now(),
+            callback,
+        });
+        Ok(())
+    }
+}
+
+impl fmt::Debug for TxTracker<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxTracker")
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
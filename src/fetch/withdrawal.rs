@@ -0,0 +1,277 @@
+//! Pipeline of outbound withdrawals
+//!
+//! A [`WithdrawalProcessor`] turns withdrawal requests into signed transfers
+//! from a hot wallet and drives them through a [`TxQueue`]: the fee budget is
+//! checked before signing, signatures are collected from every configured
+//! signer, and the queue submits with retries and polls until the transfers
+//! are mined. Nonces are derived from withdrawal ids and creation times are
+//! stored with the withdrawals, so a withdrawal always signs to the same
+//! command: requested twice, e.g. after a restart, it is only queued once,
+//! and it is not paid again once mined, even after leaving the queue.
+
+use std::fmt;
+
+use log::debug;
+use rust_decimal::Decimal;
+
+use crate::{
+    crypto::Signer,
+    pact::{
+        derive_nonce, Cap, CommandPayload, CommandSigner, FeePolicy, Guard, Meta, NetworkId,
+        SigningSession, TokenTransfer,
+    },
+    ChainwebClient, FetchError, QueueEntry, QueueReport, QueueStorage, TxQueue,
+};
+
+/// Scope of the nonces derived from withdrawal ids
+pub const WITHDRAWAL_NONCE_SCOPE: &str = "withdrawal";
+
+/// A request to pay out funds from the hot wallet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Withdrawal {
+    /// Business identifier of the withdrawal, unique per withdrawal
+    pub id: String,
+    /// The receiving account
+    pub receiver: String,
+    /// The amount paid out
+    pub amount: Decimal,
+    /// The chain the funds are paid out on
+    pub chain_id: String,
+    /// Guard of the receiving account, creating it if it does not exist yet
+    pub receiver_guard: Option<Guard>,
+    /// Creation time of the payout command, in seconds since the Unix epoch
+    pub creation_time: u64,
+}
+
+impl Withdrawal {
+    /// Create a withdrawal of `amount` to `receiver` on `chain_id`
+    ///
+    /// `creation_time` is usually the time the withdrawal was requested, and
+    /// must be stored with it: the request key of the payout depends on it.
+    /// The payout can be mined until its TTL has passed since then.
+    pub fn new(
+        id: &str,
+        receiver: &str,
+        amount: Decimal,
+        chain_id: &str,
+        creation_time: u64,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            receiver: receiver.to_string(),
+            amount,
+            chain_id: chain_id.to_string(),
+            receiver_guard: None,
+            creation_time,
+        }
+    }
+
+    /// Set the guard of the receiving account, paying out with `transfer-create`
+    pub fn with_receiver_guard(mut self, guard: impl Into<Guard>) -> Self {
+        self.receiver_guard = Some(guard.into());
+        self
+    }
+}
+
+/// Signs, submits and tracks withdrawals from a hot wallet
+///
+/// # Examples
+///
+/// ```ignore
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use kadena::crypto::PactKeypair;
+/// use kadena::fetch::{ChainwebClient, FileStorage, TxQueue, Withdrawal, WithdrawalProcessor};
+/// use kadena::pact::FeePolicy;
+/// use rust_decimal::Decimal;
+///
+/// let client = ChainwebClient::new("https://api.chainweb.com", "mainnet01");
+/// let keypair = PactKeypair::from_secret_key("...")?;
+/// let queue = TxQueue::open(FileStorage::new("withdrawals.json"))?;
+/// let mut processor = WithdrawalProcessor::new(&client, queue, "k:hot-wallet")
+///     .with_signer(&keypair)
+///     .with_fee_policy(FeePolicy::new().with_max_fee(Decimal::new(1, 2)));
+///
+/// let withdrawal = Withdrawal::new("w-42", "k:abc123", Decimal::new(15, 1), "0", 1_700_000_000);
+/// processor.submit(&withdrawal).await?;
+/// while !processor.is_settled() {
+///     processor.process().await?;
+///     tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct WithdrawalProcessor<'a, S: QueueStorage> {
+    client: &'a ChainwebClient,
+    queue: TxQueue<S>,
+    sender: String,
+    token: String,
+    signers: Vec<&'a (dyn Signer + Sync)>,
+    fee_policy: Option<FeePolicy>,
+    gas_limit: Option<u64>,
+    gas_price: Option<f64>,
+}
+
+impl<'a, S: QueueStorage> WithdrawalProcessor<'a, S> {
+    /// Create a processor paying `coin` out of `sender` through `queue`
+    pub fn new(client: &'a ChainwebClient, queue: TxQueue<S>, sender: &str) -> Self {
+        Self {
+            client,
+            queue,
+            sender: sender.to_string(),
+            token: "coin".to_string(),
+            signers: Vec::new(),
+            fee_policy: None,
+            gas_limit: None,
+            gas_price: None,
+        }
+    }
+
+    /// Pay out a `fungible-v2` token other than `coin`
+    pub fn with_token(mut self, module: &str) -> Self {
+        self.token = module.to_string();
+        self
+    }
+
+    /// Add a signer of the hot wallet's guard
+    pub fn with_signer(mut self, signer: &'a (dyn Signer + Sync)) -> Self {
+        self.signers.push(signer);
+        self
+    }
+
+    /// Reject withdrawals whose fees exceed `policy`, before signing them
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = Some(policy);
+        self
+    }
+
+    /// Set the gas limit of payouts, instead of the network default
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Set the gas price of payouts, instead of the network default
+    pub fn with_gas_price(mut self, gas_price: f64) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    /// Sign a withdrawal and add it to the queue, returning its request key
+    ///
+    /// A withdrawal whose id is already queued is not signed again; the
+    /// request key of the queued command is returned instead. Neither is a
+    /// withdrawal whose payout the node reports as mined, e.g. after
+    /// [`drain_settled`](Self::drain_settled) or the loss of the queue.
+    pub async fn submit(&mut self, withdrawal: &Withdrawal) -> Result<String, FetchError> {
+        let nonce = derive_nonce(WITHDRAWAL_NONCE_SCOPE, &withdrawal.id);
+        if let Some(entry) = self.queued(&nonce) {
+            return Ok(entry.cmd.hash.clone());
+        }
+
+        let mut meta = Meta::for_network(
+            NetworkId::from(self.client.network()),
+            &withdrawal.chain_id,
+            &self.sender,
+        )
+        .with_creation_time(withdrawal.creation_time);
+        if let Some(gas_limit) = self.gas_limit {
+            meta = meta.with_gas_limit(gas_limit);
+        }
+        if let Some(gas_price) = self.gas_price {
+            meta = meta.with_gas_price(gas_price);
+        }
+        if let Some(policy) = &self.fee_policy {
+            policy.check(&meta)?;
+        }
+
+        let mut transfer = TokenTransfer::new_unchecked(&self.token).transfer(
+            &self.sender,
+            &withdrawal.receiver,
+            withdrawal.amount,
+        );
+        if let Some(guard) = &withdrawal.receiver_guard {
+            transfer = transfer.with_receiver_guard(guard.clone());
+        }
+        transfer.check_receiver_principal()?;
+        let caps = vec![Cap::new("coin.GAS"), transfer.cap()];
+        let payload = CommandPayload::new(meta)
+            .with_nonce(nonce)
+            .with_network_id(self.client.network())
+            .with_code(transfer.code())
+            .with_env_data(transfer.env_data())
+            .with_signers(
+                self.signers
+                    .iter()
+                    .map(|signer| {
+                        CommandSigner::new(signer.scheme(), signer.public_key(), caps.clone())
+                    })
+                    .collect(),
+            );
+
+        let mut session = SigningSession::new(&payload)?;
+        let mined = self
+            .client
+            .chain(&withdrawal.chain_id)
+            .find_mined(session.hash())
+            .await?;
+        if mined.is_some() {
+            debug!("Withdrawal {} is mined already", withdrawal.id);
+            return Ok(session.hash().to_string());
+        }
+        for signer in &self.signers {
+            session.sign(*signer)?;
+        }
+        let cmd = session.into_cmd()?;
+        let request_key = cmd.hash.clone();
+        self.queue.enqueue(cmd, &withdrawal.chain_id)?;
+        Ok(request_key)
+    }
+
+    /// Get the queue entry of a withdrawal by id
+    pub fn get(&self, id: &str) -> Option<&QueueEntry> {
+        self.queued(&derive_nonce(WITHDRAWAL_NONCE_SCOPE, id))
+    }
+
+    /// Submit queued withdrawals and poll the submitted ones once
+    ///
+    /// See [`TxQueue::process`].
+    pub async fn process(&mut self) -> Result<QueueReport, FetchError> {
+        self.queue.process(self.client).await
+    }
+
+    /// Returns true once every withdrawal is mined or failed
+    pub fn is_settled(&self) -> bool {
+        self.queue.is_settled()
+    }
+
+    /// Get the queue of the processor
+    pub fn queue(&self) -> &TxQueue<S> {
+        &self.queue
+    }
+
+    /// Remove the mined and failed withdrawals from the queue, returning them
+    pub fn drain_settled(&mut self) -> Result<Vec<QueueEntry>, FetchError> {
+        self.queue.drain_settled()
+    }
+
+    fn queued(&self, nonce: &str) -> Option<&QueueEntry> {
+        self.queue.entries().iter().find(|entry| {
+            entry
+                .cmd
+                .payload()
+                .is_ok_and(|payload| payload.nonce == nonce)
+        })
+    }
+}
+
+impl<S: QueueStorage> fmt::Debug for WithdrawalProcessor<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithdrawalProcessor")
+            .field("sender", &self.sender)
+            .field("token", &self.token)
+            .field("signers", &self.signers.len())
+            .field("fee_policy", &self.fee_policy)
+            .field("entries", &self.queue.entries().len())
+            .finish()
+    }
+}
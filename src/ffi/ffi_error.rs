@@ -0,0 +1,30 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FfiError {
+    #[error("Null pointer passed as {0}")]
+    NullPointer(&'static str),
+    #[error("Argument {0} is not valid UTF-8")]
+    InvalidUtf8(&'static str),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(#[from] serde_json::Error),
+    #[error("Crypto error: {0}")]
+    CryptoError(#[from] crate::crypto::CryptoError),
+    #[error("Command error: {0}")]
+    CommandError(#[from] crate::pact::CommandError),
+}
+
+impl FfiError {
+    /// A stable, machine-readable code for the kind of error
+    ///
+    /// Wrapped crypto and command errors report the code of the underlying error.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::NullPointer(_) => "ffi.null_pointer",
+            Self::InvalidUtf8(_) => "ffi.invalid_utf8",
+            Self::InvalidRequest(_) => "ffi.invalid_request",
+            Self::CryptoError(e) => e.error_code(),
+            Self::CommandError(e) => e.error_code(),
+        }
+    }
+}
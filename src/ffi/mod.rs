@@ -0,0 +1,197 @@
+//! C ABI for mobile and other non-Rust hosts
+//!
+//! This module exposes key generation, hashing, signing and command
+//! preparation as `extern "C"` functions, so Kotlin (through JNI) and Swift
+//! apps can use this crate as their Kadena core. The matching declarations
+//! live in `include/kadena.h`. Build the library with the `ffi` feature as a
+//! static or dynamic library, e.g.:
+//!
+//! ```bash
+//! cargo rustc --release --no-default-features --features ffi --crate-type staticlib
+//! ```
+//!
+//! ## Conventions
+//!
+//! - Strings are NUL-terminated UTF-8, both ways.
+//! - Every returned string is owned by the caller and must be released with
+//!   [`kadena_string_free`].
+//! - Failing functions return a null pointer; [`kadena_last_error`] then
+//!   describes the error of the last failed call on the current thread.
+//! - Structured values, such as keypairs and commands, are exchanged as JSON.
+
+pub mod ffi_error;
+
+pub use ffi_error::*;
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    ptr, slice,
+};
+
+use serde_json::json;
+
+use crate::{
+    crypto::{base64url_decode, hash, PactKeypair},
+    pact::ExecRequest,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Generate a new keypair
+///
+/// Returns `{"publicKey": "...", "secretKey": "..."}` with hex encoded keys.
+#[no_mangle]
+pub extern "C" fn kadena_keypair_generate() -> *mut c_char {
+    let keypair = PactKeypair::generate();
+    into_c_string(
+        json!({
+            "publicKey": keypair.public_key(),
+            "secretKey": keypair.secret_key(),
+        })
+        .to_string(),
+    )
+}
+
+/// Derive the hex public key of a hex secret key
+///
+/// # Safety
+///
+/// `secret_key` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kadena_public_key(secret_key: *const c_char) -> *mut c_char {
+    ffi_call(|| {
+        let keypair = PactKeypair::from_secret_key(read_str(secret_key, "secret_key")?)?;
+        Ok(keypair.public_key().to_string())
+    })
+}
+
+/// Hash `len` bytes at `data` with Blake2b-256, returning the base64url digest
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes; it may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn kadena_hash(data: *const u8, len: usize) -> *mut c_char {
+    ffi_call(|| Ok(hash(read_bytes(data, len, "data")?)))
+}
+
+/// Sign `len` bytes at `message`, returning the hex signature
+///
+/// # Safety
+///
+/// `secret_key` must be null or point to a NUL-terminated string, and
+/// `message` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn kadena_sign(
+    secret_key: *const c_char,
+    message: *const u8,
+    len: usize,
+) -> *mut c_char {
+    ffi_call(|| {
+        let keypair = PactKeypair::from_secret_key(read_str(secret_key, "secret_key")?)?;
+        Ok(keypair.sign(read_bytes(message, len, "message")?)?.to_hex())
+    })
+}
+
+/// Sign a base64url command hash, as found in the `hash` field of a command
+///
+/// # Safety
+///
+/// Both arguments must be null or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn kadena_sign_hash(
+    secret_key: *const c_char,
+    hash: *const c_char,
+) -> *mut c_char {
+    ffi_call(|| {
+        let keypair = PactKeypair::from_secret_key(read_str(secret_key, "secret_key")?)?;
+        let hash = base64url_decode(read_str(hash, "hash")?)?;
+        Ok(keypair.sign(&hash)?.to_hex())
+    })
+}
+
+/// Prepare and sign an execution command from a JSON request
+///
+/// The request is an [`ExecRequest`]: an object with the fields `code`,
+/// `chainId`, `sender`, `signers` (a list of `{"secretKey", "caps"}`) and the
+/// optional fields `data`, `networkId`, `nonce`, `gasLimit`, `gasPrice` and
+/// `ttl`. Returns the command JSON, ready to be sent to a node.
+///
+/// # Safety
+///
+/// `request` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kadena_prepare_exec(request: *const c_char) -> *mut c_char {
+    ffi_call(|| {
+        let request: ExecRequest = serde_json::from_str(read_str(request, "request")?)?;
+        Ok(serde_json::to_string(&request.prepare()?)?)
+    })
+}
+
+/// Describe the error of the last failed call on this thread
+///
+/// Returns null if no call has failed yet. The returned string must be freed
+/// with [`kadena_string_free`].
+#[no_mangle]
+pub extern "C" fn kadena_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null_mut(), |message| into_c_string(message.clone()))
+    })
+}
+
+/// Release a string returned by this library
+///
+/// # Safety
+///
+/// `s` must be null or a pointer returned by one of the `kadena_*` functions
+/// that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn kadena_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Run `f`, converting its result into a C string or recording its error
+fn ffi_call(f: impl FnOnce() -> Result<String, FfiError>) -> *mut c_char {
+    match f() {
+        Ok(value) => into_c_string(value),
+        Err(e) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(e.to_string()));
+            ptr::null_mut()
+        }
+    }
+}
+
+fn into_c_string(value: String) -> *mut c_char {
+    // Strings produced by this module never contain interior NUL bytes
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+unsafe fn read_str<'a>(s: *const c_char, name: &'static str) -> Result<&'a str, FfiError> {
+    if s.is_null() {
+        return Err(FfiError::NullPointer(name));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| FfiError::InvalidUtf8(name))
+}
+
+unsafe fn read_bytes<'a>(
+    data: *const u8,
+    len: usize,
+    name: &'static str,
+) -> Result<&'a [u8], FfiError> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(FfiError::NullPointer(name)),
+        (false, _) => Ok(slice::from_raw_parts(data, len)),
+    }
+}
@@ -14,6 +14,7 @@
 //! ## Quick Start
 //!
 //! ```rust
+//! # #[cfg(feature = "fetch")] {
 //! use kadena::{
 //!     crypto::PactKeypair,
 //!     pact::{
@@ -64,6 +65,7 @@
 //!
 //! //Send the tx
 //! //let result = client.local(&transaction_cmd).await?;
+//! # }
 //! ```
 //!
 //! ## Modules
@@ -74,6 +76,35 @@
 //!   - [`pact::cap`] - Capability creation and management
 //!   - [`pact::command`] - Command preparation and signing
 //! - [`fetch`] - API client management
+//! - [`wallet`] - Owned and watch-only wallet accounts
+//! - [`ffi`] - C ABI bindings (feature `ffi`)
+//! - [`testing`] - Mock node for integration tests (feature `testing`)
+//! - [`wasm`] - JavaScript bindings (feature `wasm`)
+//!
+//! ## Cargo Features
+//!
+//! The crate is split into cargo features so that signing-only consumers do not
+//! pull in an HTTP stack or an async runtime:
+//!
+//! - `crypto` - Key generation, signing and hashing
+//! - `pact` - Command building, without any networking
+//! - `fetch` - The [`fetch::ApiClient`], backed by `reqwest`; requires one of the runtime features
+//! - `runtime-tokio` - Timers and Unix sockets on tokio
+//! - `runtime-agnostic` - Timers that work under any executor, see [`fetch::timer`]
+//! - `wallet` - Wallet accounts, enables `fetch` and `runtime-tokio`
+//! - `keystore` - `@kadena/hd-wallet` mnemonic derivation and encrypted key storage
+//! - `merkle` - Merkle trees and proofs
+//! - `ffi` - C ABI for mobile apps, see [`ffi`]
+//! - `testing` - A mock node for integration tests, see [`testing`]
+//! - `wasm` - JavaScript bindings and the browser's randomness source, see [`wasm`]
+//!
+//! All of the above except `runtime-agnostic`, `ffi`, `testing` and `wasm`
+//! are enabled by default. A build for signing only looks like:
+//!
+//! ```toml
+//! [dependencies]
+//! kadena = { version = "0.1.0", default-features = false, features = ["pact"] }
+//! ```
 //!
 //! ## Examples
 //!
@@ -90,12 +121,13 @@
 //! let signature = keypair.sign(message).unwrap();
 //!
 //! // Verify the signature
-//! assert!(keypair.verify(message, &signature).unwrap());
+//! assert!(keypair.verify(message, &signature.to_hex()).unwrap());
 //! ```
 //!
 //! ### Creating a Transfer Transaction
 //!
 //! ```rust
+//! # #[cfg(feature = "pact")] {
 //! use kadena::pact::{
 //!     meta::Meta,
 //!     cap::Cap,
@@ -127,6 +159,7 @@
 //!     meta,
 //!     Some("testnet04".to_string()),
 //! ).unwrap();
+//! # }
 //! ```
 //!
 //! ## Performance
@@ -159,6 +192,10 @@
 //! }
 //! ```
 //!
+//! Every error enum also has an `error_code()` accessor returning a stable code,
+//! such as `"crypto.invalid_hex"`, to map failures to metrics or messages
+//! without matching on display strings.
+//!
 //! ## Contribution
 //!
 //! Contributions are welcome! Please feel free to submit a Pull Request. Check out our
@@ -168,10 +205,26 @@
 //!
 //! This project is licensed under the MIT License.
 //!
+#[cfg(feature = "crypto")]
 pub mod crypto;
+#[cfg(feature = "fetch")]
 pub mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "pact")]
 pub mod pact;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "wallet")]
+pub mod wallet;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+#[cfg(feature = "crypto")]
 pub use crypto::*;
+#[cfg(feature = "fetch")]
 pub use fetch::*;
+#[cfg(feature = "pact")]
 pub use pact::*;
+#[cfg(feature = "wallet")]
+pub use wallet::*;
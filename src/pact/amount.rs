@@ -0,0 +1,165 @@
+use std::{fmt, str::FromStr};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::{transfer::pact_decimal, CommandError, PactValue};
+
+/// Number of decimal places of a KDA amount, as enforced by `coin`
+pub const KDA_DECIMALS: u32 = 12;
+
+/// A non-negative KDA amount, with KDA's 12-decimal precision
+///
+/// Amounts are exact decimals: they never go through floating point, and
+/// arithmetic is checked instead of overflowing or going negative.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::Amount;
+///
+/// let amount: Amount = "1.5 KDA".parse().unwrap();
+/// let total = amount.checked_add("0.25".parse().unwrap()).unwrap();
+/// assert_eq!(total.to_string(), "1.75 KDA");
+/// assert_eq!(total.to_pact(), "1.75");
+///
+/// // More precision than KDA supports is rejected
+/// assert!("0.0000000000001".parse::<Amount>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(Decimal);
+
+impl Amount {
+    /// The zero amount
+    pub const ZERO: Amount = Amount(Decimal::ZERO);
+
+    /// Creates an amount, rejecting negative values and excess precision
+    pub fn new(amount: Decimal) -> Result<Self, CommandError> {
+        let amount = amount.normalize();
+        if amount.is_sign_negative() && !amount.is_zero() {
+            return Err(CommandError::InvalidAmount(format!(
+                "{} is negative",
+                amount
+            )));
+        }
+        if amount.scale() > KDA_DECIMALS {
+            return Err(CommandError::InvalidAmount(format!(
+                "{} has more than {} decimal places",
+                amount, KDA_DECIMALS
+            )));
+        }
+        Ok(Self(amount.abs()))
+    }
+
+    /// Creates an amount, rounding down to KDA's precision
+    ///
+    /// Used for computed amounts, e.g. a share of a balance. Negative values
+    /// are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Amount;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let third = Amount::rounded(Decimal::ONE / Decimal::from(3)).unwrap();
+    /// assert_eq!(third.to_pact(), "0.333333333333");
+    /// ```
+    pub fn rounded(amount: Decimal) -> Result<Self, CommandError> {
+        Self::new(amount.round_dp_with_strategy(KDA_DECIMALS, RoundingStrategy::ToZero))
+    }
+
+    /// Parses an amount such as `1.5`, `1.5 KDA` or `1.5KDA`
+    pub fn parse(text: &str) -> Result<Self, CommandError> {
+        let text = text.trim();
+        let number = text
+            .strip_suffix("KDA")
+            .or_else(|| text.strip_suffix("kda"))
+            .unwrap_or(text)
+            .trim_end();
+        let decimal = Decimal::from_str_exact(number)
+            .map_err(|e| CommandError::InvalidAmount(format!("{}: {}", text, e)))?;
+        Self::new(decimal)
+    }
+
+    /// The maximum gas fee of a transaction, `gas_limit × gas_price`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Amount;
+    ///
+    /// let fee = Amount::fee(2500, 0.00000001).unwrap();
+    /// assert_eq!(fee.to_string(), "0.000025 KDA");
+    /// ```
+    pub fn fee(gas_limit: u64, gas_price: f64) -> Result<Self, CommandError> {
+        let gas_price = Decimal::from_str(&gas_price.to_string())
+            .map_err(|e| CommandError::InvalidAmount(format!("gas price {}: {}", gas_price, e)))?;
+        let fee = Decimal::from(gas_limit)
+            .checked_mul(gas_price)
+            .ok_or_else(|| CommandError::InvalidAmount("gas fee overflows".to_string()))?;
+        Self::rounded(fee)
+    }
+
+    /// Returns the amount as a decimal
+    pub fn as_decimal(&self) -> Decimal {
+        self.0
+    }
+
+    /// Returns the amount as a Pact decimal literal, e.g. `1.0`
+    pub fn to_pact(&self) -> String {
+        pact_decimal(self.0)
+    }
+
+    /// Adds two amounts, returning `None` on overflow
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    /// Subtracts an amount, returning `None` if the result would be negative
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        (self.0 >= other.0).then(|| Self(self.0 - other.0))
+    }
+
+    /// Multiplies the amount, rounding down to KDA's precision
+    ///
+    /// Returns `None` on overflow or for a negative factor.
+    pub fn checked_mul(self, factor: Decimal) -> Option<Amount> {
+        self.0
+            .checked_mul(factor)
+            .and_then(|amount| Self::rounded(amount).ok())
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} KDA", self.0)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<Decimal> for Amount {
+    type Error = CommandError;
+
+    fn try_from(amount: Decimal) -> Result<Self, Self::Error> {
+        Self::new(amount)
+    }
+}
+
+impl From<Amount> for Decimal {
+    fn from(amount: Amount) -> Self {
+        amount.0
+    }
+}
+
+impl From<Amount> for PactValue {
+    fn from(amount: Amount) -> Self {
+        PactValue::Decimal(amount.0)
+    }
+}
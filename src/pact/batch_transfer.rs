@@ -0,0 +1,302 @@
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    cap::Cap, meta::Meta, transfer::pact_decimal, Cmd, CommandError, FeePolicy, Guard, PactKeypair,
+    TokenTransfer, Transfer,
+};
+
+/// Estimated gas of a batch transaction, apart from its transfers
+pub const BATCH_BASE_GAS: u64 = 400;
+/// Estimated gas of each `transfer` in a batch
+pub const TRANSFER_GAS: u64 = 600;
+/// Estimated gas of each `transfer-create` in a batch
+pub const TRANSFER_CREATE_GAS: u64 = 1100;
+
+/// Environment data key holding the transfers of a looping batch
+const TRANSFERS_KEY: &str = "transfers";
+
+/// Builder for a single transaction performing many transfers from one sender
+///
+/// Transfers are called one after the other, or, when looping, by mapping
+/// over a list of transfers sent in the environment data, which keeps the code
+/// short for large airdrops. Transfers creating the receiving account are
+/// always called one after the other.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::BatchTransfer;
+/// use rust_decimal::Decimal;
+///
+/// let batch = BatchTransfer::new("alice")
+///     .add("bob", Decimal::new(1, 0))
+///     .add("carol", Decimal::new(2, 0))
+///     .add("bob", Decimal::new(3, 0));
+/// assert_eq!(
+///     batch.code(),
+///     "(coin.transfer \"alice\" \"bob\" 1.0)\n(coin.transfer \"alice\" \"carol\" 2.0)\n(coin.transfer \"alice\" \"bob\" 3.0)"
+/// );
+///
+/// // One capability per receiver, covering all of its transfers
+/// let caps = batch.caps();
+/// assert_eq!(caps.len(), 2);
+/// assert_eq!(caps[0].args[2], serde_json::json!({"decimal": "4.0"}));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchTransfer {
+    /// The `fungible-v2` module of the token, e.g. `coin`
+    pub module: String,
+    /// The sending account
+    pub sender: String,
+    /// The transfers, in order
+    pub transfers: Vec<Transfer>,
+    /// Whether plain transfers are called by mapping over the environment data
+    pub looping: bool,
+    /// Budget checked before signing, if any
+    pub fee_policy: Option<FeePolicy>,
+}
+
+impl BatchTransfer {
+    /// Creates an empty batch of coin transfers from `sender`
+    pub fn new(sender: &str) -> Self {
+        Self {
+            module: "coin".to_string(),
+            sender: sender.to_string(),
+            transfers: Vec::new(),
+            looping: false,
+            fee_policy: None,
+        }
+    }
+
+    /// Sets the `fungible-v2` module of the token transferred
+    pub fn with_module(mut self, module: &str) -> Self {
+        self.module = module.to_string();
+        for transfer in &mut self.transfers {
+            transfer.module = module.to_string();
+        }
+        self
+    }
+
+    /// Calls plain transfers by mapping over a list in the environment data
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Adds a transfer of `amount` to `receiver`
+    pub fn add(mut self, receiver: &str, amount: Decimal) -> Self {
+        let transfer = self.transfer(receiver, amount);
+        self.transfers.push(transfer);
+        self
+    }
+
+    /// Adds a transfer of `amount` to `receiver`, creating the account if needed
+    ///
+    /// A keyset guard is sent in the environment data under its own name.
+    pub fn add_create(mut self, receiver: &str, amount: Decimal, guard: impl Into<Guard>) -> Self {
+        let transfer = self
+            .transfer(receiver, amount)
+            .with_receiver_guard(guard)
+            .with_keyset_name(&format!("ks{}", self.transfers.len()));
+        self.transfers.push(transfer);
+        self
+    }
+
+    /// Returns the total amount transferred
+    pub fn total(&self) -> Decimal {
+        self.transfers.iter().map(|t| t.amount).sum()
+    }
+
+    /// Returns the code performing the transfers
+    pub fn code(&self) -> String {
+        let (plain, create): (Vec<&Transfer>, Vec<&Transfer>) = self
+            .transfers
+            .iter()
+            .partition(|t| t.receiver_guard.is_none());
+
+        if self.looping && !plain.is_empty() {
+            let looped = format!(
+                "(map (lambda (t) ({}.transfer {} (at 'receiver t) (at 'amount t))) (read-msg {}))",
+                self.module,
+                Value::from(self.sender.as_str()),
+                Value::from(TRANSFERS_KEY)
+            );
+            std::iter::once(looped)
+                .chain(create.iter().map(|t| t.code()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            self.transfers
+                .iter()
+                .map(Transfer::code)
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+
+    /// Returns the environment data of the transfers
+    ///
+    /// Holds the keyset receiver guards and, when looping, the list of plain
+    /// transfers.
+    pub fn env_data(&self) -> Value {
+        let mut data = Map::new();
+        for transfer in &self.transfers {
+            if let Value::Object(guard_data) = transfer.env_data() {
+                data.extend(guard_data);
+            }
+        }
+        if self.looping {
+            let transfers: Vec<Value> = self
+                .transfers
+                .iter()
+                .filter(|t| t.receiver_guard.is_none())
+                .map(|t| {
+                    json!({
+                        "receiver": t.receiver,
+                        "amount": { "decimal": pact_decimal(t.amount) },
+                    })
+                })
+                .collect();
+            if !transfers.is_empty() {
+                data.insert(TRANSFERS_KEY.to_string(), Value::Array(transfers));
+            }
+        }
+        Value::Object(data)
+    }
+
+    /// Returns one `TRANSFER` capability per receiver, covering all its transfers
+    pub fn caps(&self) -> Vec<Cap> {
+        let mut totals: Vec<(&str, Decimal)> = Vec::new();
+        for transfer in &self.transfers {
+            match totals.iter_mut().find(|(r, _)| *r == transfer.receiver) {
+                Some((_, total)) => *total += transfer.amount,
+                None => totals.push((&transfer.receiver, transfer.amount)),
+            }
+        }
+        totals
+            .into_iter()
+            .map(|(receiver, amount)| self.transfer(receiver, amount).cap())
+            .collect()
+    }
+
+    /// Returns a conservative estimate of the gas the batch consumes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{BatchTransfer, BATCH_BASE_GAS, TRANSFER_GAS};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let batch = BatchTransfer::new("alice").add("bob", Decimal::ONE);
+    /// assert_eq!(batch.estimated_gas(), BATCH_BASE_GAS + TRANSFER_GAS);
+    /// ```
+    pub fn estimated_gas(&self) -> u64 {
+        BATCH_BASE_GAS + self.transfers.iter().map(transfer_gas).sum::<u64>()
+    }
+
+    /// Splits the batch into batches whose estimated gas fits `max_gas_limit`
+    ///
+    /// Transfers keep their order. A transfer exceeding the limit on its own
+    /// gets a batch of its own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::BatchTransfer;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let batch = (0..100).fold(BatchTransfer::new("alice"), |batch, i| {
+    ///     batch.add(&format!("k:{}", i), Decimal::ONE)
+    /// });
+    /// let batches = batch.split(15_000);
+    /// assert!(batches.iter().all(|b| b.estimated_gas() <= 15_000));
+    /// assert_eq!(batches.iter().map(|b| b.transfers.len()).sum::<usize>(), 100);
+    /// ```
+    pub fn split(&self, max_gas_limit: u64) -> Vec<Self> {
+        let mut batches = Vec::new();
+        let mut current = self.empty();
+        let mut gas = BATCH_BASE_GAS;
+        for transfer in &self.transfers {
+            let cost = transfer_gas(transfer);
+            if !current.transfers.is_empty() && gas + cost > max_gas_limit {
+                batches.push(std::mem::replace(&mut current, self.empty()));
+                gas = BATCH_BASE_GAS;
+            }
+            let transfer = match &transfer.receiver_guard {
+                Some(_) => transfer
+                    .clone()
+                    .with_keyset_name(&format!("ks{}", current.transfers.len())),
+                None => transfer.clone(),
+            };
+            current.transfers.push(transfer);
+            gas += cost;
+        }
+        if !current.transfers.is_empty() {
+            batches.push(current);
+        }
+        batches
+    }
+
+    /// Checks the gas fees of prepared commands against `policy`
+    ///
+    /// Batches split with [`split`](Self::split) keep the policy.
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = Some(policy);
+        self
+    }
+
+    /// Prepares the signed batch command
+    ///
+    /// Each signer is scoped to `coin.GAS` and the `TRANSFER` capability of
+    /// every receiver. The gas limit of `meta` is left as is; see
+    /// [`estimated_gas`](Self::estimated_gas).
+    pub fn prepare(
+        &self,
+        signers: &[&PactKeypair],
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        if let Some(policy) = &self.fee_policy {
+            policy.check(&meta)?;
+        }
+        let mut caps = vec![Cap::new("coin.GAS")];
+        caps.extend(self.caps());
+        let signers: Vec<(&PactKeypair, Vec<Cap>)> =
+            signers.iter().map(|kp| (*kp, caps.clone())).collect();
+
+        Cmd::prepare_exec(
+            &signers,
+            Vec::new(),
+            None,
+            &self.code(),
+            Some(self.env_data()),
+            meta,
+            network_id,
+        )
+    }
+
+    /// A transfer of the batch token from the batch sender
+    fn transfer(&self, receiver: &str, amount: Decimal) -> Transfer {
+        TokenTransfer::new_unchecked(&self.module).transfer(&self.sender, receiver, amount)
+    }
+
+    /// A batch with the same settings and no transfers
+    fn empty(&self) -> Self {
+        Self {
+            module: self.module.clone(),
+            sender: self.sender.clone(),
+            transfers: Vec::new(),
+            looping: self.looping,
+            fee_policy: self.fee_policy,
+        }
+    }
+}
+
+fn transfer_gas(transfer: &Transfer) -> u64 {
+    if transfer.receiver_guard.is_some() {
+        TRANSFER_CREATE_GAS
+    } else {
+        TRANSFER_GAS
+    }
+}
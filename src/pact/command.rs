@@ -3,7 +3,11 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use crate::{base64url_decode, cap::Cap, hash, meta::Meta, CommandError, PactKeypair};
+use crate::{
+    base64url_decode, cap::Cap, hash, meta::Meta, pact::number_mode::serialize_payload, read_memo,
+    validate_code, with_memo, AsyncSigner, CommandError, PactKeypair, RequestKey, Signature,
+    Signer, ED25519,
+};
 
 /// Implementation for SignaturePayload
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,9 +21,17 @@ impl SignaturePayload {
     }
 }
 
+impl From<Signature> for SignaturePayload {
+    fn from(sig: Signature) -> Self {
+        Self::new(sig.to_hex())
+    }
+}
+
 /// Implementation for CommandSigner
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandSigner {
+    /// Signature scheme, `ED25519` when a command leaves it out
+    #[serde(default = "default_scheme")]
     pub scheme: String,
     #[serde(rename = "pubKey")]
     pub pub_key: String,
@@ -27,14 +39,23 @@ pub struct CommandSigner {
 }
 
 impl CommandSigner {
-    /// Creates a new ED25519 signer
-    pub fn new_ed25519(pub_key: &str, caps: Vec<Cap>) -> Self {
+    /// Creates a new signer using the signature scheme named `scheme`
+    pub fn new(scheme: &str, pub_key: &str, caps: Vec<Cap>) -> Self {
         Self {
-            scheme: "ED25519".to_string(),
+            scheme: scheme.to_string(),
             pub_key: pub_key.to_string(),
             clist: caps,
         }
     }
+
+    /// Creates a new ED25519 signer
+    pub fn new_ed25519(pub_key: &str, caps: Vec<Cap>) -> Self {
+        Self::new(ED25519, pub_key, caps)
+    }
+}
+
+fn default_scheme() -> String {
+    ED25519.to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,9 +71,43 @@ impl CommandVerifier {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct ExecPayload {
-    pub exec: ExecCommand,
+/// The executable part of a command: Pact code or a defpact continuation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Payload {
+    Exec(ExecCommand),
+    Cont(ContCommand),
+}
+
+impl Default for Payload {
+    fn default() -> Self {
+        Self::Exec(ExecCommand::default())
+    }
+}
+
+impl Payload {
+    /// Returns the environment data of the payload
+    pub fn data(&self) -> &Value {
+        match self {
+            Self::Exec(exec) => &exec.data,
+            Self::Cont(cont) => &cont.data,
+        }
+    }
+
+    /// Returns the Pact code, for execution payloads
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Self::Exec(exec) => Some(&exec.code),
+            Self::Cont(_) => None,
+        }
+    }
+
+    pub(crate) fn data_mut(&mut self) -> &mut Value {
+        match self {
+            Self::Exec(exec) => &mut exec.data,
+            Self::Cont(cont) => &mut cont.data,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -70,6 +125,48 @@ impl ExecCommand {
     }
 }
 
+/// Continuation of a multi-step defpact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContCommand {
+    #[serde(rename = "pactId")]
+    pub pact_id: String,
+    pub step: u32,
+    pub rollback: bool,
+    pub data: Value,
+    pub proof: Option<String>,
+}
+
+impl ContCommand {
+    /// Creates a continuation of `pact_id` at `step`
+    pub fn new(pact_id: impl Into<String>, step: u32) -> Self {
+        Self {
+            pact_id: pact_id.into(),
+            step,
+            rollback: false,
+            data: json!({}),
+            proof: None,
+        }
+    }
+
+    /// Marks the continuation as a rollback of the step
+    pub fn with_rollback(mut self, rollback: bool) -> Self {
+        self.rollback = rollback;
+        self
+    }
+
+    /// Sets the environment data of the continuation
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the SPV proof, for cross-chain continuations
+    pub fn with_proof(mut self, proof: impl Into<String>) -> Self {
+        self.proof = Some(proof.into());
+        self
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandPayload {
     pub nonce: String,
@@ -78,7 +175,7 @@ pub struct CommandPayload {
     pub verifiers: Vec<CommandVerifier>,
     #[serde(rename = "networkId")]
     pub network_id: Option<String>,
-    pub payload: ExecPayload,
+    pub payload: Payload,
 }
 
 impl CommandPayload {
@@ -89,10 +186,30 @@ impl CommandPayload {
             signers: Vec::new(),
             verifiers: Vec::new(),
             network_id: None,
-            payload: ExecPayload::default(),
+            payload: Payload::default(),
         }
     }
 
+    /// Parses a serialized payload, e.g. the `cmd` of a command
+    ///
+    /// Payloads received from wallets or the network are untrusted; any input
+    /// yields a payload or an error, never a panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{CommandPayload, Meta};
+    ///
+    /// let cmd = serde_json::to_string(&CommandPayload::new(Meta::new("0", "k:abc"))).unwrap();
+    /// let payload = CommandPayload::parse(&cmd).unwrap();
+    /// assert_eq!(payload.meta.sender, "k:abc");
+    ///
+    /// assert!(CommandPayload::parse("{\"nonce\": 1").is_err());
+    /// ```
+    pub fn parse(cmd: &str) -> Result<Self, CommandError> {
+        Ok(serde_json::from_str(cmd)?)
+    }
+
     pub fn with_nonce(mut self, nonce: String) -> Self {
         self.nonce = nonce;
         self
@@ -103,8 +220,23 @@ impl CommandPayload {
         self
     }
 
+    /// Sets the Pact code, turning a continuation payload into an execution payload
     pub fn with_code(mut self, code: impl Into<String>) -> Self {
-        self.payload.exec.code = code.into();
+        match &mut self.payload {
+            Payload::Exec(exec) => exec.code = code.into(),
+            Payload::Cont(cont) => {
+                self.payload = Payload::Exec(ExecCommand {
+                    code: code.into(),
+                    data: cont.data.take(),
+                })
+            }
+        }
+        self
+    }
+
+    /// Replaces the payload with a defpact continuation
+    pub fn with_continuation(mut self, cont: ContCommand) -> Self {
+        self.payload = Payload::Cont(cont);
         self
     }
 
@@ -119,10 +251,22 @@ impl CommandPayload {
     }
 
     pub fn with_env_data(mut self, data: Value) -> Self {
-        self.payload.exec.data = data;
+        *self.payload.data_mut() = data;
         self
     }
 
+    /// Attaches an application memo to the environment data
+    pub fn with_memo(mut self, memo: &str) -> Self {
+        let data = self.payload.data_mut();
+        *data = with_memo(data.take(), memo);
+        self
+    }
+
+    /// Returns the application memo carried in the environment data, if any
+    pub fn memo(&self) -> Option<&str> {
+        read_memo(self.payload.data())
+    }
+
     pub fn add_signer(mut self, signer: CommandSigner) -> Self {
         self.signers.push(signer);
         self
@@ -135,6 +279,33 @@ impl CommandPayload {
     }
 }
 
+/// Human-readable overview of a prepared command
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSummary {
+    /// The command hash
+    pub hash: String,
+    /// The network identifier
+    pub network_id: Option<String>,
+    /// The chain the command targets
+    pub chain_id: String,
+    /// The gas payer
+    pub sender: String,
+    /// The gas limit
+    pub gas_limit: u64,
+    /// The gas price
+    pub gas_price: f64,
+    /// The Pact code to execute, for execution commands
+    pub code: Option<String>,
+    /// The defpact id, for continuation commands
+    pub pact_id: Option<String>,
+    /// Public keys of the signers
+    pub signers: Vec<String>,
+    /// Number of signatures attached
+    pub signature_count: usize,
+    /// The application memo, if any
+    pub memo: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cmd {
     /// List of signatures for the command
@@ -159,7 +330,8 @@ impl Cmd {
     ///
     /// # Returns
     ///
-    /// Returns a Result containing the prepared Cmd if successful, or a CommandError if preparation fails
+    /// Returns a Result containing the prepared Cmd if successful, or a CommandError if preparation fails.
+    /// The Pact code is checked with [`validate_code`] before anything is signed.
     ///
     /// # Examples
     ///
@@ -168,7 +340,7 @@ impl Cmd {
     /// use kadena::crypto::PactKeypair;
     ///
     /// let keypair = PactKeypair::generate();
-    /// let meta = Meta::new("0", &format!("k:{}", keypair.public_key));
+    /// let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
     /// let caps = vec![Cap::new("coin.GAS")];
     ///
     /// let cmd = Cmd::prepare_exec(
@@ -190,50 +362,262 @@ impl Cmd {
         meta: Meta,
         network_id: Option<String>,
     ) -> Result<Self, CommandError> {
-        // Create signers
-        let signers_data: Vec<CommandSigner> = signers
+        Self::exec_with(
+            signers, verifiers, nonce, pact_code, env_data, meta, network_id,
+        )
+    }
+
+    /// Prepares an execution command signed by arbitrary [`Signer`] implementations
+    ///
+    /// Behaves like [`Cmd::prepare_exec`], but accepts any signer, such as keys
+    /// held in a hardware security module. Signing failures are returned as
+    /// [`CommandError::SigningError`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::{PactKeypair, Signer};
+    /// use kadena::pact::{Cap, Cmd, Meta};
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let signer: &dyn Signer = &keypair;
+    /// let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+    ///
+    /// let cmd = Cmd::prepare_exec_with_signers(
+    ///     &[(signer, vec![Cap::new("coin.GAS")])],
+    ///     Vec::new(),
+    ///     None,
+    ///     "(+ 1 2)",
+    ///     None,
+    ///     meta,
+    ///     Some("testnet04".to_string()),
+    /// ).unwrap();
+    /// assert_eq!(cmd.sigs.len(), 1);
+    /// ```
+    pub fn prepare_exec_with_signers(
+        signers: &[(&dyn Signer, Vec<Cap>)],
+        verifiers: Vec<CommandVerifier>,
+        nonce: Option<&str>,
+        pact_code: &str,
+        env_data: Option<Value>,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Self, CommandError> {
+        Self::exec_with(
+            signers, verifiers, nonce, pact_code, env_data, meta, network_id,
+        )
+    }
+
+    fn exec_with<S: Signer + ?Sized>(
+        signers: &[(&S, Vec<Cap>)],
+        verifiers: Vec<CommandVerifier>,
+        nonce: Option<&str>,
+        pact_code: &str,
+        env_data: Option<Value>,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Self, CommandError> {
+        let command_signers = signers
             .iter()
-            .map(|(kp, caps)| CommandSigner::new_ed25519(&kp.public_key, caps.clone()))
+            .map(|(signer, caps)| {
+                CommandSigner::new(signer.scheme(), signer.public_key(), caps.clone())
+            })
             .collect();
+        let command_payload = exec_payload(
+            command_signers,
+            verifiers,
+            nonce,
+            pact_code,
+            env_data,
+            meta,
+            network_id,
+        )?;
 
-        // Create command payload
-        let command_payload = CommandPayload::new(meta)
-            .with_nonce(
-                nonce
-                    .map(ToString::to_string)
-                    .unwrap_or_else(generate_random_nonce),
-            )
-            .with_code(pact_code.to_string())
-            .with_signers(signers_data)
-            .with_verifiers(verifiers);
-
-        // Add optional fields
-        let command_payload = if let Some(network_id) = network_id {
-            command_payload.with_network_id(network_id)
-        } else {
-            command_payload
-        };
-
-        let command_payload = if let Some(data) = env_data {
-            command_payload.with_env_data(data)
-        } else {
-            command_payload
-        };
+        let keys: Vec<&S> = signers.iter().map(|(signer, _)| *signer).collect();
+        Self::sign_payload(&keys, &command_payload)
+    }
 
+    /// Prepares an execution command signed by [`AsyncSigner`] implementations
+    ///
+    /// Used with signers reached over the network, such as cloud KMS keys.
+    /// Signatures are requested one signer at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::PactKeypair;
+    /// use kadena::pact::{Cap, Cmd, Meta};
+    ///
+    /// # futures::executor::block_on(async {
+    /// let keypair = PactKeypair::generate();
+    /// let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+    ///
+    /// let cmd = Cmd::prepare_exec_async(
+    ///     &[(&keypair, vec![Cap::new("coin.GAS")])],
+    ///     Vec::new(),
+    ///     None,
+    ///     "(+ 1 2)",
+    ///     None,
+    ///     meta,
+    ///     Some("testnet04".to_string()),
+    /// )
+    /// .await
+    /// .unwrap();
+    /// assert_eq!(cmd.sigs.len(), 1);
+    /// # });
+    /// ```
+    pub async fn prepare_exec_async<S: AsyncSigner>(
+        signers: &[(&S, Vec<Cap>)],
+        verifiers: Vec<CommandVerifier>,
+        nonce: Option<&str>,
+        pact_code: &str,
+        env_data: Option<Value>,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Self, CommandError> {
+        let command_signers = signers
+            .iter()
+            .map(|(signer, caps)| {
+                CommandSigner::new(signer.scheme(), signer.public_key(), caps.clone())
+            })
+            .collect();
+        let command_payload = exec_payload(
+            command_signers,
+            verifiers,
+            nonce,
+            pact_code,
+            env_data,
+            meta,
+            network_id,
+        )?;
+
+        let cmd = serialize_payload(&command_payload)?;
+        let cmd_hash = hash(cmd.as_bytes());
+        let hash_bytes = base64url_decode(&cmd_hash)?;
+
+        let mut sigs = Vec::with_capacity(signers.len());
+        for (signer, _) in signers {
+            let sig = signer
+                .sign(&hash_bytes)
+                .await
+                .map_err(|e| CommandError::SigningError(e.to_string()))?;
+            sigs.push(SignaturePayload::from(sig));
+        }
+
+        Ok(Self {
+            hash: cmd_hash,
+            sigs,
+            cmd,
+        })
+    }
+
+    /// Prepares a defpact continuation command with signatures
+    ///
+    /// # Arguments
+    ///
+    /// * `signers` - A slice of tuples containing keypairs and their associated capabilities
+    /// * `verifiers` - Verifiers attached to the command
+    /// * `nonce` - Optional nonce value, if not provided a random one will be generated
+    /// * `cont` - The continuation to execute
+    /// * `meta` - Metadata for the command
+    /// * `network_id` - Optional network identifier
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, Cmd, ContCommand, Meta};
+    /// use kadena::crypto::PactKeypair;
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let meta = Meta::new("1", &format!("k:{}", keypair.public_key()));
+    ///
+    /// let cmd = Cmd::prepare_cont(
+    ///     &[(&keypair, vec![Cap::new("coin.GAS")])],
+    ///     Vec::new(),
+    ///     None,
+    ///     ContCommand::new("pact-id", 1).with_proof("spv-proof"),
+    ///     meta,
+    ///     Some("testnet04".to_string()),
+    /// ).unwrap();
+    /// assert_eq!(cmd.summarize().unwrap().pact_id.as_deref(), Some("pact-id"));
+    /// ```
+    pub fn prepare_cont(
+        signers: &[(&PactKeypair, Vec<Cap>)],
+        verifiers: Vec<CommandVerifier>,
+        nonce: Option<&str>,
+        cont: ContCommand,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Self, CommandError> {
+        let command_signers = signers
+            .iter()
+            .map(|(kp, caps)| CommandSigner::new_ed25519(kp.public_key(), caps.clone()))
+            .collect();
+        let command_payload = build_payload(command_signers, verifiers, nonce, meta, network_id)
+            .with_continuation(cont);
+
+        let keys: Vec<&PactKeypair> = signers.iter().map(|(kp, _)| *kp).collect();
+        Self::sign_payload(&keys, &command_payload)
+    }
+
+    /// Signs an already built command payload
+    ///
+    /// The signers must match the payload signers, in order. Used to
+    /// sign payloads built ahead of time, e.g. for watch-only accounts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::{PactKeypair, Signer};
+    /// use kadena::pact::{Cap, Cmd, CommandPayload, CommandSigner, Meta};
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let payload = CommandPayload::new(Meta::new("0", &format!("k:{}", keypair.public_key())))
+    ///     .with_code("(+ 1 2)".to_string())
+    ///     .add_signer(CommandSigner::new_ed25519(keypair.public_key(), vec![Cap::new("coin.GAS")]));
+    ///
+    /// let cmd = Cmd::from_payload(&payload, &[&keypair as &dyn Signer]).unwrap();
+    /// assert_eq!(cmd.sigs.len(), 1);
+    /// ```
+    pub fn from_payload(
+        command_payload: &CommandPayload,
+        signers: &[&dyn Signer],
+    ) -> Result<Self, CommandError> {
+        let expected: Vec<&str> = command_payload
+            .signers
+            .iter()
+            .map(|signer| signer.pub_key.as_str())
+            .collect();
+        let provided: Vec<&str> = signers.iter().map(|signer| signer.public_key()).collect();
+        if expected != provided {
+            return Err(CommandError::SigningError(format!(
+                "signers {:?} do not match the payload signers {:?}",
+                provided, expected
+            )));
+        }
+
+        Self::sign_payload(signers, command_payload)
+    }
+
+    fn sign_payload<S: Signer + ?Sized>(
+        signers: &[&S],
+        command_payload: &CommandPayload,
+    ) -> Result<Self, CommandError> {
         // Serialize and hash
-        let cmd = serde_json::to_string(&command_payload)?;
+        let cmd = serialize_payload(command_payload)?;
         let cmd_hash = hash(cmd.as_bytes());
 
         // Create signatures
         let hash_bytes = base64url_decode(&cmd_hash)?;
         let sigs = signers
             .iter()
-            .filter_map(|(kp, _)| {
-                kp.sign(&hash_bytes)
-                    .map(|sig| -> SignaturePayload { SignaturePayload::new(sig) })
-                    .ok()
+            .map(|signer| {
+                signer
+                    .sign(&hash_bytes)
+                    .map(SignaturePayload::from)
+                    .map_err(|e| CommandError::SigningError(e.to_string()))
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Self {
             hash: cmd_hash,
@@ -241,6 +625,104 @@ impl Cmd {
             cmd,
         })
     }
+
+    /// Returns the request key of the command
+    pub fn request_key(&self) -> Result<RequestKey, CommandError> {
+        RequestKey::parse(&self.hash)
+    }
+
+    /// Parses the serialized command back into its payload
+    pub fn payload(&self) -> Result<CommandPayload, CommandError> {
+        CommandPayload::parse(&self.cmd)
+    }
+
+    /// Summarizes the command for display or logging
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{with_memo, Cmd, Meta};
+    /// use serde_json::json;
+    ///
+    /// let cmd = Cmd::prepare_exec(
+    ///     &[],
+    ///     Vec::new(),
+    ///     None,
+    ///     "(coin.details \"k:abc\")",
+    ///     Some(with_memo(json!({}), "invoice-7")),
+    ///     Meta::new("0", "k:abc"),
+    ///     Some("testnet04".to_string()),
+    /// ).unwrap();
+    ///
+    /// let summary = cmd.summarize().unwrap();
+    /// assert_eq!(summary.memo.as_deref(), Some("invoice-7"));
+    /// assert_eq!(summary.chain_id, "0");
+    /// ```
+    pub fn summarize(&self) -> Result<CommandSummary, CommandError> {
+        let payload = self.payload()?;
+        Ok(CommandSummary {
+            hash: self.hash.clone(),
+            memo: payload.memo().map(ToString::to_string),
+            network_id: payload.network_id,
+            chain_id: payload.meta.chain_id,
+            sender: payload.meta.sender,
+            gas_limit: payload.meta.gas_limit,
+            gas_price: payload.meta.gas_price,
+            code: payload.payload.code().map(ToString::to_string),
+            pact_id: match &payload.payload {
+                Payload::Cont(cont) => Some(cont.pact_id.clone()),
+                Payload::Exec(_) => None,
+            },
+            signers: payload.signers.into_iter().map(|s| s.pub_key).collect(),
+            signature_count: self.sigs.len(),
+        })
+    }
+}
+
+/// Builds a command payload carrying the signers and common fields.
+fn build_payload(
+    signers: Vec<CommandSigner>,
+    verifiers: Vec<CommandVerifier>,
+    nonce: Option<&str>,
+    meta: Meta,
+    network_id: Option<String>,
+) -> CommandPayload {
+    let command_payload = CommandPayload::new(meta)
+        .with_nonce(
+            nonce
+                .map(ToString::to_string)
+                .unwrap_or_else(generate_random_nonce),
+        )
+        .with_signers(signers)
+        .with_verifiers(verifiers);
+
+    if let Some(network_id) = network_id {
+        command_payload.with_network_id(network_id)
+    } else {
+        command_payload
+    }
+}
+
+/// Builds the payload of an execution command after validating its code.
+fn exec_payload(
+    signers: Vec<CommandSigner>,
+    verifiers: Vec<CommandVerifier>,
+    nonce: Option<&str>,
+    pact_code: &str,
+    env_data: Option<Value>,
+    meta: Meta,
+    network_id: Option<String>,
+) -> Result<CommandPayload, CommandError> {
+    validate_code(pact_code)?;
+
+    let command_payload =
+        build_payload(signers, verifiers, nonce, meta, network_id).with_code(pact_code.to_string());
+
+    Ok(if let Some(data) = env_data {
+        command_payload.with_env_data(data)
+    } else {
+        command_payload
+    })
 }
 
 /// Generates a random nonce for a command.
@@ -8,4 +8,77 @@ pub enum CommandError {
     Base64Error(#[from] crate::crypto::CryptoError),
     #[error("Signing error: {0}")]
     SigningError(String),
+    #[error("Invalid continuation: {0}")]
+    InvalidContinuation(String),
+    #[error("Keyset not satisfied: {0}")]
+    UnsatisfiedKeyset(String),
+    #[error("Invalid principal: {0}")]
+    InvalidPrincipal(String),
+    #[error("Invalid guard: {0}")]
+    InvalidGuard(String),
+    #[error("Invalid Pact value: {0}")]
+    InvalidPactValue(String),
+    #[error("Invalid code template: {0}")]
+    InvalidTemplate(String),
+    #[error("Invalid signing session: {0}")]
+    InvalidSession(String),
+    #[error("Invalid request key: {0}")]
+    InvalidRequestKey(String),
+    #[error("Binary encoding error: {0}")]
+    BinaryEncoding(String),
+    #[error("Invalid Pact code: {0}")]
+    InvalidCode(#[from] crate::pact::LintError),
+    #[error("Compatibility mismatch: {0}")]
+    CompatMismatch(String),
+    #[error("Invalid gas station use: {0}")]
+    InvalidGasStation(String),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("Fee budget exceeded: {0}")]
+    FeeExceeded(String),
+    #[error("Module source error: {0}")]
+    ModuleSource(String),
+    #[error("Missing interface: {0}")]
+    MissingInterface(String),
+    #[error("Imprecise number: {0}")]
+    ImpreciseNumber(String),
+}
+
+impl CommandError {
+    /// A stable, machine-readable code for the kind of error
+    ///
+    /// Wrapped crypto and lint errors report the code of the underlying error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{CommandError, RequestKey};
+    ///
+    /// let error = RequestKey::parse("not a key").unwrap_err();
+    /// assert_eq!(error.error_code(), "command.invalid_request_key");
+    /// ```
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::SerializationError(_) => "command.serialization",
+            Self::Base64Error(e) => e.error_code(),
+            Self::SigningError(_) => "command.signing",
+            Self::InvalidContinuation(_) => "command.invalid_continuation",
+            Self::UnsatisfiedKeyset(_) => "command.unsatisfied_keyset",
+            Self::InvalidPrincipal(_) => "command.invalid_principal",
+            Self::InvalidGuard(_) => "command.invalid_guard",
+            Self::InvalidPactValue(_) => "command.invalid_pact_value",
+            Self::InvalidTemplate(_) => "command.invalid_template",
+            Self::InvalidSession(_) => "command.invalid_session",
+            Self::InvalidRequestKey(_) => "command.invalid_request_key",
+            Self::BinaryEncoding(_) => "command.binary_encoding",
+            Self::InvalidCode(e) => e.error_code(),
+            Self::CompatMismatch(_) => "command.compat_mismatch",
+            Self::InvalidGasStation(_) => "command.invalid_gas_station",
+            Self::InvalidAmount(_) => "command.invalid_amount",
+            Self::FeeExceeded(_) => "command.fee_exceeded",
+            Self::ModuleSource(_) => "command.module_source",
+            Self::MissingInterface(_) => "command.missing_interface",
+            Self::ImpreciseNumber(_) => "command.imprecise_number",
+        }
+    }
 }
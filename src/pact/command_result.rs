@@ -0,0 +1,293 @@
+//! Typed command results
+//!
+//! Results of `local`, `poll` and `listen` requests share the same shape.
+//! [`CommandResult`] exposes it as typed fields, so callers do not have to
+//! navigate raw JSON.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{GasLogEntry, GasReport, PactEvent, PactExec};
+
+/// Outcome of a command execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum PactResult {
+    /// The command succeeded with a value
+    Success { data: Value },
+    /// The command failed with an error
+    Failure { error: Value },
+}
+
+impl PactResult {
+    /// Classifies the error of a failed command
+    ///
+    /// Returns `None` for successful commands.
+    pub fn failure(&self) -> Option<PactFailure> {
+        match self {
+            Self::Success { .. } => None,
+            Self::Failure { error } => Some(PactFailure::from_error(error)),
+        }
+    }
+}
+
+/// Well-known on-chain failures of `coin` and `fungible-v2` transfers
+///
+/// Each variant keeps the message reported by the node.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::PactFailure;
+///
+/// let failure = PactFailure::parse("Keyset failure (keys-all): [abc123...]");
+/// assert!(matches!(failure, PactFailure::KeysetFailure(_)));
+/// assert_eq!(failure.error_code(), "pact.keyset_failure");
+/// assert_eq!(failure.message(), "Keyset failure (keys-all): [abc123...]");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PactFailure {
+    /// The debited account's balance is below the amount
+    #[error("{0}")]
+    InsufficientFunds(String),
+    /// The account does not exist on the chain
+    #[error("{0}")]
+    AccountNotFound(String),
+    /// The signatures do not satisfy the account's keyset
+    #[error("{0}")]
+    KeysetFailure(String),
+    /// The signers did not grant a matching `TRANSFER` or other capability
+    #[error("{0}")]
+    CapabilityMismatch(String),
+    /// Any other failure
+    #[error("{0}")]
+    Other(String),
+}
+
+impl PactFailure {
+    /// Classifies an error message reported by a node
+    pub fn parse(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let message = message.to_string();
+        if lower.contains("insufficient funds") {
+            Self::InsufficientFunds(message)
+        } else if lower.contains("row not found")
+            || lower.contains("no value found in table")
+            || lower.contains("account does not exist")
+            || lower.contains("account not found")
+        {
+            Self::AccountNotFound(message)
+        } else if lower.contains("keyset failure") {
+            Self::KeysetFailure(message)
+        } else if lower.contains("capability not installed")
+            || lower.contains("not granted")
+            || lower.contains("exceeded for balance")
+        {
+            Self::CapabilityMismatch(message)
+        } else {
+            Self::Other(message)
+        }
+    }
+
+    /// Classifies the `error` of a failed command result
+    ///
+    /// Accepts the object form with a `message` field as well as bare strings.
+    pub fn from_error(error: &Value) -> Self {
+        match error["message"].as_str().or_else(|| error.as_str()) {
+            Some(message) => Self::parse(message),
+            None => Self::parse(&error.to_string()),
+        }
+    }
+
+    /// The message reported by the node
+    pub fn message(&self) -> &str {
+        match self {
+            Self::InsufficientFunds(message)
+            | Self::AccountNotFound(message)
+            | Self::KeysetFailure(message)
+            | Self::CapabilityMismatch(message)
+            | Self::Other(message) => message,
+        }
+    }
+
+    /// A stable, machine-readable code for the kind of failure
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::InsufficientFunds(_) => "pact.insufficient_funds",
+            Self::AccountNotFound(_) => "pact.account_not_found",
+            Self::KeysetFailure(_) => "pact.keyset_failure",
+            Self::CapabilityMismatch(_) => "pact.capability_mismatch",
+            Self::Other(_) => "pact.other",
+        }
+    }
+}
+
+/// Block in which a command was included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockMetadata {
+    /// Height of the block
+    pub block_height: u64,
+    /// Hash of the block, empty for local execution
+    #[serde(default)]
+    pub block_hash: String,
+    /// Creation time of the block, in microseconds since the Unix epoch
+    pub block_time: u64,
+    /// Hash of the parent block
+    pub prev_block_hash: String,
+    /// Public metadata of the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_meta: Option<Value>,
+}
+
+impl BlockMetadata {
+    /// Number of confirmations of the block given the current chain height
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::BlockMetadata;
+    ///
+    /// let meta = BlockMetadata {
+    ///     block_height: 100,
+    ///     block_hash: "hash".to_string(),
+    ///     block_time: 1_700_000_000_000_000,
+    ///     prev_block_hash: "prev".to_string(),
+    ///     public_meta: None,
+    /// };
+    /// assert_eq!(meta.confirmations(105), 6);
+    /// assert_eq!(meta.block_time_secs(), 1_700_000_000);
+    /// ```
+    pub fn confirmations(&self, current_height: u64) -> u64 {
+        current_height
+            .checked_sub(self.block_height)
+            .map_or(0, |depth| depth + 1)
+    }
+
+    /// Creation time of the block, in seconds since the Unix epoch
+    pub fn block_time_secs(&self) -> u64 {
+        self.block_time / 1_000_000
+    }
+}
+
+/// Result of a command, as returned by `local`, `poll` and `listen`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandResult {
+    /// Request key of the command
+    pub req_key: String,
+    /// Transaction id, absent for local execution
+    #[serde(default)]
+    pub tx_id: Option<u64>,
+    /// Outcome of the execution
+    pub result: PactResult,
+    /// Gas consumed
+    #[serde(default)]
+    pub gas: u64,
+    /// Hash of the transaction logs
+    #[serde(default)]
+    pub logs: Option<String>,
+    /// Defpact state, for commands running a defpact step
+    #[serde(default)]
+    pub continuation: Option<PactExec>,
+    /// Events emitted by the command
+    #[serde(default)]
+    pub events: Vec<PactEvent>,
+    /// Block in which the command was included
+    #[serde(default)]
+    pub meta_data: Option<BlockMetadata>,
+}
+
+impl CommandResult {
+    /// Parses a command result from its JSON representation
+    pub fn from_value(value: Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    /// Returns true if the command succeeded
+    pub fn is_success(&self) -> bool {
+        matches!(self.result, PactResult::Success { .. })
+    }
+
+    /// Classifies the error of a failed command, see [`PactFailure`]
+    pub fn failure(&self) -> Option<PactFailure> {
+        self.result.failure()
+    }
+
+    /// Returns the block height at which the command was included
+    pub fn block_height(&self) -> Option<u64> {
+        self.meta_data.as_ref().map(|meta| meta.block_height)
+    }
+
+    /// Returns the hash of the block in which the command was included
+    pub fn block_hash(&self) -> Option<&str> {
+        self.meta_data.as_ref().map(|meta| meta.block_hash.as_str())
+    }
+}
+
+/// Result of a `local` request run with preflight checks
+///
+/// Preflight runs the command as if it were included in the next block, with
+/// the buy-gas and signature checks of a real submission.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightResult {
+    /// Result of the simulated execution
+    pub result: CommandResult,
+    /// Warnings raised by the node, e.g. deprecated natives
+    pub warnings: Vec<String>,
+    /// Per-operation gas consumption, when the node reported it
+    pub gas_logs: Option<Vec<GasLogEntry>>,
+}
+
+impl PreflightResult {
+    /// Parses the response of a preflight `local` request
+    pub fn from_value(value: Value) -> Result<Self, serde_json::Error> {
+        let gas_logs = find_gas_logs(&value);
+        let warnings = value["preflightWarnings"]
+            .as_array()
+            .map(|warnings| {
+                warnings
+                    .iter()
+                    .map(|w| {
+                        w.as_str()
+                            .map_or_else(|| w.to_string(), ToString::to_string)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let result = match value {
+            Value::Object(mut obj) if obj.contains_key("preflightResult") => {
+                obj.remove("preflightResult").unwrap_or_default()
+            }
+            value => value,
+        };
+
+        Ok(Self {
+            result: serde_json::from_value(result)?,
+            warnings,
+            gas_logs,
+        })
+    }
+
+    /// Builds the gas report of the simulated execution
+    pub fn gas_report(&self) -> GasReport {
+        GasReport::new(self.result.gas, self.gas_logs.clone().unwrap_or_default())
+    }
+}
+
+/// Looks for gas logs in a `local` response
+///
+/// Nodes report them either next to the result or in its metadata, as
+/// `[name, gas]` pairs or `{"name": .., "amount": ..}` objects.
+fn find_gas_logs(value: &Value) -> Option<Vec<GasLogEntry>> {
+    let result = value.get("preflightResult").unwrap_or(value);
+    [
+        &value["gasLogs"],
+        &result["gasLogs"],
+        &result["metaData"]["gasLogs"],
+    ]
+    .into_iter()
+    .find_map(|logs| logs.as_array())
+    .map(|logs| logs.iter().filter_map(GasLogEntry::from_value).collect())
+}
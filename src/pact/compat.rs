@@ -0,0 +1,157 @@
+//! Compatibility with the JavaScript tooling
+//!
+//! Wallets and dapps commonly build commands with pact-js, so a command built
+//! by this crate from the same inputs should serialize, hash and sign to the
+//! same bytes. A [`CompatVector`] records the inputs of a command together
+//! with the expected `cmd` string, hash and signatures, and
+//! [`verify_vector`] checks this crate against it.
+//!
+//! The built-in corpus was written by hand; none of its vectors were captured
+//! from pact-js or Chainweaver. It pins the current encoding of this crate and
+//! catches drift when upgrading this crate or its dependencies through
+//! [`verify_builtin_vectors`], but does not show compatibility with any wallet.
+//! To check that, capture vectors from your own frontend and pass them to
+//! [`verify_vector`].
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{value::RawValue, Value};
+
+use crate::{
+    crypto::{base64url_decode, hash, PactKeypair},
+    pact::{CommandError, CommandPayload},
+};
+
+const BUILTIN_VECTORS: &str = include_str!("compat_vectors.json");
+
+/// A command with its expected encodings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatVector {
+    /// Name of the vector, used in error messages
+    pub name: String,
+    /// The command payload, as a JSON object
+    pub payload: Value,
+    /// The expected serialized payload
+    pub cmd: String,
+    /// The expected base64url Blake2b-256 hash of `cmd`
+    pub hash: String,
+    /// The expected signatures of the hash
+    #[serde(default)]
+    pub signatures: Vec<CompatSignature>,
+}
+
+/// An expected signature of a [`CompatVector`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatSignature {
+    /// Hex secret key of the signer
+    pub secret_key: String,
+    /// Expected hex signature
+    pub sig: String,
+}
+
+/// Check that this crate serializes, hashes and signs a vector byte for byte
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{builtin_vectors, verify_vector};
+///
+/// for vector in builtin_vectors() {
+///     verify_vector(&vector).unwrap();
+/// }
+/// ```
+pub fn verify_vector(vector: &CompatVector) -> Result<(), CommandError> {
+    let mismatch = |what: &str, expected: &str, actual: &str| {
+        CommandError::CompatMismatch(format!(
+            "{}: {} differs, expected {} but got {}",
+            vector.name, what, expected, actual
+        ))
+    };
+
+    let payload: CommandPayload = serde_json::from_value(vector.payload.clone())?;
+    let cmd = serde_json::to_string(&payload)?;
+    if cmd != vector.cmd {
+        return Err(mismatch("cmd", &vector.cmd, &cmd));
+    }
+
+    let cmd_hash = hash(cmd.as_bytes());
+    if cmd_hash != vector.hash {
+        return Err(mismatch("hash", &vector.hash, &cmd_hash));
+    }
+
+    let hash_bytes = base64url_decode(&cmd_hash)?;
+    for expected in &vector.signatures {
+        let sig = PactKeypair::from_secret_key(&expected.secret_key)?
+            .sign(&hash_bytes)?
+            .to_hex();
+        if sig != expected.sig {
+            return Err(mismatch("signature", &expected.sig, &sig));
+        }
+    }
+    Ok(())
+}
+
+/// The hand-written corpus of vectors shipped with this crate
+pub fn builtin_vectors() -> Vec<CompatVector> {
+    serde_json::from_str(BUILTIN_VECTORS).expect("built-in compatibility vectors are valid")
+}
+
+/// Verify every vector of the built-in corpus
+pub fn verify_builtin_vectors() -> Result<(), CommandError> {
+    builtin_vectors().iter().try_for_each(verify_vector)
+}
+
+/// Format a number like JavaScript's `Number.prototype.toString`
+///
+/// JavaScript writes integral values without a fraction and only switches to
+/// exponent notation below `1e-6` or from `1e21`, where `serde_json` writes
+/// `100.0` and `1e-6`.
+pub(crate) fn js_number(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        // JSON has no representation of infinities and NaN
+        return if value == 0.0 { "0" } else { "null" }.to_string();
+    }
+
+    // Shortest round-trip digits and the exponent of the first one
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("exponent notation has an exponent");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().expect("exponent is an integer") + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { '+' } else { '-' };
+        let exponent = (n - 1).abs();
+        match digits.split_at(1) {
+            (first, "") => format!("{}e{}{}", first, sign, exponent),
+            (first, rest) => format!("{}.{}e{}{}", first, rest, sign, exponent),
+        }
+    };
+
+    if value < 0.0 {
+        format!("-{}", body)
+    } else {
+        body
+    }
+}
+
+/// Serialize a number as JavaScript would in JSON, for fields Pact parses as decimals
+pub(crate) fn serialize_js_number<S: Serializer>(
+    value: &f64,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if !serializer.is_human_readable() {
+        return serializer.serialize_f64(*value);
+    }
+    let number = RawValue::from_string(js_number(*value)).map_err(serde::ser::Error::custom)?;
+    number.serialize(serializer)
+}
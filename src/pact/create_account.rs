@@ -0,0 +1,95 @@
+use crate::{cap::Cap, check_account_principal, meta::Meta, Cmd, CommandError, Guard, PactKeypair};
+
+/// Builder for `coin.create-account` transactions
+///
+/// Keyset guards are injected in the environment data and read back with
+/// `read-keyset`. For principal accounts (`k:`, `w:`, `r:` and `c:` names), preparing
+/// the command fails unless the account name matches the guard, which
+/// `coin.create-account` would otherwise reject on chain.
+#[derive(Debug, Clone)]
+pub struct CreateAccount {
+    /// The account to create
+    pub account: String,
+    /// The guard of the new account
+    pub guard: Guard,
+    /// Environment data key holding a keyset guard
+    pub keyset_name: String,
+}
+
+impl CreateAccount {
+    /// Creates a builder for `account` guarded by `guard`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{CreateAccount, KeySet};
+    ///
+    /// let create = CreateAccount::new("k:abc123", KeySet::keys_all(["abc123"]));
+    /// assert_eq!(create.code(), "(coin.create-account \"k:abc123\" (read-keyset \"ks\"))");
+    /// assert!(create.check_principal().is_ok());
+    /// ```
+    pub fn new(account: &str, guard: impl Into<Guard>) -> Self {
+        Self {
+            account: account.to_string(),
+            guard: guard.into(),
+            keyset_name: "ks".to_string(),
+        }
+    }
+
+    /// Sets the environment data key holding a keyset guard
+    pub fn with_keyset_name(mut self, keyset_name: &str) -> Self {
+        self.keyset_name = keyset_name.to_string();
+        self
+    }
+
+    /// Returns the account creation code
+    pub fn code(&self) -> String {
+        format!(
+            "(coin.create-account {} {})",
+            serde_json::Value::from(self.account.as_str()),
+            self.guard.code(&self.keyset_name)
+        )
+    }
+
+    /// Checks that a principal account name matches its guard
+    ///
+    /// Names without a reserved prefix, e.g. `k:`, are accepted with any guard.
+    pub fn check_principal(&self) -> Result<(), CommandError> {
+        check_account_principal(&self.account, &self.guard)
+    }
+
+    /// Prepares the signed account creation command
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, CreateAccount, KeySet, Meta};
+    /// use kadena::crypto::PactKeypair;
+    ///
+    /// let payer = PactKeypair::generate();
+    /// let meta = Meta::new("0", &format!("k:{}", payer.public_key()));
+    ///
+    /// // The account name does not match the keyset
+    /// let create = CreateAccount::new("k:abc123", KeySet::keys_all(["def456"]));
+    /// let result = create.prepare(&[(&payer, vec![Cap::new("coin.GAS")])], meta, None);
+    /// assert!(result.is_err());
+    /// ```
+    pub fn prepare(
+        &self,
+        signers: &[(&PactKeypair, Vec<Cap>)],
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        self.check_principal()?;
+
+        Cmd::prepare_exec(
+            signers,
+            Vec::new(),
+            None,
+            &self.code(),
+            Some(self.guard.env_data(&self.keyset_name)),
+            meta,
+            network_id,
+        )
+    }
+}
@@ -0,0 +1,277 @@
+//! Multi-step defpact tracking
+//!
+//! A defpact runs as a sequence of steps, each submitted as its own command.
+//! [`PactInstance`] records the progress reported in command results and
+//! prepares the continuation command for the next step. [`PactExec`] is the
+//! typed `continuation` field of command results it is built from.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{cap::Cap, meta::Meta, Cmd, CommandError, CommandResult, ContCommand, PactKeypair};
+
+/// Defpact state reported in the `continuation` field of a command result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PactExec {
+    /// The defpact id
+    pub pact_id: String,
+    /// The step just executed
+    pub step: u32,
+    /// Total number of steps of the defpact
+    pub step_count: u32,
+    /// Whether the step executed, `None` outside of nested defpacts
+    #[serde(default)]
+    pub executed: Option<bool>,
+    /// Whether the step has a rollback
+    #[serde(default)]
+    pub step_has_rollback: bool,
+    /// The defpact function and its arguments
+    #[serde(default)]
+    pub continuation: Option<PactContinuation>,
+    /// Data yielded by the step to the next one
+    #[serde(default, rename = "yield")]
+    pub yield_data: Option<Yield>,
+}
+
+/// Defpact function and arguments a defpact was started with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PactContinuation {
+    /// Fully qualified name of the defpact
+    pub def: String,
+    /// Arguments of the defpact
+    #[serde(default)]
+    pub args: Vec<Value>,
+}
+
+/// Data yielded by a defpact step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Yield {
+    /// The yielded object
+    pub data: Value,
+    /// Target of a cross-chain yield, `None` for same-chain yields
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+    /// Chain the data was yielded on
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Target of a cross-chain yield
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    /// Chain on which the next step must run
+    pub target_chain_id: String,
+    /// Hash of the module that yielded the data
+    pub module_hash: String,
+}
+
+impl PactExec {
+    /// Returns the next step to execute, or `None` once all steps ran
+    pub fn next_step(&self) -> Option<u32> {
+        let next = self.step + 1;
+        (next < self.step_count).then_some(next)
+    }
+
+    /// Returns true once the last step of the defpact has been executed
+    pub fn is_complete(&self) -> bool {
+        self.next_step().is_none()
+    }
+
+    /// Returns the chain on which the next step must run, for cross-chain yields
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::PactExec;
+    /// use serde_json::json;
+    ///
+    /// let exec: PactExec = serde_json::from_value(json!({
+    ///     "pactId": "abc",
+    ///     "step": 0,
+    ///     "stepCount": 2,
+    ///     "executed": null,
+    ///     "stepHasRollback": false,
+    ///     "continuation": {"def": "coin.transfer-crosschain", "args": ["k:a", "k:b", {}, "1", 1.0]},
+    ///     "yield": {
+    ///         "data": {"amount": 1.0},
+    ///         "provenance": {"targetChainId": "1", "moduleHash": "hash"},
+    ///         "source": "0"
+    ///     }
+    /// }))
+    /// .unwrap();
+    ///
+    /// assert_eq!(exec.target_chain_id(), Some("1"));
+    /// assert_eq!(exec.next_continuation().unwrap().step, 1);
+    /// ```
+    pub fn target_chain_id(&self) -> Option<&str> {
+        self.yield_data
+            .as_ref()?
+            .provenance
+            .as_ref()
+            .map(|p| p.target_chain_id.as_str())
+    }
+
+    /// Returns the continuation for the next step
+    pub fn next_continuation(&self) -> Result<ContCommand, CommandError> {
+        let step = self.next_step().ok_or_else(|| {
+            CommandError::InvalidContinuation(format!("pact {} is complete", self.pact_id))
+        })?;
+        Ok(ContCommand::new(self.pact_id.clone(), step))
+    }
+}
+
+/// State of a running defpact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PactInstance {
+    /// The defpact id
+    pub pact_id: String,
+    /// Total number of steps of the defpact
+    pub step_count: u32,
+    /// Whether the last executed step has a rollback
+    pub step_has_rollback: bool,
+    /// Steps executed so far, in execution order
+    pub executed_steps: Vec<u32>,
+}
+
+impl PactInstance {
+    /// Captures a defpact from a command result
+    ///
+    /// Accepts either a command result or a `local`/`poll` response wrapping it
+    /// under `result`, and reads its `continuation` field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::PactInstance;
+    /// use serde_json::json;
+    ///
+    /// let result = json!({
+    ///     "reqKey": "abc",
+    ///     "result": {"status": "success", "data": "Write succeeded"},
+    ///     "continuation": {"pactId": "abc", "step": 0, "stepCount": 2, "stepHasRollback": false}
+    /// });
+    ///
+    /// let instance = PactInstance::from_result(&result).unwrap();
+    /// assert_eq!(instance.next_step(), Some(1));
+    /// ```
+    pub fn from_result(result: &Value) -> Result<Self, CommandError> {
+        Ok(Self::from_exec(&find_continuation(result)?))
+    }
+
+    /// Captures a defpact from its typed state
+    pub fn from_exec(exec: &PactExec) -> Self {
+        Self {
+            pact_id: exec.pact_id.clone(),
+            step_count: exec.step_count,
+            step_has_rollback: exec.step_has_rollback,
+            executed_steps: vec![exec.step],
+        }
+    }
+
+    /// Captures a defpact from a typed command result
+    pub fn from_command_result(result: &CommandResult) -> Result<Self, CommandError> {
+        result
+            .continuation
+            .as_ref()
+            .map(Self::from_exec)
+            .ok_or_else(|| CommandError::InvalidContinuation("missing continuation".to_string()))
+    }
+
+    /// Records the result of a continuation command
+    ///
+    /// Fails if the result belongs to another defpact.
+    pub fn update(&mut self, result: &Value) -> Result<(), CommandError> {
+        self.update_exec(&find_continuation(result)?)
+    }
+
+    /// Records the typed state reported by a continuation command
+    ///
+    /// Fails if the state belongs to another defpact.
+    pub fn update_exec(&mut self, continuation: &PactExec) -> Result<(), CommandError> {
+        if continuation.pact_id != self.pact_id {
+            return Err(CommandError::InvalidContinuation(format!(
+                "result belongs to pact {} instead of {}",
+                continuation.pact_id, self.pact_id
+            )));
+        }
+        self.step_has_rollback = continuation.step_has_rollback;
+        self.executed_steps.push(continuation.step);
+        Ok(())
+    }
+
+    /// Returns the last executed step
+    pub fn current_step(&self) -> u32 {
+        self.executed_steps.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the next step to execute, or `None` once all steps ran
+    pub fn next_step(&self) -> Option<u32> {
+        let next = self.current_step() + 1;
+        (next < self.step_count).then_some(next)
+    }
+
+    /// Returns true once the last step of the defpact has been executed
+    pub fn is_complete(&self) -> bool {
+        self.next_step().is_none()
+    }
+
+    /// Returns the continuation for the next step
+    pub fn next_continuation(&self) -> Result<ContCommand, CommandError> {
+        let step = self.next_step().ok_or_else(|| {
+            CommandError::InvalidContinuation(format!("pact {} is complete", self.pact_id))
+        })?;
+        Ok(ContCommand::new(self.pact_id.clone(), step))
+    }
+
+    /// Returns the continuation rolling back the last executed step
+    pub fn rollback_continuation(&self) -> Result<ContCommand, CommandError> {
+        if !self.step_has_rollback {
+            return Err(CommandError::InvalidContinuation(format!(
+                "step {} of pact {} has no rollback",
+                self.current_step(),
+                self.pact_id
+            )));
+        }
+        Ok(ContCommand::new(self.pact_id.clone(), self.current_step()).with_rollback(true))
+    }
+
+    /// Prepares the signed command executing the next step
+    ///
+    /// # Arguments
+    ///
+    /// * `signers` - A slice of tuples containing keypairs and their associated capabilities
+    /// * `env_data` - Optional environment data for the step
+    /// * `proof` - Optional SPV proof, for steps continuing on another chain
+    /// * `meta` - Metadata for the command
+    /// * `network_id` - Optional network identifier
+    pub fn prepare_next(
+        &self,
+        signers: &[(&PactKeypair, Vec<Cap>)],
+        env_data: Option<Value>,
+        proof: Option<String>,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        let mut cont = self.next_continuation()?;
+        if let Some(data) = env_data {
+            cont = cont.with_data(data);
+        }
+        if let Some(proof) = proof {
+            cont = cont.with_proof(proof);
+        }
+        Cmd::prepare_cont(signers, Vec::new(), None, cont, meta, network_id)
+    }
+}
+
+fn find_continuation(result: &Value) -> Result<PactExec, CommandError> {
+    let continuation = if result["continuation"].is_object() {
+        &result["continuation"]
+    } else {
+        &result["result"]["continuation"]
+    };
+
+    PactExec::deserialize(continuation)
+        .map_err(|e| CommandError::InvalidContinuation(e.to_string()))
+}
@@ -0,0 +1,73 @@
+//! Typed Pact events
+//!
+//! Modules emit events, such as `coin.TRANSFER`, from `@event` capabilities.
+//! Command results list them with their module and parameters.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Module that emitted an event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleName {
+    /// Namespace of the module, if any
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Name of the module
+    pub name: String,
+}
+
+impl ModuleName {
+    /// Fully qualified module name, e.g. `free.my-module`
+    pub fn qualified(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}.{}", namespace, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// An event emitted by a command
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::PactEvent;
+/// use serde_json::json;
+///
+/// let event: PactEvent = serde_json::from_value(json!({
+///     "name": "TRANSFER",
+///     "module": {"namespace": null, "name": "coin"},
+///     "moduleHash": "rE7DU8jlQL9x_MPYuniZJf5ICBTAEHAIFQCB4blofP4",
+///     "params": ["k:alice", "k:bob", 1.5]
+/// }))
+/// .unwrap();
+///
+/// assert_eq!(event.qualified_name(), "coin.TRANSFER");
+/// assert_eq!(event.param(1).and_then(|p| p.as_str()), Some("k:bob"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PactEvent {
+    /// Name of the event
+    pub name: String,
+    /// Module that emitted the event
+    pub module: ModuleName,
+    /// Hash of the module that emitted the event
+    #[serde(default)]
+    pub module_hash: String,
+    /// Parameters of the event
+    #[serde(default)]
+    pub params: Vec<Value>,
+}
+
+impl PactEvent {
+    /// Fully qualified event name, e.g. `coin.TRANSFER`
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.module.qualified(), self.name)
+    }
+
+    /// Returns the parameter at `index`
+    pub fn param(&self, index: usize) -> Option<&Value> {
+        self.params.get(index)
+    }
+}
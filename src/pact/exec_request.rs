@@ -0,0 +1,121 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    crypto::PactKeypair,
+    pact::{Cap, Cmd, CommandError, Meta, NetworkId},
+};
+
+/// An execution command described as JSON, signed with raw secret keys
+///
+/// This is the request format of the language bindings, which cannot pass
+/// keypairs or metadata builders across their boundary. Gas settings that are
+/// not given default to the profile of `networkId`, see [`Meta::for_network`].
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::PactKeypair;
+/// use kadena::pact::ExecRequest;
+/// use serde_json::json;
+///
+/// let keypair = PactKeypair::generate();
+/// let request: ExecRequest = serde_json::from_value(json!({
+///     "code": "(+ 1 2)",
+///     "chainId": "0",
+///     "sender": format!("k:{}", keypair.public_key()),
+///     "networkId": "testnet04",
+///     "signers": [{"secretKey": keypair.secret_key(), "caps": []}],
+/// })).unwrap();
+///
+/// let cmd = request.prepare().unwrap();
+/// assert_eq!(cmd.sigs.len(), 1);
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecRequest {
+    pub code: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+    pub chain_id: String,
+    pub sender: String,
+    #[serde(default)]
+    pub network_id: Option<String>,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    #[serde(default)]
+    pub gas_price: Option<f64>,
+    #[serde(default)]
+    pub ttl: Option<u64>,
+    #[serde(default)]
+    pub signers: Vec<ExecSigner>,
+}
+
+/// A signer of an [`ExecRequest`] and the capabilities it grants
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecSigner {
+    pub secret_key: String,
+    #[serde(default)]
+    pub caps: Vec<Cap>,
+}
+
+impl fmt::Debug for ExecSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecSigner")
+            .field("secret_key", &"<redacted>")
+            .field("caps", &self.caps)
+            .finish()
+    }
+}
+
+impl ExecRequest {
+    /// Build the metadata and sign the command
+    pub fn prepare(self) -> Result<Cmd, CommandError> {
+        let mut meta = match &self.network_id {
+            Some(network_id) => Meta::for_network(
+                NetworkId::from(network_id.as_str()),
+                &self.chain_id,
+                &self.sender,
+            ),
+            None => Meta::new(&self.chain_id, &self.sender),
+        };
+        if let Some(gas_limit) = self.gas_limit {
+            meta = meta.with_gas_limit(gas_limit);
+        }
+        if let Some(gas_price) = self.gas_price {
+            meta = meta.with_gas_price(gas_price);
+        }
+        if let Some(ttl) = self.ttl {
+            meta = meta.with_ttl(ttl);
+        }
+
+        let keypairs = self
+            .signers
+            .iter()
+            .map(|signer| {
+                PactKeypair::from_secret_key(&signer.secret_key)
+                    .map_err(|e| CommandError::SigningError(format!("Invalid secret key: {}", e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let signers: Vec<(&PactKeypair, Vec<Cap>)> = keypairs
+            .iter()
+            .zip(self.signers)
+            .map(|(keypair, signer)| (keypair, signer.caps))
+            .collect();
+
+        Cmd::prepare_exec(
+            &signers,
+            Vec::new(),
+            self.nonce.as_deref(),
+            &self.code,
+            self.data,
+            meta,
+            self.network_id,
+        )
+    }
+}
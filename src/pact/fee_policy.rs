@@ -0,0 +1,111 @@
+use log::warn;
+use rust_decimal::Decimal;
+
+use crate::{meta::Meta, Cmd, CommandError};
+
+/// What a [`FeePolicy`] does with commands exceeding its budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeeAction {
+    /// Fail with [`CommandError::FeeExceeded`]
+    #[default]
+    Reject,
+    /// Log a warning and let the command through
+    Warn,
+}
+
+/// A budget on the gas fees of commands, for automated senders
+///
+/// The worst-case fee of a command is its gas limit times its gas price, the
+/// most the sender can be charged. Builders given a policy check it before
+/// signing, and an [`ApiClient`](crate::ApiClient) configured with one checks
+/// every command before sending it.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{FeePolicy, Meta};
+/// use rust_decimal::Decimal;
+///
+/// let policy = FeePolicy::new()
+///     .with_max_gas_price(0.0000001)
+///     .with_max_fee(Decimal::new(1, 3));
+///
+/// let meta = Meta::new("0", "k:abc123").with_gas_limit(10_000);
+/// assert!(policy.check(&meta).is_ok());
+///
+/// let error = policy.check(&meta.with_gas_limit(200_000)).unwrap_err();
+/// assert_eq!(error.error_code(), "command.fee_exceeded");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeePolicy {
+    /// The highest gas price, in KDA per gas unit
+    pub max_gas_price: Option<f64>,
+    /// The highest worst-case fee, in KDA
+    pub max_fee: Option<Decimal>,
+    /// What to do with commands exceeding the budget
+    pub action: FeeAction,
+}
+
+impl FeePolicy {
+    /// Creates a policy without limits, rejecting commands once limits are set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the highest gas price
+    pub fn with_max_gas_price(mut self, gas_price: f64) -> Self {
+        self.max_gas_price = Some(gas_price);
+        self
+    }
+
+    /// Sets the highest worst-case fee, in KDA
+    pub fn with_max_fee(mut self, fee: Decimal) -> Self {
+        self.max_fee = Some(fee);
+        self
+    }
+
+    /// Only log a warning for commands exceeding the budget
+    pub fn warn_only(mut self) -> Self {
+        self.action = FeeAction::Warn;
+        self
+    }
+
+    /// Checks the gas settings of a command against the budget
+    pub fn check(&self, meta: &Meta) -> Result<(), CommandError> {
+        match self.violation(meta)? {
+            Some(violation) if self.action == FeeAction::Reject => {
+                Err(CommandError::FeeExceeded(violation))
+            }
+            Some(violation) => {
+                warn!("Fee budget exceeded: {}", violation);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a signed command against the budget
+    pub fn check_cmd(&self, cmd: &Cmd) -> Result<(), CommandError> {
+        self.check(&cmd.payload()?.meta)
+    }
+
+    /// Describes how `meta` exceeds the budget, if it does
+    fn violation(&self, meta: &Meta) -> Result<Option<String>, CommandError> {
+        if let Some(max) = self.max_gas_price.filter(|max| meta.gas_price > *max) {
+            return Ok(Some(format!(
+                "gas price {} exceeds the maximum of {}",
+                meta.gas_price, max
+            )));
+        }
+        if let Some(max) = self.max_fee {
+            let fee = meta.max_fee()?;
+            if fee.as_decimal() > max {
+                return Ok(Some(format!(
+                    "worst-case fee of {} exceeds the maximum of {} KDA",
+                    fee, max
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
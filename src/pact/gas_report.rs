@@ -0,0 +1,99 @@
+//! Gas consumption reports
+//!
+//! Nodes can report the gas charged for each operation of a command. A
+//! [`GasReport`] aggregates these logs to find expensive calls and to derive a
+//! tighter gas limit.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Gas charged for a single operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasLogEntry {
+    /// The operation, e.g. a native function name
+    pub operation: String,
+    /// Gas charged for the operation
+    pub gas: u64,
+}
+
+impl GasLogEntry {
+    /// Parses a gas log entry, either a `[name, gas]` pair or an object
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::GasLogEntry;
+    /// use serde_json::json;
+    ///
+    /// let entry = GasLogEntry::from_value(&json!(["read", 20])).unwrap();
+    /// assert_eq!(entry.operation, "read");
+    /// assert_eq!(entry.gas, 20);
+    /// ```
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let (operation, gas) = match value {
+            Value::Array(pair) => (pair.first()?, pair.get(1)?),
+            Value::Object(obj) => (
+                obj.get("name").or_else(|| obj.get("operation"))?,
+                obj.get("amount").or_else(|| obj.get("gas"))?,
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            operation: operation.as_str()?.to_string(),
+            gas: gas.as_u64()?,
+        })
+    }
+}
+
+/// Gas consumption of a command, broken down by operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasReport {
+    /// Total gas consumed by the command
+    pub total: u64,
+    /// Gas charged per operation, in execution order
+    pub entries: Vec<GasLogEntry>,
+}
+
+impl GasReport {
+    /// Creates a report from the total gas and the gas logs
+    pub fn new(total: u64, entries: Vec<GasLogEntry>) -> Self {
+        Self { total, entries }
+    }
+
+    /// Returns true if the report has a per-operation breakdown
+    pub fn has_breakdown(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// Returns the gas consumed per operation, most expensive first
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{GasLogEntry, GasReport};
+    ///
+    /// let entry = |operation: &str, gas| GasLogEntry { operation: operation.to_string(), gas };
+    /// let report = GasReport::new(70, vec![entry("read", 20), entry("write", 30), entry("read", 20)]);
+    /// assert_eq!(report.by_operation()[0], ("read".to_string(), 40));
+    /// ```
+    pub fn by_operation(&self) -> Vec<(String, u64)> {
+        let mut totals: Vec<(String, u64)> = Vec::new();
+        for entry in &self.entries {
+            match totals.iter_mut().find(|(op, _)| *op == entry.operation) {
+                Some((_, gas)) => *gas += entry.gas,
+                None => totals.push((entry.operation.clone(), entry.gas)),
+            }
+        }
+        totals.sort_by_key(|(_, gas)| std::cmp::Reverse(*gas));
+        totals
+    }
+
+    /// Returns a gas limit covering the measured usage plus a safety margin
+    ///
+    /// # Arguments
+    ///
+    /// * `margin_percent` - Extra gas to allow, as a percentage of the measured total
+    pub fn suggested_gas_limit(&self, margin_percent: u64) -> u64 {
+        self.total + (self.total * margin_percent).div_ceil(100)
+    }
+}
@@ -0,0 +1,227 @@
+use serde_json::{json, Value};
+
+use crate::{cap::Cap, meta::Meta, Cmd, CommandError, ContCommand, PactKeypair};
+
+/// Account of the gas station paying for cross-chain transfer finishes
+pub const KADENA_XCHAIN_GAS: &str = "kadena-xchain-gas";
+
+/// A gas station account paying the gas of the transactions routed through it
+///
+/// Stations implementing `gas-payer-v1` are used by signing their
+/// `GAS_PAYER` capability instead of `coin.GAS`. The `kadena-xchain-gas`
+/// station needs no signature at all, but only pays for continuations within
+/// its gas limit and gas price.
+#[derive(Debug, Clone)]
+pub struct GasStation {
+    /// The station account, used as the sender of the transaction
+    pub account: String,
+    /// The module defining the `GAS_PAYER` capability, if the station has one
+    pub module: Option<String>,
+    /// The highest gas limit the station pays for
+    pub max_gas_limit: Option<u64>,
+    /// The highest gas price the station pays
+    pub max_gas_price: Option<f64>,
+}
+
+impl GasStation {
+    /// Creates a station paying from `account` through `module`'s `GAS_PAYER`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{GasStation, Meta};
+    ///
+    /// let station = GasStation::new("free.gas-station", "free-gas");
+    /// let meta = Meta::new("0", "free-gas");
+    /// let cap = station.gas_payer_cap("k:abc123", &meta).unwrap();
+    /// assert_eq!(cap.name, "free.gas-station.GAS_PAYER");
+    /// assert_eq!(cap.args[0], "k:abc123");
+    /// ```
+    pub fn new(module: &str, account: &str) -> Self {
+        Self {
+            account: account.to_string(),
+            module: Some(module.to_string()),
+            max_gas_limit: None,
+            max_gas_price: None,
+        }
+    }
+
+    /// The `kadena-xchain-gas` station, paying for cross-chain transfer finishes
+    pub fn kadena_xchain_gas() -> Self {
+        Self {
+            account: KADENA_XCHAIN_GAS.to_string(),
+            module: None,
+            max_gas_limit: Some(850),
+            max_gas_price: Some(0.00000001),
+        }
+    }
+
+    /// Sets the highest gas limit the station pays for
+    pub fn with_max_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.max_gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Sets the highest gas price the station pays
+    pub fn with_max_gas_price(mut self, gas_price: f64) -> Self {
+        self.max_gas_price = Some(gas_price);
+        self
+    }
+
+    /// Creates metadata with the station as sender, within its gas limits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::GasStation;
+    ///
+    /// let meta = GasStation::kadena_xchain_gas().meta("1");
+    /// assert_eq!(meta.sender, "kadena-xchain-gas");
+    /// assert_eq!(meta.gas_limit, 850);
+    /// ```
+    pub fn meta(&self, chain_id: &str) -> Meta {
+        let mut meta = Meta::new(chain_id, &self.account);
+        if let Some(gas_limit) = self.max_gas_limit {
+            meta.gas_limit = meta.gas_limit.min(gas_limit);
+        }
+        if let Some(gas_price) = self.max_gas_price {
+            meta.gas_price = meta.gas_price.min(gas_price);
+        }
+        meta
+    }
+
+    /// Returns the `GAS_PAYER` capability for `user`, if the station has one
+    ///
+    /// The arguments follow `gas-payer-v1`: the user, the gas limit and the
+    /// gas price of the transaction.
+    pub fn gas_payer_cap(&self, user: &str, meta: &Meta) -> Option<Cap> {
+        self.module.as_ref().map(|module| {
+            Cap::with_args(
+                &format!("{}.GAS_PAYER", module),
+                vec![
+                    json!(user),
+                    json!({ "int": meta.gas_limit }),
+                    json!(meta.gas_price),
+                ],
+            )
+        })
+    }
+
+    /// Prepares an execution command whose gas is paid by the station
+    ///
+    /// The sender of `meta` is replaced by the station account. Each signer
+    /// is scoped to the `GAS_PAYER` capability for `user` and to its own caps,
+    /// which should not include `coin.GAS`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::PactKeypair;
+    /// use kadena::pact::{Cap, GasStation, Meta};
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let user = format!("k:{}", keypair.public_key());
+    /// let station = GasStation::new("free.gas-station", "free-gas");
+    ///
+    /// let cmd = station
+    ///     .prepare_exec(
+    ///         &user,
+    ///         &[(&keypair, vec![Cap::new("free.game.PLAY")])],
+    ///         "(free.game.play)",
+    ///         None,
+    ///         station.meta("0"),
+    ///         Some("testnet04".to_string()),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(cmd.payload().unwrap().signers[0].clist.len(), 2);
+    /// ```
+    pub fn prepare_exec(
+        &self,
+        user: &str,
+        signers: &[(&PactKeypair, Vec<Cap>)],
+        pact_code: &str,
+        env_data: Option<Value>,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        let meta = self.check_meta(meta)?;
+        let gas_payer = self.gas_payer_cap(user, &meta).ok_or_else(|| {
+            CommandError::InvalidGasStation(format!("{} only pays for continuations", self.account))
+        })?;
+
+        let signers: Vec<(&PactKeypair, Vec<Cap>)> = signers
+            .iter()
+            .map(|(kp, caps)| {
+                let mut scoped = vec![gas_payer.clone()];
+                scoped.extend(caps.iter().cloned());
+                (*kp, scoped)
+            })
+            .collect();
+
+        Cmd::prepare_exec(
+            &signers,
+            Vec::new(),
+            None,
+            pact_code,
+            env_data,
+            meta,
+            network_id,
+        )
+    }
+
+    /// Prepares a continuation, such as a cross-chain finish, paid by the station
+    ///
+    /// The sender of `meta` is replaced by the station account. Stations with
+    /// a `GAS_PAYER` capability need a signer, the others need none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{ContCommand, GasStation};
+    ///
+    /// let station = GasStation::kadena_xchain_gas();
+    /// let cont = ContCommand::new("pact-id", 1).with_proof("spv-proof");
+    ///
+    /// let cmd = station
+    ///     .prepare_cont("k:abc123", &[], cont, station.meta("1"), None)
+    ///     .unwrap();
+    /// assert!(cmd.sigs.is_empty());
+    /// ```
+    pub fn prepare_cont(
+        &self,
+        user: &str,
+        signers: &[&PactKeypair],
+        cont: ContCommand,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        let meta = self.check_meta(meta)?;
+        let signers: Vec<(&PactKeypair, Vec<Cap>)> = match self.gas_payer_cap(user, &meta) {
+            Some(gas_payer) => signers
+                .iter()
+                .map(|kp| (*kp, vec![gas_payer.clone()]))
+                .collect(),
+            None => signers.iter().map(|kp| (*kp, Vec::new())).collect(),
+        };
+
+        Cmd::prepare_cont(&signers, Vec::new(), None, cont, meta, network_id)
+    }
+
+    /// Sets the station as sender and checks the gas limits
+    fn check_meta(&self, mut meta: Meta) -> Result<Meta, CommandError> {
+        if let Some(max) = self.max_gas_limit.filter(|max| meta.gas_limit > *max) {
+            return Err(CommandError::InvalidGasStation(format!(
+                "gas limit {} exceeds the {} limit of {}",
+                meta.gas_limit, self.account, max
+            )));
+        }
+        if let Some(max) = self.max_gas_price.filter(|max| meta.gas_price > *max) {
+            return Err(CommandError::InvalidGasStation(format!(
+                "gas price {} exceeds the {} limit of {}",
+                meta.gas_price, self.account, max
+            )));
+        }
+        meta.sender = self.account.clone();
+        Ok(meta)
+    }
+}
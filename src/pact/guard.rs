@@ -0,0 +1,427 @@
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+
+use crate::{cap::Cap, hash, keyset::read_keyset_code, CommandError, KeySet, KEYS_ALL};
+
+/// A guard protecting an account
+#[derive(Debug, Clone, PartialEq)]
+pub enum Guard {
+    /// A keyset, sent in the environment data
+    KeySet(KeySet),
+    /// A reference to a keyset defined on chain, e.g. `free.my-keyset`
+    KeySetRef(String),
+    /// A guard granted by acquiring a capability
+    Capability(Cap),
+    /// A guard enforced by calling a function, e.g. `free.vault.enforce-owner`
+    User {
+        /// Fully qualified name of the function
+        fun: String,
+        /// Arguments the function is called with
+        args: Vec<Value>,
+    },
+    /// A guard only satisfied from within a module
+    Module {
+        /// Fully qualified name of the module, e.g. `free.vault`
+        module: String,
+        /// Name distinguishing guards of the same module
+        name: String,
+    },
+    /// A guard only satisfied from within a defpact
+    Pact {
+        /// Id of the defpact
+        pact_id: String,
+        /// Name distinguishing guards of the same defpact
+        name: String,
+    },
+}
+
+impl Guard {
+    /// Returns the Pact expression building the guard
+    ///
+    /// Keysets are read from the environment data key `keyset_name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, Guard};
+    ///
+    /// let guard = Guard::Capability(Cap::new("free.vault.VAULT").add_arg("alice"));
+    /// assert_eq!(guard.code("ks"), "(create-capability-guard (free.vault.VAULT \"alice\"))");
+    /// ```
+    pub fn code(&self, keyset_name: &str) -> String {
+        match self {
+            Guard::KeySet(_) => read_keyset_code(keyset_name),
+            Guard::KeySetRef(name) => {
+                format!("(keyset-ref-guard {})", Value::from(name.as_str()))
+            }
+            Guard::Capability(cap) => {
+                format!(
+                    "(create-capability-guard {})",
+                    application(&cap.name, &cap.args)
+                )
+            }
+            Guard::User { fun, args } => {
+                format!("(create-user-guard {})", application(fun, args))
+            }
+            Guard::Module { name, .. } => {
+                format!("(create-module-guard {})", Value::from(name.as_str()))
+            }
+            Guard::Pact { name, .. } => {
+                format!("(create-pact-guard {})", Value::from(name.as_str()))
+            }
+        }
+    }
+
+    /// Returns the Pact JSON encoding of the guard
+    ///
+    /// This is the form guards take as capability arguments and in
+    /// environment data:
+    ///
+    /// - keysets: `{"keys": [..], "pred": ".."}`
+    /// - keyset references: `{"keysetref": {"ns": "free", "ksn": "ks"}}`, or
+    ///   `{"keysetref": "ks"}` without namespace
+    /// - capability guards: `{"cgName": "..", "cgArgs": [..], "cgPactId": null}`
+    /// - user guards: `{"fun": "..", "args": [..]}`
+    /// - module guards: `{"moduleName": {"namespace": .., "name": ".."}, "name": ".."}`
+    /// - pact guards: `{"pactId": "..", "name": ".."}`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, Guard};
+    /// use serde_json::json;
+    ///
+    /// let guard = Guard::KeySetRef("free.admin".to_string());
+    /// let cap = Cap::new("free.vault.ROTATE").add_arg(guard);
+    /// assert_eq!(cap.args[0], json!({"keysetref": {"ns": "free", "ksn": "admin"}}));
+    /// ```
+    pub fn to_value(&self) -> Value {
+        match self {
+            Guard::KeySet(keyset) => json!(keyset),
+            Guard::KeySetRef(name) => match name.split_once('.') {
+                Some((ns, ksn)) => json!({"keysetref": {"ns": ns, "ksn": ksn}}),
+                None => json!({ "keysetref": name }),
+            },
+            Guard::Capability(cap) => {
+                json!({"cgName": cap.name, "cgArgs": cap.args, "cgPactId": null})
+            }
+            Guard::User { fun, args } => json!({"fun": fun, "args": args}),
+            Guard::Module { module, name } => {
+                let (namespace, module) = match module.rsplit_once('.') {
+                    Some((namespace, module)) => (Some(namespace), module),
+                    None => (None, module.as_str()),
+                };
+                json!({"moduleName": {"namespace": namespace, "name": module}, "name": name})
+            }
+            Guard::Pact { pact_id, name } => json!({"pactId": pact_id, "name": name}),
+        }
+    }
+
+    /// Decode a guard from its Pact JSON encoding
+    ///
+    /// Accepts every encoding produced by [`Guard::to_value`], as well as
+    /// keyset references given as a single qualified name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Guard;
+    /// use serde_json::json;
+    ///
+    /// let guard = Guard::from_value(&json!({"keysetref": {"ns": "free", "ksn": "admin"}})).unwrap();
+    /// assert_eq!(guard.principal(), "r:free.admin");
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Self, CommandError> {
+        let invalid = || CommandError::InvalidGuard(value.to_string());
+        let string = |value: &Value| value.as_str().map(ToString::to_string).ok_or_else(invalid);
+        let object = value.as_object().ok_or_else(invalid)?;
+
+        if object.contains_key("keys") {
+            Ok(Guard::KeySet(KeySet::deserialize(value)?))
+        } else if let Some(keyset_ref) = object.get("keysetref") {
+            match keyset_ref {
+                Value::String(name) => Ok(Guard::KeySetRef(name.clone())),
+                keyset_ref => {
+                    let ksn = string(&keyset_ref["ksn"])?;
+                    Ok(Guard::KeySetRef(match keyset_ref["ns"].as_str() {
+                        Some(ns) => format!("{}.{}", ns, ksn),
+                        None => ksn,
+                    }))
+                }
+            }
+        } else if let Some(cap_name) = object.get("cgName") {
+            let args = object.get("cgArgs").cloned().unwrap_or_default();
+            Ok(Guard::Capability(Cap {
+                name: string(cap_name)?,
+                args: serde_json::from_value(args)?,
+            }))
+        } else if let Some(fun) = object.get("fun") {
+            let args = object.get("args").cloned().unwrap_or_default();
+            Ok(Guard::User {
+                fun: string(fun)?,
+                args: serde_json::from_value(args)?,
+            })
+        } else if let Some(module) = object.get("moduleName") {
+            let name = string(&module["name"])?;
+            Ok(Guard::Module {
+                module: match module["namespace"].as_str() {
+                    Some(namespace) => format!("{}.{}", namespace, name),
+                    None => name,
+                },
+                name: string(&value["name"])?,
+            })
+        } else if let Some(pact_id) = object.get("pactId") {
+            Ok(Guard::Pact {
+                pact_id: string(pact_id)?,
+                name: string(&value["name"])?,
+            })
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// Returns the environment data the guard expression reads
+    ///
+    /// Only keysets are read from the environment data; use
+    /// [`Guard::to_value`] to send any other guard as data.
+    pub fn env_data(&self, keyset_name: &str) -> Value {
+        match self {
+            Guard::KeySet(keyset) => json!({ keyset_name: keyset }),
+            _ => json!({}),
+        }
+    }
+
+    /// Returns the principal account name of the guard
+    ///
+    /// Keysets map to `k:` or `w:` principals, keyset references to `r:`,
+    /// capability guards to `c:`, user guards to `u:`, module guards to `m:`
+    /// and pact guards to `p:` principals.
+    ///
+    /// As `create-principal` does, the principal of a user guard is the
+    /// function name followed by the hash of the JSON encoding of each
+    /// argument, which must be in Pact's encoding, like the arguments of
+    /// [`Cap::guard_principal`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Guard;
+    /// use serde_json::json;
+    ///
+    /// let guard = Guard::User {
+    ///     fun: "free.vault.enforce-owner".to_string(),
+    ///     args: vec![json!("alice")],
+    /// };
+    /// assert!(guard.principal().starts_with("u:free.vault.enforce-owner:"));
+    /// ```
+    pub fn principal(&self) -> String {
+        match self {
+            Guard::KeySet(keyset) => keyset.principal(),
+            Guard::KeySetRef(name) => format!("r:{}", name),
+            Guard::Capability(cap) => cap.guard_principal(),
+            Guard::User { fun, args } => {
+                let preimage: String = args.iter().map(Value::to_string).collect();
+                format!("u:{}:{}", fun, hash(preimage.as_bytes()))
+            }
+            Guard::Module { module, name } => format!("m:{}:{}", module, name),
+            Guard::Pact { pact_id, name } => format!("p:{}:{}", pact_id, name),
+        }
+    }
+}
+
+/// Checks offline that `account` is the principal account of `guard`
+///
+/// Reproduces Pact's `create-principal`, so a claimed `k:`, `w:`, `c:` or
+/// `u:` account can be verified against the guard it is said to have before
+/// crediting it.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{validate_principal, KeySet};
+///
+/// let keyset = KeySet::keys_all(["abc123"]);
+/// assert!(validate_principal("k:abc123", &keyset.clone().into()).is_ok());
+/// assert!(validate_principal("k:def456", &keyset.into()).is_err());
+///
+/// let multisig = KeySet::keys_2(["abc123", "def456"]);
+/// assert!(validate_principal(&multisig.principal(), &multisig.into()).is_ok());
+/// ```
+pub fn validate_principal(account: &str, guard: &Guard) -> Result<(), CommandError> {
+    let principal = guard.principal();
+    if principal == account {
+        Ok(())
+    } else {
+        Err(CommandError::InvalidPrincipal(format!(
+            "account {} does not match its guard principal {}",
+            account, principal
+        )))
+    }
+}
+
+/// Checks that `account` matches `guard` if it has a reserved name
+///
+/// Coin reserves every name starting with a single character and a colon,
+/// e.g. `k:` or `m:`: such an account is only created if its name is the
+/// principal of its guard, so an unknown prefix like `x:` is always refused.
+/// Other names are accepted with any guard.
+pub fn check_account_principal(account: &str, guard: &Guard) -> Result<(), CommandError> {
+    if account.chars().nth(1) == Some(':') {
+        validate_principal(account, guard)
+    } else {
+        Ok(())
+    }
+}
+
+impl Cap {
+    /// Returns the `c:` principal account of the capability guard of this capability
+    ///
+    /// As `create-principal` does, the principal hashes the capability name
+    /// followed by the JSON encoding of each argument. The arguments must be
+    /// in Pact's encoding for the principal to match the one on chain, e.g.
+    /// integers as `{"int": ..}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{validate_principal, Cap, Guard, Transfer};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let vault = Cap::new("free.vault.VAULT").add_arg("alice");
+    /// let account = vault.guard_principal();
+    /// assert!(account.starts_with("c:"));
+    /// assert!(validate_principal(&account, &Guard::Capability(vault.clone())).is_ok());
+    ///
+    /// let deposit = Transfer::new("k:abc123", &account, Decimal::ONE)
+    ///     .with_receiver_guard(Guard::Capability(vault));
+    /// assert!(deposit.code().contains("(create-capability-guard (free.vault.VAULT \"alice\"))"));
+    /// ```
+    pub fn guard_principal(&self) -> String {
+        let mut preimage = self.name.clone();
+        for arg in &self.args {
+            preimage.push_str(&arg.to_string());
+        }
+        format!("c:{}", hash(preimage.as_bytes()))
+    }
+}
+
+impl From<Guard> for Value {
+    fn from(guard: Guard) -> Self {
+        guard.to_value()
+    }
+}
+
+impl From<&Guard> for Value {
+    fn from(guard: &Guard) -> Self {
+        guard.to_value()
+    }
+}
+
+impl Serialize for Guard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Guard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Self::from_value(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The result of `coin.details`
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::AccountDetails;
+/// use serde_json::json;
+///
+/// let details: AccountDetails = serde_json::from_value(json!({
+///     "account": "k:abc123",
+///     "balance": {"decimal": "12.5"},
+///     "guard": {"keys": ["abc123"], "pred": "keys-all"}
+/// }))
+/// .unwrap();
+///
+/// assert_eq!(details.balance.to_string(), "12.5");
+/// assert!(details.is_principal());
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountDetails {
+    /// Name of the account
+    pub account: String,
+    /// Balance of the account
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub balance: Decimal,
+    /// Guard of the account
+    pub guard: Guard,
+}
+
+impl AccountDetails {
+    /// Returns true if the account name is the principal of its guard
+    pub fn is_principal(&self) -> bool {
+        self.guard.principal() == self.account
+    }
+}
+
+/// Decodes a Pact decimal, encoded as a JSON number, a string or `{"decimal": "..."}`
+fn deserialize_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    let number = match &value {
+        Value::Object(obj) => obj.get("decimal").or_else(|| obj.get("int")),
+        value => Some(value),
+    };
+    let text = match number {
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::String(s)) => s.clone(),
+        _ => String::new(),
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|_| serde::de::Error::custom(format!("invalid decimal {}", value)))
+}
+
+/// Renders a function or capability application, e.g. `(free.vault.VAULT "alice")`
+fn application(name: &str, args: &[Value]) -> String {
+    let mut parts = vec![name.to_string()];
+    parts.extend(args.iter().map(Value::to_string));
+    format!("({})", parts.join(" "))
+}
+
+impl From<KeySet> for Guard {
+    fn from(keyset: KeySet) -> Self {
+        Guard::KeySet(keyset)
+    }
+}
+
+impl KeySet {
+    /// Returns the principal account name guarded by this keyset
+    ///
+    /// A single key under `keys-all` gives `k:<key>`; any other keyset gives
+    /// `w:<hash of the sorted keys>:<predicate>`, as `create-principal` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::KeySet;
+    ///
+    /// assert_eq!(KeySet::keys_all(["abc123"]).principal(), "k:abc123");
+    /// assert!(KeySet::keys_2(["a", "b"]).principal().ends_with(":keys-2"));
+    /// ```
+    pub fn principal(&self) -> String {
+        match self.keys.as_slice() {
+            [key] if self.pred == KEYS_ALL => format!("k:{}", key),
+            keys => {
+                let mut keys = keys.to_vec();
+                keys.sort();
+                keys.dedup();
+                format!("w:{}:{}", hash(keys.concat().as_bytes()), self.pred)
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Structured inspection and comparison of command payloads
+//!
+//! Approval tools show reviewers what a command does and, when a proposed
+//! command is regenerated, exactly what changed. [`CommandPayload::inspect`]
+//! extracts the capabilities granted by each signer, the amounts moved by
+//! transfer capabilities and a hash of the code, and
+//! [`CommandPayload::diff`] lists every field that differs between two
+//! payloads.
+
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+
+use crate::{cap::Cap, hash, CommandError, CommandPayload, PactValue};
+
+/// Capabilities granted by a signer
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignerCaps {
+    /// Public key of the signer
+    pub pub_key: String,
+    /// Capabilities the signature is scoped to; empty for unrestricted signatures
+    pub caps: Vec<Cap>,
+}
+
+/// An amount a transfer capability allows to move
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapAmount {
+    /// Fully qualified name of the capability, e.g. `coin.TRANSFER`
+    pub cap: String,
+    /// Public key of the signer granting the capability
+    pub signer: String,
+    /// Sending account
+    pub from: String,
+    /// Receiving account
+    pub to: String,
+    /// Amount allowed
+    pub amount: Decimal,
+}
+
+/// Structured view of a command payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandInspection {
+    /// The network identifier
+    pub network_id: Option<String>,
+    /// The chain the command targets
+    pub chain_id: String,
+    /// The gas payer
+    pub sender: String,
+    /// Maximum gas fee, the gas limit times the gas price
+    pub max_gas_fee: f64,
+    /// Hash of the Pact code, for execution commands
+    pub code_hash: Option<String>,
+    /// Capabilities granted by each signer
+    pub signers: Vec<SignerCaps>,
+    /// Amounts allowed by `TRANSFER` and `TRANSFER_XCHAIN` capabilities
+    pub amounts: Vec<CapAmount>,
+}
+
+impl CommandInspection {
+    /// Total amount allowed by the transfer capabilities of a module, e.g. `coin`
+    pub fn total_amount(&self, module: &str) -> Decimal {
+        self.amounts
+            .iter()
+            .filter(|a| a.cap.rsplit_once('.').is_some_and(|(m, _)| m == module))
+            .map(|a| a.amount)
+            .sum()
+    }
+}
+
+/// A field that differs between two payloads
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Dotted path of the field, e.g. `meta.gasLimit` or `signers.<key>.clist`
+    pub path: String,
+    /// Value in the original payload, `None` if the field was added
+    pub before: Option<Value>,
+    /// Value in the other payload, `None` if the field was removed
+    pub after: Option<Value>,
+}
+
+/// The differences between two payloads
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PayloadDiff {
+    /// Changed fields, in payload order
+    pub changes: Vec<FieldChange>,
+}
+
+impl PayloadDiff {
+    /// Returns true if the payloads are identical
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the change of a field, if it changed
+    pub fn get(&self, path: &str) -> Option<&FieldChange> {
+        self.changes.iter().find(|change| change.path == path)
+    }
+
+    /// Returns true if anything besides the nonce and creation time changed
+    ///
+    /// Regenerating a command always changes these two fields.
+    pub fn is_material(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| change.path != "nonce" && change.path != "meta.creationTime")
+    }
+}
+
+impl CommandPayload {
+    /// Extract the capabilities, amounts and code hash of the payload
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, CommandPayload, CommandSigner, Meta};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let payload = CommandPayload::new(Meta::new("0", "k:alice"))
+    ///     .with_code("(coin.transfer \"k:alice\" \"k:bob\" 2.5)".to_string())
+    ///     .add_signer(CommandSigner::new_ed25519(
+    ///         "alice-key",
+    ///         vec![Cap::new("coin.GAS"), Cap::transfer("k:alice", "k:bob", 2.5)],
+    ///     ));
+    ///
+    /// let inspection = payload.inspect();
+    /// assert_eq!(inspection.signers[0].caps.len(), 2);
+    /// assert_eq!(inspection.total_amount("coin"), Decimal::new(25, 1));
+    /// assert!(inspection.code_hash.is_some());
+    /// ```
+    pub fn inspect(&self) -> CommandInspection {
+        let amounts = self
+            .signers
+            .iter()
+            .flat_map(|signer| {
+                signer
+                    .clist
+                    .iter()
+                    .filter_map(|cap| cap_amount(&signer.pub_key, cap))
+            })
+            .collect();
+
+        CommandInspection {
+            network_id: self.network_id.clone(),
+            chain_id: self.meta.chain_id.clone(),
+            sender: self.meta.sender.clone(),
+            max_gas_fee: self.meta.gas_limit as f64 * self.meta.gas_price,
+            code_hash: self.payload.code().map(|code| hash(code.as_bytes())),
+            signers: self
+                .signers
+                .iter()
+                .map(|signer| SignerCaps {
+                    pub_key: signer.pub_key.clone(),
+                    caps: signer.clist.clone(),
+                })
+                .collect(),
+            amounts,
+        }
+    }
+
+    /// List the fields that differ from another payload
+    ///
+    /// Signers are matched by public key, so reordering signers only changes
+    /// `signerOrder`, while their capabilities appear under
+    /// `signers.<key>.clist`. Other lists are compared whole.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{CommandPayload, Meta};
+    ///
+    /// let proposed = CommandPayload::new(Meta::new("0", "k:alice")).with_code("(+ 1 2)".to_string());
+    /// let regenerated = CommandPayload {
+    ///     meta: proposed.meta.clone().with_gas_limit(5000),
+    ///     ..proposed.clone()
+    /// };
+    ///
+    /// let diff = proposed.diff(&regenerated).unwrap();
+    /// assert_eq!(diff.changes.len(), 1);
+    /// assert_eq!(diff.changes[0].path, "meta.gasLimit");
+    /// ```
+    pub fn diff(&self, other: &CommandPayload) -> Result<PayloadDiff, CommandError> {
+        let mut diff = PayloadDiff::default();
+        diff_values(
+            "",
+            &comparable(self)?,
+            &comparable(other)?,
+            &mut diff.changes,
+        );
+        Ok(diff)
+    }
+}
+
+/// The amount allowed by a transfer capability
+fn cap_amount(signer: &str, cap: &Cap) -> Option<CapAmount> {
+    let (_, name) = cap.name.rsplit_once('.')?;
+    if name != "TRANSFER" && name != "TRANSFER_XCHAIN" {
+        return None;
+    }
+    let amount = match PactValue::from_value(cap.args.get(2)?).ok()? {
+        PactValue::Decimal(amount) => amount,
+        PactValue::Integer(amount) => Decimal::from(amount),
+        _ => return None,
+    };
+    Some(CapAmount {
+        cap: cap.name.clone(),
+        signer: signer.to_string(),
+        from: cap.args.first()?.as_str()?.to_string(),
+        to: cap.args.get(1)?.as_str()?.to_string(),
+        amount,
+    })
+}
+
+/// The payload as JSON, with signers keyed by public key
+fn comparable(payload: &CommandPayload) -> Result<Value, CommandError> {
+    let mut value = serde_json::to_value(payload)?;
+    let order: Vec<Value> = payload
+        .signers
+        .iter()
+        .map(|s| Value::from(s.pub_key.as_str()))
+        .collect();
+    let signers: Map<String, Value> = payload
+        .signers
+        .iter()
+        .map(|s| Ok((s.pub_key.clone(), serde_json::to_value(s)?)))
+        .collect::<Result<_, CommandError>>()?;
+    value["signers"] = Value::Object(signers);
+    value["signerOrder"] = Value::Array(order);
+    Ok(value)
+}
+
+fn diff_values(path: &str, before: &Value, after: &Value, changes: &mut Vec<FieldChange>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, value) in before {
+                let path = join(path, key);
+                match after.get(key) {
+                    Some(other) => diff_values(&path, value, other, changes),
+                    None => changes.push(FieldChange {
+                        path,
+                        before: Some(value.clone()),
+                        after: None,
+                    }),
+                }
+            }
+            for (key, value) in after {
+                if !before.contains_key(key) {
+                    changes.push(FieldChange {
+                        path: join(path, key),
+                        before: None,
+                        after: Some(value.clone()),
+                    });
+                }
+            }
+        }
+        (before, after) if before != after => changes.push(FieldChange {
+            path: path.to_string(),
+            before: Some(before.clone()),
+            after: Some(after.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
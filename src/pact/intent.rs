@@ -0,0 +1,245 @@
+//! Classification of what a command does
+//!
+//! Wallets render the intent of a command rather than its raw code.
+//! [`CommandPayload::intent`] reads the code of a command, resolving
+//! arguments read from its environment data, and classifies it as a
+//! transfer, a cross-chain transfer, a module deployment or a generic
+//! contract call. Transfers are checked against the transfer capabilities
+//! the signers grant.
+//!
+//! The analysis only recognizes the shapes generated by wallets and SDKs,
+//! such as `(coin.transfer "k:alice" "k:bob" 1.0)`; anything else is reported
+//! as a contract call listing the functions it calls.
+
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::{pretty::Node, CommandPayload, Guard, PactValue, Payload};
+
+/// The kind of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    /// `transfer`, to an existing account
+    Transfer,
+    /// `transfer-create`, creating the receiving account if needed
+    TransferCreate,
+    /// `transfer-crosschain`, the first step of a cross-chain transfer
+    CrossChain,
+}
+
+/// A transfer extracted from a command
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferIntent {
+    /// The kind of transfer
+    pub kind: TransferKind,
+    /// Module of the token, e.g. `coin` or `free.my-token`
+    pub token: String,
+    /// Sending account
+    pub from: String,
+    /// Receiving account
+    pub to: String,
+    /// Amount transferred
+    pub amount: Decimal,
+    /// Guard of the receiving account, for `transfer-create` and cross-chain transfers
+    pub receiver_guard: Option<Guard>,
+    /// Target chain, for cross-chain transfers
+    pub target_chain: Option<String>,
+    /// True if a signer grants a transfer capability covering the amount
+    pub capped: bool,
+}
+
+/// What a command does
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandIntent {
+    /// A transfer of a token
+    Transfer(TransferIntent),
+    /// A deployment of modules or interfaces
+    Deployment {
+        /// Namespace the code enters, if any
+        namespace: Option<String>,
+        /// Qualified names of the modules and interfaces defined
+        modules: Vec<String>,
+    },
+    /// A continuation of a defpact
+    Continuation {
+        /// Id of the defpact
+        pact_id: String,
+        /// Step executed
+        step: u32,
+    },
+    /// Any other code
+    ContractCall {
+        /// Functions called at the top level, in order
+        functions: Vec<String>,
+    },
+}
+
+impl CommandPayload {
+    /// Classify the command
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, CommandIntent, CommandPayload, CommandSigner, Meta, TransferKind};
+    /// use serde_json::json;
+    ///
+    /// let payload = CommandPayload::new(Meta::new("0", "k:alice"))
+    ///     .with_code("(coin.transfer-create \"k:alice\" \"k:bob\" (read-keyset \"ks\") 2.5)".to_string())
+    ///     .with_env_data(json!({"ks": {"keys": ["bob-key"], "pred": "keys-all"}}))
+    ///     .add_signer(CommandSigner::new_ed25519(
+    ///         "alice-key",
+    ///         vec![Cap::new("coin.GAS"), Cap::transfer("k:alice", "k:bob", 2.5)],
+    ///     ));
+    ///
+    /// let CommandIntent::Transfer(transfer) = payload.intent() else { panic!() };
+    /// assert_eq!(transfer.kind, TransferKind::TransferCreate);
+    /// assert_eq!(transfer.to, "k:bob");
+    /// assert!(transfer.capped);
+    /// ```
+    pub fn intent(&self) -> CommandIntent {
+        let exec = match &self.payload {
+            Payload::Cont(cont) => {
+                return CommandIntent::Continuation {
+                    pact_id: cont.pact_id.clone(),
+                    step: cont.step,
+                }
+            }
+            Payload::Exec(exec) => exec,
+        };
+
+        let nodes = crate::pretty::parse(&exec.code);
+        let forms: Vec<(&str, &[Node])> = nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::List {
+                    open: '(', items, ..
+                } => match items.split_first() {
+                    Some((Node::Atom(head), args)) => Some((head.as_str(), args)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        let modules: Vec<&str> = forms
+            .iter()
+            .filter(|(head, _)| *head == "module" || *head == "interface")
+            .filter_map(|(_, args)| match args.first() {
+                Some(Node::Atom(name)) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        if !modules.is_empty() {
+            let namespace = forms
+                .iter()
+                .find(|(head, _)| *head == "namespace")
+                .and_then(|(_, args)| args.first())
+                .and_then(|arg| resolve(arg, &exec.data))
+                .and_then(|ns| ns.as_str().map(ToString::to_string))
+                .filter(|ns| !ns.is_empty());
+            return CommandIntent::Deployment {
+                modules: modules
+                    .iter()
+                    .map(|module| match &namespace {
+                        Some(ns) => format!("{}.{}", ns, module),
+                        None => module.to_string(),
+                    })
+                    .collect(),
+                namespace,
+            };
+        }
+
+        if let [(head, args)] = forms.as_slice() {
+            if let Some(transfer) = self.transfer(head, args, &exec.data) {
+                return CommandIntent::Transfer(transfer);
+            }
+        }
+        CommandIntent::ContractCall {
+            functions: forms.iter().map(|(head, _)| head.to_string()).collect(),
+        }
+    }
+
+    /// Reads a transfer call, e.g. `(coin.transfer "k:alice" "k:bob" 1.0)`
+    fn transfer(&self, head: &str, args: &[Node], data: &Value) -> Option<TransferIntent> {
+        let (token, function) = head.rsplit_once('.')?;
+        let kind = match (function, args.len()) {
+            ("transfer", 3) => TransferKind::Transfer,
+            ("transfer-create", 4) => TransferKind::TransferCreate,
+            ("transfer-crosschain", 5) => TransferKind::CrossChain,
+            _ => return None,
+        };
+        let args: Vec<Value> = args
+            .iter()
+            .map(|arg| resolve(arg, data))
+            .collect::<Option<_>>()?;
+        let string = |value: &Value| value.as_str().map(ToString::to_string);
+
+        let amount = decimal(args.last()?)?;
+        let cap_name = match kind {
+            TransferKind::CrossChain => format!("{}.TRANSFER_XCHAIN", token),
+            _ => format!("{}.TRANSFER", token),
+        };
+        let from = string(&args[0])?;
+        let to = string(&args[1])?;
+        let capped = self.inspect().amounts.iter().any(|cap| {
+            cap.cap == cap_name && cap.from == from && cap.to == to && cap.amount >= amount
+        });
+
+        Some(TransferIntent {
+            kind,
+            token: token.to_string(),
+            from,
+            to,
+            amount,
+            receiver_guard: match kind {
+                TransferKind::Transfer => None,
+                _ => Some(Guard::from_value(&args[2]).ok()?),
+            },
+            target_chain: match kind {
+                TransferKind::CrossChain => Some(string(&args[3])?),
+                _ => None,
+            },
+            capped,
+        })
+    }
+}
+
+/// The value of an argument: a literal, or a read from the environment data
+fn resolve(node: &Node, data: &Value) -> Option<Value> {
+    match node {
+        Node::Atom(atom) if atom.starts_with('"') => serde_json::from_str(atom).ok(),
+        Node::Atom(atom) => Decimal::from_str(atom)
+            .ok()
+            .map(|_| json!({ "decimal": atom })),
+        Node::List {
+            open: '(', items, ..
+        } => match items.as_slice() {
+            [Node::Atom(function), Node::Atom(name)] if function == "keyset-ref-guard" => {
+                let name: String = serde_json::from_str(name).ok()?;
+                Some(json!({ "keysetref": name }))
+            }
+            [Node::Atom(read), Node::Atom(key)]
+                if matches!(
+                    read.as_str(),
+                    "read-msg" | "read-decimal" | "read-string" | "read-keyset" | "read-integer"
+                ) =>
+            {
+                let key: String = serde_json::from_str(key).ok()?;
+                data.get(&key).cloned()
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A decimal given as a literal or as data
+fn decimal(value: &Value) -> Option<Decimal> {
+    match PactValue::from_value(value).ok()? {
+        PactValue::Decimal(amount) => Some(amount),
+        PactValue::Integer(amount) => Some(Decimal::from(amount)),
+        _ => None,
+    }
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Built-in predicate requiring all keys to sign
+pub const KEYS_ALL: &str = "keys-all";
+/// Built-in predicate requiring any key to sign
+pub const KEYS_ANY: &str = "keys-any";
+/// Built-in predicate requiring at least two keys to sign
+pub const KEYS_2: &str = "keys-2";
+
+/// A Pact keyset: a set of public keys and a predicate over the signing keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySet {
+    pub keys: Vec<String>,
+    pub pred: String,
+}
+
+impl KeySet {
+    /// Creates a keyset with an arbitrary predicate
+    pub fn new<I, S>(keys: I, pred: &str) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            keys: keys.into_iter().map(Into::into).collect(),
+            pred: pred.to_string(),
+        }
+    }
+
+    /// Creates a keyset requiring all keys to sign
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::KeySet;
+    /// use serde_json::json;
+    ///
+    /// let keyset = KeySet::keys_all(["abc123"]);
+    /// assert_eq!(
+    ///     serde_json::to_value(&keyset).unwrap(),
+    ///     json!({"keys": ["abc123"], "pred": "keys-all"})
+    /// );
+    /// ```
+    pub fn keys_all<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(keys, KEYS_ALL)
+    }
+
+    /// Creates a keyset requiring any key to sign
+    pub fn keys_any<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(keys, KEYS_ANY)
+    }
+
+    /// Creates a keyset requiring at least two keys to sign
+    pub fn keys_2<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(keys, KEYS_2)
+    }
+
+    /// Checks whether signatures from `signing_keys` satisfy the keyset
+    ///
+    /// Returns `None` for custom predicates, which cannot be evaluated offline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::KeySet;
+    ///
+    /// let keyset = KeySet::keys_2(["a", "b", "c"]);
+    /// assert_eq!(keyset.is_satisfied_by(&["a", "c"]), Some(true));
+    /// assert_eq!(keyset.is_satisfied_by(&["a", "d"]), Some(false));
+    /// ```
+    pub fn is_satisfied_by(&self, signing_keys: &[&str]) -> Option<bool> {
+        let matched = self
+            .keys
+            .iter()
+            .filter(|key| signing_keys.contains(&key.as_str()))
+            .count();
+        match self.pred.as_str() {
+            KEYS_ALL => Some(matched == self.keys.len()),
+            KEYS_ANY => Some(matched >= 1),
+            KEYS_2 => Some(matched >= 2),
+            _ => None,
+        }
+    }
+
+    /// Returns the environment data defining the keyset under `name`
+    ///
+    /// Deployment data files bind each keyset the code reads with
+    /// `read-keyset` under its name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::KeySet;
+    /// use serde_json::json;
+    ///
+    /// assert_eq!(
+    ///     KeySet::keys_all(["abc123"]).to_data("my-keyset"),
+    ///     json!({"my-keyset": {"keys": ["abc123"], "pred": "keys-all"}})
+    /// );
+    /// ```
+    pub fn to_data(&self, name: &str) -> Value {
+        json!({ name: self })
+    }
+
+    /// Registers the keyset under `name` in `data` and returns the expression reading it
+    ///
+    /// Code and environment data use the same name, so they cannot drift apart.
+    /// Environment data that is not an object is replaced by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::KeySet;
+    /// use serde_json::json;
+    ///
+    /// let mut data = json!({"amount": 1.0});
+    /// let keyset = KeySet::keys_all(["abc123"]).read_keyset("admin-ks", &mut data);
+    /// assert_eq!(keyset, "(read-keyset \"admin-ks\")");
+    /// assert_eq!(data["admin-ks"]["keys"], json!(["abc123"]));
+    /// assert_eq!(data["amount"], 1.0);
+    ///
+    /// let code = format!("(free.vault.create \"alice\" {})", keyset);
+    /// ```
+    pub fn read_keyset(&self, name: &str, data: &mut Value) -> String {
+        if !data.is_object() {
+            *data = json!({});
+        }
+        data[name] = json!(self);
+        read_keyset_code(name)
+    }
+
+    /// Renders [`to_data`](Self::to_data) as indented JSON, ready to paste in a data file
+    pub fn to_json_snippet(&self, name: &str) -> String {
+        serde_json::to_string_pretty(&self.to_data(name)).unwrap_or_default()
+    }
+
+    /// Renders [`to_data`](Self::to_data) as YAML, ready to paste in a deployment file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::KeySet;
+    ///
+    /// let yaml = KeySet::keys_any(["abc123", "def456"]).to_yaml_snippet("my-keyset");
+    /// assert_eq!(
+    ///     yaml,
+    ///     "my-keyset:\n  keys:\n    - abc123\n    - def456\n  pred: keys-any\n"
+    /// );
+    /// ```
+    pub fn to_yaml_snippet(&self, name: &str) -> String {
+        let mut yaml = format!("{}:\n", yaml_scalar(name));
+        if self.keys.is_empty() {
+            yaml.push_str("  keys: []\n");
+        } else {
+            yaml.push_str("  keys:\n");
+            for key in &self.keys {
+                yaml.push_str(&format!("    - {}\n", yaml_scalar(key)));
+            }
+        }
+        yaml.push_str(&format!("  pred: {}\n", yaml_scalar(&self.pred)));
+        yaml
+    }
+}
+
+/// The expression reading the keyset named `name` from the environment data
+pub(crate) fn read_keyset_code(name: &str) -> String {
+    format!("(read-keyset {})", Value::from(name))
+}
+
+/// A YAML scalar, quoted unless it is a plain key, name or predicate
+fn yaml_scalar(s: &str) -> String {
+    let plain = s
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        && s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !matches!(
+            s.to_ascii_lowercase().as_str(),
+            "true" | "false" | "yes" | "no" | "on" | "off" | "null"
+        );
+    if plain {
+        s.to_string()
+    } else {
+        Value::from(s).to_string()
+    }
+}
@@ -0,0 +1,233 @@
+//! Offline sanity checks for Pact code
+//!
+//! These checks catch the most common formatting mistakes in hand-written or
+//! generated Pact code before a command is signed, instead of after gas has been
+//! spent on an on-chain parse error. They do not parse Pact itself.
+//!
+//! [`lint_signers`] additionally reviews what a command asks its signers to
+//! grant, returning warnings for wallets to surface before signing.
+
+use std::fmt;
+
+use crate::{pretty::Node, CommandPayload, LintError, Payload};
+
+/// Capabilities used to pay for gas, which the code never references
+const GAS_CAPS: [&str; 2] = ["GAS", "GAS_PAYER"];
+
+/// Check Pact code for emptiness, unbalanced delimiters and unterminated strings
+///
+/// Parentheses, brackets and braces must be balanced and correctly nested.
+/// Delimiters inside string literals and `;` comments are ignored. Positions in
+/// errors are byte offsets into `code`.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{validate_code, LintError};
+///
+/// assert!(validate_code("(coin.details \"k:abc\")").is_ok());
+/// assert_eq!(
+///     validate_code("(+ 1 2"),
+///     Err(LintError::Unclosed { delimiter: '(', position: 0 })
+/// );
+/// ```
+pub fn validate_code(code: &str) -> Result<(), LintError> {
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    let mut chars = code.char_indices();
+    let mut has_content = false;
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            ';' => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                has_content = true;
+                let mut terminated = false;
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            terminated = true;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if !terminated {
+                    return Err(LintError::UnterminatedString { position });
+                }
+            }
+            '(' | '[' | '{' => {
+                has_content = true;
+                stack.push((c, position));
+            }
+            ')' | ']' | '}' => {
+                has_content = true;
+                match stack.pop() {
+                    None => {
+                        return Err(LintError::UnexpectedClosing {
+                            delimiter: c,
+                            position,
+                        })
+                    }
+                    Some((open, _)) if closing_for(open) != c => {
+                        return Err(LintError::MismatchedDelimiter {
+                            expected: closing_for(open),
+                            found: c,
+                            position,
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+            c if !c.is_whitespace() => has_content = true,
+            _ => {}
+        }
+    }
+
+    if let Some((delimiter, position)) = stack.pop() {
+        return Err(LintError::Unclosed {
+            delimiter,
+            position,
+        });
+    }
+    if !has_content {
+        return Err(LintError::EmptyCode);
+    }
+    Ok(())
+}
+
+fn closing_for(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// A risk found in what a command asks its signers to grant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerWarning {
+    /// The signer grants every capability, as its signature is not scoped
+    UnscopedSigner {
+        /// Public key of the signer
+        pub_key: String,
+    },
+    /// The signer grants a capability of a module the code never references
+    UnusedCapability {
+        /// Public key of the signer
+        pub_key: String,
+        /// Fully qualified name of the capability
+        cap: String,
+    },
+}
+
+impl fmt::Display for SignerWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerWarning::UnscopedSigner { pub_key } => write!(
+                f,
+                "Signer {} is unscoped and grants every capability",
+                pub_key
+            ),
+            SignerWarning::UnusedCapability { pub_key, cap } => write!(
+                f,
+                "Signer {} grants {} which the code does not use",
+                pub_key, cap
+            ),
+        }
+    }
+}
+
+/// Review the signers of a command for unscoped signatures and unused capabilities
+///
+/// A signer with an empty capability list signs for every capability the
+/// code may request. A capability is reported as unused when the code never
+/// references its module, neither in a qualified name nor as a module
+/// reference. Gas capabilities are exempt, as are continuations, whose code
+/// is not part of the command.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{lint_signers, Cap, CommandPayload, CommandSigner, Meta, SignerWarning};
+///
+/// let payload = CommandPayload::new(Meta::new("0", "k:alice"))
+///     .with_code("(coin.transfer \"k:alice\" \"k:bob\" 1.0)".to_string())
+///     .add_signer(CommandSigner::new_ed25519(
+///         "alice-key",
+///         vec![Cap::new("coin.GAS"), Cap::new("free.dex.SWAP")],
+///     ))
+///     .add_signer(CommandSigner::new_ed25519("bob-key", Vec::new()));
+///
+/// assert_eq!(
+///     lint_signers(&payload),
+///     [
+///         SignerWarning::UnusedCapability {
+///             pub_key: "alice-key".to_string(),
+///             cap: "free.dex.SWAP".to_string(),
+///         },
+///         SignerWarning::UnscopedSigner { pub_key: "bob-key".to_string() },
+///     ]
+/// );
+/// ```
+pub fn lint_signers(payload: &CommandPayload) -> Vec<SignerWarning> {
+    let references = match &payload.payload {
+        Payload::Exec(exec) => {
+            let mut references = Vec::new();
+            collect_references(&crate::pretty::parse(&exec.code), &mut references);
+            Some(references)
+        }
+        Payload::Cont(_) => None,
+    };
+
+    let mut warnings = Vec::new();
+    for signer in &payload.signers {
+        if signer.clist.is_empty() {
+            warnings.push(SignerWarning::UnscopedSigner {
+                pub_key: signer.pub_key.clone(),
+            });
+            continue;
+        }
+        let Some(references) = &references else {
+            continue;
+        };
+        for cap in &signer.clist {
+            let Some((module, name)) = cap.name.rsplit_once('.') else {
+                continue;
+            };
+            if GAS_CAPS.contains(&name) || references.iter().any(|r| r == module) {
+                continue;
+            }
+            warnings.push(SignerWarning::UnusedCapability {
+                pub_key: signer.pub_key.clone(),
+                cap: cap.name.clone(),
+            });
+        }
+    }
+    warnings
+}
+
+/// Collects the symbols of code and the modules of its qualified names
+fn collect_references(nodes: &[Node], references: &mut Vec<String>) {
+    for node in nodes {
+        match node {
+            Node::Atom(atom) if !atom.starts_with('"') => {
+                if let Some((module, _)) = atom.rsplit_once('.') {
+                    references.push(module.to_string());
+                }
+                // Module references, e.g. `coin` passed as a modref, count as well
+                references.push(atom.clone());
+            }
+            Node::List { items, .. } => collect_references(items, references),
+            _ => {}
+        }
+    }
+}
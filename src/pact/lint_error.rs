@@ -0,0 +1,33 @@
+use thiserror::Error;
+
+/// Errors detected by the offline Pact code lint
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LintError {
+    #[error("Pact code is empty")]
+    EmptyCode,
+    #[error("Unexpected closing '{delimiter}' at position {position}")]
+    UnexpectedClosing { delimiter: char, position: usize },
+    #[error("Expected '{expected}' but found '{found}' at position {position}")]
+    MismatchedDelimiter {
+        expected: char,
+        found: char,
+        position: usize,
+    },
+    #[error("Unclosed '{delimiter}' opened at position {position}")]
+    Unclosed { delimiter: char, position: usize },
+    #[error("Unterminated string starting at position {position}")]
+    UnterminatedString { position: usize },
+}
+
+impl LintError {
+    /// A stable, machine-readable code for the kind of error
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::EmptyCode => "lint.empty_code",
+            Self::UnexpectedClosing { .. } => "lint.unexpected_closing",
+            Self::MismatchedDelimiter { .. } => "lint.mismatched_delimiter",
+            Self::Unclosed { .. } => "lint.unclosed",
+            Self::UnterminatedString { .. } => "lint.unterminated_string",
+        }
+    }
+}
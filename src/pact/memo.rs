@@ -0,0 +1,46 @@
+//! Application memos attached to transactions
+//!
+//! Pact has no dedicated memo field, so memos travel in the command's
+//! environment data under the [`MEMO_KEY`] key. Contracts ignore unknown env
+//! data keys, which makes the memo visible to indexers and exchanges without
+//! affecting execution.
+
+use serde_json::{json, Value};
+
+/// Environment data key under which the memo is stored
+pub const MEMO_KEY: &str = "memo";
+
+/// Adds a memo to environment data
+///
+/// If `data` is not a JSON object it is replaced by an object holding only the memo.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{read_memo, with_memo};
+/// use serde_json::json;
+///
+/// let data = with_memo(json!({"ks": {"keys": [], "pred": "keys-all"}}), "deposit-42");
+/// assert_eq!(read_memo(&data), Some("deposit-42"));
+/// ```
+pub fn with_memo(data: Value, memo: &str) -> Value {
+    match data {
+        Value::Object(mut map) => {
+            map.insert(MEMO_KEY.to_string(), json!(memo));
+            Value::Object(map)
+        }
+        _ => json!({ MEMO_KEY: memo }),
+    }
+}
+
+/// Reads the memo back from environment data or a transaction record
+///
+/// Accepts the environment data object itself, a command payload
+/// (`payload.exec.data`), or a transaction history record embedding the
+/// environment data under `data`.
+pub fn read_memo(value: &Value) -> Option<&str> {
+    value[MEMO_KEY]
+        .as_str()
+        .or_else(|| value["data"][MEMO_KEY].as_str())
+        .or_else(|| value["payload"]["exec"]["data"][MEMO_KEY].as_str())
+}
@@ -1,5 +1,80 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{Amount, CommandError, NetworkId};
+
+/// Default number of seconds the creation time is backdated by
+///
+/// Nodes reject commands created in the future, so a client whose clock runs
+/// ahead of the node's would see its fresh commands rejected.
+pub const DEFAULT_CREATION_TIME_SKEW: u64 = 10;
+
+/// Default gas limit, price and TTL of commands
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::{MetaProfile, NetworkId};
+///
+/// let mainnet = MetaProfile::for_network(&NetworkId::Mainnet);
+/// assert!(mainnet.gas_price > MetaProfile::DEFAULT.gas_price);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetaProfile {
+    /// Maximum gas units the command may consume
+    pub gas_limit: u64,
+    /// Price of a gas unit, in KDA
+    pub gas_price: f64,
+    /// Time to live of the command, in seconds
+    pub ttl: u64,
+    /// Seconds the creation time is backdated by when set from the clock
+    pub creation_time_skew: u64,
+}
+
+impl MetaProfile {
+    /// Values used by [`Meta::new`]
+    pub const DEFAULT: MetaProfile = MetaProfile {
+        gas_limit: 1500,
+        gas_price: 0.00000001,
+        ttl: 3600,
+        creation_time_skew: DEFAULT_CREATION_TIME_SKEW,
+    };
+
+    /// Mainnet, with a higher gas price so commands are mined when blocks are congested
+    pub const MAINNET: MetaProfile = MetaProfile {
+        gas_limit: 2500,
+        gas_price: 0.0000001,
+        ttl: 3600,
+        creation_time_skew: DEFAULT_CREATION_TIME_SKEW,
+    };
+
+    /// Testnet, where gas is free to come by
+    pub const TESTNET: MetaProfile = MetaProfile::DEFAULT;
+
+    /// Local development networks, with room for module deployments
+    pub const DEVELOPMENT: MetaProfile = MetaProfile {
+        gas_limit: 150000,
+        gas_price: 0.00000001,
+        ttl: 28800,
+        creation_time_skew: DEFAULT_CREATION_TIME_SKEW,
+    };
+
+    /// Returns the profile of a network, [`DEFAULT`](Self::DEFAULT) for custom networks
+    pub fn for_network(network: &NetworkId) -> Self {
+        match network {
+            NetworkId::Mainnet => Self::MAINNET,
+            NetworkId::Testnet => Self::TESTNET,
+            NetworkId::Development => Self::DEVELOPMENT,
+            NetworkId::Custom(_) => Self::DEFAULT,
+        }
+    }
+}
+
+impl Default for MetaProfile {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 /// Metadata for a Pact command.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Meta {
@@ -8,7 +83,10 @@ pub struct Meta {
     pub sender: String,
     #[serde(rename = "gasLimit")]
     pub gas_limit: u64,
-    #[serde(rename = "gasPrice")]
+    #[serde(
+        rename = "gasPrice",
+        serialize_with = "crate::pact::compat::serialize_js_number"
+    )]
     pub gas_price: f64,
     pub ttl: u64,
     #[serde(rename = "creationTime")]
@@ -33,16 +111,32 @@ impl Meta {
     /// assert_eq!(meta.gas_limit, 1500);
     /// ```
     pub fn new(chain_id: &str, sender: &str) -> Self {
+        Self::with_profile(MetaProfile::DEFAULT, chain_id, sender)
+    }
+
+    /// Creates a new Meta instance with the defaults of a network
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Meta, NetworkId};
+    ///
+    /// let meta = Meta::for_network(NetworkId::Mainnet, "0", "k:abc123");
+    /// assert_eq!(meta.gas_limit, 2500);
+    /// ```
+    pub fn for_network(network: NetworkId, chain_id: &str, sender: &str) -> Self {
+        Self::with_profile(MetaProfile::for_network(&network), chain_id, sender)
+    }
+
+    /// Creates a new Meta instance with the values of a profile
+    pub fn with_profile(profile: MetaProfile, chain_id: &str, sender: &str) -> Self {
         Self {
             chain_id: chain_id.to_string(),
             sender: sender.to_string(),
-            gas_limit: 1500,       // Common default
-            gas_price: 0.00000001, // Common default
-            ttl: 3600,             // Default 1 hour
-            creation_time: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            gas_limit: profile.gas_limit,
+            gas_price: profile.gas_price,
+            ttl: profile.ttl,
+            creation_time: backdated_now(profile.creation_time_skew),
         }
     }
 
@@ -97,4 +191,55 @@ impl Meta {
         self.ttl = ttl;
         self
     }
+
+    /// Returns the maximum gas fee, the gas limit times the gas price
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Meta;
+    ///
+    /// let meta = Meta::new("0", "k:abc123").with_gas_limit(1000);
+    /// assert_eq!(meta.max_fee().unwrap().to_pact(), "0.00001");
+    /// ```
+    pub fn max_fee(&self) -> Result<Amount, CommandError> {
+        Amount::fee(self.gas_limit, self.gas_price)
+    }
+
+    /// Sets the creation time, in seconds since the Unix epoch
+    ///
+    /// Together with a [`derive_nonce`](crate::pact::derive_nonce) nonce, a
+    /// stored creation time rebuilds a command with the same request key.
+    pub fn with_creation_time(mut self, creation_time: u64) -> Self {
+        self.creation_time = creation_time;
+        self
+    }
+
+    /// Sets the creation time to the current time backdated by `skew` seconds
+    ///
+    /// Use a larger skew than [`DEFAULT_CREATION_TIME_SKEW`] for nodes whose
+    /// clocks lag further behind, or zero to use the current time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Meta;
+    ///
+    /// let meta = Meta::new("0", "k:abc123");
+    /// let backdated = meta.clone().with_creation_time_skew(60);
+    /// assert!(backdated.creation_time + 50 <= meta.creation_time);
+    /// ```
+    pub fn with_creation_time_skew(mut self, skew: u64) -> Self {
+        self.creation_time = backdated_now(skew);
+        self
+    }
+}
+
+/// The current Unix time in seconds, minus `skew`
+fn backdated_now(skew: u64) -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .saturating_sub(skew)
 }
@@ -6,8 +6,40 @@
 //! ## Module Structure
 //!
 //! - [`meta`] - Transaction metadata handling
+//! - [`amount`] - KDA amounts and gas fees
 //! - [`cap`] - Capability creation and management
+//! - [`batch_transfer`] - Many transfers in a single transaction
+//! - `cbor` - Versioned binary encoding of stored transactions (feature `cbor`)
 //! - [`command`] - Command preparation and signing
+//! - [`command_result`] - Typed command results
+//! - [`compat`] - Golden vectors shared with the JavaScript tooling
+//! - [`create_account`] - Account creation with structured guards
+//! - [`defpact`] - Multi-step defpact tracking
+//! - [`event`] - Typed Pact events
+//! - [`exec_request`] - JSON described execution commands for language bindings
+//! - [`fee_policy`] - Gas fee budgets for automated senders
+//! - [`gas_station`] - Transactions paid by gas stations
+//! - [`gas_report`] - Per-operation gas consumption reports
+//! - [`guard`] - Account guards and their principals
+//! - [`inspect`] - Structured inspection and diffing of command payloads
+//! - [`intent`] - Classification of what a command does
+//! - [`keyset`] - Keysets and their predicates
+//! - [`lint`] - Offline sanity checks for Pact code
+//! - [`memo`] - Application memos carried in environment data
+//! - [`module_description`] - Typed `describe-module` and `describe-keyset` results
+//! - [`module_source`] - Loading of module sources and their local dependencies
+//! - [`module_upgrade`] - Hash-pinned module upgrades
+//! - [`network`] - Kadena network identifiers
+//! - [`number_mode`](mod@number_mode) - Handling of imprecise numbers in signed payloads
+//! - [`pact_value`] - Typed Pact values with order-stable objects
+//! - [`pretty`] - Pretty-printing of Pact code
+//! - [`replay`] - Deterministic nonces for idempotent payouts
+//! - [`request_key`] - Request keys in base64url and hex form
+//! - [`rotate`] - Account guard rotation
+//! - [`rotation_ceremony`] - Key rotation of multisig treasury accounts
+//! - [`signing_session`] - Multi-party signature collection
+//! - [`template`] - Pact code templates with named parameters
+//! - [`transfer`] - Coin and `fungible-v2` token transfers
 //!
 //! ## Examples
 //!
@@ -40,12 +72,80 @@
 //! ).unwrap();
 //! ```
 
+pub mod amount;
+pub mod batch_transfer;
 pub mod cap;
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod command;
 pub mod command_error;
+pub mod command_result;
+pub mod compat;
+pub mod create_account;
+pub mod defpact;
+pub mod event;
+pub mod exec_request;
+pub mod fee_policy;
+pub mod gas_report;
+pub mod gas_station;
+pub mod guard;
+pub mod inspect;
+pub mod intent;
+pub mod keyset;
+pub mod lint;
+pub mod lint_error;
+pub mod memo;
 pub mod meta;
+pub mod module_description;
+pub mod module_source;
+pub mod module_upgrade;
+pub mod network;
+pub mod number_mode;
+pub mod pact_value;
+pub mod pretty;
+pub mod replay;
+pub mod request_key;
+pub mod rotate;
+pub mod rotation_ceremony;
+pub mod signing_session;
+pub mod template;
+pub mod transfer;
 
+pub use amount::*;
+pub use batch_transfer::*;
 pub use cap::*;
+#[cfg(feature = "cbor")]
+pub use cbor::*;
 pub use command::*;
 pub use command_error::*;
+pub use command_result::*;
+pub use compat::*;
+pub use create_account::*;
+pub use defpact::*;
+pub use event::*;
+pub use exec_request::*;
+pub use fee_policy::*;
+pub use gas_report::*;
+pub use gas_station::*;
+pub use guard::*;
+pub use inspect::*;
+pub use intent::*;
+pub use keyset::*;
+pub use lint::*;
+pub use lint_error::*;
+pub use memo::*;
 pub use meta::*;
+pub use module_description::*;
+pub use module_source::*;
+pub use module_upgrade::*;
+pub use network::*;
+pub use number_mode::*;
+pub use pact_value::*;
+pub use pretty::*;
+pub use replay::*;
+pub use request_key::*;
+pub use rotate::*;
+pub use rotation_ceremony::*;
+pub use signing_session::*;
+pub use template::*;
+pub use transfer::*;
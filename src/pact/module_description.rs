@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{crypto::hash, CommandError};
+
+/// The interface of fungible tokens, which the token builders call
+pub const FUNGIBLE_V2: &str = "fungible-v2";
+
+/// A deployed module, as reported by `describe-module`
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::ModuleDescription;
+///
+/// let module: ModuleDescription = serde_json::from_value(serde_json::json!({
+///     "name": "coin",
+///     "hash": "rE7DU8jlQL9x_MPYuniZJf5ICBTAEHAIFQCB4blofP4",
+///     "interfaces": ["fungible-v2", "fungible-xchain-v1"],
+///     "code": "(module coin GOVERNANCE ...)",
+///     "blessed": [],
+///     "keyset": "GOVERNANCE"
+/// }))
+/// .unwrap();
+///
+/// assert!(module.implements("fungible-v2"));
+/// assert_eq!(module.governance.as_deref(), Some("GOVERNANCE"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleDescription {
+    /// Name of the module, qualified by its namespace if it has one
+    pub name: String,
+    /// Hash of the deployed module version
+    pub hash: String,
+    /// Interfaces the module implements
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+    /// Source code of the deployed module version
+    pub code: String,
+    /// Hashes of earlier versions still allowed in tables and defpacts
+    #[serde(default)]
+    pub blessed: Vec<String>,
+    /// Governance of the module: a keyset name or a capability
+    #[serde(default, rename = "keyset", alias = "governance")]
+    pub governance: Option<String>,
+}
+
+impl ModuleDescription {
+    /// Returns the code reading the description of `module`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::ModuleDescription;
+    ///
+    /// assert_eq!(
+    ///     ModuleDescription::query_code("free.my-module"),
+    ///     "(describe-module \"free.my-module\")"
+    /// );
+    /// ```
+    pub fn query_code(module: &str) -> String {
+        format!("(describe-module {})", Value::from(module))
+    }
+
+    /// Returns true if the module implements `interface`
+    ///
+    /// The interface matches with or without its namespace.
+    pub fn implements(&self, interface: &str) -> bool {
+        self.interfaces
+            .iter()
+            .any(|i| i == interface || i.rsplit_once('.').map(|(_, name)| name) == Some(interface))
+    }
+
+    /// Check that the module implements `interface`
+    ///
+    /// Fails with [`CommandError::MissingInterface`] otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{ModuleDescription, FUNGIBLE_V2};
+    ///
+    /// let module: ModuleDescription = serde_json::from_value(serde_json::json!({
+    ///     "name": "free.registry",
+    ///     "hash": "registry-hash",
+    ///     "interfaces": [],
+    ///     "code": "(module registry GOV)"
+    /// }))
+    /// .unwrap();
+    ///
+    /// let error = module.require_interface(FUNGIBLE_V2).unwrap_err();
+    /// assert_eq!(error.error_code(), "command.missing_interface");
+    /// ```
+    pub fn require_interface(&self, interface: &str) -> Result<(), CommandError> {
+        if self.implements(interface) {
+            return Ok(());
+        }
+        Err(CommandError::MissingInterface(format!(
+            "{} does not implement {}",
+            self.name, interface
+        )))
+    }
+
+    /// Returns true if `hash` is the deployed or a blessed version of the module
+    pub fn accepts_hash(&self, hash: &str) -> bool {
+        self.hash == hash || self.blessed.iter().any(|b| b == hash)
+    }
+
+    /// Returns true if the deployed version is the `expected` one
+    ///
+    /// Blessed versions do not match: only the deployed version runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{ModuleDescription, ModuleExpectation};
+    ///
+    /// let source = "(module my-module GOV (defcap GOV () true))";
+    /// let deployed: ModuleDescription = serde_json::from_value(serde_json::json!({
+    ///     "name": "free.my-module",
+    ///     "hash": ModuleExpectation::source(source).expected_hash(),
+    ///     "code": source
+    /// }))
+    /// .unwrap();
+    ///
+    /// assert!(deployed.matches(&ModuleExpectation::from(source)));
+    /// assert!(deployed.matches(&deployed.hash.as_str().into()));
+    /// assert!(!deployed.matches(&"(module my-module GOV)".into()));
+    /// ```
+    pub fn matches(&self, expected: &ModuleExpectation) -> bool {
+        match expected {
+            ModuleExpectation::Hash(hash) => self.hash == *hash,
+            ModuleExpectation::Source(source) => {
+                self.code.trim() == source.trim() || self.hash == expected.expected_hash()
+            }
+        }
+    }
+}
+
+/// The version of a module an application was written against
+///
+/// A string converts to a [`Source`](Self::Source) if it holds Pact code, and
+/// to a [`Hash`](Self::Hash) otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleExpectation {
+    /// The module hash, as reported by `describe-module`
+    Hash(String),
+    /// The source of the module form, from `(module` to its closing parenthesis
+    Source(String),
+}
+
+impl ModuleExpectation {
+    /// Expect the module version with hash `hash`
+    pub fn hash(hash: &str) -> Self {
+        Self::Hash(hash.to_string())
+    }
+
+    /// Expect the module version deployed from `source`
+    pub fn source(source: &str) -> Self {
+        Self::Source(source.to_string())
+    }
+
+    /// The expected module hash
+    ///
+    /// Pact hashes the source of a module form into its module hash, so the
+    /// hash of a source is computed locally.
+    pub fn expected_hash(&self) -> String {
+        match self {
+            Self::Hash(hash) => hash.clone(),
+            Self::Source(source) => hash(source.trim().as_bytes()),
+        }
+    }
+}
+
+impl From<&str> for ModuleExpectation {
+    fn from(expected: &str) -> Self {
+        if expected.trim_start().starts_with('(') {
+            Self::source(expected)
+        } else {
+            Self::hash(expected.trim())
+        }
+    }
+}
+
+/// Returns the code reading the keyset registered as `name`, with `describe-keyset`
+///
+/// The result deserializes as a [`KeySet`](crate::KeySet).
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::describe_keyset_code;
+///
+/// assert_eq!(describe_keyset_code("free.admin"), "(describe-keyset \"free.admin\")");
+/// ```
+pub fn describe_keyset_code(name: &str) -> String {
+    format!("(describe-keyset {})", Value::from(name))
+}
@@ -0,0 +1,276 @@
+//! Loading of Pact module sources for deployment
+//!
+//! A [`ModuleLoader`] reads a Pact file together with the files it depends on
+//! and joins them, dependencies first, into a single deployable source. A file
+//! depends on another through an `;; @include <path>` line, or by naming an
+//! interface or module in `implements` or `use` that a local file
+//! `<name>.pact` defines. Names without a local file, such as `fungible-v2`,
+//! are taken to be deployed already.
+//!
+//! Comments make up a good part of large contracts, and a deployment pays gas
+//! and transaction size for every byte of its code. The loader can strip them
+//! and reports the size of the final source.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{pretty::Node, validate_code, CommandError};
+
+/// Directive including another file, relative to the including one
+const INCLUDE_DIRECTIVE: &str = ";; @include ";
+
+/// Reads Pact files and resolves their local dependencies
+///
+/// # Examples
+///
+/// ```no_run
+/// use kadena::pact::{ModuleLoader, ModuleUpgrade};
+///
+/// let loaded = ModuleLoader::new()
+///     .with_search_path("contracts/interfaces")
+///     .with_strip_comments(true)
+///     .load("contracts/my-token.pact")
+///     .unwrap();
+/// loaded.check_size(50_000).unwrap();
+/// println!("deploying {} bytes from {} files", loaded.size(), loaded.files.len());
+///
+/// let upgrade = ModuleUpgrade::new("free.my-token", &loaded.source, "deployed-hash");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ModuleLoader {
+    search_paths: Vec<PathBuf>,
+    strip_comments: bool,
+}
+
+impl ModuleLoader {
+    /// Create a loader resolving dependencies next to the loaded files only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also look for `<name>.pact` files in `dir`
+    pub fn with_search_path(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.search_paths.push(dir.into());
+        self
+    }
+
+    /// Remove comments and blank lines from the loaded source
+    pub fn with_strip_comments(mut self, strip: bool) -> Self {
+        self.strip_comments = strip;
+        self
+    }
+
+    /// Load the file at `path` with its dependencies
+    ///
+    /// Every file is included once, after the files it depends on. Fails with
+    /// [`CommandError::ModuleSource`] if a file cannot be read or files depend
+    /// on each other in a cycle, and with [`CommandError::InvalidCode`] if a
+    /// file does not pass [`validate_code`].
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<LoadedSource, CommandError> {
+        let mut loading = LoadState::default();
+        self.visit(&canonical(path.as_ref())?, &mut loading)?;
+
+        let separator = if self.strip_comments { "\n" } else { "\n\n" };
+        let source = loading
+            .loaded
+            .iter()
+            .map(|(_, code)| code.trim())
+            .collect::<Vec<_>>()
+            .join(separator);
+        Ok(LoadedSource {
+            source,
+            files: loading.loaded.into_iter().map(|(path, _)| path).collect(),
+        })
+    }
+
+    fn visit(&self, path: &Path, state: &mut LoadState) -> Result<(), CommandError> {
+        if state.loaded.iter().any(|(loaded, _)| loaded == path) {
+            return Ok(());
+        }
+        if let Some(start) = state.visiting.iter().position(|p| p == path) {
+            let cycle: Vec<String> = state.visiting[start..]
+                .iter()
+                .chain([&path.to_path_buf()])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(CommandError::ModuleSource(format!(
+                "dependency cycle {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let code = fs::read_to_string(path).map_err(|e| {
+            CommandError::ModuleSource(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        validate_code(&code)?;
+
+        state.visiting.push(path.to_path_buf());
+        for dependency in self.dependencies(path, &code)? {
+            self.visit(&dependency, state)?;
+        }
+        state.visiting.pop();
+
+        let code = if self.strip_comments {
+            strip_comments(&code)
+        } else {
+            code
+        };
+        state.loaded.push((path.to_path_buf(), code));
+        Ok(())
+    }
+
+    /// Files `code` includes or whose interfaces and modules it references
+    fn dependencies(&self, path: &Path, code: &str) -> Result<Vec<PathBuf>, CommandError> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut dependencies = Vec::new();
+        for line in code.lines() {
+            if let Some(include) = line.trim().strip_prefix(INCLUDE_DIRECTIVE) {
+                dependencies.push(canonical(&dir.join(include.trim()))?);
+            }
+        }
+
+        let mut names = Vec::new();
+        collect_references(&crate::pretty::parse(code), &mut names);
+        for name in names {
+            let file = format!("{}.pact", name);
+            let found = [dir]
+                .into_iter()
+                .chain(self.search_paths.iter().map(PathBuf::as_path))
+                .map(|dir| dir.join(&file))
+                .find(|candidate| candidate.is_file());
+            if let Some(found) = found {
+                let found = canonical(&found)?;
+                if found != path && !dependencies.contains(&found) {
+                    dependencies.push(found);
+                }
+            }
+        }
+        Ok(dependencies)
+    }
+}
+
+/// A deployable source joined from one or more files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedSource {
+    /// The code to deploy
+    pub source: String,
+    /// The files the code was read from, in the order they were joined
+    pub files: Vec<PathBuf>,
+}
+
+impl LoadedSource {
+    /// Size of the source in bytes
+    pub fn size(&self) -> usize {
+        self.source.len()
+    }
+
+    /// Check that the source is at most `max_bytes` long
+    pub fn check_size(&self, max_bytes: usize) -> Result<(), CommandError> {
+        if self.size() > max_bytes {
+            return Err(CommandError::ModuleSource(format!(
+                "source of {} bytes exceeds the limit of {} bytes",
+                self.size(),
+                max_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Remove `;` comments and the lines they leave blank from Pact code
+///
+/// Semicolons inside string literals are kept.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::strip_comments;
+///
+/// let code = ";; Greetings\n(defun greet () \"a;b\") ; says a;b\n\n(greet)";
+/// assert_eq!(strip_comments(code), "(defun greet () \"a;b\")\n(greet)");
+/// ```
+pub fn strip_comments(code: &str) -> String {
+    let mut stripped = String::with_capacity(code.len());
+    let mut chars = code.chars();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                stripped.push(c);
+            }
+            '\\' if in_string => {
+                stripped.push(c);
+                stripped.extend(chars.next());
+            }
+            ';' if !in_string => {
+                if chars.by_ref().any(|c| c == '\n') {
+                    stripped.push('\n');
+                }
+            }
+            c => stripped.push(c),
+        }
+    }
+
+    // Lines starting or ending inside a multi-line string are kept as they are
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_string = false;
+    for line in stripped.lines() {
+        let starts_in_string = in_string;
+        let mut escaped = false;
+        for c in line.chars() {
+            if escaped {
+                escaped = false;
+                continue;
+            }
+            match c {
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                _ => {}
+            }
+        }
+        if starts_in_string || in_string {
+            lines.push(line);
+        } else if !line.trim().is_empty() {
+            lines.push(line.trim_end());
+        }
+    }
+    lines.join("\n")
+}
+
+#[derive(Default)]
+struct LoadState {
+    /// Files being loaded, each depending on the next
+    visiting: Vec<PathBuf>,
+    /// Loaded files and their code, dependencies first
+    loaded: Vec<(PathBuf, String)>,
+}
+
+fn canonical(path: &Path) -> Result<PathBuf, CommandError> {
+    path.canonicalize()
+        .map_err(|e| CommandError::ModuleSource(format!("cannot read {}: {}", path.display(), e)))
+}
+
+/// Collect the names in `(implements ..)` and `(use ..)` forms, without namespace
+fn collect_references(nodes: &[Node], names: &mut Vec<String>) {
+    for node in nodes {
+        if let Node::List {
+            open: '(', items, ..
+        } = node
+        {
+            if let [Node::Atom(head), Node::Atom(name), ..] = items.as_slice() {
+                if head == "implements" || head == "use" {
+                    let name = name.trim_matches('"');
+                    let name = name.rsplit_once('.').map_or(name, |(_, name)| name);
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+            collect_references(items, names);
+        }
+    }
+}
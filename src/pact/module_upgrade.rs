@@ -0,0 +1,130 @@
+use serde_json::{json, Value};
+
+use crate::{cap::Cap, meta::Meta, Cmd, CommandError, ModuleDescription, PactKeypair};
+
+/// Builder for module upgrade transactions pinned to the currently deployed module hash
+///
+/// The generated code starts with an `enforce` on the hash reported by
+/// `describe-module`, so the upgrade fails before redefining anything if the
+/// module deployed on chain is not the one the upgrade was prepared against.
+#[derive(Debug, Clone)]
+pub struct ModuleUpgrade {
+    /// Fully qualified module name, e.g. `free.my-module`
+    pub module: String,
+    /// Source code of the new module version
+    pub source: String,
+    /// Hash of the module version expected to be deployed
+    pub expected_hash: String,
+    /// Environment data sent along with the upgrade
+    pub env_data: Value,
+}
+
+impl ModuleUpgrade {
+    /// Creates a new module upgrade
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Fully qualified name of the module being upgraded
+    /// * `source` - Source code of the new module version
+    /// * `expected_hash` - Hash of the module version currently expected on chain
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::ModuleUpgrade;
+    ///
+    /// let upgrade = ModuleUpgrade::new(
+    ///     "free.my-module",
+    ///     "(module my-module GOV (defcap GOV () true))",
+    ///     "rE7DU8jlQL9x_MPYuniZJf5ICBTAEHAIFQCB4blofP4",
+    /// );
+    /// assert!(upgrade.code().starts_with("(enforce (= (at 'hash (describe-module \"free.my-module\"))"));
+    /// ```
+    pub fn new(module: &str, source: &str, expected_hash: &str) -> Self {
+        Self {
+            module: module.to_string(),
+            source: source.to_string(),
+            expected_hash: expected_hash.to_string(),
+            env_data: json!({}),
+        }
+    }
+
+    /// Creates an upgrade pinned to the module version described by `deployed`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{ModuleDescription, ModuleUpgrade};
+    ///
+    /// let deployed: ModuleDescription = serde_json::from_value(serde_json::json!({
+    ///     "name": "free.my-module",
+    ///     "hash": "deployed-hash",
+    ///     "code": "(module my-module GOV (defcap GOV () true))"
+    /// }))
+    /// .unwrap();
+    ///
+    /// let upgrade = ModuleUpgrade::pinned_to(&deployed, "(module my-module GOV)");
+    /// assert_eq!(upgrade.module, "free.my-module");
+    /// assert_eq!(upgrade.expected_hash, "deployed-hash");
+    /// ```
+    pub fn pinned_to(deployed: &ModuleDescription, source: &str) -> Self {
+        Self::new(&deployed.name, source, &deployed.hash)
+    }
+
+    /// Sets the environment data sent along with the upgrade
+    pub fn with_env_data(mut self, data: Value) -> Self {
+        self.env_data = data;
+        self
+    }
+
+    /// Returns the expression enforcing the deployed module hash
+    pub fn guard_code(&self) -> String {
+        format!(
+            "(enforce (= (at 'hash {}) {}) {})",
+            ModuleDescription::query_code(&self.module),
+            Value::from(self.expected_hash.as_str()),
+            Value::from(format!(
+                "Deployed module {} does not match expected hash {}",
+                self.module, self.expected_hash
+            )),
+        )
+    }
+
+    /// Returns the full upgrade code: the hash guard followed by the new module source
+    pub fn code(&self) -> String {
+        format!("{}\n{}", self.guard_code(), self.source)
+    }
+
+    /// Prepares the signed upgrade command
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, Meta, ModuleUpgrade};
+    /// use kadena::crypto::PactKeypair;
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+    ///
+    /// let cmd = ModuleUpgrade::new("free.my-module", "(module my-module GOV)", "hash")
+    ///     .prepare(&[(&keypair, vec![Cap::new("coin.GAS")])], meta, Some("testnet04".to_string()))
+    ///     .unwrap();
+    /// assert_eq!(cmd.sigs.len(), 1);
+    /// ```
+    pub fn prepare(
+        &self,
+        signers: &[(&PactKeypair, Vec<Cap>)],
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        Cmd::prepare_exec(
+            signers,
+            Vec::new(),
+            None,
+            &self.code(),
+            Some(self.env_data.clone()),
+            meta,
+            network_id,
+        )
+    }
+}
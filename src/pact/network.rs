@@ -0,0 +1,82 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A Kadena network
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::NetworkId;
+///
+/// let network: NetworkId = "mainnet01".parse().unwrap();
+/// assert_eq!(network, NetworkId::Mainnet);
+/// assert_eq!(NetworkId::Testnet.as_str(), "testnet04");
+/// assert_eq!(NetworkId::from("my-devnet").as_str(), "my-devnet");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NetworkId {
+    /// The production network, `mainnet01`
+    Mainnet,
+    /// The public test network, `testnet04`
+    Testnet,
+    /// A local development network, `development`
+    Development,
+    /// Any other network
+    Custom(String),
+}
+
+impl NetworkId {
+    /// Get the network identifier used in commands
+    pub fn as_str(&self) -> &str {
+        match self {
+            NetworkId::Mainnet => "mainnet01",
+            NetworkId::Testnet => "testnet04",
+            NetworkId::Development => "development",
+            NetworkId::Custom(id) => id,
+        }
+    }
+}
+
+impl From<&str> for NetworkId {
+    fn from(id: &str) -> Self {
+        match id {
+            "mainnet01" => NetworkId::Mainnet,
+            "testnet04" => NetworkId::Testnet,
+            "development" => NetworkId::Development,
+            _ => NetworkId::Custom(id.to_string()),
+        }
+    }
+}
+
+impl From<NetworkId> for String {
+    fn from(network: NetworkId) -> Self {
+        network.as_str().to_string()
+    }
+}
+
+impl FromStr for NetworkId {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+impl fmt::Display for NetworkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for NetworkId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for NetworkId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from(String::deserialize(deserializer)?.as_str()))
+    }
+}
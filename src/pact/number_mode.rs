@@ -0,0 +1,149 @@
+//! Handling of bare JSON numbers in environment data and capability arguments
+//!
+//! Pact reads a bare JSON number with a fractional part as a decimal, but the
+//! number went through a double on the way: `0.1 + 0.2` ends up as
+//! `0.30000000000000004 KDA`. The precise encodings are `{"decimal": ".."}`
+//! and `{"int": ..}`. The crate-wide [`NumberMode`] decides what happens to
+//! bare floats, and to integers beyond 2^53, when a command is signed.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use log::warn;
+use serde_json::{json, Value};
+
+use crate::{CommandError, CommandPayload};
+
+/// Largest integer a double holds exactly
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+static NUMBER_MODE: AtomicU8 = AtomicU8::new(NumberMode::Permissive as u8);
+
+/// What signing does with imprecise numbers in env data and capability arguments
+///
+/// The transfer builders already use the precise encodings. Decimals of a
+/// [`PactValue`](crate::PactValue) that a double holds exactly are encoded as
+/// bare numbers, and are converted or rejected like any other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberMode {
+    /// Sign numbers as given
+    #[default]
+    Permissive,
+    /// Convert imprecise numbers to their precise encoding, logging a warning
+    Lenient,
+    /// Fail with [`CommandError::ImpreciseNumber`]
+    Strict,
+}
+
+impl NumberMode {
+    /// Apply the mode to a JSON value, `path` naming the value in messages
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::NumberMode;
+    /// use serde_json::json;
+    ///
+    /// let mut data = json!({"amount": 1.5, "count": 3, "fee": {"decimal": "0.1"}});
+    /// NumberMode::Lenient.apply(&mut data, "env data").unwrap();
+    /// assert_eq!(data["amount"], json!({"decimal": "1.5"}));
+    /// assert_eq!(data["count"], 3);
+    ///
+    /// let error = NumberMode::Strict.apply(&mut json!([0.5]), "env data").unwrap_err();
+    /// assert_eq!(error.error_code(), "command.imprecise_number");
+    /// assert!(error.to_string().contains("env data[0]"));
+    /// ```
+    pub fn apply(&self, value: &mut Value, path: &str) -> Result<(), CommandError> {
+        match value {
+            Value::Number(number) if *self != NumberMode::Permissive => {
+                let precise = if let Some(int) = number.as_u64() {
+                    (int > MAX_SAFE_INTEGER).then(|| json!({ "int": int }))
+                } else if let Some(int) = number.as_i64() {
+                    (int.unsigned_abs() > MAX_SAFE_INTEGER).then(|| json!({ "int": int }))
+                } else {
+                    Some(json!({ "decimal": number.to_string() }))
+                };
+                let Some(precise) = precise else {
+                    return Ok(());
+                };
+                if *self == NumberMode::Strict {
+                    return Err(CommandError::ImpreciseNumber(format!(
+                        "bare number {} in {}, use {}",
+                        number, path, precise
+                    )));
+                }
+                warn!(
+                    "Converted bare number {} in {} to {}",
+                    number, path, precise
+                );
+                *value = precise;
+            }
+            Value::Array(items) => {
+                for (n, item) in items.iter_mut().enumerate() {
+                    self.apply(item, &format!("{}[{}]", path, n))?;
+                }
+            }
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    self.apply(field, &format!("{}.{}", path, key))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Apply the mode to the env data and capability arguments of a payload
+    pub fn apply_to_payload(&self, payload: &mut CommandPayload) -> Result<(), CommandError> {
+        if *self == NumberMode::Permissive {
+            return Ok(());
+        }
+        self.apply(payload.payload.data_mut(), "env data")?;
+        for signer in &mut payload.signers {
+            for cap in &mut signer.clist {
+                for (n, arg) in cap.args.iter_mut().enumerate() {
+                    self.apply(arg, &format!("argument {} of {}", n, cap.name))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn from_u8(mode: u8) -> Self {
+        match mode {
+            1 => NumberMode::Lenient,
+            2 => NumberMode::Strict,
+            _ => NumberMode::Permissive,
+        }
+    }
+}
+
+/// Set the number mode applied when commands are signed, for the whole process
+///
+/// # Examples
+///
+/// ```no_run
+/// use kadena::pact::{set_number_mode, NumberMode};
+///
+/// // At startup: refuse to sign amounts that went through a double
+/// set_number_mode(NumberMode::Strict);
+/// ```
+pub fn set_number_mode(mode: NumberMode) {
+    NUMBER_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Get the number mode applied when commands are signed
+pub fn number_mode() -> NumberMode {
+    NumberMode::from_u8(NUMBER_MODE.load(Ordering::Relaxed))
+}
+
+/// Serialize a payload for signing, under the current [`number_mode`]
+pub(crate) fn serialize_payload(payload: &CommandPayload) -> Result<String, CommandError> {
+    match number_mode() {
+        NumberMode::Permissive => Ok(serde_json::to_string(payload)?),
+        mode => {
+            let mut payload = payload.clone();
+            mode.apply_to_payload(&mut payload)?;
+            Ok(serde_json::to_string(&payload)?)
+        }
+    }
+}
@@ -0,0 +1,365 @@
+//! Typed Pact values
+//!
+//! [`PactValue`] models the values Pact code reads from environment data and
+//! receives as capability arguments, with their canonical JSON encodings.
+//!
+//! ## Object ordering
+//!
+//! Pact compares objects as maps: two objects with the same fields are equal
+//! whatever their order. A command is however hashed over its exact bytes, so
+//! reordering the fields of an object in its payload changes its hash. To keep
+//! round trips through this crate stable:
+//!
+//! - [`PactValue::Object`] keeps fields in insertion order, and decoding keeps
+//!   them in the order they appear in the JSON
+//! - encoding writes fields in that same order, never sorting them
+//! - equality ignores field order, as in Pact
+//!
+//! Numbers are written in Pact's canonical encoding, so decoding and encoding
+//! a canonical payload gives back the same bytes.
+
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+use crate::{CommandError, Guard};
+
+/// Largest integer written as a plain JSON number, as in Pact
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// A Pact value
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::PactValue;
+/// use serde_json::json;
+///
+/// let value = PactValue::object([
+///     ("receiver", PactValue::from("k:bob")),
+///     ("amount", PactValue::from(rust_decimal::Decimal::new(15, 1))),
+///     ("chain", PactValue::from(1)),
+/// ]);
+/// assert_eq!(
+///     serde_json::to_string(&value).unwrap(),
+///     r#"{"receiver":"k:bob","amount":1.5,"chain":1}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum PactValue {
+    /// A string
+    String(String),
+    /// An integer
+    Integer(i64),
+    /// A decimal
+    Decimal(Decimal),
+    /// A boolean
+    Bool(bool),
+    /// A time, in ISO 8601 format, e.g. `2024-01-01T00:00:00Z`
+    Time(String),
+    /// A list
+    List(Vec<PactValue>),
+    /// An object, keeping its fields in insertion order
+    Object(IndexMap<String, PactValue>),
+    /// A guard
+    Guard(Guard),
+    /// A reference to a module, as passed to functions taking a module
+    /// implementing an interface, e.g. `coin` as a `fungible-v2`
+    ModRef {
+        /// Fully qualified name of the module, e.g. `free.my-token`
+        name: String,
+        /// Fully qualified names of the interfaces the module implements
+        interfaces: Vec<String>,
+    },
+}
+
+impl PactValue {
+    /// Create an object from its fields, keeping their order
+    pub fn object<I, K>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, PactValue)>,
+        K: Into<String>,
+    {
+        PactValue::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    /// Create a reference to a module implementing `interfaces`
+    pub fn modref<I, S>(name: &str, interfaces: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        PactValue::ModRef {
+            name: name.to_string(),
+            interfaces: interfaces.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns the canonical JSON encoding of the value
+    ///
+    /// - integers are plain numbers, or `{"int": n}` beyond 2^53
+    /// - decimals are plain numbers, or `{"decimal": ".."}` when a double
+    ///   cannot represent them exactly
+    /// - times are `{"time": ".."}`
+    /// - guards use the encodings of [`Guard::to_value`]
+    /// - module references are `{"refName": "..", "refSpec": [..]}`
+    pub fn to_value(&self) -> Value {
+        match self {
+            PactValue::String(s) => Value::from(s.as_str()),
+            PactValue::Integer(i) if i.abs() <= MAX_SAFE_INTEGER => Value::from(*i),
+            PactValue::Integer(i) => json!({ "int": i }),
+            PactValue::Decimal(d) => {
+                decimal_number(d).unwrap_or_else(|| json!({ "decimal": d.to_string() }))
+            }
+            PactValue::Bool(b) => Value::from(*b),
+            PactValue::Time(t) => json!({ "time": t }),
+            PactValue::List(items) => Value::Array(items.iter().map(Self::to_value).collect()),
+            PactValue::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_value()))
+                    .collect::<Map<_, _>>(),
+            ),
+            PactValue::Guard(guard) => guard.to_value(),
+            PactValue::ModRef { name, interfaces } => {
+                json!({"refName": name, "refSpec": interfaces})
+            }
+        }
+    }
+
+    /// Returns the Pact code literal of the value
+    ///
+    /// Strings are quoted and escaped, decimals always carry a decimal point
+    /// and times are built with `time`. Keysets have no literal form and must
+    /// be passed in environment data instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::PactValue;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let value = PactValue::List(vec![
+    ///     PactValue::from("say \"hi\""),
+    ///     PactValue::from(Decimal::new(2, 0)),
+    /// ]);
+    /// assert_eq!(value.to_code().unwrap(), r#"["say \"hi\"" 2.0]"#);
+    /// ```
+    pub fn to_code(&self) -> Result<String, CommandError> {
+        Ok(match self {
+            PactValue::String(s) => Value::from(s.as_str()).to_string(),
+            PactValue::Integer(i) => i.to_string(),
+            PactValue::Decimal(d) => {
+                let text = d.to_string();
+                if text.contains('.') {
+                    text
+                } else {
+                    format!("{}.0", text)
+                }
+            }
+            PactValue::Bool(b) => b.to_string(),
+            PactValue::Time(t) => format!("(time {})", Value::from(t.as_str())),
+            PactValue::List(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(Self::to_code)
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(" ")
+            ),
+            PactValue::Object(fields) => format!(
+                "{{{}}}",
+                fields
+                    .iter()
+                    .map(|(k, v)| Ok(format!("{}: {}", Value::from(k.as_str()), v.to_code()?)))
+                    .collect::<Result<Vec<_>, CommandError>>()?
+                    .join(", ")
+            ),
+            PactValue::Guard(Guard::KeySet(_)) => {
+                return Err(CommandError::InvalidPactValue(
+                    "keysets have no code literal, pass them in env data".to_string(),
+                ))
+            }
+            PactValue::Guard(guard) => guard.code(""),
+            PactValue::ModRef { name, .. } => name.clone(),
+        })
+    }
+
+    /// Decode a value from its JSON encoding
+    ///
+    /// Plain integers decode as integers and other numbers as decimals.
+    /// Objects holding a single `int`, `decimal` or `time` field decode as
+    /// the corresponding literal, and objects holding exactly `refName` and
+    /// `refSpec` as a module reference. Every other object, including the
+    /// encodings of guards, decodes as an object so that its fields keep
+    /// their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::PactValue;
+    /// use serde_json::json;
+    ///
+    /// let modref = PactValue::from_value(&json!({
+    ///     "refName": {"namespace": null, "name": "coin"},
+    ///     "refSpec": [{"namespace": null, "name": "fungible-v2"}]
+    /// }))
+    /// .unwrap();
+    /// assert_eq!(modref, PactValue::modref("coin", ["fungible-v2"]));
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Self, CommandError> {
+        let invalid = || CommandError::InvalidPactValue(value.to_string());
+        match value {
+            Value::Null => Err(invalid()),
+            Value::Bool(b) => Ok(PactValue::Bool(*b)),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Ok(PactValue::Integer(i)),
+                None => parse_decimal(&n.to_string())
+                    .map(PactValue::Decimal)
+                    .ok_or_else(invalid),
+            },
+            Value::String(s) => Ok(PactValue::String(s.clone())),
+            Value::Array(items) => Ok(PactValue::List(
+                items
+                    .iter()
+                    .map(Self::from_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            Value::Object(fields) => match fields.iter().next() {
+                Some((key, literal)) if fields.len() == 1 && key == "int" => literal
+                    .as_i64()
+                    .or_else(|| literal.as_str().and_then(|s| s.parse().ok()))
+                    .map(PactValue::Integer)
+                    .ok_or_else(invalid),
+                Some((key, literal)) if fields.len() == 1 && key == "decimal" => {
+                    let text = match literal {
+                        Value::String(s) => s.clone(),
+                        literal => literal.to_string(),
+                    };
+                    parse_decimal(&text)
+                        .map(PactValue::Decimal)
+                        .ok_or_else(invalid)
+                }
+                Some((key, Value::String(time)))
+                    if fields.len() == 1 && (key == "time" || key == "timep") =>
+                {
+                    Ok(PactValue::Time(time.clone()))
+                }
+                _ if fields.len() == 2
+                    && fields.contains_key("refName")
+                    && fields.contains_key("refSpec") =>
+                {
+                    let interfaces = match &fields["refSpec"] {
+                        Value::Null => Vec::new(),
+                        Value::Array(specs) => specs
+                            .iter()
+                            .map(|spec| module_name(spec).ok_or_else(invalid))
+                            .collect::<Result<_, _>>()?,
+                        _ => return Err(invalid()),
+                    };
+                    Ok(PactValue::ModRef {
+                        name: module_name(&fields["refName"]).ok_or_else(invalid)?,
+                        interfaces,
+                    })
+                }
+                _ => Ok(PactValue::Object(
+                    fields
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), Self::from_value(v)?)))
+                        .collect::<Result<_, CommandError>>()?,
+                )),
+            },
+        }
+    }
+}
+
+/// The decimal as a plain JSON number, if a double holds it exactly
+fn decimal_number(decimal: &Decimal) -> Option<Value> {
+    let double: f64 = decimal.to_string().parse().ok()?;
+    let number = serde_json::Number::from_f64(double)?;
+    (parse_decimal(&number.to_string())? == *decimal).then_some(Value::Number(number))
+}
+
+/// A qualified module name, given as a string or `{"namespace": .., "name": ..}`
+fn module_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name.clone()),
+        value => {
+            let name = value["name"].as_str()?;
+            Some(match value["namespace"].as_str() {
+                Some(namespace) => format!("{}.{}", namespace, name),
+                None => name.to_string(),
+            })
+        }
+    }
+}
+
+fn parse_decimal(text: &str) -> Option<Decimal> {
+    Decimal::from_str(text)
+        .or_else(|_| Decimal::from_scientific(text))
+        .ok()
+}
+
+impl Serialize for PactValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PactValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Self::from_value(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<PactValue> for Value {
+    fn from(value: PactValue) -> Self {
+        value.to_value()
+    }
+}
+
+impl From<&str> for PactValue {
+    fn from(s: &str) -> Self {
+        PactValue::String(s.to_string())
+    }
+}
+
+impl From<String> for PactValue {
+    fn from(s: String) -> Self {
+        PactValue::String(s)
+    }
+}
+
+impl From<i64> for PactValue {
+    fn from(i: i64) -> Self {
+        PactValue::Integer(i)
+    }
+}
+
+impl From<Decimal> for PactValue {
+    fn from(d: Decimal) -> Self {
+        PactValue::Decimal(d)
+    }
+}
+
+impl From<bool> for PactValue {
+    fn from(b: bool) -> Self {
+        PactValue::Bool(b)
+    }
+}
+
+impl From<Guard> for PactValue {
+    fn from(guard: Guard) -> Self {
+        PactValue::Guard(guard)
+    }
+}
+
+impl From<Vec<PactValue>> for PactValue {
+    fn from(items: Vec<PactValue>) -> Self {
+        PactValue::List(items)
+    }
+}
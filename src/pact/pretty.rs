@@ -0,0 +1,219 @@
+//! Pretty-printing of Pact code
+//!
+//! [`CodeFormatter`] lays out Pact code with consistent indentation: a form
+//! that fits on the remaining width of its line stays on one line, any other
+//! form keeps its head and first argument on its first line and puts every
+//! further element on its own line. Runs of whitespace are normalized, so
+//! formatting is idempotent and two pieces of code differing only in layout
+//! format identically.
+
+use crate::{validate_code, LintError};
+
+/// Default maximum line width
+pub const DEFAULT_MAX_WIDTH: usize = 80;
+
+/// Default indentation of the arguments of a broken form
+pub const DEFAULT_INDENT: usize = 2;
+
+/// A node of the code, as read by the formatter
+#[derive(Debug)]
+pub(crate) enum Node {
+    /// A symbol, literal or string, with any attached punctuation such as `:` or `,`
+    Atom(String),
+    /// A `;` comment, without the line break ending it
+    Comment(String),
+    /// A parenthesized, bracketed or braced list
+    List {
+        open: char,
+        close: char,
+        items: Vec<Node>,
+    },
+}
+
+/// Formats Pact code
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::CodeFormatter;
+///
+/// let formatter = CodeFormatter::new().with_max_width(40);
+/// let code = formatter
+///     .format("(coin.transfer-create \"k:alice\" \"k:bob\" (read-keyset \"ks\") 1.0)")
+///     .unwrap();
+/// assert_eq!(
+///     code,
+///     "(coin.transfer-create \"k:alice\"\n  \"k:bob\"\n  (read-keyset \"ks\")\n  1.0)"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeFormatter {
+    max_width: usize,
+    indent: usize,
+}
+
+impl Default for CodeFormatter {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_MAX_WIDTH,
+            indent: DEFAULT_INDENT,
+        }
+    }
+}
+
+impl CodeFormatter {
+    /// Create a formatter with the default width and indentation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the width beyond which forms are broken over several lines
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Set the indentation of the arguments of a broken form
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Format code, checking it with [`validate_code`] first
+    ///
+    /// Top-level forms and comments are put on separate lines.
+    pub fn format(&self, code: &str) -> Result<String, LintError> {
+        validate_code(code)?;
+        let nodes = parse(code);
+        let lines: Vec<String> = nodes.iter().map(|node| self.render(node, 0, 0)).collect();
+        Ok(lines.join("\n"))
+    }
+
+    /// Renders a node starting at `column` and followed by `trailing` closing delimiters
+    fn render(&self, node: &Node, column: usize, trailing: usize) -> String {
+        match node {
+            Node::Atom(atom) => atom.clone(),
+            Node::Comment(comment) => comment.clone(),
+            Node::List { open, close, items } => {
+                if let Some(flat) = flat(node) {
+                    if column + flat.chars().count() + trailing <= self.max_width {
+                        return flat;
+                    }
+                }
+                if items.is_empty() {
+                    return format!("{}{}", open, close);
+                }
+
+                // Forms keep their first argument next to their head; the
+                // elements of lists and objects line up after the bracket
+                let (inline, indent) = match (open, items.as_slice()) {
+                    ('(', [Node::Atom(_), Node::Comment(_), ..]) => (1, column + self.indent),
+                    ('(', [Node::Atom(_), ..]) => (2, column + self.indent),
+                    _ => (1, column + 1),
+                };
+
+                let mut out = open.to_string();
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 && (i >= inline || matches!(items[i - 1], Node::Comment(_))) {
+                        out.push('\n');
+                        out.push_str(&" ".repeat(indent));
+                    } else if i > 0 {
+                        out.push(' ');
+                    }
+                    let start = match out.rfind('\n') {
+                        Some(n) => out[n + 1..].chars().count(),
+                        None => column + out.chars().count(),
+                    };
+                    let closing = if i + 1 == items.len() {
+                        trailing + 1
+                    } else {
+                        0
+                    };
+                    out.push_str(&self.render(item, start, closing));
+                }
+                if matches!(items.last(), Some(Node::Comment(_))) {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(indent));
+                }
+                out.push(*close);
+                out
+            }
+        }
+    }
+}
+
+/// Format code with the default width and indentation
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::format_code;
+///
+/// assert_eq!(format_code("(+   1\n  2)").unwrap(), "(+ 1 2)");
+/// ```
+pub fn format_code(code: &str) -> Result<String, LintError> {
+    CodeFormatter::new().format(code)
+}
+
+/// Renders a node on a single line, unless it holds a comment
+fn flat(node: &Node) -> Option<String> {
+    match node {
+        Node::Atom(atom) => Some(atom.clone()),
+        Node::Comment(_) => None,
+        Node::List { open, close, items } => {
+            let items = items.iter().map(flat).collect::<Option<Vec<_>>>()?;
+            Some(format!("{}{}{}", open, items.join(" "), close))
+        }
+    }
+}
+
+/// Reads validated code into nodes
+pub(crate) fn parse(code: &str) -> Vec<Node> {
+    let mut stack: Vec<(char, Vec<Node>)> = vec![(' ', Vec::new())];
+    let mut chars = code.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            ';' => {
+                let mut comment = String::from(';');
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    comment.push(c);
+                }
+                let comment = comment.trim_end().to_string();
+                stack.last_mut().unwrap().1.push(Node::Comment(comment));
+            }
+            '(' | '[' | '{' => stack.push((c, Vec::new())),
+            ')' | ']' | '}' => {
+                let (open, items) = stack.pop().unwrap();
+                stack.last_mut().unwrap().1.push(Node::List {
+                    open,
+                    close: c,
+                    items,
+                });
+            }
+            c => {
+                let mut atom = String::new();
+                let mut in_string = false;
+                let mut c = Some(c);
+                while let Some(current) = c {
+                    atom.push(current);
+                    if in_string {
+                        match current {
+                            '\\' => atom.extend(chars.next()),
+                            '"' => in_string = false,
+                            _ => {}
+                        }
+                    } else if current == '"' {
+                        in_string = true;
+                    }
+                    c = chars.next_if(|next| {
+                        in_string || !(next.is_whitespace() || "()[]{};".contains(*next))
+                    });
+                }
+                stack.last_mut().unwrap().1.push(Node::Atom(atom));
+            }
+        }
+    }
+    stack.pop().map(|(_, nodes)| nodes).unwrap_or_default()
+}
@@ -0,0 +1,106 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{base64url_decode, base64url_encode, bin_to_hex, hex_to_bin, CommandError};
+
+/// Length of a request key, the Blake2b-256 hash of a command
+const REQUEST_KEY_LEN: usize = 32;
+
+/// The request key of a command, identifying it on chain
+///
+/// Request keys are rendered base64url-encoded by the Pact API, but some tools
+/// show them hex-encoded. Both forms are accepted, and the key is stored
+/// normalized to base64url.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestKey(String);
+
+impl RequestKey {
+    /// Parses a request key encoded in base64url or hex
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::RequestKey;
+    ///
+    /// let key = RequestKey::parse("TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c").unwrap();
+    /// let from_hex = RequestKey::parse(&key.to_hex()).unwrap();
+    /// assert_eq!(key, from_hex);
+    /// assert_eq!(from_hex.as_str(), "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c");
+    /// ```
+    pub fn parse(key: &str) -> Result<Self, CommandError> {
+        let key = key.trim();
+        let key = key
+            .strip_prefix("0x")
+            .filter(|hex| hex.len() == REQUEST_KEY_LEN * 2)
+            .unwrap_or(key);
+
+        let bytes = if key.len() == REQUEST_KEY_LEN * 2 {
+            hex_to_bin(key)
+        } else {
+            base64url_decode(key)
+        }
+        .map_err(|e| CommandError::InvalidRequestKey(format!("{}: {}", key, e)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Creates a request key from the raw hash bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommandError> {
+        if bytes.len() != REQUEST_KEY_LEN {
+            return Err(CommandError::InvalidRequestKey(format!(
+                "expected {} bytes, got {}",
+                REQUEST_KEY_LEN,
+                bytes.len()
+            )));
+        }
+        Ok(Self(base64url_encode(bytes)))
+    }
+
+    /// Get the base64url form used by the Pact API
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Get the hex form
+    pub fn to_hex(&self) -> String {
+        bin_to_hex(&self.to_bytes())
+    }
+
+    /// Get the raw hash bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        base64url_decode(&self.0).expect("request keys are stored as valid base64url")
+    }
+}
+
+impl fmt::Display for RequestKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for RequestKey {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl AsRef<str> for RequestKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Serialize for RequestKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RequestKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Self::parse(&key).map_err(serde::de::Error::custom)
+    }
+}
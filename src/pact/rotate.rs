@@ -0,0 +1,125 @@
+use serde_json::{json, Value};
+
+use crate::{cap::Cap, meta::Meta, Cmd, CommandError, KeySet, PactKeypair};
+
+/// Builder for `coin.rotate` transactions replacing an account's guard
+///
+/// The new keyset travels in the environment data and is read back with
+/// `read-keyset`. By default, preparing the command fails unless the signing
+/// keys satisfy the new keyset, so an account cannot be rotated to a guard its
+/// owner is unable to sign for.
+#[derive(Debug, Clone)]
+pub struct GuardRotation {
+    /// The account whose guard is rotated
+    pub account: String,
+    /// The new guard of the account
+    pub keyset: KeySet,
+    /// Environment data key holding the new keyset
+    pub keyset_name: String,
+    /// Whether the signing keys must satisfy the new keyset
+    pub lockout_check: bool,
+}
+
+impl GuardRotation {
+    /// Creates a rotation of `account` to `keyset`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{GuardRotation, KeySet};
+    ///
+    /// let rotation = GuardRotation::new("alice", KeySet::keys_all(["abc123"]));
+    /// assert_eq!(rotation.code(), "(coin.rotate \"alice\" (read-keyset \"ks\"))");
+    /// ```
+    pub fn new(account: &str, keyset: KeySet) -> Self {
+        Self {
+            account: account.to_string(),
+            keyset,
+            keyset_name: "ks".to_string(),
+            lockout_check: true,
+        }
+    }
+
+    /// Sets the environment data key holding the new keyset
+    pub fn with_keyset_name(mut self, keyset_name: &str) -> Self {
+        self.keyset_name = keyset_name.to_string();
+        self
+    }
+
+    /// Enables or disables the check that the signing keys satisfy the new keyset
+    ///
+    /// Disable it only when handing the account over to keys held by someone else.
+    pub fn with_lockout_check(mut self, lockout_check: bool) -> Self {
+        self.lockout_check = lockout_check;
+        self
+    }
+
+    /// Returns the rotation code
+    pub fn code(&self) -> String {
+        format!(
+            "(coin.rotate {} (read-keyset {}))",
+            Value::from(self.account.as_str()),
+            Value::from(self.keyset_name.as_str())
+        )
+    }
+
+    /// Returns the environment data carrying the new keyset
+    pub fn env_data(&self) -> Value {
+        json!({ &self.keyset_name: self.keyset })
+    }
+
+    /// Returns the `coin.ROTATE` capability for the account
+    pub fn cap(&self) -> Cap {
+        Cap::new("coin.ROTATE").add_arg(self.account.as_str())
+    }
+
+    /// Prepares the signed rotation command
+    ///
+    /// Each signer is scoped to `coin.GAS` and `coin.ROTATE`. The signers must be
+    /// able to satisfy the current guard of the account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{GuardRotation, KeySet, Meta};
+    /// use kadena::crypto::PactKeypair;
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let account = format!("k:{}", keypair.public_key());
+    ///
+    /// // Rotating to a keyset the signer is not part of is refused
+    /// let rotation = GuardRotation::new(&account, KeySet::keys_all(["other-key"]));
+    /// assert!(rotation.prepare(&[&keypair], Meta::new("0", &account), None).is_err());
+    /// ```
+    pub fn prepare(
+        &self,
+        signers: &[&PactKeypair],
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        if self.lockout_check {
+            let signing_keys: Vec<&str> = signers.iter().map(|kp| kp.public_key()).collect();
+            if self.keyset.is_satisfied_by(&signing_keys) != Some(true) {
+                return Err(CommandError::UnsatisfiedKeyset(format!(
+                    "signing keys do not satisfy the new guard of {}",
+                    self.account
+                )));
+            }
+        }
+
+        let signers: Vec<(&PactKeypair, Vec<Cap>)> = signers
+            .iter()
+            .map(|kp| (*kp, vec![Cap::new("coin.GAS"), self.cap()]))
+            .collect();
+
+        Cmd::prepare_exec(
+            &signers,
+            Vec::new(),
+            None,
+            &self.code(),
+            Some(self.env_data()),
+            meta,
+            network_id,
+        )
+    }
+}
@@ -0,0 +1,231 @@
+//! Key rotation of multisig treasury accounts
+//!
+//! The guard of a `w:` account is fixed by its name, and `coin.rotate`
+//! refuses to rotate principal accounts. Rotating the keys of a `w:` treasury
+//! therefore moves its balance to the `w:` account of the new keyset with
+//! `transfer-create`. A [`KeyRotation`] plans that move and checks that the
+//! chosen approvers reach the quorum of both keysets, so funds never land in
+//! an account its new key holders cannot sign for. The resulting
+//! [`RotationCeremony`] collects their approvals through a
+//! [`SigningSession`].
+
+use rust_decimal::Decimal;
+
+use crate::{
+    cap::Cap, meta::Meta, Cmd, CommandError, CommandPayload, CommandSigner, KeySet, Signer,
+    SigningSession, TokenTransfer, Transfer,
+};
+
+/// Plan of a treasury key rotation
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::PactKeypair;
+/// use kadena::pact::{KeyRotation, KeySet, Meta};
+/// use rust_decimal::Decimal;
+///
+/// let old: Vec<_> = (0..3).map(|_| PactKeypair::generate()).collect();
+/// let new: Vec<_> = (0..3).map(|_| PactKeypair::generate()).collect();
+/// let old_keyset = KeySet::keys_2(old.iter().map(|k| k.public_key()));
+/// let new_keyset = KeySet::keys_2(new.iter().map(|k| k.public_key()));
+///
+/// let rotation = KeyRotation::new(old_keyset, new_keyset, Decimal::new(1_000, 0))
+///     .with_old_approvers([old[0].public_key(), old[1].public_key()])
+///     .with_new_approvers([new[0].public_key(), new[2].public_key()]);
+/// let meta = Meta::new("0", &rotation.old_account());
+/// let mut ceremony = rotation.prepare(meta, "testnet04").unwrap();
+///
+/// for keypair in [&old[0], &old[1], &new[0], &new[2]] {
+///     ceremony.sign(keypair).unwrap();
+/// }
+/// assert!(ceremony.is_approved());
+/// let cmd = ceremony.into_cmd().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyRotation {
+    /// Keyset of the current treasury account
+    pub old_keyset: KeySet,
+    /// Keyset of the new treasury account
+    pub new_keyset: KeySet,
+    /// Balance moved to the new account
+    pub amount: Decimal,
+    /// The `fungible-v2` module of the token, e.g. `coin`
+    pub token: String,
+    /// Keys of the old keyset approving the rotation
+    pub old_approvers: Vec<String>,
+    /// Keys of the new keyset approving the rotation
+    pub new_approvers: Vec<String>,
+}
+
+impl KeyRotation {
+    /// Plan moving `amount` from the account of `old_keyset` to that of `new_keyset`
+    ///
+    /// Every key of each keyset approves unless approvers are chosen.
+    pub fn new(old_keyset: KeySet, new_keyset: KeySet, amount: Decimal) -> Self {
+        Self {
+            old_approvers: old_keyset.keys.clone(),
+            new_approvers: new_keyset.keys.clone(),
+            old_keyset,
+            new_keyset,
+            amount,
+            token: "coin".to_string(),
+        }
+    }
+
+    /// Rotate the account of a `fungible-v2` token other than `coin`
+    pub fn with_token(mut self, module: &str) -> Self {
+        self.token = module.to_string();
+        self
+    }
+
+    /// Choose the keys of the old keyset approving the rotation
+    pub fn with_old_approvers<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.old_approvers = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Choose the keys of the new keyset approving the rotation
+    pub fn with_new_approvers<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.new_approvers = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The account funds are moved from
+    pub fn old_account(&self) -> String {
+        self.old_keyset.principal()
+    }
+
+    /// The account funds are moved to
+    pub fn new_account(&self) -> String {
+        self.new_keyset.principal()
+    }
+
+    /// The transfer moving the balance to the new account
+    pub fn transfer(&self) -> Transfer {
+        TokenTransfer::new_unchecked(&self.token)
+            .transfer(&self.old_account(), &self.new_account(), self.amount)
+            .with_receiver_guard(self.new_keyset.clone())
+    }
+
+    /// Check the plan and start collecting approvals
+    ///
+    /// Fails with [`CommandError::UnsatisfiedKeyset`] unless the approvers
+    /// of each keyset are members of it and satisfy its predicate. Old
+    /// approvers are scoped to `coin.GAS` and the token's `TRANSFER`; new
+    /// approvers only prove they hold their keys and are scoped to
+    /// `coin.GAS`.
+    pub fn prepare(&self, meta: Meta, network_id: &str) -> Result<RotationCeremony, CommandError> {
+        if self.old_account() == self.new_account() {
+            return Err(CommandError::UnsatisfiedKeyset(
+                "the new keyset is the current one".to_string(),
+            ));
+        }
+        check_quorum("old", &self.old_keyset, &self.old_approvers)?;
+        check_quorum("new", &self.new_keyset, &self.new_approvers)?;
+
+        let transfer = self.transfer();
+        let mut signers: Vec<CommandSigner> = self
+            .old_approvers
+            .iter()
+            .map(|key| CommandSigner::new_ed25519(key, vec![Cap::new("coin.GAS"), transfer.cap()]))
+            .collect();
+        signers.extend(
+            self.new_approvers
+                .iter()
+                .filter(|key| !self.old_approvers.contains(key))
+                .map(|key| CommandSigner::new_ed25519(key, vec![Cap::new("coin.GAS")])),
+        );
+
+        let payload = CommandPayload::new(meta)
+            .with_network_id(network_id)
+            .with_code(transfer.code())
+            .with_env_data(transfer.env_data())
+            .with_signers(signers);
+
+        Ok(RotationCeremony {
+            rotation: self.clone(),
+            session: SigningSession::new(&payload)?,
+        })
+    }
+}
+
+/// A key rotation collecting the approvals of the old and new key holders
+#[derive(Debug, Clone)]
+pub struct RotationCeremony {
+    rotation: KeyRotation,
+    session: SigningSession,
+}
+
+impl RotationCeremony {
+    /// The plan of the rotation
+    pub fn rotation(&self) -> &KeyRotation {
+        &self.rotation
+    }
+
+    /// The signing session, e.g. to hand it to wallets as `SigData`
+    pub fn session(&self) -> &SigningSession {
+        &self.session
+    }
+
+    /// The command hash, which approvers sign
+    pub fn hash(&self) -> &str {
+        self.session.hash()
+    }
+
+    /// Add an approval signed elsewhere, e.g. by a hardware wallet
+    pub fn approve(&mut self, public_key: &str, sig: &str) -> Result<(), CommandError> {
+        self.session.add_signature(public_key, sig)
+    }
+
+    /// Approve with a local signer
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), CommandError> {
+        self.session.sign(signer)
+    }
+
+    /// Keys of the approvers that have not signed yet
+    pub fn missing_approvals(&self) -> Vec<&str> {
+        self.session.missing_signers()
+    }
+
+    /// Returns true once every approver has signed
+    pub fn is_approved(&self) -> bool {
+        self.session.is_complete()
+    }
+
+    /// Turn the approved ceremony into the command moving the funds
+    ///
+    /// Fails while approvals are missing.
+    pub fn into_cmd(self) -> Result<Cmd, CommandError> {
+        self.session.into_cmd()
+    }
+}
+
+fn check_quorum(which: &str, keyset: &KeySet, approvers: &[String]) -> Result<(), CommandError> {
+    if let Some(stranger) = approvers.iter().find(|key| !keyset.keys.contains(key)) {
+        return Err(CommandError::UnsatisfiedKeyset(format!(
+            "{} is not a key of the {} keyset",
+            stranger, which
+        )));
+    }
+    let keys: Vec<&str> = approvers.iter().map(String::as_str).collect();
+    match keyset.is_satisfied_by(&keys) {
+        Some(true) => Ok(()),
+        Some(false) => Err(CommandError::UnsatisfiedKeyset(format!(
+            "the approvers do not satisfy the {} keyset ({})",
+            which, keyset.pred
+        ))),
+        None => Err(CommandError::UnsatisfiedKeyset(format!(
+            "the quorum of the {} keyset's predicate {} cannot be checked",
+            which, keyset.pred
+        ))),
+    }
+}
@@ -0,0 +1,356 @@
+//! Multi-party signing of a command
+//!
+//! A [`SigningSession`] holds a command whose signatures are collected from
+//! several parties over time. Sessions serialize to the unsigned command
+//! format Pact tools exchange, with `null` in place of missing signatures:
+//!
+//! ```text
+//! {"cmd": "..", "hash": "..", "sigs": [{"sig": "ab12.."}, {"sig": null}]}
+//! ```
+//!
+//! A session can be stored, e.g. in a database, and resumed later. Decoding
+//! checks its integrity: the hash must be the hash of the command, there must
+//! be one signature slot per signer, and every collected signature must be
+//! valid, so a tampered session is rejected instead of failing on submission.
+//!
+//! Decoding also accepts the `SigData` variants other tools produce, with
+//! signatures keyed by public key:
+//!
+//! ```text
+//! {"cmd": "..", "hash": "..", "sigs": [{"pubKey": "d75a..", "sig": "ab12.."}]}
+//! {"cmd": "..", "hash": "..", "sigs": {"d75a..": "ab12..", "3d40..": null}}
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    base64url_decode, hash, pact::number_mode::serialize_payload, Cmd, CommandError,
+    CommandPayload, SchemeRegistry, SignaturePayload, Signer, ED25519,
+};
+
+/// A command collecting the signatures of its signers
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::PactKeypair;
+/// use kadena::pact::{Cap, CommandPayload, CommandSigner, Meta, SigningSession};
+///
+/// let alice = PactKeypair::generate();
+/// let bob = PactKeypair::generate();
+/// let payload = CommandPayload::new(Meta::new("0", &format!("k:{}", alice.public_key())))
+///     .with_code("(+ 1 2)".to_string())
+///     .add_signer(CommandSigner::new_ed25519(alice.public_key(), vec![Cap::new("coin.GAS")]))
+///     .add_signer(CommandSigner::new_ed25519(bob.public_key(), Vec::new()));
+///
+/// let mut session = SigningSession::new(&payload).unwrap();
+/// session.sign(&alice).unwrap();
+///
+/// // Store the session until bob signs
+/// let stored = serde_json::to_string(&session).unwrap();
+/// let mut session: SigningSession = serde_json::from_str(&stored).unwrap();
+/// assert_eq!(session.missing_signers(), [bob.public_key()]);
+///
+/// session.sign(&bob).unwrap();
+/// let cmd = session.into_cmd().unwrap();
+/// assert_eq!(cmd.sigs.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SigData", into = "RawSession")]
+pub struct SigningSession {
+    cmd: String,
+    hash: String,
+    signers: Vec<String>,
+    schemes: Vec<String>,
+    sigs: Vec<Option<String>>,
+}
+
+/// The serialized form of a session
+#[derive(Serialize)]
+struct RawSession {
+    cmd: String,
+    hash: String,
+    sigs: Vec<RawSignature>,
+}
+
+#[derive(Serialize)]
+struct RawSignature {
+    sig: Option<String>,
+}
+
+/// A session as decoded, checked when converted
+#[derive(Deserialize)]
+struct SigData {
+    cmd: String,
+    hash: String,
+    sigs: SigDataSigs,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SigDataSigs {
+    /// Signatures by position, optionally naming their signer
+    List(Vec<SigDataSignature>),
+    /// Signatures keyed by the public key of their signer
+    Keyed(HashMap<String, Option<String>>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SigDataSignature {
+    #[serde(default)]
+    pub_key: Option<String>,
+    #[serde(default)]
+    sig: Option<String>,
+}
+
+impl SigningSession {
+    /// Start collecting signatures for a payload
+    pub fn new(payload: &CommandPayload) -> Result<Self, CommandError> {
+        let cmd = serialize_payload(payload)?;
+        let hash = hash(cmd.as_bytes());
+        let sigs = vec![None; payload.signers.len()];
+        Self::resume(cmd, hash, sigs)
+    }
+
+    /// Resume signing a command that may already carry some signatures
+    ///
+    /// Signatures are matched to signers by position; empty signatures count
+    /// as missing.
+    pub fn from_cmd(cmd: &Cmd) -> Result<Self, CommandError> {
+        let sigs = cmd
+            .sigs
+            .iter()
+            .map(|s| Some(s.sig.clone()).filter(|sig| !sig.is_empty()))
+            .collect();
+        Self::resume(cmd.cmd.clone(), cmd.hash.clone(), sigs)
+    }
+
+    /// Decode a session from `SigData`, checking its integrity
+    ///
+    /// Unlike decoding with `serde`, the error keeps its [`CommandError`]
+    /// variant. Any input yields a session or an error, never a panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::crypto::PactKeypair;
+    /// use kadena::pact::{CommandPayload, CommandSigner, Meta, SigningSession};
+    /// use serde_json::json;
+    ///
+    /// let keypair = PactKeypair::generate();
+    /// let payload = CommandPayload::new(Meta::new("0", "k:abc"))
+    ///     .add_signer(CommandSigner::new_ed25519(keypair.public_key(), Vec::new()));
+    /// let mut session = SigningSession::new(&payload).unwrap();
+    /// session.sign(&keypair).unwrap();
+    ///
+    /// let sig_data = json!({
+    ///     "cmd": session.cmd(),
+    ///     "hash": session.hash(),
+    ///     "sigs": {keypair.public_key(): session.clone().into_cmd().unwrap().sigs[0].sig},
+    /// });
+    /// let parsed = SigningSession::parse(&sig_data.to_string()).unwrap();
+    /// assert!(parsed.is_complete());
+    ///
+    /// assert!(SigningSession::parse("{\"cmd\": \"\"}").is_err());
+    /// ```
+    pub fn parse(sig_data: &str) -> Result<Self, CommandError> {
+        Self::try_from(serde_json::from_str::<SigData>(sig_data)?)
+    }
+
+    /// Builds a session, checking the hash and the collected signatures
+    fn resume(
+        cmd: String,
+        hash: String,
+        mut sigs: Vec<Option<String>>,
+    ) -> Result<Self, CommandError> {
+        if crate::hash(cmd.as_bytes()) != hash {
+            return Err(CommandError::InvalidSession(
+                "hash does not match the command".to_string(),
+            ));
+        }
+        let payload = CommandPayload::parse(&cmd)?;
+        let (signers, schemes): (Vec<String>, Vec<String>) = payload
+            .signers
+            .into_iter()
+            .map(|s| (s.pub_key, s.scheme))
+            .unzip();
+        if sigs.len() > signers.len() {
+            return Err(CommandError::InvalidSession(format!(
+                "{} signatures for {} signers",
+                sigs.len(),
+                signers.len()
+            )));
+        }
+        sigs.resize(signers.len(), None);
+
+        let session = Self {
+            cmd,
+            hash,
+            signers,
+            schemes,
+            sigs,
+        };
+        for (signer, sig) in session.signers.iter().zip(&session.sigs) {
+            if let Some(sig) = sig {
+                session.check_signature(signer, sig)?;
+            }
+        }
+        Ok(session)
+    }
+
+    /// The serialized command
+    pub fn cmd(&self) -> &str {
+        &self.cmd
+    }
+
+    /// The command hash, which signers sign
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Parses the command back into its payload
+    pub fn payload(&self) -> Result<CommandPayload, CommandError> {
+        CommandPayload::parse(&self.cmd)
+    }
+
+    /// Public keys of the signers, in order
+    pub fn signers(&self) -> &[String] {
+        &self.signers
+    }
+
+    /// Public keys of the signers that have not signed yet
+    pub fn missing_signers(&self) -> Vec<&str> {
+        self.signers
+            .iter()
+            .zip(&self.sigs)
+            .filter(|(_, sig)| sig.is_none())
+            .map(|(signer, _)| signer.as_str())
+            .collect()
+    }
+
+    /// Returns true once every signer has signed
+    pub fn is_complete(&self) -> bool {
+        self.sigs.iter().all(Option::is_some)
+    }
+
+    /// Add a signature produced elsewhere, e.g. by a hardware wallet
+    ///
+    /// The signature is checked against the signer's public key. Every slot
+    /// of a signer listed several times receives the signature.
+    pub fn add_signature(&mut self, public_key: &str, sig: &str) -> Result<(), CommandError> {
+        if !self.signers.iter().any(|signer| signer == public_key) {
+            return Err(CommandError::SigningError(format!(
+                "{} is not a signer of the command",
+                public_key
+            )));
+        }
+        self.check_signature(public_key, sig)?;
+        for (signer, slot) in self.signers.iter().zip(self.sigs.iter_mut()) {
+            if signer == public_key {
+                *slot = Some(sig.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Sign the command with a local signer
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), CommandError> {
+        let sig = signer
+            .sign(&base64url_decode(&self.hash)?)
+            .map_err(|e| CommandError::SigningError(e.to_string()))?;
+        self.add_signature(signer.public_key(), &sig.to_hex())
+    }
+
+    /// Turn the session into a command ready for submission
+    ///
+    /// Fails while signatures are missing.
+    pub fn into_cmd(self) -> Result<Cmd, CommandError> {
+        let missing = self.missing_signers();
+        if !missing.is_empty() {
+            return Err(CommandError::SigningError(format!(
+                "missing signatures from {:?}",
+                missing
+            )));
+        }
+        Ok(Cmd {
+            sigs: self
+                .sigs
+                .into_iter()
+                .map(|sig| SignaturePayload::new(sig.unwrap_or_default()))
+                .collect(),
+            cmd: self.cmd,
+            hash: self.hash,
+        })
+    }
+
+    /// Checks a signature with the scheme of the signer
+    fn check_signature(&self, public_key: &str, sig: &str) -> Result<(), CommandError> {
+        let scheme = self
+            .signers
+            .iter()
+            .position(|signer| signer == public_key)
+            .map_or(ED25519, |i| self.schemes[i].as_str());
+        let valid = SchemeRegistry::builtin()
+            .verify(scheme, &base64url_decode(&self.hash)?, sig, public_key)
+            .map_err(|e| CommandError::SigningError(e.to_string()))?;
+        if !valid {
+            return Err(CommandError::InvalidSession(format!(
+                "invalid signature from {}",
+                public_key
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<SigData> for SigningSession {
+    type Error = CommandError;
+
+    fn try_from(data: SigData) -> Result<Self, Self::Error> {
+        let sigs: Vec<(Option<String>, Option<String>)> = match data.sigs {
+            SigDataSigs::List(sigs) => sigs.into_iter().map(|s| (s.pub_key, s.sig)).collect(),
+            SigDataSigs::Keyed(sigs) => sigs
+                .into_iter()
+                .map(|(key, sig)| (Some(key), sig))
+                .collect(),
+        };
+        if sigs.iter().all(|(pub_key, _)| pub_key.is_none()) {
+            let sigs = sigs.into_iter().map(|(_, sig)| sig).collect();
+            return Self::resume(data.cmd, data.hash, sigs);
+        }
+
+        let mut session = Self::resume(data.cmd, data.hash, Vec::new())?;
+        if sigs.len() > session.signers.len() {
+            return Err(CommandError::InvalidSession(format!(
+                "{} signatures for {} signers",
+                sigs.len(),
+                session.signers.len()
+            )));
+        }
+        for (position, (pub_key, sig)) in sigs.into_iter().enumerate() {
+            let Some(sig) = sig.filter(|sig| !sig.is_empty()) else {
+                continue;
+            };
+            let pub_key = pub_key.unwrap_or_else(|| session.signers[position].clone());
+            session.add_signature(&pub_key, &sig)?;
+        }
+        Ok(session)
+    }
+}
+
+impl From<SigningSession> for RawSession {
+    fn from(session: SigningSession) -> Self {
+        Self {
+            cmd: session.cmd,
+            hash: session.hash,
+            sigs: session
+                .sigs
+                .into_iter()
+                .map(|sig| RawSignature { sig })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Pact code templates with named parameters
+//!
+//! A template is Pact code with `{name}` placeholders. Each placeholder is
+//! replaced by the code literal of the [`PactValue`] bound to it, so values
+//! are always quoted and escaped correctly. Only braces around a bare name
+//! are placeholders: braces inside string literals and comments are left
+//! alone, as are Pact objects, whose fields are quoted, and bindings such as
+//! `{ 'a := a }`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{validate_code, CommandError, PactValue};
+
+/// A Pact code template
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::code_template;
+/// use rust_decimal::Decimal;
+///
+/// let code = code_template("(coin.transfer {from} {to} {amount})")
+///     .bind("from", "k:alice")
+///     .bind("to", "k:bob \"the builder\"")
+///     .bind("amount", Decimal::new(10, 0))
+///     .render()
+///     .unwrap();
+/// assert_eq!(code, r#"(coin.transfer "k:alice" "k:bob \"the builder\"" 10.0)"#);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeTemplate {
+    template: String,
+    bindings: BTreeMap<String, PactValue>,
+}
+
+/// Create a template from Pact code with `{name}` placeholders
+pub fn code_template(template: &str) -> CodeTemplate {
+    CodeTemplate::new(template)
+}
+
+impl CodeTemplate {
+    /// Create a template from Pact code with `{name}` placeholders
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// Bind a value to a placeholder, replacing any previous binding
+    pub fn bind(mut self, name: &str, value: impl Into<PactValue>) -> Self {
+        self.bindings.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Returns the names of the placeholders of the template, in order of appearance
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for segment in segments(&self.template) {
+            if let Segment::Placeholder(name) = segment {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Render the template
+    ///
+    /// Fails if a placeholder is unbound, a binding is unused, a value has no
+    /// code literal, or the rendered code does not pass [`validate_code`].
+    pub fn render(&self) -> Result<String, CommandError> {
+        let mut code = String::with_capacity(self.template.len());
+        let mut used = BTreeSet::new();
+        for segment in segments(&self.template) {
+            match segment {
+                Segment::Text(text) => code.push_str(text),
+                Segment::Placeholder(name) => {
+                    let value = self.bindings.get(name).ok_or_else(|| {
+                        CommandError::InvalidTemplate(format!("unbound placeholder {{{}}}", name))
+                    })?;
+                    code.push_str(&value.to_code()?);
+                    used.insert(name);
+                }
+            }
+        }
+        if let Some(unused) = self.bindings.keys().find(|k| !used.contains(k.as_str())) {
+            return Err(CommandError::InvalidTemplate(format!(
+                "no placeholder {{{}}}",
+                unused
+            )));
+        }
+
+        validate_code(&code)?;
+        Ok(code)
+    }
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits a template into literal text and placeholders
+fn segments(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let bytes = template.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            b'"' => in_string = true,
+            b';' => {
+                i = template[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                continue;
+            }
+            b'{' => {
+                let name_end = template[i + 1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .map(|n| i + 1 + n);
+                if let Some(end) = name_end.filter(|end| *end > i + 1 && bytes[*end] == b'}') {
+                    segments.push(Segment::Text(&template[start..i]));
+                    segments.push(Segment::Placeholder(&template[i + 1..end]));
+                    start = end + 1;
+                    i = end;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    segments.push(Segment::Text(&template[start..]));
+    segments
+}
@@ -0,0 +1,233 @@
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::{
+    cap::Cap, check_account_principal, meta::Meta, Cmd, CommandError, FeePolicy, Guard,
+    ModuleDescription, PactKeypair, FUNGIBLE_V2,
+};
+
+/// Builder for `coin.transfer` and `coin.transfer-create` transactions
+///
+/// Setting a receiver guard switches to `transfer-create`, which creates the
+/// receiving account if it does not exist yet. Transfers of other
+/// `fungible-v2` tokens are built with [`TokenTransfer`].
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    /// The `fungible-v2` module of the token, e.g. `coin`
+    pub module: String,
+    /// The sending account
+    pub sender: String,
+    /// The receiving account
+    pub receiver: String,
+    /// The amount transferred
+    pub amount: Decimal,
+    /// Guard of the receiving account, for `transfer-create`
+    pub receiver_guard: Option<Guard>,
+    /// Environment data key holding a keyset receiver guard
+    pub keyset_name: String,
+    /// Budget checked before signing, if any
+    pub fee_policy: Option<FeePolicy>,
+}
+
+impl Transfer {
+    /// Creates a transfer of `amount` from `sender` to `receiver`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::Transfer;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let transfer = Transfer::new("alice", "bob", Decimal::new(15, 1));
+    /// assert_eq!(transfer.code(), "(coin.transfer \"alice\" \"bob\" 1.5)");
+    /// ```
+    pub fn new(sender: &str, receiver: &str, amount: Decimal) -> Self {
+        Self {
+            module: "coin".to_string(),
+            sender: sender.to_string(),
+            receiver: receiver.to_string(),
+            amount,
+            receiver_guard: None,
+            keyset_name: "ks".to_string(),
+            fee_policy: None,
+        }
+    }
+
+    /// Sets the guard of the receiving account, creating it if needed
+    pub fn with_receiver_guard(mut self, guard: impl Into<Guard>) -> Self {
+        self.receiver_guard = Some(guard.into());
+        self
+    }
+
+    /// Sets the environment data key holding a keyset receiver guard
+    pub fn with_keyset_name(mut self, keyset_name: &str) -> Self {
+        self.keyset_name = keyset_name.to_string();
+        self
+    }
+
+    /// Returns the transfer code
+    pub fn code(&self) -> String {
+        let sender = Value::from(self.sender.as_str());
+        let receiver = Value::from(self.receiver.as_str());
+        let amount = pact_decimal(self.amount);
+        match &self.receiver_guard {
+            Some(guard) => format!(
+                "({}.transfer-create {} {} {} {})",
+                self.module,
+                sender,
+                receiver,
+                guard.code(&self.keyset_name),
+                amount
+            ),
+            None => format!(
+                "({}.transfer {} {} {})",
+                self.module, sender, receiver, amount
+            ),
+        }
+    }
+
+    /// Returns the environment data carrying a keyset receiver guard
+    pub fn env_data(&self) -> Value {
+        self.receiver_guard
+            .as_ref()
+            .map_or_else(|| json!({}), |guard| guard.env_data(&self.keyset_name))
+    }
+
+    /// Returns the `TRANSFER` capability of the token scoping the sender's signature
+    pub fn cap(&self) -> Cap {
+        Cap::with_args(
+            &format!("{}.TRANSFER", self.module),
+            vec![
+                json!(self.sender),
+                json!(self.receiver),
+                json!({ "decimal": pact_decimal(self.amount) }),
+            ],
+        )
+    }
+
+    /// Checks that a principal receiver matches the guard of `transfer-create`
+    ///
+    /// The receiver of a `c:` vault account must be the principal of its
+    /// capability guard, like `k:` and `w:` accounts of their keyset. Plain
+    /// transfers and receivers without a reserved prefix are accepted.
+    pub fn check_receiver_principal(&self) -> Result<(), CommandError> {
+        match &self.receiver_guard {
+            Some(guard) => check_account_principal(&self.receiver, guard),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the gas fees of prepared commands against `policy`
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = Some(policy);
+        self
+    }
+
+    /// Prepares the signed transfer command
+    ///
+    /// Each signer is scoped to `coin.GAS` and the token's `TRANSFER`. Fails
+    /// if the receiver does not match its guard, see
+    /// [`Transfer::check_receiver_principal`].
+    pub fn prepare(
+        &self,
+        signers: &[&PactKeypair],
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<Cmd, CommandError> {
+        if let Some(policy) = &self.fee_policy {
+            policy.check(&meta)?;
+        }
+        self.check_receiver_principal()?;
+        let signers: Vec<(&PactKeypair, Vec<Cap>)> = signers
+            .iter()
+            .map(|kp| (*kp, vec![Cap::new("coin.GAS"), self.cap()]))
+            .collect();
+
+        Cmd::prepare_exec(
+            &signers,
+            Vec::new(),
+            None,
+            &self.code(),
+            Some(self.env_data()),
+            meta,
+            network_id,
+        )
+    }
+}
+
+/// Builder for transfers of any `fungible-v2` token
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::TokenTransfer;
+/// use rust_decimal::Decimal;
+///
+/// let transfer = TokenTransfer::new_unchecked("free.some-token").transfer("alice", "bob", Decimal::new(2, 0));
+/// assert_eq!(transfer.code(), "(free.some-token.transfer \"alice\" \"bob\" 2.0)");
+/// assert_eq!(transfer.cap().name, "free.some-token.TRANSFER");
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokenTransfer {
+    /// The `fungible-v2` module of the token
+    pub module: String,
+}
+
+impl TokenTransfer {
+    /// Creates a builder for transfers of the token defined by `module`,
+    /// without checking that it is a token
+    ///
+    /// Transfers of a module that does not implement [`FUNGIBLE_V2`] fail on
+    /// chain, or call functions that merely share the names of its transfer
+    /// functions. Prefer [`TokenTransfer::checked`] for modules not known in
+    /// advance.
+    pub fn new_unchecked(module: &str) -> Self {
+        Self {
+            module: module.to_string(),
+        }
+    }
+
+    /// Creates a builder for transfers of a deployed token
+    ///
+    /// Fails with [`CommandError::MissingInterface`] unless the module
+    /// implements [`FUNGIBLE_V2`], whose functions the transfers call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{ModuleDescription, TokenTransfer};
+    ///
+    /// let deployed: ModuleDescription = serde_json::from_value(serde_json::json!({
+    ///     "name": "free.some-token",
+    ///     "hash": "token-hash",
+    ///     "interfaces": ["fungible-v2"],
+    ///     "code": "(module some-token GOV)"
+    /// }))
+    /// .unwrap();
+    ///
+    /// let token = TokenTransfer::checked(&deployed).unwrap();
+    /// assert_eq!(token.module, "free.some-token");
+    /// ```
+    pub fn checked(deployed: &ModuleDescription) -> Result<Self, CommandError> {
+        deployed.require_interface(FUNGIBLE_V2)?;
+        Ok(Self::new_unchecked(&deployed.name))
+    }
+
+    /// Creates a transfer of `amount` from `sender` to `receiver`
+    pub fn transfer(&self, sender: &str, receiver: &str, amount: Decimal) -> Transfer {
+        Transfer {
+            module: self.module.clone(),
+            ..Transfer::new(sender, receiver, amount)
+        }
+    }
+}
+
+/// Formats a decimal as a Pact decimal literal, which requires a decimal point
+pub(crate) fn pact_decimal(amount: Decimal) -> String {
+    let amount = amount.normalize().to_string();
+    if amount.contains('.') {
+        amount
+    } else {
+        format!("{}.0", amount)
+    }
+}
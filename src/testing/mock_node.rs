@@ -0,0 +1,251 @@
+use serde_json::{json, Value};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, Request, ResponseTemplate,
+};
+
+use crate::{
+    pact::{BlockMetadata, Cmd, CommandPayload, CommandResult, PactResult},
+    ApiClient, ApiConfig,
+};
+
+/// Height of the block in which [`MockNode`] reports polled commands
+pub const MOCK_BLOCK_HEIGHT: u64 = 1;
+
+/// An in-memory Chainweb node serving the Pact API of one chain
+///
+/// Stubs answer `local`, `send`, `poll` and `listen` requests for any command,
+/// using the hash of the submitted command as request key, and every request
+/// is recorded so tests can assert what was submitted. When several stubs
+/// match a request, the one registered first answers; call
+/// [`MockNode::reset`] to replace them.
+///
+/// # Examples
+///
+/// ```
+/// use kadena::crypto::PactKeypair;
+/// use kadena::pact::{Cmd, Meta, PactResult};
+/// use kadena::testing::MockNode;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let node = MockNode::start().await;
+/// node.stub_send().await;
+/// node.stub_listen(PactResult::Success { data: json!("Write succeeded") }).await;
+///
+/// let keypair = PactKeypair::generate();
+/// let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+/// let cmd = Cmd::prepare_exec(
+///     &[(&keypair, vec![])],
+///     Vec::new(),
+///     None,
+///     "(+ 1 2)",
+///     None,
+///     meta,
+///     Some("development".to_string()),
+/// ).unwrap();
+///
+/// let client = node.client();
+/// client.send(&cmd).await.unwrap();
+/// let result = client.listen(&cmd.hash).await.unwrap();
+/// assert!(result.is_success());
+///
+/// node.assert_sent(|payload| payload.payload.code() == Some("(+ 1 2)")).await;
+/// # }
+/// ```
+pub struct MockNode {
+    server: MockServer,
+    network: String,
+    chain_id: String,
+}
+
+impl MockNode {
+    /// Start a node for chain `0` of the `development` network
+    pub async fn start() -> Self {
+        Self::start_with("development", "0").await
+    }
+
+    /// Start a node for a chain of a network
+    pub async fn start_with(network: &str, chain_id: &str) -> Self {
+        Self {
+            server: MockServer::start().await,
+            network: network.to_string(),
+            chain_id: chain_id.to_string(),
+        }
+    }
+
+    /// Base URL of the node
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The underlying mock server, to mount custom stubs
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Path of a Pact API endpoint, e.g. `local`
+    pub fn endpoint_path(&self, endpoint: &str) -> String {
+        format!(
+            "/chainweb/0.0/{}/chain/{}/pact/api/v1/{}",
+            self.network, self.chain_id, endpoint
+        )
+    }
+
+    /// A configuration pointing at this node
+    pub fn config(&self) -> ApiConfig {
+        ApiConfig::new(&self.uri(), &self.network, &self.chain_id)
+    }
+
+    /// A client for this node
+    pub fn client(&self) -> ApiClient {
+        ApiClient::new(self.config())
+    }
+
+    /// Answer `local` requests with `result`
+    pub async fn stub_local(&self, result: PactResult) {
+        self.stub("local", move |request: &Request| {
+            let cmd: Cmd = parse_body(request);
+            ResponseTemplate::new(200).set_body_json(command_result(&cmd.hash, &result, false))
+        })
+        .await;
+    }
+
+    /// Accept every `send` request, answering with the submitted request keys
+    pub async fn stub_send(&self) {
+        self.stub("send", |request: &Request| {
+            let keys: Vec<String> = sent_in(request).into_iter().map(|cmd| cmd.hash).collect();
+            ResponseTemplate::new(200).set_body_json(json!({ "requestKeys": keys }))
+        })
+        .await;
+    }
+
+    /// Answer `poll` requests with `result` for every requested key
+    pub async fn stub_poll(&self, result: PactResult) {
+        self.stub("poll", move |request: &Request| {
+            let body: Value = parse_body(request);
+            let results: serde_json::Map<String, Value> = body["requestKeys"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|key| (key.to_string(), command_result(key, &result, true)))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(results)
+        })
+        .await;
+    }
+
+    /// Answer `listen` requests with `result`
+    pub async fn stub_listen(&self, result: PactResult) {
+        self.stub("listen", move |request: &Request| {
+            let body: Value = parse_body(request);
+            let key = body["listen"].as_str().unwrap_or_default();
+            ResponseTemplate::new(200).set_body_json(command_result(key, &result, true))
+        })
+        .await;
+    }
+
+    /// Fail requests to an endpoint, e.g. `send`, with a plain text error
+    pub async fn stub_error(&self, endpoint: &str, status: u16, message: &str) {
+        let message = message.to_string();
+        self.stub(endpoint, move |_: &Request| {
+            ResponseTemplate::new(status).set_body_string(message.clone())
+        })
+        .await;
+    }
+
+    /// Remove all stubs and recorded requests
+    pub async fn reset(&self) {
+        self.server.reset().await;
+    }
+
+    /// Commands submitted with `send`, in order
+    pub async fn sent_commands(&self) -> Vec<Cmd> {
+        self.requests_to("send")
+            .await
+            .iter()
+            .flat_map(sent_in)
+            .collect()
+    }
+
+    /// Commands executed with `local`, in order
+    pub async fn local_commands(&self) -> Vec<Cmd> {
+        self.requests_to("local")
+            .await
+            .iter()
+            .map(parse_body)
+            .collect()
+    }
+
+    /// Assert that a submitted command has a payload matching `predicate`
+    ///
+    /// # Panics
+    ///
+    /// Panics if no command submitted with `send` matches.
+    pub async fn assert_sent(&self, predicate: impl Fn(&CommandPayload) -> bool) {
+        let sent = self.sent_commands().await;
+        let matched = sent
+            .iter()
+            .any(|cmd| cmd.payload().is_ok_and(|payload| predicate(&payload)));
+        assert!(
+            matched,
+            "no matching command among {} sent: {:?}",
+            sent.len(),
+            sent.iter().map(|cmd| &cmd.cmd).collect::<Vec<_>>()
+        );
+    }
+
+    async fn stub(
+        &self,
+        endpoint: &str,
+        respond: impl Fn(&Request) -> ResponseTemplate + Send + Sync + 'static,
+    ) {
+        Mock::given(method("POST"))
+            .and(path(self.endpoint_path(endpoint)))
+            .respond_with(respond)
+            .mount(&self.server)
+            .await;
+    }
+
+    async fn requests_to(&self, endpoint: &str) -> Vec<Request> {
+        let path = self.endpoint_path(endpoint);
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| request.url.path() == path)
+            .collect()
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(request: &Request) -> T {
+    serde_json::from_slice(&request.body).expect("request body should be a Pact API payload")
+}
+
+fn sent_in(request: &Request) -> Vec<Cmd> {
+    let body: Value = parse_body(request);
+    serde_json::from_value(body["cmds"].clone()).expect("send request should carry commands")
+}
+
+fn command_result(request_key: &str, result: &PactResult, mined: bool) -> Value {
+    let result = CommandResult {
+        req_key: request_key.to_string(),
+        tx_id: mined.then_some(1),
+        result: result.clone(),
+        gas: 0,
+        logs: None,
+        continuation: None,
+        events: Vec::new(),
+        meta_data: mined.then(|| BlockMetadata {
+            block_height: MOCK_BLOCK_HEIGHT,
+            block_hash: "mock-block".to_string(),
+            block_time: 0,
+            prev_block_hash: "mock-parent".to_string(),
+            public_meta: None,
+        }),
+    };
+    serde_json::to_value(result).expect("command results serialize to JSON")
+}
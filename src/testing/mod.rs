@@ -0,0 +1,9 @@
+//! Test utilities for code built on this crate
+//!
+//! [`MockNode`] stands in for a Chainweb node in integration tests, so
+//! applications can exercise their submission logic without a real network.
+//! Enable the `testing` feature in `dev-dependencies` only.
+
+pub mod mock_node;
+
+pub use mock_node::*;
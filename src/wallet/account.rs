@@ -0,0 +1,81 @@
+use crate::crypto::{PactKeypair, Signer};
+
+/// An account tracked by a wallet
+///
+/// Accounts are either owned, when the wallet holds their secret key, or
+/// watch-only, when only their name and possibly their public key are known.
+/// Watch-only accounts can build transactions and be monitored, but signing
+/// them requires an external [`Signer`].
+#[derive(Debug, Clone)]
+pub struct WalletAccount {
+    account: String,
+    public_key: Option<String>,
+    keypair: Option<PactKeypair>,
+}
+
+impl WalletAccount {
+    /// Creates an owned `k:` account from a keypair
+    pub fn from_keypair(keypair: PactKeypair) -> Self {
+        Self {
+            account: format!("k:{}", keypair.public_key()),
+            public_key: Some(keypair.public_key().to_string()),
+            keypair: Some(keypair),
+        }
+    }
+
+    /// Creates a watch-only `k:` account from a public key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::wallet::WalletAccount;
+    ///
+    /// let account = WalletAccount::watch_public_key("abc123");
+    /// assert_eq!(account.account(), "k:abc123");
+    /// assert!(account.is_watch_only());
+    /// ```
+    pub fn watch_public_key(public_key: &str) -> Self {
+        Self {
+            account: format!("k:{}", public_key),
+            public_key: Some(public_key.to_string()),
+            keypair: None,
+        }
+    }
+
+    /// Creates a watch-only account from its name
+    pub fn watch_account(account: &str) -> Self {
+        Self {
+            account: account.to_string(),
+            public_key: None,
+            keypair: None,
+        }
+    }
+
+    /// Sets the public key signing for a watch-only account
+    pub fn with_public_key(mut self, public_key: &str) -> Self {
+        if self.keypair.is_none() {
+            self.public_key = Some(public_key.to_string());
+        }
+        self
+    }
+
+    /// Get the account name
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    /// Get the public key signing for the account, if known
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+
+    /// Returns true if the wallet does not hold the secret key of the account
+    pub fn is_watch_only(&self) -> bool {
+        self.keypair.is_none()
+    }
+
+    /// Get the signer of an owned account
+    pub fn signer(&self) -> Option<&dyn Signer> {
+        self.keypair.as_ref().map(|kp| kp as &dyn Signer)
+    }
+}
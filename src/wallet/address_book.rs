@@ -0,0 +1,167 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pact::{Meta, Transfer},
+    WalletError,
+};
+
+/// A named account in an address book
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    /// The account name
+    pub account: String,
+    /// Chain the contact prefers to receive funds on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<String>,
+    /// Free-form notes about the guard of the account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard_notes: Option<String>,
+}
+
+impl Contact {
+    /// Creates a contact for an account
+    pub fn new(account: &str) -> Self {
+        Self {
+            account: account.to_string(),
+            chain_id: None,
+            guard_notes: None,
+        }
+    }
+
+    /// Sets the chain the contact prefers to receive funds on
+    pub fn with_chain_id(mut self, chain_id: &str) -> Self {
+        self.chain_id = Some(chain_id.to_string());
+        self
+    }
+
+    /// Sets notes about the guard of the account
+    pub fn with_guard_notes(mut self, notes: &str) -> Self {
+        self.guard_notes = Some(notes.to_string());
+        self
+    }
+}
+
+/// Contacts indexed by name, persisted as a JSON file
+///
+/// # Examples
+///
+/// ```
+/// use kadena::pact::Meta;
+/// use kadena::wallet::{AddressBook, Contact};
+/// use rust_decimal::Decimal;
+///
+/// let mut book = AddressBook::new();
+/// book.insert("bob", Contact::new("k:def456").with_chain_id("1"));
+///
+/// let meta = Meta::new("1", "k:abc123");
+/// let transfer = book.transfer("k:abc123", "bob", Decimal::ONE, &meta).unwrap();
+/// assert_eq!(transfer.receiver, "k:def456");
+/// assert!(book.transfer("k:abc123", "bob", Decimal::ONE, &Meta::new("0", "k:abc123")).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressBook {
+    contacts: BTreeMap<String, Contact>,
+}
+
+impl AddressBook {
+    /// Creates an empty address book
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads an address book from a JSON file, or starts an empty one if it does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WalletError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Self::import_json(&json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves the address book to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WalletError> {
+        fs::write(path, self.export_json()?)?;
+        Ok(())
+    }
+
+    /// Parses an address book exported with [`AddressBook::export_json`]
+    pub fn import_json(json: &str) -> Result<Self, WalletError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Exports the address book as JSON
+    pub fn export_json(&self) -> Result<String, WalletError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Adds the contacts of another address book, replacing those with the same name
+    pub fn merge(&mut self, other: AddressBook) {
+        self.contacts.extend(other.contacts);
+    }
+
+    /// Adds or replaces a contact, returning the previous one
+    pub fn insert(&mut self, name: &str, contact: Contact) -> Option<Contact> {
+        self.contacts.insert(name.to_string(), contact)
+    }
+
+    /// Removes a contact
+    pub fn remove(&mut self, name: &str) -> Option<Contact> {
+        self.contacts.remove(name)
+    }
+
+    /// Get a contact by name
+    pub fn get(&self, name: &str) -> Option<&Contact> {
+        self.contacts.get(name)
+    }
+
+    /// Iterates over the contacts in name order
+    pub fn contacts(&self) -> impl Iterator<Item = (&str, &Contact)> {
+        self.contacts
+            .iter()
+            .map(|(name, contact)| (name.as_str(), contact))
+    }
+
+    /// Resolves a contact name to its account
+    ///
+    /// Strings that are not contact names are returned unchanged, so raw
+    /// account names can be used wherever a contact is accepted.
+    pub fn resolve<'a>(&'a self, name_or_account: &'a str) -> &'a str {
+        self.get(name_or_account)
+            .map_or(name_or_account, |contact| contact.account.as_str())
+    }
+
+    /// Builds a transfer to a contact, to be sent with `meta`
+    ///
+    /// Fails with [`WalletError::UnknownContact`] if `contact` is not in the
+    /// address book, and with [`WalletError::ChainMismatch`] if the contact
+    /// receives on another chain than `meta.chain_id`. A plain transfer does
+    /// not cross chains, so funds sent there would not reach the contact.
+    pub fn transfer(
+        &self,
+        sender: &str,
+        name: &str,
+        amount: Decimal,
+        meta: &Meta,
+    ) -> Result<Transfer, WalletError> {
+        let contact = self
+            .get(name)
+            .ok_or_else(|| WalletError::UnknownContact(name.to_string()))?;
+        if let Some(chain_id) = &contact.chain_id {
+            if *chain_id != meta.chain_id {
+                return Err(WalletError::ChainMismatch {
+                    contact: name.to_string(),
+                    expected: chain_id.clone(),
+                    found: meta.chain_id.clone(),
+                });
+            }
+        }
+        Ok(Transfer::new(
+            self.resolve(sender),
+            &contact.account,
+            amount,
+        ))
+    }
+}
@@ -0,0 +1,34 @@
+//! Wallet account management
+//!
+//! This module groups the accounts of a user, whether their keys are held by
+//! the wallet or elsewhere, and builds, signs and monitors transactions for them.
+//!
+//! ## Module Structure
+//!
+//! - [`account`] - Owned and watch-only accounts
+//! - [`address_book`] - Named contacts
+//! - [`wallet`] - The account collection and its operations
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use kadena::crypto::PactKeypair;
+//! use kadena::wallet::{Wallet, WalletAccount};
+//!
+//! let mut wallet = Wallet::new();
+//! wallet.add(WalletAccount::from_keypair(PactKeypair::generate())).unwrap();
+//! wallet.add(WalletAccount::watch_account("alice")).unwrap();
+//!
+//! assert!(wallet.get("alice").unwrap().is_watch_only());
+//! ```
+
+pub mod account;
+pub mod address_book;
+#[allow(clippy::module_inception)]
+pub mod wallet;
+pub mod wallet_error;
+
+pub use account::*;
+pub use address_book::*;
+pub use wallet::*;
+pub use wallet_error::*;
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::{
+    crypto::Signer,
+    fetch::{AccountBalances, ChainwebClient, ChainwebDataClient, HistoryEntry},
+    pact::{validate_code, Cap, Cmd, CommandPayload, CommandSigner, Meta},
+    WalletAccount, WalletError,
+};
+
+/// A transfer received by a wallet account, detected from a balance increase
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncomingTransfer {
+    /// The receiving account
+    pub account: String,
+    /// The chain the funds arrived on
+    pub chain_id: String,
+    /// The amount received since the previous refresh
+    pub amount: Decimal,
+}
+
+/// A collection of owned and watch-only accounts
+#[derive(Debug, Clone, Default)]
+pub struct Wallet {
+    accounts: Vec<WalletAccount>,
+    balances: HashMap<String, AccountBalances>,
+}
+
+impl Wallet {
+    /// Creates an empty wallet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an account to the wallet
+    pub fn add(&mut self, account: WalletAccount) -> Result<(), WalletError> {
+        if self
+            .accounts
+            .iter()
+            .any(|a| a.account() == account.account())
+        {
+            return Err(WalletError::DuplicateAccount(account.account().to_string()));
+        }
+        self.accounts.push(account);
+        Ok(())
+    }
+
+    /// Get an account by name
+    pub fn get(&self, account: &str) -> Result<&WalletAccount, WalletError> {
+        self.accounts
+            .iter()
+            .find(|a| a.account() == account)
+            .ok_or_else(|| WalletError::UnknownAccount(account.to_string()))
+    }
+
+    /// Get all accounts of the wallet
+    pub fn accounts(&self) -> &[WalletAccount] {
+        &self.accounts
+    }
+
+    /// Builds an unsigned execution payload for an account
+    ///
+    /// The account's public key, when known, is listed as signer with `caps`.
+    /// Works for watch-only accounts; sign the result with [`Wallet::sign`].
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account sending the transaction
+    /// * `caps` - Capabilities granted by the account's signature
+    /// * `pact_code` - The Pact code to execute
+    /// * `env_data` - Optional environment data
+    /// * `meta` - Metadata for the command
+    /// * `network_id` - Optional network identifier
+    pub fn build(
+        &self,
+        account: &str,
+        caps: Vec<Cap>,
+        pact_code: &str,
+        env_data: Option<Value>,
+        meta: Meta,
+        network_id: Option<String>,
+    ) -> Result<CommandPayload, WalletError> {
+        let account = self.get(account)?;
+        validate_code(pact_code).map_err(crate::pact::CommandError::from)?;
+
+        let mut payload = CommandPayload::new(meta).with_code(pact_code.to_string());
+        if let Some(public_key) = account.public_key() {
+            payload = payload.add_signer(CommandSigner::new_ed25519(public_key, caps));
+        }
+        if let Some(data) = env_data {
+            payload = payload.with_env_data(data);
+        }
+        if let Some(network_id) = network_id {
+            payload = payload.with_network_id(network_id);
+        }
+        Ok(payload)
+    }
+
+    /// Signs a payload built with [`Wallet::build`]
+    ///
+    /// Owned accounts sign with their own key. Watch-only accounts require an
+    /// `external` signer, whose key must match the account's public key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kadena::pact::{Cap, Meta};
+    /// use kadena::wallet::{Wallet, WalletAccount, WalletError};
+    ///
+    /// let mut wallet = Wallet::new();
+    /// wallet.add(WalletAccount::watch_public_key("abc123")).unwrap();
+    ///
+    /// let payload = wallet
+    ///     .build("k:abc123", vec![Cap::new("coin.GAS")], "(+ 1 2)", None, Meta::new("0", "k:abc123"), None)
+    ///     .unwrap();
+    /// let result = wallet.sign("k:abc123", &payload, None);
+    /// assert!(matches!(result, Err(WalletError::WatchOnly(_))));
+    /// ```
+    pub fn sign(
+        &self,
+        account: &str,
+        payload: &CommandPayload,
+        external: Option<&dyn Signer>,
+    ) -> Result<Cmd, WalletError> {
+        let account = self.get(account)?;
+        let signer = account
+            .signer()
+            .or(external)
+            .ok_or_else(|| WalletError::WatchOnly(account.account().to_string()))?;
+
+        if let Some(expected) = account.public_key() {
+            if expected != signer.public_key() {
+                return Err(WalletError::SignerMismatch {
+                    expected: expected.to_string(),
+                    found: signer.public_key().to_string(),
+                });
+            }
+        }
+
+        Ok(Cmd::from_payload(payload, &[signer])?)
+    }
+
+    /// Queries the balances of an account on every chain
+    pub async fn balance(
+        &self,
+        client: &ChainwebClient,
+        account: &str,
+    ) -> Result<AccountBalances, WalletError> {
+        let account = self.get(account)?;
+        Ok(client.total_balance(account.account()).await?)
+    }
+
+    /// Queries the complete transfer history of an account from a chainweb-data indexer
+    pub async fn history(
+        &self,
+        client: &ChainwebDataClient,
+        account: &str,
+        chain_filter: Option<&str>,
+    ) -> Result<Vec<HistoryEntry>, WalletError> {
+        let account = self.get(account)?;
+        Ok(client.history_all(account.account(), chain_filter).await?)
+    }
+
+    /// Refreshes the balances of all accounts and reports incoming transfers
+    ///
+    /// A transfer is reported for every chain whose balance increased since the
+    /// previous refresh. The first refresh only records the balances.
+    pub async fn refresh(
+        &mut self,
+        client: &ChainwebClient,
+    ) -> Result<Vec<IncomingTransfer>, WalletError> {
+        let mut incoming = Vec::new();
+        for account in &self.accounts {
+            let current = client.total_balance(account.account()).await?;
+            if let Some(previous) = self.balances.get(account.account()) {
+                incoming.extend(balance_increases(previous, &current));
+            }
+            self.balances.insert(account.account().to_string(), current);
+        }
+        Ok(incoming)
+    }
+
+    /// Get the balances recorded by the last refresh of an account
+    pub fn last_balances(&self, account: &str) -> Option<&AccountBalances> {
+        self.balances.get(account)
+    }
+}
+
+fn balance_increases(
+    previous: &AccountBalances,
+    current: &AccountBalances,
+) -> Vec<IncomingTransfer> {
+    current
+        .balances
+        .iter()
+        .filter_map(|chain| {
+            let before = previous
+                .balances
+                .iter()
+                .find(|b| b.chain_id == chain.chain_id)
+                .map_or(Decimal::ZERO, |b| b.balance);
+            (chain.balance > before).then(|| IncomingTransfer {
+                account: current.account.clone(),
+                chain_id: chain.chain_id.clone(),
+                amount: chain.balance - before,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WalletError {
+    #[error("Unknown account: {0}")]
+    UnknownAccount(String),
+    #[error("Unknown contact: {0}")]
+    UnknownContact(String),
+    #[error("Account already in wallet: {0}")]
+    DuplicateAccount(String),
+    #[error("Account {0} is watch-only and no external signer was provided")]
+    WatchOnly(String),
+    #[error("Signer key {found} does not match account key {expected}")]
+    SignerMismatch { expected: String, found: String },
+    #[error("Contact {contact} receives on chain {expected}, not chain {found}")]
+    ChainMismatch {
+        contact: String,
+        expected: String,
+        found: String,
+    },
+    #[error("Command error: {0}")]
+    CommandError(#[from] crate::pact::CommandError),
+    #[error("Fetch error: {0}")]
+    FetchError(#[from] crate::fetch::FetchError),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+impl WalletError {
+    /// A stable, machine-readable code for the kind of error
+    ///
+    /// Wrapped command and fetch errors report the code of the underlying error.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::UnknownAccount(_) => "wallet.unknown_account",
+            Self::UnknownContact(_) => "wallet.unknown_contact",
+            Self::DuplicateAccount(_) => "wallet.duplicate_account",
+            Self::WatchOnly(_) => "wallet.watch_only",
+            Self::SignerMismatch { .. } => "wallet.signer_mismatch",
+            Self::ChainMismatch { .. } => "wallet.chain_mismatch",
+            Self::CommandError(e) => e.error_code(),
+            Self::FetchError(e) => e.error_code(),
+            Self::IoError(_) => "wallet.io",
+            Self::SerializationError(_) => "wallet.serialization",
+        }
+    }
+}
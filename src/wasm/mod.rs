@@ -0,0 +1,92 @@
+//! JavaScript bindings through wasm-bindgen
+//!
+//! Exposes keypairs, hashing and command preparation to JavaScript, so web
+//! wallets hash and sign with the same code as services built on this crate.
+//! Build the package with the `wasm` feature, e.g.:
+//!
+//! ```bash
+//! wasm-pack build --target web -- --no-default-features --features wasm
+//! ```
+//!
+//! Commands cross the boundary as JSON strings:
+//!
+//! ```js
+//! import init, { PactKeypair, prepareExec } from "./pkg/kadena.js";
+//!
+//! await init();
+//! const keypair = PactKeypair.generate();
+//! const cmd = JSON.parse(prepareExec(JSON.stringify({
+//!   code: "(+ 1 2)",
+//!   chainId: "0",
+//!   sender: `k:${keypair.publicKey}`,
+//!   networkId: "testnet04",
+//!   signers: [{ secretKey: keypair.secretKey, caps: [] }],
+//! })));
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    crypto::{base64url_decode, PactKeypair},
+    pact::ExecRequest,
+};
+
+/// An ED25519 keypair
+#[wasm_bindgen(js_name = PactKeypair)]
+pub struct WasmKeypair(PactKeypair);
+
+#[wasm_bindgen(js_class = PactKeypair)]
+impl WasmKeypair {
+    /// Generate a new random keypair
+    pub fn generate() -> WasmKeypair {
+        WasmKeypair(PactKeypair::generate())
+    }
+
+    /// Restore a keypair from a hex secret key
+    #[wasm_bindgen(js_name = fromSecretKey)]
+    pub fn from_secret_key(secret_key: &str) -> Result<WasmKeypair, JsError> {
+        Ok(WasmKeypair(PactKeypair::from_secret_key(secret_key)?))
+    }
+
+    /// The hex public key
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> String {
+        self.0.public_key().to_string()
+    }
+
+    /// The hex secret key
+    #[wasm_bindgen(getter, js_name = secretKey)]
+    pub fn secret_key(&self) -> String {
+        self.0.secret_key().to_string()
+    }
+
+    /// Sign a message, returning the hex signature
+    pub fn sign(&self, message: &[u8]) -> Result<String, JsError> {
+        Ok(self.0.sign(message)?.to_hex())
+    }
+
+    /// Sign a base64url command hash, as found in the `hash` field of a command
+    #[wasm_bindgen(js_name = signHash)]
+    pub fn sign_hash(&self, hash: &str) -> Result<String, JsError> {
+        Ok(self.0.sign(&base64url_decode(hash)?)?.to_hex())
+    }
+
+    /// Verify a hex signature of a message
+    pub fn verify(&self, message: &[u8], signature: &str) -> Result<bool, JsError> {
+        Ok(self.0.verify(message, signature)?)
+    }
+}
+
+/// Blake2b-256 hash of `data`, base64url encoded
+#[wasm_bindgen]
+pub fn hash(data: &[u8]) -> String {
+    crate::crypto::hash(data)
+}
+
+/// Prepare and sign an execution command from an [`ExecRequest`] JSON string,
+/// returning the command JSON
+#[wasm_bindgen(js_name = prepareExec)]
+pub fn prepare_exec(request: &str) -> Result<String, JsError> {
+    let request: ExecRequest = serde_json::from_str(request)?;
+    Ok(serde_json::to_string(&request.prepare()?)?)
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "crypto")]
+
 use kadena::crypto::*;
 
 #[test]
@@ -7,10 +9,345 @@ fn test_keypair_generation_and_restoration() {
     assert_eq!(keypair.public_key(), restored.public_key());
 }
 
+#[test]
+fn test_generation_with_seeded_rng() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let first = PactKeypair::generate_with_rng(&mut rng);
+    let second = PactKeypair::generate_with_rng(&mut rng);
+    assert_ne!(first.public_key(), second.public_key());
+
+    let replayed = PactKeypair::generate_with_rng(&mut StdRng::seed_from_u64(7));
+    assert_eq!(replayed.secret_key(), first.secret_key());
+    assert_eq!(
+        PactKeypair::from_secret_key(replayed.secret_key())
+            .unwrap()
+            .public_key(),
+        first.public_key()
+    );
+}
+
 #[test]
 fn test_signing_and_verification() {
     let keypair = PactKeypair::generate();
     let msg = b"test message";
     let signature = keypair.sign(msg).unwrap();
-    assert!(keypair.verify(msg, &signature).unwrap());
+    assert!(keypair.verify(msg, &signature.to_hex()).unwrap());
+}
+
+#[test]
+fn test_keypair_keys_agree_with_hex_forms() {
+    let keypair = PactKeypair::generate();
+    let restored = PactKeypair::from_secret_key(keypair.secret_key()).unwrap();
+    let msg = b"batch payout";
+    // Ed25519 signatures are deterministic
+    assert_eq!(
+        keypair.sign(msg).unwrap().to_hex(),
+        restored.clone().sign(msg).unwrap().to_hex()
+    );
+    assert_eq!(
+        bin_to_hex(&keypair.public_key_bytes().unwrap()),
+        keypair.public_key()
+    );
+
+    let signature = restored.sign(msg).unwrap().to_hex();
+    assert!(!PactKeypair::generate().verify(msg, &signature).unwrap());
+    assert!(keypair.verify(msg, "not hex").is_err());
+    assert!(keypair.verify(msg, "abcd").is_err());
+}
+
+#[test]
+fn test_raw_byte_verification() {
+    let keypair = PactKeypair::generate();
+    let other = PactKeypair::generate();
+    let msg = b"test message";
+    let signature = keypair.sign(msg).unwrap().to_bytes();
+
+    let public_key = keypair.public_key_bytes().unwrap();
+    assert_eq!(bin_to_hex(&public_key), keypair.public_key());
+    assert!(verify_signature_bytes(msg, &signature, &public_key).unwrap());
+    assert!(keypair.verify_bytes(msg, &signature).unwrap());
+    assert!(!other.verify_bytes(msg, &signature).unwrap());
+    assert_eq!(
+        verify_signature_bytes(msg, &signature, &public_key).unwrap(),
+        verify_signature(msg, &bin_to_hex(&signature), keypair.public_key()).unwrap()
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_signature_encodings() {
+    let keypair = PactKeypair::generate();
+    let signature = keypair.sign(b"test message").unwrap();
+
+    assert_eq!(
+        hex_to_bin(&signature.to_hex()).unwrap(),
+        signature.as_bytes()
+    );
+    assert_eq!(
+        base64url_decode(&signature.to_base64url()).unwrap(),
+        signature.as_bytes()
+    );
+    assert_eq!(signature.to_string(), signature.to_hex());
+    assert_eq!(signature.as_string(), signature.to_hex());
+    assert_eq!(String::from(signature), signature.to_hex());
+
+    assert_eq!(signature.to_hex().parse::<Signature>().unwrap(), signature);
+    assert_eq!(
+        serde_json::to_value(signature).unwrap(),
+        serde_json::json!(signature.to_hex())
+    );
+    assert!(Signature::from_hex("abcd").is_err());
+    assert!(Signature::try_from(&[0u8; 32][..]).is_err());
+}
+
+#[cfg(feature = "keystore")]
+mod keystore_tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_slip10_vector() {
+        // SLIP-0010 ED25519 test vector 1, chain m/0H
+        let seed = hex_to_bin("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            bin_to_hex(&slip10_derive(&seed, &[])),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            bin_to_hex(&slip10_derive(&seed, &[0])),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+    }
+
+    #[test]
+    fn test_encrypted_mnemonic_round_trip() {
+        let encrypted = EncryptedMnemonic::encrypt(PHRASE, "correct horse").unwrap();
+        let restored: EncryptedMnemonic = encrypted.to_string().parse().unwrap();
+
+        assert_eq!(restored.decrypt("correct horse").unwrap(), PHRASE);
+        assert!(matches!(
+            restored.decrypt("wrong"),
+            Err(CryptoError::DecryptionError)
+        ));
+        assert_eq!(
+            restored.keypair("correct horse", 3).unwrap().public_key(),
+            keypair_from_mnemonic(PHRASE, 3).unwrap().public_key()
+        );
+    }
+
+    #[test]
+    fn test_chainweaver_derivation_vector() {
+        // ed25519-bip32 V2 test vector D1, stored without password: extended
+        // secret key, public key, chain code
+        let root: ChainweaverRootKey = concat!(
+            "f8a29231ee38d6c5bf715d5bac21c750577aa3798b22d79d65bf97d6fadea15a",
+            "dcd1ee1abdf78bd4be64731a12deb94d3671784112eb6f364b871851fd1c9a24",
+            "86ae05cac74c490e097646814c76a9ac813c470afb6ce6429bf836b1bf517878",
+            "7384db9ad6003bbd08b3b1ddc0d07a597293ff85e961bf252b331262eddfad0d",
+        )
+        .parse()
+        .unwrap();
+
+        // Derived at 0', then signing "Hello World"
+        let keypair = root.keypair("", 0).unwrap();
+        assert_eq!(
+            keypair.public_key(),
+            "9c99845ae0a60881decf7874b94ad02e7540628b588f8b4305d668cc53986978"
+        );
+        let signature = keypair.sign(b"Hello World");
+        assert_eq!(
+            signature.to_hex(),
+            "90194d57cde4fdadd01eb7cf161780c277e129fc7135b97779a3268837e4cd2e\
+             9444b9bb91c0e84d23bba870df3c4bda91a110ef735638fa7a34ea2046d4be04"
+        );
+        assert!(
+            verify_signature(b"Hello World", &signature.to_hex(), keypair.public_key()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_chainweaver_root_key_passwords() {
+        let plain = ChainweaverRootKey::from_mnemonic(PHRASE, "").unwrap();
+        let encrypted = ChainweaverRootKey::from_mnemonic(PHRASE, "correct horse").unwrap();
+
+        // Only the secret key is encrypted
+        assert_ne!(plain.as_bytes()[..64], encrypted.as_bytes()[..64]);
+        assert_eq!(plain.as_bytes()[64..], encrypted.as_bytes()[64..]);
+        assert_eq!(
+            encrypted.keypair("correct horse", 1).unwrap().public_key(),
+            plain.keypair("", 1).unwrap().public_key()
+        );
+        assert!(matches!(
+            encrypted.keypair("wrong", 1),
+            Err(CryptoError::DecryptionError)
+        ));
+
+        let changed = encrypted
+            .change_password("correct horse", "battery")
+            .unwrap();
+        assert_eq!(
+            changed.keypair("battery", 1).unwrap().public_key(),
+            plain.keypair("", 1).unwrap().public_key()
+        );
+        assert_eq!(changed.change_password("battery", "").unwrap(), plain);
+
+        // Chainweaver keys differ from the SLIP-10 keys of the same phrase
+        assert_ne!(
+            plain.keypair("", 0).unwrap().public_key(),
+            keypair_from_mnemonic(PHRASE, 0).unwrap().public_key()
+        );
+        assert!("abcd".parse::<ChainweaverRootKey>().is_err());
+    }
+
+    #[test]
+    fn test_invalid_mnemonic_rejected() {
+        assert!(matches!(
+            EncryptedMnemonic::encrypt("not a mnemonic", "password"),
+            Err(CryptoError::InvalidMnemonic(_))
+        ));
+    }
+}
+
+#[cfg(feature = "shamir")]
+mod shamir_tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_recover_through_strings() {
+        let keypair = PactKeypair::generate();
+        let shares = split_secret(&keypair, 3, 2).unwrap();
+
+        let serialized: Vec<String> = shares.iter().map(ToString::to_string).collect();
+        let parsed: Vec<SecretShare> = [&serialized[0], &serialized[2]]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let recovered = recover_secret(&parsed).unwrap();
+        assert_eq!(recovered.secret_key(), keypair.secret_key());
+    }
+
+    #[test]
+    fn test_share_integrity_checks() {
+        let keypair = PactKeypair::generate();
+        let shares = split_secret(&keypair, 3, 2).unwrap();
+
+        let mut corrupted = shares[0].to_string();
+        corrupted.replace_range(20..21, "x");
+        assert!(corrupted.parse::<SecretShare>().is_err());
+
+        assert!(matches!(
+            recover_secret(&shares[..1]),
+            Err(CryptoError::InvalidShare(_))
+        ));
+
+        let other = split_secret(&PactKeypair::generate(), 3, 2).unwrap();
+        assert!(recover_secret(&[shares[0].clone(), other[1].clone()]).is_err());
+    }
+}
+
+#[cfg(feature = "kms")]
+mod kms_tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use kadena::pact::{Cap, Cmd, Meta};
+
+    /// A KMS returning base64 signatures and DER public keys, like AWS KMS does
+    struct MockKms(PactKeypair);
+
+    impl KmsClient for MockKms {
+        async fn sign(&self, _key_id: &str, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+            Ok(STANDARD.encode(self.0.sign(msg)?.as_bytes()).into_bytes())
+        }
+
+        async fn public_key(&self, _key_id: &str) -> Result<Vec<u8>, CryptoError> {
+            let mut spki = hex_to_bin("302a300506032b6570032100")?;
+            spki.extend(hex_to_bin(self.0.public_key())?);
+            Ok(spki)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kms_signer_signs_commands() {
+        let keypair = PactKeypair::generate();
+        let signer = KmsSigner::new(MockKms(keypair.clone()), "alias/test")
+            .await
+            .unwrap();
+        assert_eq!(AsyncSigner::public_key(&signer), keypair.public_key());
+
+        let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+        let cmd = Cmd::prepare_exec_async(
+            &[(&signer, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            "(+ 1 2)",
+            None,
+            meta,
+            Some("testnet04".to_string()),
+        )
+        .await
+        .unwrap();
+
+        let hash = base64url_decode(&cmd.hash).unwrap();
+        assert!(keypair.verify(&hash, &cmd.sigs[0].sig).unwrap());
+    }
+
+    #[test]
+    fn test_normalize_rejects_unknown_encodings() {
+        assert!(matches!(
+            normalize_signature(&[0u8; 70]),
+            Err(CryptoError::KmsError(_))
+        ));
+        assert!(normalize_public_key(&[1u8; 32]).is_ok());
+    }
+}
+
+#[cfg(feature = "merkle")]
+mod merkle_tests {
+    use kadena::crypto::{merkle_leaf, merkle_node, MerkleProofObject, MerkleSide, MerkleTree};
+
+    #[test]
+    fn test_merkle_tree_shape_and_proofs() {
+        let leaves: Vec<_> = (0u8..5).map(|i| merkle_leaf(&[i])).collect();
+        let tree = MerkleTree::new(leaves.clone()).unwrap();
+
+        // Five leaves split into a full subtree of four and a single leaf
+        let left = merkle_node(
+            &merkle_node(&leaves[0], &leaves[1]),
+            &merkle_node(&leaves[2], &leaves[3]),
+        );
+        assert_eq!(tree.root(), merkle_node(&left, &leaves[4]));
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.proof(index).unwrap();
+            let decoded = MerkleProofObject::decode(&proof.encode()).unwrap();
+            assert_eq!(decoded, proof);
+            assert!(decoded.verify(*leaf, &tree.root()));
+        }
+        assert_eq!(tree.proof(4).unwrap().steps.len(), 1);
+        assert!(tree.proof(5).is_none());
+        assert!(MerkleTree::new(Vec::new()).is_err());
+        assert!(MerkleProofObject::decode(&[0, 0, 0, 1, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_proof_object_layout() {
+        // Step count as big-endian u32, leaf position as big-endian u64
+        let mut bytes = vec![0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0x01];
+        bytes.extend_from_slice(&[7; 32]);
+        let proof = MerkleProofObject::decode(&bytes).unwrap();
+        assert_eq!(proof.position, (1 << 32) + 2);
+        assert_eq!(proof.steps, vec![(MerkleSide::Right, [7; 32])]);
+        assert_eq!(proof.encode(), bytes);
+
+        assert!(MerkleProofObject::decode(&bytes[..44]).is_err());
+        assert!(MerkleProofObject::decode(&bytes[4..]).is_err());
+        assert!(MerkleProofObject::decode(&[0; 12])
+            .unwrap()
+            .steps
+            .is_empty());
+    }
 }
@@ -1,8 +1,46 @@
-use kadena::{ApiClient, ApiConfig, Cmd, FetchError};
+#![cfg(feature = "fetch")]
+
+use kadena::{
+    base64url_encode, ApiClient, ApiConfig, ApiRoute, BlockHeader, ChainSelector, ChainwebClient,
+    ChainwebDataClient, Cmd, EndpointKind, FetchError, FileStorage, HttpMethod, HttpRequest,
+    HttpResponse, InProcessTransport, LocalOptions, NodePool, QueueStatus, RequestKey,
+    SelectionStrategy, SpvOptions, SubmitGuard, TransferDirection, TxQueue,
+};
 use serde_json::json;
-use wiremock::matchers::{method, path};
+use std::time::Duration;
+use wiremock::matchers::{header, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
+const REQUEST_KEY: &str = "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c";
+
+/// A header whose hash matches its contents, `nonce` telling forks apart
+fn header_json(chain_id: u32, height: u64, parent: &str, nonce: u64) -> serde_json::Value {
+    with_computed_hash(json!({
+        "featureFlags": 0,
+        "creationTime": 1_700_000_000_000_000u64 + height,
+        "parent": parent,
+        "adjacents": {"5": base64url_encode(&[5; 32]), "10": base64url_encode(&[10; 32]), "15": base64url_encode(&[15; 32])},
+        "target": base64url_encode(&[0xff; 32]),
+        "payloadHash": base64url_encode(&[0; 32]),
+        "chainId": chain_id,
+        "weight": base64url_encode(&[0; 32]),
+        "height": height,
+        "chainwebVersion": "testnet04",
+        "epochStart": 1_700_000_000_000_000u64,
+        "nonce": nonce.to_string(),
+        "hash": base64url_encode(&[0; 32])
+    }))
+}
+
+/// Recompute the hash of a header, e.g. after changing its payload
+fn with_computed_hash(mut header: serde_json::Value) -> serde_json::Value {
+    header["hash"] = json!(BlockHeader::from_json(&header)
+        .unwrap()
+        .compute_hash()
+        .unwrap());
+    header
+}
+
 #[tokio::test]
 async fn test_local_execution() {
     let mock_server = MockServer::start().await;
@@ -47,6 +85,229 @@ async fn test_send_transaction() {
     assert_eq!(result, json!({"requestKeys": ["test_key"]}));
 }
 
+#[tokio::test]
+async fn test_authenticated_requests() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(header("Authorization", "Bearer s3cret"))
+        .and(header("X-API-Key", "key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "bearer"})))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/pact/api/v1/local"))
+        // base64 of "ops:hunter2"
+        .and(header("Authorization", "Basic b3BzOmh1bnRlcjI="))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "basic"})))
+        .mount(&mock_server)
+        .await;
+
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+
+    let bearer = ApiConfig::new(&mock_server.uri(), "testnet04", "0")
+        .with_api_key("key")
+        .with_bearer_token("s3cret");
+    let debug = format!("{:?}", bearer);
+    assert!(!debug.contains("s3cret") && !debug.contains("\"key\""));
+    let result = ApiClient::new(bearer).local(&cmd).await.unwrap();
+    assert_eq!(result, json!({"result": "bearer"}));
+
+    let basic = ApiConfig::new(&mock_server.uri(), "testnet04", "1")
+        .with_basic_auth("ops", Some("hunter2"));
+    let result = ApiClient::new(basic).local(&cmd).await.unwrap();
+    assert_eq!(result, json!({"result": "basic"}));
+
+    let anonymous = ApiConfig::new(&mock_server.uri(), "testnet04", "0");
+    assert!(ApiClient::new(anonymous).local(&cmd).await.is_err());
+}
+
+#[tokio::test]
+async fn test_http2_prior_knowledge() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "h2"})))
+        .mount(&mock_server)
+        .await;
+
+    let config = ApiConfig::new(&mock_server.uri(), "testnet04", "0")
+        .with_http2_prior_knowledge()
+        .with_http2_adaptive_window(true)
+        .with_tcp_keepalive(Duration::from_secs(15));
+    assert_eq!(config.tcp_keepalive, Some(Duration::from_secs(15)));
+
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+    // Prior-knowledge requests only succeed against servers speaking HTTP/2
+    let client = ApiClient::new(config);
+    assert_eq!(client.local(&cmd).await.unwrap(), json!({"result": "h2"}));
+}
+
+#[tokio::test]
+async fn test_in_process_transport() {
+    let transport = InProcessTransport::new(|request: HttpRequest| {
+        assert_eq!(request.method, HttpMethod::Post);
+        assert_eq!(request.header("x-api-key"), Some("key"));
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        match request.path() {
+            "/chainweb/0.0/development/chain/0/pact/api/v1/local" => {
+                HttpResponse::json(200, &json!({"echo": body["hash"]}))
+            }
+            _ => HttpResponse::new(404, "not found"),
+        }
+    });
+    let config = ApiConfig::new("http://node", "development", "0").with_api_key("key");
+    let client = ApiClient::with_transport(config, transport);
+
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+    assert_eq!(
+        client.local(&cmd).await.unwrap(),
+        json!({"echo": "test_hash"})
+    );
+    assert!(matches!(
+        client.send(&cmd).await,
+        Err(FetchError::ApiError(body)) if body == "not found"
+    ));
+}
+
+/// Records every log message, so tests can check what would be written
+struct CapturingLogger(std::sync::Mutex<Vec<String>>);
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger(std::sync::Mutex::new(Vec::new()));
+
+#[tokio::test]
+async fn test_payload_logging_redacts_secrets() {
+    use kadena::fetch::{redact_payload, PayloadLogging, REDACTED};
+    use kadena::pact::SignaturePayload;
+
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let respond = |_: HttpRequest| HttpResponse::json(200, &json!({}));
+    let cmd = Cmd {
+        hash: "redaction_hash".to_string(),
+        sigs: vec![SignaturePayload {
+            sig: "secret_signature".to_string(),
+        }],
+        cmd: json!({"payload": {"exec": {"code": "(+ 1 2)", "data": {"password": "hunter2"}}}})
+            .to_string(),
+    };
+
+    let client = ApiClient::with_transport(
+        ApiConfig::new("http://node", "development", "0"),
+        InProcessTransport::new(respond),
+    );
+    client.local(&cmd).await.unwrap();
+    client.send(&cmd).await.unwrap();
+    let logged: Vec<String> = LOGGER
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.contains("request to http://node"))
+        .cloned()
+        .collect();
+    assert_eq!(logged.len(), 2);
+    assert!(logged.iter().all(|line| line.contains("(+ 1 2)")));
+    assert!(logged
+        .iter()
+        .all(|line| !line.contains("hunter2") && !line.contains("secret_signature")));
+
+    let unsafe_client = ApiClient::with_transport(
+        ApiConfig::new("http://unsafe-node", "development", "0").with_unsafe_payload_logging(),
+        InProcessTransport::new(respond),
+    );
+    unsafe_client.local(&cmd).await.unwrap();
+    assert!(LOGGER
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|line| line.contains("http://unsafe-node") && line.contains("hunter2")));
+
+    let batch =
+        json!({"cmds": [{"hash": "h", "sigs": [{"sig": "s"}, {"sig": null}], "cmd": "not json"}]});
+    let redacted = redact_payload(&batch);
+    assert_eq!(
+        redacted["cmds"][0]["sigs"],
+        json!([{"sig": REDACTED}, {"sig": null}])
+    );
+    assert_eq!(redacted["cmds"][0]["cmd"], "not json");
+    assert_eq!(PayloadLogging::Unsafe.apply(&batch), batch);
+}
+
+#[cfg(all(unix, feature = "runtime-tokio"))]
+#[tokio::test]
+async fn test_unix_socket_transport() {
+    use kadena::UnixSocketTransport;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let dir = std::env::temp_dir().join(format!("kadena-uds-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let socket = dir.join("node.sock");
+    let _ = std::fs::remove_file(&socket);
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 4096];
+        let n = stream.read(&mut request).await.unwrap();
+        let request = String::from_utf8_lossy(&request[..n]).into_owned();
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n\
+                  8\r\n{\"status\r\n7\r\n\": \"ok\"\r\n1\r\n}\r\n0\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        request
+    });
+
+    let client = ApiClient::with_transport(
+        ApiConfig::new("http://localhost", "testnet04", "0"),
+        UnixSocketTransport::new(&socket),
+    );
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+    assert_eq!(client.local(&cmd).await.unwrap(), json!({"status": "ok"}));
+
+    let request = server.await.unwrap();
+    assert!(
+        request.starts_with("POST /chainweb/0.0/testnet04/chain/0/pact/api/v1/local HTTP/1.1\r\n")
+    );
+    assert!(request.contains("Host: localhost\r\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[tokio::test]
 async fn test_api_error_handling() {
     let mock_server = MockServer::start().await;
@@ -67,3 +328,2749 @@ async fn test_api_error_handling() {
     let result = client.local(&cmd).await;
     assert!(matches!(result, Err(FetchError::ApiError(_))));
 }
+
+#[tokio::test]
+async fn test_html_error_pages() {
+    let mock_server = MockServer::start().await;
+    let page = format!(
+        "<!DOCTYPE html><html><body><h1>502 Bad Gateway</h1>{}</body></html>",
+        "x".repeat(1000)
+    );
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(502).set_body_raw(page.clone(), "text/html"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("maintenance"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(&mock_server)
+        .await;
+
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    match client.local(&cmd).await {
+        Err(FetchError::UnexpectedResponse {
+            status,
+            content_type,
+            body,
+        }) => {
+            assert_eq!(status, 502);
+            assert_eq!(content_type.as_deref(), Some("text/html"));
+            assert!(body.contains("502 Bad Gateway") && body.len() < 300);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "1"));
+    assert!(matches!(
+        client.local(&cmd).await,
+        Err(FetchError::UnexpectedResponse { status: 200, body, .. }) if body == "maintenance"
+    ));
+
+    let chainweb = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    assert!(matches!(
+        chainweb.cut().await,
+        Err(FetchError::UnexpectedResponse { status: 200, .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_total_balance_across_chains() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"balance": 1.5, "account": "k:abc"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"balance": {"decimal": "2.000000000001"}}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/2/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "failure", "error": {"message": "with-read: row not found: k:abc"}}
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04")
+        .with_chain_ids(["0", "1", "2"])
+        .with_max_concurrency(2);
+    let balances = client.total_balance("k:abc").await.unwrap();
+
+    assert_eq!(balances.balances.len(), 2);
+    assert_eq!(balances.balances[0].chain_id, "0");
+    assert_eq!(balances.balances[1].chain_id, "1");
+    assert_eq!(balances.total.to_string(), "3.500000000001");
+}
+
+#[tokio::test]
+async fn test_round_robin_chain_selection() {
+    let client = ChainwebClient::new("http://localhost", "testnet04").with_chain_ids(["0", "1"]);
+    let selector = ChainSelector::new(SelectionStrategy::RoundRobin);
+
+    assert_eq!(selector.select(&client, "k:abc").await.unwrap(), "0");
+    assert_eq!(selector.select(&client, "k:abc").await.unwrap(), "1");
+    assert_eq!(selector.select(&client, "k:abc").await.unwrap(), "0");
+}
+
+#[tokio::test]
+async fn test_least_congested_chain_selection() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/mempool/getPending"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "hashes": ["a", "b", "c"], "highwaterMark": [0, 0]
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/mempool/getPending"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "hashes": ["d"], "highwaterMark": [0, 0]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04").with_chain_ids(["0", "1"]);
+    let selector = ChainSelector::new(SelectionStrategy::LeastCongested);
+
+    assert_eq!(selector.select(&client, "k:abc").await.unwrap(), "1");
+}
+
+#[tokio::test]
+async fn test_send_transfer_on_selected_chain() {
+    use kadena::crypto::PactKeypair;
+    use kadena::pact::{Meta, Transfer};
+    use rust_decimal::Decimal;
+
+    let mock_server = MockServer::start().await;
+    for chain in ["1", "2"] {
+        Mock::given(method("POST"))
+            .and(path(format!(
+                "/chainweb/0.0/testnet04/chain/{}/pact/api/v1/send",
+                chain
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [chain]})))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let keypair = PactKeypair::generate();
+    let transfer = Transfer::new("k:abc", "k:def", Decimal::ONE);
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04").with_chain_ids(["1", "2"]);
+
+    // Without a selector the chain of the metadata is used
+    let sent = client
+        .send_transfer(&transfer, &[&keypair], Meta::new("2", "k:abc"))
+        .await
+        .unwrap();
+    assert_eq!(sent.chain_id, "2");
+
+    let client = client.with_chain_selector(ChainSelector::new(SelectionStrategy::RoundRobin));
+    for expected in ["1", "2"] {
+        let sent = client
+            .send_transfer(&transfer, &[&keypair], Meta::new("0", "k:abc"))
+            .await
+            .unwrap();
+        assert_eq!(sent.chain_id, expected);
+        assert_eq!(sent.response["requestKeys"][0], expected);
+        let payload = sent.cmd.payload().unwrap();
+        assert_eq!(payload.meta.chain_id, expected);
+        assert_eq!(payload.network_id.as_deref(), Some("testnet04"));
+    }
+}
+
+#[tokio::test]
+async fn test_await_spv_retries_until_available() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/spv"))
+        .respond_with(ResponseTemplate::new(400).set_body_string(
+            "SPV target not reachable: target chain not reachable. Chainweb instance is too young",
+        ))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/spv"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!("proof")))
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let options = SpvOptions::default().with_interval(Duration::from_millis(10));
+    let proof = client.await_spv(REQUEST_KEY, "1", &options).await.unwrap();
+    assert_eq!(proof, "proof");
+}
+
+#[tokio::test]
+async fn test_await_spv_times_out() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/spv"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("SPV target not reachable"))
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let options = SpvOptions::default()
+        .with_timeout(Duration::from_millis(50))
+        .with_interval(Duration::from_millis(20));
+    let result = client.await_spv(REQUEST_KEY, "1", &options).await;
+    assert!(matches!(result, Err(FetchError::Timeout(_))));
+}
+
+#[tokio::test]
+async fn test_long_running_operations_stop_on_cancellation() {
+    use kadena::fetch::{CancellationToken, TxTracker};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/spv"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("SPV target not reachable"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+
+    let token = CancellationToken::new();
+    let shutdown = async {
+        kadena::fetch::sleep(Duration::from_millis(50)).await;
+        token.cancel();
+    };
+
+    let options = SpvOptions::default()
+        .with_interval(Duration::from_millis(10))
+        .with_cancellation(token.clone());
+    let mut tracker = TxTracker::new(&client)
+        .with_interval(Duration::from_millis(10))
+        .with_cancellation(token.clone());
+    tracker.track(REQUEST_KEY).unwrap();
+
+    let (spv, tracked, _) = futures::join!(
+        client.await_spv(REQUEST_KEY, "1", &options),
+        tracker.run(),
+        shutdown
+    );
+    assert!(matches!(spv, Err(FetchError::Cancelled)));
+    assert_eq!(spv.unwrap_err().error_code(), "fetch.cancelled");
+    tracked.unwrap();
+    assert_eq!(tracker.pending(), [REQUEST_KEY]);
+
+    // Cancelled tokens stop operations before they start
+    let listen = token.run(client.listen(REQUEST_KEY)).await;
+    assert!(matches!(listen, Err(FetchError::Cancelled)));
+}
+
+#[cfg(feature = "runtime-agnostic")]
+#[test]
+fn test_await_spv_without_tokio() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let attempts = AtomicUsize::new(0);
+    let transport = InProcessTransport::new(move |_: HttpRequest| {
+        if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+            HttpResponse::new(400, "SPV target not reachable")
+        } else {
+            HttpResponse::json(200, &json!("proof"))
+        }
+    });
+    let client =
+        ApiClient::with_transport(ApiConfig::new("http://node", "testnet04", "0"), transport);
+    let options = SpvOptions::default().with_interval(Duration::from_millis(10));
+
+    // No tokio runtime is running here, so any tokio timer would panic
+    let proof = futures::executor::block_on(client.await_spv(REQUEST_KEY, "1", &options)).unwrap();
+    assert_eq!(proof, "proof");
+}
+
+#[tokio::test]
+async fn test_history_follows_pagination() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/txs/account/k:abc"))
+        .and(query_param("next", "cursor-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "amount": "0.5", "blockTime": "2024-01-01T00:00:00Z", "chain": 1,
+            "fromAccount": "k:abc", "toAccount": "k:def", "height": 90,
+            "requestKey": "rk2", "token": "coin", "crossChainAccount": null, "crossChainId": null
+        }])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/txs/account/k:abc"))
+        .and(query_param("chain", "1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Chainweb-Next", "cursor-1")
+                .set_body_json(json!([{
+                    "amount": 2.25, "blockTime": "2024-01-02T00:00:00Z", "chain": 1,
+                    "fromAccount": "k:def", "toAccount": "k:abc", "height": 100,
+                    "requestKey": "rk1", "token": "coin", "crossChainAccount": null, "crossChainId": null
+                }])),
+        )
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebDataClient::new(&mock_server.uri());
+    let history = client.history_all("k:abc", Some("1")).await.unwrap();
+
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].direction, TransferDirection::Incoming);
+    assert_eq!(history[0].counterparty, "k:def");
+    assert_eq!(history[0].amount.to_string(), "2.25");
+    assert_eq!(history[0].chain_id, "1");
+    assert_eq!(history[1].direction, TransferDirection::Outgoing);
+    assert_eq!(history[1].request_key, "rk2");
+}
+
+#[tokio::test]
+async fn test_poll_exposes_block_metadata() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            REQUEST_KEY: {
+                "reqKey": REQUEST_KEY,
+                "txId": 42,
+                "result": {"status": "success", "data": "Write succeeded"},
+                "gas": 518,
+                "logs": "logs-hash",
+                "continuation": null,
+                "events": [],
+                "metaData": {
+                    "blockHeight": 4000000,
+                    "blockTime": 1700000000123456u64,
+                    "blockHash": "block-hash",
+                    "prevBlockHash": "prev-hash",
+                    "publicMeta": {"chainId": "0"}
+                }
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    // Request keys are accepted hex-encoded too
+    let hex_key = RequestKey::parse(REQUEST_KEY).unwrap().to_hex();
+    let results = client.poll(&[&hex_key]).await.unwrap();
+
+    let result = &results[REQUEST_KEY];
+    assert!(result.is_success());
+    assert_eq!(result.tx_id, Some(42));
+    assert_eq!(result.block_height(), Some(4000000));
+    assert_eq!(result.block_hash(), Some("block-hash"));
+
+    let meta = result.meta_data.as_ref().unwrap();
+    assert_eq!(meta.prev_block_hash, "prev-hash");
+    assert_eq!(meta.block_time_secs(), 1700000000);
+}
+
+#[tokio::test]
+async fn test_preflight_gas_report() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(query_param("preflight", "true"))
+        .and(query_param("signatureVerification", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "preflightResult": {
+                "reqKey": "rk1",
+                "result": {"status": "success", "data": 3},
+                "gas": 75,
+                "metaData": {
+                    "blockHeight": 100,
+                    "blockTime": 1700000000000000u64,
+                    "prevBlockHash": "prev-hash",
+                    "gasLogs": [["GUnreduced", 5], ["read", 20], ["write", 30], ["read", 20]]
+                }
+            },
+            "preflightWarnings": ["Using deprecated native"]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let preflight = client.preflight(&cmd).await.unwrap();
+    assert!(preflight.result.is_success());
+    assert_eq!(preflight.warnings, vec!["Using deprecated native"]);
+
+    let report = preflight.gas_report();
+    assert_eq!(report.total, 75);
+    assert_eq!(report.by_operation()[0], ("read".to_string(), 40));
+    assert_eq!(report.suggested_gas_limit(20), 90);
+}
+
+#[tokio::test]
+async fn test_local_gas_report() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(query_param("preflight", "false"))
+        .and(query_param("gasLogs", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "reqKey": "rk1",
+            "result": {"status": "success", "data": 3},
+            "gas": 12,
+            "gasLogs": [{"name": "+", "amount": 1}, {"name": "enforce-keyset", "amount": 11}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let cmd = Cmd {
+        hash: "test_hash".to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let report = client
+        .local_gas_report(&cmd, LocalOptions::default())
+        .await
+        .unwrap();
+    assert!(report.has_breakdown());
+    assert_eq!(report.total, 12);
+    assert_eq!(report.by_operation()[0], ("enforce-keyset".to_string(), 11));
+}
+
+#[tokio::test]
+async fn test_send_guarded_refuses_resubmission() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/send"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [REQUEST_KEY]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let cmd = Cmd {
+        hash: REQUEST_KEY.to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+
+    let path =
+        std::env::temp_dir().join(format!("kadena-submit-guard-{}.json", std::process::id()));
+    let guard = SubmitGuard::with_persistence(Duration::from_secs(3600), &path).unwrap();
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+
+    client.send_guarded(&cmd, &guard).await.unwrap();
+    assert!(matches!(
+        client.send_guarded(&cmd, &guard).await,
+        Err(FetchError::DuplicateSubmission(_))
+    ));
+
+    // The submission is remembered across restarts
+    let reloaded = SubmitGuard::with_persistence(Duration::from_secs(3600), &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(reloaded.is_duplicate(REQUEST_KEY));
+}
+
+#[tokio::test]
+async fn test_tx_queue_retries_and_resumes() {
+    let mock_server = MockServer::start().await;
+    let api = "/chainweb/0.0/testnet04/chain/1/pact/api/v1";
+
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [REQUEST_KEY]})),
+        )
+        .mount(&mock_server)
+        .await;
+    // The failed submission is polled, and not found, before it is retried
+    Mock::given(method("POST"))
+        .and(path(format!("{}/poll", api)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/poll", api)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            REQUEST_KEY: {
+                "reqKey": REQUEST_KEY,
+                "result": {"status": "success", "data": "Write succeeded"},
+                "gas": 600
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let path = std::env::temp_dir().join(format!("kadena-tx-queue-{}.json", std::process::id()));
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let cmd = Cmd {
+        hash: REQUEST_KEY.to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+
+    let mut queue = TxQueue::open(FileStorage::new(&path)).unwrap();
+    queue.enqueue(cmd, "1").unwrap();
+
+    let report = queue.process(&client).await.unwrap();
+    assert_eq!(report.retried, 1);
+    drop(queue);
+
+    // A restarted process resumes the pending entry
+    let mut queue = TxQueue::open(FileStorage::new(&path)).unwrap();
+    assert_eq!(queue.entries()[0].attempts, 1);
+
+    let report = queue.process(&client).await.unwrap();
+    assert_eq!(report.submitted, 1);
+    assert_eq!(report.confirmed, 1);
+    assert!(queue.is_settled());
+
+    let settled = queue.drain_settled().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(settled[0].status, QueueStatus::Confirmed { .. }));
+}
+
+#[tokio::test]
+async fn test_tx_queue_polls_before_failing() {
+    use kadena::crypto::PactKeypair;
+    use kadena::fetch::MemoryStorage;
+    use kadena::pact::Meta;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+    let api = "/chainweb/0.0/testnet04/chain/1/pact/api/v1";
+    let keypair = PactKeypair::generate();
+    let cmd = |nonce: &str, creation_time: u64| {
+        let meta = Meta::new("1", "sender").with_creation_time(creation_time);
+        Cmd::prepare_exec(
+            &[(&keypair, vec![])],
+            vec![],
+            Some(nonce),
+            "(+ 1 2)",
+            None,
+            meta,
+            Some("testnet04".to_string()),
+        )
+        .unwrap()
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // Mined although its submission timed out, seen before, and expired
+    let (lost, duplicate, expired) = (cmd("lost", now), cmd("duplicate", now), cmd("expired", 0));
+
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .and(body_string_contains(lost.hash.clone()))
+        .respond_with(ResponseTemplate::new(504).set_body_string("gateway timeout"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .and(body_string_contains(duplicate.hash.clone()))
+        .respond_with(ResponseTemplate::new(400).set_body_string(format!(
+            "Validation failed for hash \"{}\": Transaction already exists on chain",
+            duplicate.hash
+        )))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"requestKeys": []})))
+        .mount(&mock_server)
+        .await;
+    let mined = json!({
+        lost.hash.clone(): {
+            "reqKey": lost.hash,
+            "result": {"status": "success", "data": 3},
+            "gas": 600
+        }
+    });
+    Mock::given(method("POST"))
+        .and(path(format!("{}/poll", api)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mined))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let mut queue = TxQueue::open(MemoryStorage::default())
+        .unwrap()
+        .with_max_attempts(1);
+    for cmd in [&lost, &duplicate, &expired] {
+        queue.enqueue(cmd.clone(), "1").unwrap();
+    }
+
+    let report = queue.process(&client).await.unwrap();
+    assert_eq!(report.submitted, 2);
+    assert_eq!(report.retried, 0);
+    assert_eq!(report.confirmed, 1);
+    assert_eq!(report.failed, 1);
+    assert!(matches!(
+        queue.get(&lost.hash).unwrap().status,
+        QueueStatus::Confirmed { .. }
+    ));
+    assert_eq!(
+        queue.get(&duplicate.hash).unwrap().status,
+        QueueStatus::Submitted
+    );
+    match &queue.get(&expired.hash).unwrap().status {
+        QueueStatus::Failed { error } => assert!(error.contains("expired")),
+        status => panic!("unexpected status {:?}", status),
+    }
+}
+
+#[tokio::test]
+async fn test_chainweb_client_sends_credentials() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .and(header("Authorization", "Basic b3BzOnB3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"height": 42})))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/header/updates"))
+        .and(header("Authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("", "text/event-stream"))
+        .mount(&mock_server)
+        .await;
+
+    let basic =
+        ChainwebClient::new(&mock_server.uri(), "testnet04").with_basic_auth("ops", Some("pw"));
+    assert_eq!(basic.cut().await.unwrap()["height"], 42);
+    assert!(basic.header_updates().await.is_err());
+
+    let bearer = ChainwebClient::new(&mock_server.uri(), "testnet04").with_bearer_token("token");
+    assert!(bearer.header_updates().await.is_ok());
+    assert!(bearer.cut().await.is_err());
+}
+
+#[tokio::test]
+async fn test_p2p_endpoint_routes() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/3/mempool/getPending"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"hashes": ["h1"]})))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"height": 42})))
+        .mount(&mock_server)
+        .await;
+
+    let client =
+        ChainwebClient::new(&mock_server.uri(), "testnet04").with_endpoint(EndpointKind::P2p);
+    assert_eq!(client.mempool_pending("3").await.unwrap(), vec!["h1"]);
+    assert_eq!(client.cut().await.unwrap()["height"], 42);
+
+    let cmd = Cmd {
+        hash: "hash".to_string(),
+        sigs: vec![],
+        cmd: "{}".to_string(),
+    };
+    let result = client.chain("3").local(&cmd).await;
+    assert!(matches!(result, Err(FetchError::UnsupportedRoute { .. })));
+
+    let p2p = ApiConfig::new(&mock_server.uri(), "testnet04", "3").with_endpoint(EndpointKind::P2p);
+    assert!(matches!(
+        p2p.header_updates_url(),
+        Err(FetchError::UnsupportedRoute {
+            route: ApiRoute::HeaderUpdates,
+            ..
+        })
+    ));
+
+    let service =
+        ApiConfig::new(&mock_server.uri(), "testnet04", "3").with_endpoint(EndpointKind::Service);
+    assert!(service.header_updates_url().is_ok());
+    assert!(service.peer_url(10).is_err());
+    assert_eq!(
+        p2p.peer_url(10).unwrap(),
+        format!(
+            "{}/chainweb/0.0/testnet04/cut/peer?limit=10",
+            mock_server.uri()
+        )
+    );
+    assert!(matches!(
+        service.mempool_url("getPending"),
+        Err(FetchError::UnsupportedRoute {
+            route: ApiRoute::Mempool,
+            ..
+        })
+    ));
+    let service_client =
+        ChainwebClient::new(&mock_server.uri(), "testnet04").with_endpoint(EndpointKind::Service);
+    assert!(matches!(
+        service_client.mempool_pending("3").await,
+        Err(FetchError::UnsupportedRoute { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_node_pool_discovery_and_failover() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .and(header("Authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"height": 42})))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut/peer"))
+        .and(header("Authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "items": [{"id": null, "address": {"hostname": "127.0.0.1", "port": 1}}],
+            "next": null
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(header("Authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "success"})))
+        .mount(&mock_server)
+        .await;
+
+    let pool = NodePool::new("testnet04")
+        .with_timeout(2)
+        .with_bearer_token("token")
+        .with_node("http://127.0.0.1:1", EndpointKind::Service)
+        .with_node(&mock_server.uri(), EndpointKind::Gateway);
+
+    // The advertised peer is unreachable, so it is screened out
+    assert!(pool.discover_peers().await.unwrap().is_empty());
+    assert_eq!(pool.nodes().len(), 2);
+
+    let cmd = Cmd {
+        hash: "hash".to_string(),
+        sigs: vec![],
+        cmd: "{}".to_string(),
+    };
+    let result = pool
+        .execute("0", ApiRoute::Pact, |client| {
+            let cmd = cmd.clone();
+            async move { client.local(&cmd).await }
+        })
+        .await
+        .unwrap();
+    assert_eq!(result, json!({"result": "success"}));
+
+    let nodes = pool.nodes();
+    assert!(!nodes[0].healthy);
+    assert!(nodes[1].healthy);
+    assert_eq!(pool.check_health().await, 1);
+}
+
+#[tokio::test]
+async fn test_node_pool_checks_pact_servers() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/version"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("4.13.0"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/local"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"result": "success"})))
+        .mount(&mock_server)
+        .await;
+
+    let pool = NodePool::new("development").with_node(&mock_server.uri(), EndpointKind::PactServer);
+    assert_eq!(pool.check_health().await, 1);
+    assert!(pool.pool_stats()[0].latency.is_some());
+    // A Pact server lists no peers, so discovery does not ask it
+    assert!(pool.discover_peers().await.unwrap().is_empty());
+
+    let cmd = Cmd {
+        hash: "hash".to_string(),
+        sigs: vec![],
+        cmd: "{}".to_string(),
+    };
+    let result = pool
+        .execute("0", ApiRoute::Pact, |client| {
+            let cmd = cmd.clone();
+            async move { client.local(&cmd).await }
+        })
+        .await
+        .unwrap();
+    assert_eq!(result, json!({"result": "success"}));
+}
+
+#[tokio::test]
+async fn test_node_pool_prefers_fastest_node() {
+    let slow = MockServer::start().await;
+    let fast = MockServer::start().await;
+
+    for (server, delay) in [(&slow, 300), (&fast, 0)] {
+        Mock::given(method("GET"))
+            .and(path("/chainweb/0.0/testnet04/cut"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"height": 42}))
+                    .set_delay(Duration::from_millis(delay)),
+            )
+            .mount(server)
+            .await;
+    }
+
+    let pool = NodePool::new("testnet04")
+        .with_node(&slow.uri(), EndpointKind::Gateway)
+        .with_node(&fast.uri(), EndpointKind::Gateway);
+    assert_eq!(pool.check_health().await, 2);
+
+    let base_url = pool
+        .execute("0", ApiRoute::Cut, |client| async move {
+            Ok(client.config().base_url.clone())
+        })
+        .await
+        .unwrap();
+    assert_eq!(base_url, fast.uri());
+
+    let stats = pool.pool_stats();
+    assert!(!stats[0].preferred);
+    assert!(stats[1].preferred);
+    assert!(stats[0].latency.unwrap() > stats[1].latency.unwrap());
+    assert_eq!(stats[1].requests, 2);
+    assert_eq!(stats[1].errors, 0);
+}
+
+#[tokio::test]
+async fn test_payload_with_outputs_size_limit() {
+    let mock_server = MockServer::start().await;
+
+    // base64url of `{"hash":"h"}` and `{"reqKey":"h"}`
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/payload/p1/outputs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "transactions": [["eyJoYXNoIjoiaCJ9", "eyJyZXFLZXkiOiJoIn0"]],
+            "minerData": "",
+            "coinbase": "eyJyZXFLZXkiOiJjIn0",
+            "payloadHash": "p1",
+            "transactionsHash": "t1",
+            "outputsHash": "o1"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let payload = client.payload_with_outputs("1", "p1").await.unwrap();
+    assert_eq!(payload.len(), 1);
+    let (tx, output) = payload.transactions().next().unwrap().unwrap();
+    assert_eq!(tx["hash"], "h");
+    assert_eq!(output["reqKey"], "h");
+    assert_eq!(payload.coinbase().unwrap()["reqKey"], "c");
+
+    let client = client.with_max_body_size(64);
+    let result = client.payload_with_outputs("1", "p1").await;
+    assert!(matches!(
+        result,
+        Err(FetchError::BodyTooLarge { limit: 64 })
+    ));
+
+    // Error bodies are bounded as well
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/payload/p2/outputs"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("x".repeat(1000)))
+        .mount(&mock_server)
+        .await;
+    let result = client.payload_with_outputs("1", "p2").await;
+    assert!(matches!(
+        result,
+        Err(FetchError::BodyTooLarge { limit: 64 })
+    ));
+}
+
+#[cfg(feature = "merkle")]
+mod spv_proof_tests {
+    use super::*;
+    use kadena::crypto::{base64url_encode, merkle_leaf, merkle_node};
+    use kadena::fetch::SpvProof;
+
+    /// Builds a proof of `output` with one sibling on each side
+    fn build_proof(output: &[u8]) -> (String, String) {
+        let left = [1u8; 32];
+        let right = [2u8; 32];
+
+        let mut leaf_input = 0x0014u16.to_le_bytes().to_vec();
+        leaf_input.extend_from_slice(output);
+        let root = merkle_node(&left, &merkle_node(&merkle_leaf(&leaf_input), &right));
+
+        let mut object = 2u32.to_be_bytes().to_vec();
+        object.extend_from_slice(&1u64.to_be_bytes());
+        object.push(0x01);
+        object.extend_from_slice(&right);
+        object.push(0x00);
+        object.extend_from_slice(&left);
+
+        let proof = json!({
+            "chain": 1,
+            "object": base64url_encode(&object),
+            "subject": {"tag": "TransactionOutput", "input": base64url_encode(output)},
+            "algorithm": "SHA512t_256"
+        });
+        (
+            base64url_encode(proof.to_string().as_bytes()),
+            base64url_encode(&root),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_spv_proof_verification() {
+        let output = json!({"reqKey": REQUEST_KEY, "result": {"status": "success"}});
+        let (encoded, block_hash) = build_proof(output.to_string().as_bytes());
+
+        let proof = SpvProof::parse(&encoded).unwrap();
+        assert_eq!(proof.chain, 1);
+        assert_eq!(proof.output().unwrap()["reqKey"], REQUEST_KEY);
+        assert_eq!(proof.block_hash(), block_hash);
+        assert!(proof.verify(&block_hash).is_ok());
+        assert!(matches!(
+            proof.verify(REQUEST_KEY),
+            Err(FetchError::InvalidProof(_))
+        ));
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/chainweb/0.0/testnet04/chain/1/header/{}",
+                block_hash
+            )))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"hash": block_hash, "height": 7})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+        let header = client.verify_spv(&proof).await.unwrap();
+        assert_eq!(header["height"], 7);
+    }
+}
+
+mod block_header_tests {
+    use super::*;
+    use kadena::crypto::MerkleProofObject;
+    use kadena::fetch::{LightClient, SpvProof};
+
+    fn header(chain_id: u32, height: u64, parent: &str, nonce: u64) -> BlockHeader {
+        BlockHeader::from_json(&header_json(chain_id, height, parent, nonce)).unwrap()
+    }
+
+    fn rehash(mut header: BlockHeader) -> BlockHeader {
+        header.hash = header.compute_hash().unwrap();
+        header
+    }
+
+    #[test]
+    fn test_block_header_encodings_and_validation() {
+        let parent = header(0, 99, &base64url_encode(&[9; 32]), 1);
+        let adjacent = header(5, 99, &parent.parent, 1);
+        let mut child = header(0, 100, &parent.hash, 1);
+        child.adjacents.insert(5, adjacent.hash.clone());
+        let child = rehash(child);
+
+        let bytes = child.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 318);
+        assert_eq!(BlockHeader::from_bytes(&bytes).unwrap(), child);
+        assert_eq!(
+            BlockHeader::from_value(&json!(base64url_encode(&bytes))).unwrap(),
+            child
+        );
+        assert!(BlockHeader::from_bytes(&bytes[..300]).is_err());
+
+        assert!(child.validate_pow().is_ok());
+        let hard = rehash(BlockHeader {
+            target: base64url_encode(&[0; 32]),
+            ..child.clone()
+        });
+        assert!(matches!(
+            hard.validate_pow(),
+            Err(FetchError::InvalidHeader(_))
+        ));
+
+        // The proof of work does not cover the hash, which must match the contents
+        let mut tampered = child.clone();
+        tampered.hash = base64url_encode(&[2; 32]);
+        assert_eq!(tampered.pow_hash().unwrap(), child.pow_hash().unwrap());
+        assert!(tampered.validate_hash().is_err());
+        assert!(tampered.validate_pow().is_err());
+        let mut tampered_bytes = bytes.clone();
+        let end = tampered_bytes.len();
+        tampered_bytes[end - 1] ^= 1;
+        assert!(BlockHeader::from_bytes(&tampered_bytes)
+            .unwrap()
+            .validate_pow()
+            .is_err());
+
+        assert!(child.validate_parent(&parent).is_ok());
+        assert!(parent.validate_parent(&child).is_err());
+
+        assert!(child
+            .validate_adjacents(std::slice::from_ref(&adjacent))
+            .is_ok());
+        let stranger = header(1, 99, &parent.parent, 1);
+        assert!(child.validate_adjacents(&[stranger]).is_err());
+        let forked = header(5, 99, &parent.parent, 2);
+        assert!(child.validate_adjacents(&[forked]).is_err());
+    }
+
+    #[test]
+    fn test_pow_targets_and_difficulty() {
+        use kadena::fetch::{estimate_hashrate, PowTarget};
+
+        // header() uses the easiest target
+        let easy = header(0, 1, &base64url_encode(&[0; 32]), 1);
+        assert_eq!(easy.pow_target().unwrap(), PowTarget::MAX);
+        assert_eq!(easy.difficulty().unwrap(), 1);
+
+        let mut bytes = [0; 32];
+        bytes[30] = 1; // 2^240
+        let target = PowTarget::from_bytes(bytes);
+        assert_eq!(target.difficulty(), 65535);
+        assert_eq!(target.leading_zeros(), 15);
+        assert!(target < PowTarget::MAX);
+        assert_eq!(PowTarget::from_difficulty(65536).difficulty(), 65536);
+        assert!(PowTarget::from_difficulty(65536) < target);
+        assert_eq!(PowTarget::from_bytes([0; 32]).difficulty(), u128::MAX);
+        assert_eq!(PowTarget::from_base64(&target.to_base64()).unwrap(), target);
+        assert!(PowTarget::from_base64("short").is_err());
+
+        let mut pow_hash = [0; 32];
+        pow_hash[30] = 1;
+        assert!(target.is_met_by(&pow_hash));
+        pow_hash[0] = 1;
+        assert!(!target.is_met_by(&pow_hash));
+
+        let hard = rehash(BlockHeader {
+            target: PowTarget::from_bytes([0; 32]).to_base64(),
+            ..easy.clone()
+        });
+        assert!(hard.validate_pow().is_err());
+
+        let mut later = header(0, 2, &easy.hash, 1);
+        later.target = target.to_base64();
+        later.creation_time = easy.creation_time + 1_000_000;
+        assert_eq!(
+            estimate_hashrate(&[easy.clone(), later.clone()]),
+            Some(65535.0)
+        );
+        assert_eq!(estimate_hashrate(&[later.clone(), easy]), None);
+        assert_eq!(estimate_hashrate(&[later]), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_binary_headers() {
+        use wiremock::matchers::{header as request_header, query_param_is_missing};
+
+        let mock_server = MockServer::start().await;
+        let chain = "/chainweb/0.0/testnet04/chain/0/header";
+        let mut headers = vec![header(0, 20, &base64url_encode(&[19; 32]), 1)];
+        for height in 21..25 {
+            let parent = headers.last().unwrap().hash.clone();
+            headers.push(header(0, height, &parent, 1));
+        }
+
+        Mock::given(method("GET"))
+            .and(path(format!("{}/{}", chain, headers[0].hash)))
+            .and(request_header("Accept", "application/octet-stream"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(headers[0].to_bytes().unwrap(), "application/octet-stream"),
+            )
+            .mount(&mock_server)
+            .await;
+        let page = |items: &[BlockHeader], next: Option<&str>| {
+            let items: Vec<String> = items.iter().map(|h| h.to_base64().unwrap()).collect();
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"items": items, "limit": items.len(), "next": next}))
+        };
+        Mock::given(method("GET"))
+            .and(path(chain))
+            .and(query_param("minheight", "20"))
+            .and(query_param("maxheight", "24"))
+            .and(query_param_is_missing("next"))
+            .respond_with(page(&headers[..3], Some("inclusive:cursor")))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(chain))
+            .and(query_param("next", "inclusive:cursor"))
+            .respond_with(page(&headers[3..], None))
+            .mount(&mock_server)
+            .await;
+
+        let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+        assert_eq!(
+            client.block_header("0", &headers[0].hash).await.unwrap(),
+            headers[0]
+        );
+        let range = client.block_headers("0", 20, 24).await.unwrap();
+        assert_eq!(range, headers);
+        for pair in range.windows(2) {
+            pair[1].validate_parent(&pair[0]).unwrap();
+        }
+        assert_eq!(
+            BlockHeader::from_base64(&headers[4].to_base64().unwrap()).unwrap(),
+            headers[4]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_light_client_over_custom_transport() {
+        let b10 = header_json(0, 10, &base64url_encode(&[9; 32]), 1);
+        let b11 = header_json(0, 11, b10["hash"].as_str().unwrap(), 1);
+        let hash = |header: &serde_json::Value| header["hash"].as_str().unwrap().to_string();
+        let header_path = format!("/chainweb/0.0/development/chain/0/header/{}", hash(&b10));
+        let events = format!(
+            "event:BlockHeader\ndata:{}\n\n",
+            json!({"header": b11, "txCount": 0})
+        );
+        let cut = json!({"hashes": {"0": {"height": 10, "hash": hash(&b10)}}});
+
+        let transport = InProcessTransport::new(move |request: HttpRequest| {
+            assert_eq!(request.header("x-api-key"), Some("key"));
+            match request.path() {
+                "/chainweb/0.0/development/cut" => HttpResponse::json(200, &cut),
+                "/chainweb/0.0/development/header/updates" => {
+                    HttpResponse::new(200, events.clone())
+                }
+                "/chainweb/0.0/development/chain/0/mempool/getPending" => {
+                    HttpResponse::json(200, &json!({"hashes": ["h1"]}))
+                }
+                path if path == header_path => HttpResponse::json(200, &b10),
+                _ => HttpResponse::new(404, "not found"),
+            }
+        });
+        let client = ChainwebClient::new("http://node", "development")
+            .with_api_key("key")
+            .with_transport(transport);
+        assert_eq!(client.mempool_pending("0").await.unwrap(), vec!["h1"]);
+
+        let light = LightClient::new(client);
+        light.sync().await.unwrap();
+        assert_eq!(light.height(0), Some(10));
+        light.follow().await.unwrap();
+        assert_eq!(light.height(0), Some(11));
+    }
+
+    #[tokio::test]
+    async fn test_light_client_follows_verified_headers() {
+        let mock_server = MockServer::start().await;
+        let b10 = header_json(0, 10, &base64url_encode(&[9; 32]), 1);
+        let b11 = header_json(0, 11, b10["hash"].as_str().unwrap(), 1);
+        let b12 = header_json(0, 12, b11["hash"].as_str().unwrap(), 1);
+        let orphan = header_json(0, 13, &base64url_encode(&[99; 32]), 1);
+        let hash = |header: &serde_json::Value| header["hash"].as_str().unwrap().to_string();
+
+        Mock::given(method("GET"))
+            .and(path("/chainweb/0.0/testnet04/cut"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "hashes": {"0": {"height": 10, "hash": hash(&b10)}}
+            })))
+            .mount(&mock_server)
+            .await;
+        // Block 11 is missing from the stream and fetched to fill the gap
+        for block in [&b10, &b11] {
+            Mock::given(method("GET"))
+                .and(path(format!(
+                    "/chainweb/0.0/testnet04/chain/0/header/{}",
+                    hash(block)
+                )))
+                .respond_with(ResponseTemplate::new(200).set_body_json(block))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let events = [&b12, &orphan]
+            .iter()
+            .map(|header| {
+                format!(
+                    "event:BlockHeader\ndata:{}\n\n",
+                    json!({"header": header, "txCount": 1})
+                )
+            })
+            .collect::<String>();
+        Mock::given(method("GET"))
+            .and(path("/chainweb/0.0/testnet04/header/updates"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+            .mount(&mock_server)
+            .await;
+
+        let light = LightClient::new(ChainwebClient::new(&mock_server.uri(), "testnet04"));
+        light.sync().await.unwrap();
+        assert_eq!(light.height(0), Some(10));
+
+        light.follow().await.unwrap();
+        assert_eq!(light.height(0), Some(12));
+        assert!(light.is_known_block(0, &hash(&b11)));
+        assert!(!light.is_known_block(0, &hash(&orphan)));
+        assert_eq!(light.confirmations(0, 11), Some(2));
+        assert_eq!(light.confirmations(1, 11), None);
+
+        let forged = rehash(BlockHeader {
+            target: base64url_encode(&[0; 32]),
+            ..header(0, 13, &hash(&b12), 1)
+        });
+        assert!(light.apply(forged).await.is_err());
+
+        // A header with a valid proof of work whose hash is the root of a forged proof
+        let proof = SpvProof {
+            chain: 0,
+            subject: br#"{"result":{"status":"success"}}"#.to_vec(),
+            object: MerkleProofObject {
+                position: 0,
+                steps: Vec::new(),
+            },
+        };
+        let mut tampered = header(0, 13, &hash(&b12), 1);
+        tampered.hash = proof.block_hash();
+        assert!(tampered.pow_hash().is_ok());
+        let error = light.apply(tampered).await.unwrap_err();
+        assert_eq!(error.error_code(), "fetch.invalid_header");
+        assert!(!light.is_known_block(0, &proof.block_hash()));
+        assert!(light.verify_spv(&proof).is_err());
+        assert_eq!(light.height(0), Some(12));
+    }
+
+    #[tokio::test]
+    async fn test_reorg_monitor_reports_replaced_blocks() {
+        use kadena::fetch::ReorgMonitor;
+        use kadena::pact::CommandResult;
+
+        let mock_server = MockServer::start().await;
+        let b10 = header(0, 10, &base64url_encode(&[9; 32]), 1);
+        let b11 = header(0, 11, &b10.hash, 1);
+        let fork11 = header(0, 11, &b10.hash, 2);
+        let fork12 = header(0, 12, &fork11.hash, 1);
+        let fork13 = header(0, 13, &fork12.hash, 1);
+        Mock::given(method("GET"))
+            .and(path("/chainweb/0.0/testnet04/cut"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "hashes": {"0": {"height": 10, "hash": b10.hash}}
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/chainweb/0.0/testnet04/chain/0/header/{}",
+                b10.hash
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(header_json(
+                0,
+                10,
+                &base64url_encode(&[9; 32]),
+                1,
+            )))
+            .mount(&mock_server)
+            .await;
+        let light = LightClient::new(ChainwebClient::new(&mock_server.uri(), "testnet04"));
+        light.sync().await.unwrap();
+        light.apply(b11.clone()).await.unwrap();
+
+        let mut reorgs = ReorgMonitor::new().with_finality_depth(4);
+        let result = CommandResult::from_value(json!({
+            "reqKey": "rk-11",
+            "result": {"status": "success", "data": "Write succeeded"},
+            "metaData": {"blockHeight": 11, "blockHash": b11.hash, "blockTime": 0, "prevBlockHash": b10.hash}
+        }))
+        .unwrap();
+        assert!(reorgs.observe_result(0, &result));
+        reorgs.observe("rk-10", 0, 10, &b10.hash);
+        reorgs.observe("rk-12", 0, 12, &base64url_encode(&[12; 32]));
+        assert!(reorgs.check(&light).is_empty());
+
+        // A competing block at the same height does not replace the head
+        light.apply(fork11.clone()).await.unwrap();
+        assert!(reorgs.check(&light).is_empty());
+        assert_eq!(light.canonical_hash(0, 11), Some(b11.hash.clone()));
+
+        // Until its branch grows longer
+        light.apply(fork12).await.unwrap();
+        let events = reorgs.check(&light);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].request_key, "rk-11");
+        assert_eq!(events[0].observed_hash, b11.hash);
+        assert_eq!(events[0].canonical_hash, fork11.hash);
+        assert_eq!(events[1].request_key, "rk-12");
+        assert_eq!(reorgs.tracked(), vec!["rk-10"]);
+
+        light.apply(fork13).await.unwrap();
+        assert!(reorgs.check(&light).is_empty());
+        assert!(!reorgs.is_tracked("rk-10"));
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_events_filters_transfers() {
+    use futures::StreamExt;
+    use kadena::fetch::EventFilter;
+
+    let mock_server = MockServer::start().await;
+    let h = |n: u8| base64url_encode(&[n; 32]);
+    let transfer = |to: &str| {
+        json!({
+            "name": "TRANSFER",
+            "module": {"namespace": null, "name": "coin"},
+            "moduleHash": "hash",
+            "params": ["k:alice", to, 2.5]
+        })
+    };
+    let output = json!({
+        "reqKey": REQUEST_KEY,
+        "result": {"status": "success", "data": "Write succeeded"},
+        "events": [transfer("k:bob"), transfer("k:carol")]
+    });
+
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/chainweb/0.0/testnet04/chain/2/payload/{}/outputs",
+            h(7)
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "transactions": [[base64url_encode(b"{}"), base64url_encode(output.to_string().as_bytes())]],
+            "minerData": "",
+            "coinbase": "",
+            "payloadHash": h(7),
+            "transactionsHash": h(8),
+            "outputsHash": h(9)
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut header = header_json(2, 50, &h(1), 1);
+    header["payloadHash"] = json!(h(7));
+    let header = with_computed_hash(header);
+    let events = format!(
+        "event:BlockHeader\ndata:{}\n\n",
+        json!({"header": header, "txCount": 1})
+    );
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/header/updates"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let observed: Vec<_> = client
+        .subscribe_events(EventFilter::transfers_to("k:bob"))
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    assert_eq!(observed.len(), 1);
+    let deposit = observed[0].as_ref().unwrap();
+    assert_eq!(deposit.request_key, REQUEST_KEY);
+    assert_eq!(deposit.chain_id, 2);
+    assert_eq!(deposit.height, 50);
+    assert_eq!(deposit.event.param(2), Some(&json!(2.5)));
+}
+
+#[tokio::test]
+async fn test_check_signatures_against_guards() {
+    use kadena::crypto::PactKeypair;
+    use kadena::pact::{Cap, CommandError, KeySet, Meta, Transfer};
+    use rust_decimal::Decimal;
+    use wiremock::matchers::body_string_contains;
+
+    let a = PactKeypair::generate();
+    let b = PactKeypair::generate();
+    let mock_server = MockServer::start().await;
+    let details = |account: &str, guard: KeySet| {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"account": account, "balance": 10.0, "guard": guard}}
+        }))
+    };
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains("alice"))
+        .respond_with(details(
+            "alice",
+            KeySet::keys_2([a.public_key(), b.public_key()]),
+        ))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains("bob"))
+        .respond_with(details("bob", KeySet::keys_all(["b0b"])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains("carol"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "failure", "error": {"message": "with-read: row not found: carol"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let meta = || Meta::new("0", "alice");
+
+    let transfer = Transfer::new("alice", "bob", Decimal::ONE);
+    let cmd = transfer.prepare(&[&a, &b], meta(), None).unwrap();
+    client.check_signatures(&cmd).await.unwrap();
+
+    let cmd = transfer.prepare(&[&a], meta(), None).unwrap();
+    assert!(matches!(
+        client.check_signatures(&cmd).await,
+        Err(FetchError::CommandError(CommandError::UnsatisfiedKeyset(_)))
+    ));
+
+    // b only grants gas, so the transfer is signed by a alone
+    let cmd = Cmd::prepare_exec(
+        &[
+            (&a, vec![Cap::new("coin.GAS"), transfer.cap()]),
+            (&b, vec![Cap::new("coin.GAS")]),
+        ],
+        Vec::new(),
+        None,
+        &transfer.code(),
+        None,
+        meta(),
+        None,
+    )
+    .unwrap();
+    assert!(client.check_signatures(&cmd).await.is_err());
+
+    let create = Transfer::new("alice", "bob", Decimal::ONE)
+        .with_receiver_guard(KeySet::keys_all(["0ther"]));
+    let cmd = create.prepare(&[&a, &b], meta(), None).unwrap();
+    assert!(matches!(
+        client.check_signatures(&cmd).await,
+        Err(FetchError::CommandError(CommandError::InvalidGuard(_)))
+    ));
+    let new_account = Transfer::new("alice", "carol", Decimal::ONE)
+        .with_receiver_guard(KeySet::keys_all(["ca401"]));
+    let cmd = new_account.prepare(&[&a, &b], meta(), None).unwrap();
+    client.check_signatures(&cmd).await.unwrap();
+
+    let unknown_sender = transfer
+        .prepare(&[&a, &b], Meta::new("0", "carol"), None)
+        .unwrap();
+    assert!(client.check_signatures(&unknown_sender).await.is_err());
+}
+
+#[tokio::test]
+async fn test_fund_from_faucet() {
+    use rust_decimal::Decimal;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/pact/api/v1/send"))
+        .and(body_string_contains(
+            r#"(user.coin-faucet.create-and-request-coin \\\"k:abc123\\\" (read-keyset \\\"ks\\\") 20.0)"#,
+        ))
+        .and(body_string_contains("user.coin-faucet.GAS_PAYER"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [REQUEST_KEY]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/development/chain/0/pact/api/v1/send"))
+        .and(body_string_contains(
+            r#"(coin.transfer-create \\\"sender00\\\""#,
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [REQUEST_KEY]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let testnet = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "1"));
+    let response = testnet
+        .fund_from_faucet("k:abc123", Decimal::new(20, 0))
+        .await
+        .unwrap();
+    assert_eq!(response["requestKeys"][0], REQUEST_KEY);
+
+    let devnet = ApiClient::new(ApiConfig::new(&mock_server.uri(), "development", "0"));
+    devnet
+        .fund_from_faucet("k:abc123", Decimal::new(100, 0))
+        .await
+        .unwrap();
+
+    let mainnet = ApiClient::new(ApiConfig::new(&mock_server.uri(), "mainnet01", "0"));
+    let err = mainnet
+        .fund_from_faucet("k:abc123", Decimal::ONE)
+        .await
+        .unwrap_err();
+    assert_eq!(err.error_code(), "fetch.faucet_unavailable");
+}
+
+#[tokio::test]
+async fn test_describe_module_and_keyset() {
+    use kadena::pact::KeySet;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains(r#"describe-module \\\"coin\\\""#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {
+                "name": "coin",
+                "hash": "coin-hash",
+                "interfaces": ["fungible-v2", "fungible-xchain-v1"],
+                "code": "(module coin GOVERNANCE)",
+                "blessed": [],
+                "keyset": "GOVERNANCE"
+            }}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains(r#"describe-module \\\"free.missing\\\""#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "failure", "error": {"message": "Cannot find module: free.missing"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains(
+            r#"describe-keyset \\\"free.admin\\\""#,
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"keys": ["abc123"], "pred": "keys-all"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+
+    let coin = client.describe_module("coin").await.unwrap().unwrap();
+    assert_eq!(coin.hash, "coin-hash");
+    assert!(coin.implements("fungible-xchain-v1"));
+    assert!(client
+        .describe_module("free.missing")
+        .await
+        .unwrap()
+        .is_none());
+
+    let keyset = client.describe_keyset("free.admin").await.unwrap().unwrap();
+    assert_eq!(keyset, KeySet::keys_all(["abc123"]));
+}
+
+#[tokio::test]
+async fn test_verify_deployed_module() {
+    use kadena::pact::ModuleExpectation;
+    use wiremock::matchers::body_string_contains;
+
+    let source = "(module oracle GOV (defcap GOV () true))";
+    let deployed_hash = ModuleExpectation::source(source).expected_hash();
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains(
+            r#"describe-module \\\"free.oracle\\\""#,
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {
+                "name": "free.oracle",
+                "hash": deployed_hash,
+                "code": source,
+                "blessed": ["old-hash"]
+            }}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .and(body_string_contains(r#"describe-module \\\"free.missing\\\""#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "failure", "error": {"message": "Cannot find module: free.missing"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+
+    let module = client
+        .verify_deployed_module("free.oracle", deployed_hash.as_str())
+        .await
+        .unwrap();
+    assert_eq!(module.name, "free.oracle");
+    assert!(client
+        .verify_deployed_module("free.oracle", format!("\n{}\n", source).as_str())
+        .await
+        .is_ok());
+
+    let error = client
+        .verify_deployed_module("free.oracle", "old-hash")
+        .await
+        .unwrap_err();
+    assert_eq!(error.error_code(), "fetch.unexpected_module");
+    assert!(error.to_string().contains("expected old-hash"));
+    let error = client
+        .verify_deployed_module("free.oracle", "(module oracle GOV)")
+        .await
+        .unwrap_err();
+    assert_eq!(error.error_code(), "fetch.unexpected_module");
+    let error = client
+        .verify_deployed_module("free.missing", "any-hash")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("not deployed on chain 0"));
+}
+
+#[tokio::test]
+async fn test_check_interface_before_token_transfers() {
+    use kadena::pact::FUNGIBLE_V2;
+    use rust_decimal::Decimal;
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+    for (module, interfaces) in [
+        (
+            "free.token",
+            json!(["free.fungible-v2", "fungible-xchain-v1"]),
+        ),
+        ("free.registry", json!([])),
+    ] {
+        Mock::given(method("POST"))
+            .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+            .and(body_string_contains(format!(
+                r#"describe-module \\\"{}\\\""#,
+                module
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": {"status": "success", "data": {
+                    "name": module,
+                    "hash": "module-hash",
+                    "interfaces": interfaces,
+                    "code": "(module m GOV)"
+                }}
+            })))
+            .mount(&mock_server)
+            .await;
+    }
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+
+    let token = client.token_transfer("free.token").await.unwrap();
+    let transfer = token.transfer("k:alice", "k:bob", Decimal::ONE);
+    assert_eq!(transfer.cap().name, "free.token.TRANSFER");
+    assert!(client
+        .check_interface("free.token", "fungible-xchain-v1")
+        .await
+        .is_ok());
+
+    let error = client.token_transfer("free.registry").await.unwrap_err();
+    assert_eq!(error.error_code(), "command.missing_interface");
+    assert!(error
+        .to_string()
+        .contains("free.registry does not implement fungible-v2"));
+    let error = client
+        .check_interface("free.registry", FUNGIBLE_V2)
+        .await
+        .unwrap_err();
+    assert_eq!(error.error_code(), "command.missing_interface");
+}
+
+#[tokio::test]
+async fn test_find_mined_commands() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            REQUEST_KEY: {
+                "reqKey": REQUEST_KEY,
+                "result": {"status": "success", "data": "Write succeeded"},
+                "gas": 518,
+                "metaData": {
+                    "blockHeight": 4000000,
+                    "blockTime": 1700000000123456u64,
+                    "blockHash": "block-hash",
+                    "prevBlockHash": "prev-hash"
+                }
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/txs/tx"))
+        .and(query_param("requestkey", REQUEST_KEY))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "requestKey": REQUEST_KEY,
+            "height": 4000000
+        }])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/txs/tx"))
+        .and(query_param("requestkey", "unknown"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let hex_key = RequestKey::parse(REQUEST_KEY).unwrap().to_hex();
+    let result = client.find_mined(&hex_key).await.unwrap().unwrap();
+    assert_eq!(result.block_height(), Some(4000000));
+
+    let indexer = ChainwebDataClient::new(&mock_server.uri());
+    assert!(indexer.is_mined(REQUEST_KEY).await.unwrap());
+    assert!(!indexer.is_mined("unknown").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_tx_tracker_reports_mined_commands() {
+    use futures::StreamExt;
+    use kadena::fetch::{TxStatus, TxTracker};
+    use std::sync::{Arc, Mutex};
+
+    const FAILED_KEY: &str = "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4g";
+    const PENDING_KEY: &str = "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4k";
+    let result = |key: &str, status: &str| {
+        json!({
+            "reqKey": key,
+            "result": {"status": status, "data": "Write succeeded", "error": {"message": "boom"}},
+            "gas": 518,
+            "metaData": {
+                "blockHeight": 4000000,
+                "blockTime": 1700000000123456u64,
+                "blockHash": "block-hash",
+                "prevBlockHash": "prev-hash"
+            }
+        })
+    };
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            REQUEST_KEY: result(REQUEST_KEY, "success"),
+            FAILED_KEY: result(FAILED_KEY, "failure"),
+        })))
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+
+    let mined = Arc::new(Mutex::new(Vec::new()));
+    let mut tracker = TxTracker::new(&client).with_interval(Duration::from_millis(10));
+    for key in [REQUEST_KEY, FAILED_KEY] {
+        let mined = mined.clone();
+        tracker
+            .watch(key, move |update| {
+                mined.lock().unwrap().push((
+                    update.request_key.clone(),
+                    matches!(update.status, TxStatus::Succeeded(_)),
+                ))
+            })
+            .unwrap();
+    }
+    tracker.track(PENDING_KEY).unwrap();
+
+    let updates = tracker.poll_once().await.unwrap();
+    assert_eq!(updates.len(), 2);
+    assert_eq!(
+        *mined.lock().unwrap(),
+        [
+            (REQUEST_KEY.to_string(), true),
+            (FAILED_KEY.to_string(), false)
+        ]
+    );
+    assert_eq!(tracker.pending(), [PENDING_KEY]);
+
+    // Commands never mined time out
+    let tracker = tracker.with_timeout(Duration::from_millis(20));
+    let updates: Vec<_> = tracker.updates().collect().await;
+    assert_eq!(updates.len(), 1);
+    let update = updates[0].as_ref().unwrap();
+    assert_eq!(update.request_key, PENDING_KEY);
+    assert!(matches!(update.status, TxStatus::TimedOut));
+}
+
+#[tokio::test]
+async fn test_mempool_pending_commands() {
+    use kadena::crypto::PactKeypair;
+    use kadena::pact::Meta;
+    use rust_decimal::Decimal;
+    use wiremock::matchers::body_json;
+
+    let keypair = PactKeypair::generate();
+    let cmd = kadena::pact::Transfer::new("alice", "bob", Decimal::ONE)
+        .prepare(
+            &[&keypair],
+            Meta::new("2", "alice"),
+            Some("testnet04".to_string()),
+        )
+        .unwrap();
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/2/mempool/getPending"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"hashes": [cmd.hash, "gone"]})),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/2/mempool/lookup"))
+        .and(body_json(json!([cmd.hash, "gone"])))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"tag": "Pending", "contents": serde_json::to_string(&cmd).unwrap()},
+            {"tag": "Missing"}
+        ])))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let pending = client.mempool_pending_commands("2").await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].hash, cmd.hash);
+    assert_eq!(pending[0].payload().unwrap().meta.sender, "alice");
+}
+
+#[tokio::test]
+async fn test_cloned_clients_share_state() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let handle = client.clone();
+            tokio::spawn(async move { handle.poll(&[REQUEST_KEY]).await })
+        })
+        .collect();
+    for handle in handles {
+        assert!(handle.await.unwrap().unwrap().is_empty());
+    }
+    assert!(client.clone().listen(REQUEST_KEY).await.is_err());
+
+    let stats = client.stats();
+    assert_eq!(stats.requests, 4);
+    assert_eq!(stats.failures, 1);
+    assert_eq!(client.clone().stats(), stats);
+}
+
+#[tokio::test]
+async fn test_pact_server_mode() {
+    use wiremock::matchers::body_string_contains;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/local"))
+        .and(body_string_contains(r#"\"networkId\":null"#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"keys": ["abcd"], "pred": "keys-all"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::pact_server(&format!("{}/", mock_server.uri())));
+    let keyset = client.describe_keyset("admin").await.unwrap().unwrap();
+    assert_eq!(keyset.keys, ["abcd"]);
+    assert!(client.poll(&[REQUEST_KEY]).await.unwrap().is_empty());
+    assert_eq!(
+        client.config().cut_url().unwrap_err().error_code(),
+        "fetch.unsupported_route"
+    );
+}
+
+#[tokio::test]
+async fn test_request_devnet_blocks() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/make-blocks"))
+        .and(wiremock::matchers::body_json(json!({"1": 2})))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "development", "1"));
+    client.request_blocks(2).await.unwrap();
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "mainnet01", "1"));
+    let error = client.request_blocks(2).await.unwrap_err();
+    assert_eq!(error.error_code(), "fetch.mining_unavailable");
+}
+
+#[tokio::test]
+async fn test_send_rejects_commands_over_fee_budget() {
+    use kadena::pact::{FeePolicy, Meta};
+    use rust_decimal::Decimal;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/send"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [REQUEST_KEY]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = ApiConfig::new(&mock_server.uri(), "testnet04", "0")
+        .with_fee_policy(FeePolicy::new().with_max_fee(Decimal::new(1, 3)));
+    let client = ApiClient::new(config);
+    let prepare = |gas_limit| {
+        let meta = Meta::new("0", "alice").with_gas_limit(gas_limit);
+        Cmd::prepare_exec(&[], vec![], None, "(+ 1 2)", None, meta, None).unwrap()
+    };
+
+    let error = client.send(&prepare(200_000)).await.unwrap_err();
+    assert_eq!(error.error_code(), "command.fee_exceeded");
+    assert_eq!(client.stats().requests, 0);
+
+    client.send(&prepare(1500)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_suggest_gas_price_from_recent_blocks() {
+    use kadena::{base64url_encode, pact::Meta};
+
+    let mock_server = MockServer::start().await;
+    let chain = "/chainweb/0.0/testnet04/chain/0";
+    let encode = |value: serde_json::Value| base64url_encode(value.to_string().as_bytes());
+    let tx = |gas_price: f64| {
+        let meta = Meta::new("0", "alice").with_gas_price(gas_price);
+        let cmd = Cmd::prepare_exec(&[], vec![], None, "(+ 1 2)", None, meta, None).unwrap();
+        (
+            encode(serde_json::to_value(cmd).unwrap()),
+            encode(json!({})),
+        )
+    };
+    let payload = |transactions: Vec<(String, String)>| {
+        json!({
+            "transactions": transactions,
+            "minerData": "",
+            "coinbase": "",
+            "payloadHash": "",
+            "transactionsHash": "",
+            "outputsHash": ""
+        })
+    };
+
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "hashes": {"0": {"height": 100, "hash": "h100"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("{}/header", chain)))
+        .and(query_param("minheight", "99"))
+        .and(query_param("limit", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "items": [{"payloadHash": "p99"}, {"payloadHash": "p100"}],
+            "limit": 2,
+            "next": null
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("{}/payload/p99/outputs", chain)))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(payload(vec![tx(0.00000001), tx(0.00000002)])),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("{}/payload/p100/outputs", chain)))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(payload(vec![tx(0.00000003), tx(0.000001)])),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let suggestion = client.suggest_gas_price_over(2).await.unwrap();
+    assert_eq!(suggestion.samples, 4);
+    assert_eq!(suggestion.low, 0.00000001);
+    assert_eq!(suggestion.median, 0.00000002);
+    assert_eq!(suggestion.high, 0.000001);
+    assert_eq!(client.stats().requests, 4);
+
+    let config = ApiConfig::new(&mock_server.uri(), "testnet04", "0").with_max_body_size(16);
+    let client = ApiClient::new(config);
+    assert!(matches!(
+        client.recent_gas_prices(2).await,
+        Err(FetchError::BodyTooLarge { limit: 16 })
+    ));
+}
+
+#[tokio::test]
+async fn test_export_history_to_csv_and_json() {
+    use kadena::HistoryExport;
+    use rust_decimal::Decimal;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/txs/account/k:abc"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "amount": "1234567.000000000001", "blockTime": "2024-01-02T00:00:00Z", "chain": 1,
+                "fromAccount": "=cmd|evil", "toAccount": "k:abc", "height": 100,
+                "requestKey": "rk1", "token": "coin", "crossChainAccount": null, "crossChainId": null
+            },
+            {
+                "amount": "0.5", "blockTime": "2024-01-01T00:00:00Z", "chain": 1,
+                "fromAccount": "k:abc", "toAccount": "bob, \"the\" builder", "height": 90,
+                "requestKey": "rk2", "token": "coin", "crossChainAccount": null, "crossChainId": 2
+            }
+        ])))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebDataClient::new(&mock_server.uri());
+    let history = client.history_all("k:abc", None).await.unwrap();
+    let export = HistoryExport::new(&history).with_fees([("rk2", Decimal::new(69, 7))]);
+
+    let csv = export.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "timestamp,direction,counterparty,amount,fee,token,request_key,chain_id,height,cross_chain_id",
+            "2024-01-02T00:00:00Z,incoming,'=cmd|evil,1234567.000000000001,,coin,rk1,1,100,",
+            r#"2024-01-01T00:00:00Z,outgoing,"bob, ""the"" builder",0.5,0.0000069,coin,rk2,1,90,2"#,
+        ]
+    );
+
+    let json = export.to_json();
+    assert_eq!(json[0]["amount"], "1234567.000000000001");
+    assert_eq!(json[0]["fee"], serde_json::Value::Null);
+    assert_eq!(json[1]["fee"], "0.0000069");
+    assert_eq!(json[1]["cross_chain_id"], "2");
+}
+
+#[tokio::test]
+async fn test_deposit_watcher_reports_confirmed_deposits() {
+    use futures::StreamExt;
+    use kadena::fetch::DepositWatcher;
+    use kadena::pact::KeySet;
+
+    let mock_server = MockServer::start().await;
+    let h = |n: u8| base64url_encode(&[n; 32]);
+    let bob = format!("k:{}", "b".repeat(64));
+    let transfer = |to: &str, amount: serde_json::Value| {
+        json!({
+            "name": "TRANSFER",
+            "module": {"namespace": null, "name": "coin"},
+            "moduleHash": "hash",
+            "params": ["k:alice", to, amount]
+        })
+    };
+    // Payload 20 is orphaned by payload 21 at the same height
+    let payloads = [
+        (20, vec![transfer(&bob, json!(1.0))]),
+        (
+            21,
+            vec![
+                transfer(&bob, json!({"decimal": "2.5"})),
+                transfer("k:carol", json!(9)),
+            ],
+        ),
+        (22, vec![transfer(&bob, json!(4))]),
+    ];
+    for (payload, events) in payloads {
+        let output = json!({
+            "reqKey": REQUEST_KEY,
+            "result": {"status": "success", "data": "Write succeeded"},
+            "events": events
+        });
+        Mock::given(method("GET"))
+            .and(wiremock::matchers::path_regex(format!(
+                "^/chainweb/0.0/testnet04/chain/[0-9]/payload/{}/outputs$",
+                h(payload)
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "transactions": [[base64url_encode(b"{}"), base64url_encode(output.to_string().as_bytes())]],
+                "minerData": "",
+                "coinbase": "",
+                "payloadHash": h(payload),
+                "transactionsHash": h(0),
+                "outputsHash": h(0)
+            })))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let header = |chain: u32, height: u64, parent: &serde_json::Value, nonce: u64, payload: u8| {
+        let mut header = header_json(chain, height, parent.as_str().unwrap(), nonce);
+        header["payloadHash"] = json!(h(payload));
+        with_computed_hash(header)
+    };
+    let update = |header: &serde_json::Value, tx_count: u64| {
+        format!(
+            "event:BlockHeader\ndata:{}\n\n",
+            json!({"header": header, "txCount": tx_count})
+        )
+    };
+    let ancestor = json!(h(49));
+    let winner = header(2, 50, &ancestor, 2, 21);
+    let child = header(2, 51, &winner["hash"], 1, 0);
+    let events = [
+        update(&header(2, 50, &ancestor, 1, 20), 1),
+        update(&winner, 1),
+        update(&header(1, 10, &ancestor, 1, 22), 1),
+        update(&child, 0),
+        update(&header(2, 52, &child["hash"], 1, 0), 0),
+    ]
+    .concat();
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/header/updates"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let mut watcher = DepositWatcher::new(&client).with_confirmations(2);
+    let error = watcher
+        .watch(&bob, KeySet::keys_all(["c".repeat(64)]))
+        .unwrap_err();
+    assert_eq!(error.error_code(), "command.invalid_principal");
+    watcher
+        .watch(&bob, KeySet::keys_all(["b".repeat(64)]))
+        .unwrap();
+
+    let deposits: Vec<_> = watcher.deposits().await.unwrap().collect().await;
+    assert_eq!(deposits.len(), 1);
+    let deposit = deposits[0].as_ref().unwrap();
+    assert_eq!(deposit.account, bob);
+    assert_eq!(deposit.sender, "k:alice");
+    assert_eq!(deposit.amount.to_string(), "2.5");
+    assert_eq!(deposit.chain_id, 2);
+    assert_eq!(deposit.height, 50);
+    assert_eq!(deposit.block_hash, winner["hash"]);
+}
+
+#[tokio::test]
+async fn test_deposit_watcher_scans_skipped_fork_blocks() {
+    use futures::StreamExt;
+    use kadena::fetch::DepositWatcher;
+
+    let mock_server = MockServer::start().await;
+    let h = |n: u8| base64url_encode(&[n; 32]);
+    let bob = format!("k:{}", "b".repeat(64));
+    // Payload 20 is in a block replaced by another one at the same height with payload 21
+    for (payload, amount) in [(20, 1), (21, 3)] {
+        let output = json!({
+            "reqKey": REQUEST_KEY,
+            "result": {"status": "success", "data": "Write succeeded"},
+            "events": [{
+                "name": "TRANSFER",
+                "module": {"namespace": null, "name": "coin"},
+                "moduleHash": "hash",
+                "params": ["k:alice", bob, amount]
+            }]
+        });
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/chainweb/0.0/testnet04/chain/2/payload/{}/outputs",
+                h(payload)
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "transactions": [[base64url_encode(b"{}"), base64url_encode(output.to_string().as_bytes())]],
+                "minerData": "",
+                "coinbase": "",
+                "payloadHash": h(payload),
+                "transactionsHash": h(0),
+                "outputsHash": h(0)
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let header = |height: u64, parent: &serde_json::Value, nonce: u64, payload: u8| {
+        let mut header = header_json(2, height, parent.as_str().unwrap(), nonce);
+        header["payloadHash"] = json!(h(payload));
+        with_computed_hash(header)
+    };
+    let update = |header: &serde_json::Value, tx_count: u64| {
+        format!(
+            "event:BlockHeader\ndata:{}\n\n",
+            json!({"header": header, "txCount": tx_count})
+        )
+    };
+    let ancestor = json!(h(49));
+    let replacement = header(50, &ancestor, 2, 21);
+    let child = header(51, &replacement["hash"], 1, 0);
+    // The stream never shows the replacement block, only its child
+    let events = [
+        update(&header(50, &ancestor, 1, 20), 1),
+        update(&child, 0),
+        update(&header(52, &child["hash"], 1, 0), 0),
+    ]
+    .concat();
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/header/updates"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/chainweb/0.0/testnet04/chain/2/header/{}",
+            replacement["hash"].as_str().unwrap()
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&replacement))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let mut watcher = DepositWatcher::new(&client).with_confirmations(2);
+    watcher.watch_unchecked(&bob);
+
+    let deposits: Vec<_> = watcher.deposits().await.unwrap().collect().await;
+    assert_eq!(deposits.len(), 1);
+    let deposit = deposits[0].as_ref().unwrap();
+    assert_eq!(deposit.amount.to_string(), "3");
+    assert_eq!(deposit.height, 50);
+    assert_eq!(deposit.block_hash, replacement["hash"]);
+}
+
+#[tokio::test]
+async fn test_withdrawal_processor_pipeline() {
+    use kadena::crypto::PactKeypair;
+    use kadena::fetch::{MemoryStorage, Withdrawal, WithdrawalProcessor};
+    use kadena::pact::{FeePolicy, KeySet};
+    use rust_decimal::Decimal;
+
+    let mock_server = MockServer::start().await;
+    let api = "/chainweb/0.0/testnet04/chain/1/pact/api/v1";
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"requestKeys": [REQUEST_KEY]})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    // The payout is not mined when first submitted
+    Mock::given(method("POST"))
+        .and(path(format!("{}/poll", api)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/poll", api)))
+        .respond_with(|request: &wiremock::Request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let key = body["requestKeys"][0].as_str().unwrap().to_string();
+            ResponseTemplate::new(200).set_body_json(json!({
+                key.clone(): {
+                    "reqKey": key,
+                    "result": {"status": "success", "data": "Write succeeded"},
+                    "gas": 600
+                }
+            }))
+        })
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let (a, b) = (PactKeypair::generate(), PactKeypair::generate());
+    let queue = TxQueue::open(MemoryStorage::default()).unwrap();
+    let mut processor = WithdrawalProcessor::new(&client, queue, "hot-wallet")
+        .with_signer(&a)
+        .with_signer(&b)
+        .with_fee_policy(FeePolicy::new().with_max_fee(Decimal::new(1, 3)));
+
+    let withdrawal = Withdrawal::new("w-1", "k:abc", Decimal::new(15, 1), "1", 1_700_000_000)
+        .with_receiver_guard(KeySet::keys_all(["abc"]));
+    let request_key = processor.submit(&withdrawal).await.unwrap();
+    // Requesting the same withdrawal again does not queue a second payout
+    assert_eq!(processor.submit(&withdrawal).await.unwrap(), request_key);
+    assert_eq!(processor.queue().entries().len(), 1);
+
+    let cmd = &processor.get("w-1").unwrap().cmd;
+    assert_eq!(cmd.sigs.len(), 2);
+    let payload = cmd.payload().unwrap();
+    assert_eq!(payload.network_id.as_deref(), Some("testnet04"));
+    assert_eq!(payload.signers[1].clist[1].name, "coin.TRANSFER");
+    assert!(payload
+        .payload
+        .code()
+        .unwrap()
+        .starts_with("(coin.transfer-create"));
+
+    let costly = Withdrawal::new("w-2", "k:abc", Decimal::ONE, "1", 1_700_000_000);
+    let mut processor = processor.with_gas_limit(200_000);
+    let error = processor.submit(&costly).await.unwrap_err();
+    assert_eq!(error.error_code(), "command.fee_exceeded");
+    assert!(processor.get("w-2").is_none());
+
+    let report = processor.process().await.unwrap();
+    assert_eq!(report.submitted, 1);
+    assert_eq!(report.confirmed, 1);
+    assert!(processor.is_settled());
+    assert_eq!(processor.drain_settled().unwrap()[0].cmd.hash, request_key);
+
+    // Once drained, the mined payout is found instead of being sent again
+    let mut processor = processor.with_gas_limit(1500);
+    assert_eq!(processor.submit(&withdrawal).await.unwrap(), request_key);
+    assert!(processor.get("w-1").is_none());
+}
+
+#[tokio::test]
+async fn test_simulation_mode_runs_sends_as_preflight() {
+    use kadena::fetch::{TxStatus, TxTracker};
+
+    let mock_server = MockServer::start().await;
+    let api = "/chainweb/0.0/testnet04/chain/0/pact/api/v1";
+    Mock::given(method("POST"))
+        .and(path(format!("{}/local", api)))
+        .and(query_param("preflight", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "preflightResult": {
+                "reqKey": REQUEST_KEY,
+                "result": {"status": "failure", "error": {"message": "Insufficient funds"}},
+                "gas": 312,
+                "metaData": {
+                    "blockHeight": 100,
+                    "blockTime": 1700000000000000u64,
+                    "prevBlockHash": "prev-hash"
+                }
+            },
+            "preflightWarnings": []
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    for route in ["send", "poll", "listen"] {
+        Mock::given(method("POST"))
+            .and(path(format!("{}/{}", api, route)))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let cmd = Cmd {
+        hash: REQUEST_KEY.to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+    let client =
+        ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0").with_simulation());
+    let response = client.send(&cmd).await.unwrap();
+    assert_eq!(response["requestKeys"][0], REQUEST_KEY);
+
+    let result = client.listen(REQUEST_KEY).await.unwrap();
+    assert_eq!(result.gas, 312);
+    assert_eq!(
+        result.failure().unwrap().error_code(),
+        "pact.insufficient_funds"
+    );
+
+    let mut tracker = TxTracker::new(&client);
+    tracker.track(REQUEST_KEY).unwrap();
+    let updates = tracker.poll_once().await.unwrap();
+    assert!(matches!(updates[0].status, TxStatus::Failed(_)));
+    assert!(tracker.is_empty());
+}
+
+#[tokio::test]
+async fn test_record_and_replay_node_interactions() {
+    use kadena::fetch::{RecordingTransport, ReplayTransport, ReqwestTransport};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            REQUEST_KEY: {
+                "reqKey": REQUEST_KEY,
+                "result": {"status": "success", "data": "Write succeeded"},
+                "gas": 600
+            }
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chainweb/0.0/testnet04/chain/0/pact/api/v1/local"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("Validation failed"))
+        .mount(&mock_server)
+        .await;
+
+    let recording =
+        std::env::temp_dir().join(format!("kadena-recording-{}.json", std::process::id()));
+    let cmd = Cmd {
+        hash: REQUEST_KEY.to_string(),
+        sigs: vec![],
+        cmd: "test_cmd".to_string(),
+    };
+    let config = ApiConfig::new(&mock_server.uri(), "testnet04", "0").with_api_key("secret");
+    let client = ApiClient::with_transport(
+        config.clone(),
+        RecordingTransport::new(ReqwestTransport::default(), &recording),
+    );
+    assert!(client.poll(&[REQUEST_KEY]).await.unwrap().is_empty());
+    assert!(client.poll(&[REQUEST_KEY]).await.unwrap()[REQUEST_KEY].is_success());
+    assert!(client.local(&cmd).await.is_err());
+    drop(mock_server);
+
+    let json = std::fs::read_to_string(&recording).unwrap();
+    assert!(!json.contains("secret"));
+
+    let client = ApiClient::with_transport(config, ReplayTransport::open(&recording).unwrap());
+    // Repeated requests are answered in recorded order
+    assert!(client.poll(&[REQUEST_KEY]).await.unwrap().is_empty());
+    assert!(client.poll(&[REQUEST_KEY]).await.unwrap()[REQUEST_KEY].is_success());
+    let error = client.local(&cmd).await.unwrap_err();
+    assert_eq!(error.to_string(), "API error: Validation failed");
+
+    let error = client.poll(&[REQUEST_KEY]).await.unwrap_err();
+    assert_eq!(error.error_code(), "fetch.not_recorded");
+    assert!(error
+        .to_string()
+        .ends_with("POST /chainweb/0.0/testnet04/chain/0/pact/api/v1/poll"));
+    std::fs::remove_file(&recording).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_chainweb_client() {
+    use kadena::fetch::{RecordingTransport, ReplayTransport, ReqwestTransport};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"height": 42})))
+        .mount(&mock_server)
+        .await;
+    // base64url of `{"hash":"h"}` and `{"reqKey":"h"}`
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/payload/p1/outputs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "transactions": [["eyJoYXNoIjoiaCJ9", "eyJyZXFLZXkiOiJoIn0"]],
+            "minerData": "",
+            "coinbase": "eyJyZXFLZXkiOiJjIn0",
+            "payloadHash": "p1",
+            "transactionsHash": "t1",
+            "outputsHash": "o1"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let recording = std::env::temp_dir().join(format!(
+        "kadena-chainweb-recording-{}.json",
+        std::process::id()
+    ));
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04").with_transport(
+        RecordingTransport::new(ReqwestTransport::default(), &recording),
+    );
+    assert_eq!(client.cut().await.unwrap()["height"], 42);
+    assert_eq!(
+        client.payload_with_outputs("1", "p1").await.unwrap().len(),
+        1
+    );
+    drop(mock_server);
+
+    let client = ChainwebClient::new("http://offline", "testnet04")
+        .with_transport(ReplayTransport::open(&recording).unwrap());
+    assert_eq!(client.cut().await.unwrap()["height"], 42);
+    let payload = client.payload_with_outputs("1", "p1").await.unwrap();
+    assert_eq!(payload.coinbase().unwrap()["reqKey"], "c");
+    assert_eq!(
+        client
+            .payload_with_outputs("1", "p2")
+            .await
+            .unwrap_err()
+            .error_code(),
+        "fetch.not_recorded"
+    );
+    std::fs::remove_file(&recording).unwrap();
+}
+
+#[tokio::test]
+async fn test_cross_chain_checks_follow_the_chain_graph() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/info"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "nodeNumberOfChains": 4,
+            "nodeGraphHistory": [
+                [100, [[0, [1, 3]], [1, [0, 2]], [2, [1, 3]], [3, [2, 0]]]],
+                [0, [[0, [1]], [1, [0]]]]
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/cut"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "hashes": {
+                "0": {"height": 150, "hash": "h0"},
+                "1": {"height": 149, "hash": "h1"}
+            },
+            "height": 299
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let history = client.chain_graph_history().await.unwrap();
+    assert!(history.at(50).unwrap().check_cross_chain(0, 2).is_err());
+
+    let graph = client.chain_graph().await.unwrap();
+    assert_eq!(graph.chains().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    assert_eq!(client.check_cross_chain("1", "3").await.unwrap().len(), 3);
+
+    let error = client.check_cross_chain("0", "9").await.unwrap_err();
+    assert_eq!(error.error_code(), "fetch.unreachable_chain");
+    assert!(error.to_string().contains("chain 9 is not part"));
+
+    let pact_server = ChainwebClient::new(&mock_server.uri(), "testnet04")
+        .with_endpoint(EndpointKind::PactServer);
+    assert!(pact_server.chain_graph_history().await.is_err());
+}
+
+#[tokio::test]
+async fn test_submit_rotation_ceremony() {
+    use kadena::crypto::PactKeypair;
+    use kadena::pact::{KeyRotation, KeySet, Meta};
+    use rust_decimal::Decimal;
+    use wiremock::matchers::body_string_contains;
+
+    let old: Vec<_> = (0..3).map(|_| PactKeypair::generate()).collect();
+    let new: Vec<_> = (0..3).map(|_| PactKeypair::generate()).collect();
+    let old_keyset = KeySet::keys_2(old.iter().map(|k| k.public_key()));
+    let rotation = KeyRotation::new(
+        old_keyset.clone(),
+        KeySet::keys_2(new.iter().map(|k| k.public_key())),
+        Decimal::new(5_000, 0),
+    )
+    .with_old_approvers([old[0].public_key(), old[1].public_key()])
+    .with_new_approvers([new[1].public_key(), new[2].public_key()]);
+    let (old_account, new_account) = (rotation.old_account(), rotation.new_account());
+
+    let mock_server = MockServer::start().await;
+    let api = "/chainweb/0.0/testnet04/chain/0/pact/api/v1";
+    Mock::given(method("POST"))
+        .and(path(format!("{}/local", api)))
+        .and(body_string_contains(old_account.as_str()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"account": old_account, "balance": 5000.0, "guard": old_keyset}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/local", api)))
+        .and(body_string_contains(new_account.as_str()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "failure", "error": {"message": "with-read: row not found"}}
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/send", api)))
+        .and(body_string_contains("coin.transfer-create"))
+        .respond_with(|request: &wiremock::Request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let key = body["cmds"][0]["hash"].clone();
+            ResponseTemplate::new(200).set_body_json(json!({ "requestKeys": [key] }))
+        })
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/listen", api)))
+        .respond_with(|request: &wiremock::Request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            ResponseTemplate::new(200).set_body_json(json!({
+                "reqKey": body["listen"],
+                "result": {"status": "success", "data": "Write succeeded"},
+                "gas": 900
+            }))
+        })
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let meta = || Meta::new("0", &old_account);
+
+    let mut ceremony = rotation.prepare(meta(), "testnet04").unwrap();
+    for keypair in [&old[0], &old[1], &new[1]] {
+        ceremony.sign(keypair).unwrap();
+    }
+    assert!(client.submit_rotation(ceremony.clone()).await.is_err());
+    ceremony.sign(&new[2]).unwrap();
+    let result = client.submit_rotation(ceremony).await.unwrap();
+    assert!(result.failure().is_none());
+
+    // The approvers satisfy the old keyset offline, but not the guard on chain
+    let on_chain = KeySet::keys_all(old.iter().map(|k| k.public_key()));
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("{}/local", api)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"account": old_account, "balance": 5000.0, "guard": on_chain}}
+        })))
+        .mount(&mock_server)
+        .await;
+    let client = ApiClient::new(ApiConfig::new(&mock_server.uri(), "testnet04", "0"));
+    let mut ceremony = rotation.prepare(meta(), "testnet04").unwrap();
+    for keypair in [&old[0], &old[1], &new[1], &new[2]] {
+        ceremony.sign(keypair).unwrap();
+    }
+    assert!(matches!(
+        client.submit_rotation(ceremony).await,
+        Err(FetchError::CommandError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_payload_outputs_decode_into_command_results() {
+    use kadena::crypto::base64url_encode;
+    use kadena::pact::PactResult;
+
+    let encode = |value: serde_json::Value| base64url_encode(value.to_string().as_bytes());
+    let transfer = json!({
+        "name": "TRANSFER",
+        "module": {"namespace": null, "name": "coin"},
+        "moduleHash": "rE7DU8jlQL9x_MPYuniZJf5ICBTAEHAIFQCB4blofP4",
+        "params": ["k:alice", "k:bob", {"decimal": "2.5"}]
+    });
+    let success = json!({
+        "gas": 736,
+        "result": {"status": "success", "data": "Write succeeded"},
+        "reqKey": REQUEST_KEY,
+        "logs": "wsATyGqckuIvlm89hhd2j4t6RMkCrcwJe_oeCYr7Th8",
+        "events": [transfer],
+        "metaData": null,
+        "continuation": null,
+        "txId": 1033
+    });
+    let failure = json!({
+        "gas": 1500,
+        "result": {"status": "failure", "error": {"message": "row not found: k:nobody", "type": "TxFailure"}},
+        "reqKey": "rk2",
+        "logs": null,
+        "events": [],
+        "metaData": null,
+        "continuation": null,
+        "txId": null
+    });
+    let coinbase = json!({
+        "gas": 0,
+        "result": {"status": "success", "data": "Write succeeded"},
+        "reqKey": "parent-hash",
+        "logs": "log",
+        "events": [{
+            "name": "TRANSFER",
+            "module": {"namespace": null, "name": "coin"},
+            "params": ["", "k:miner", 1.15]
+        }],
+        "metaData": null,
+        "continuation": null,
+        "txId": 1032
+    });
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/chainweb/0.0/testnet04/chain/1/payload/p2/outputs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "transactions": [[encode(json!({})), encode(success)], [encode(json!({})), encode(failure)]],
+            "minerData": "",
+            "coinbase": encode(coinbase),
+            "payloadHash": "p2",
+            "transactionsHash": "t2",
+            "outputsHash": "o2"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04");
+    let payload = client.payload_with_outputs("1", "p2").await.unwrap();
+    let results: Vec<_> = payload.outputs().collect::<Result<_, _>>().unwrap();
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].req_key, REQUEST_KEY);
+    assert_eq!(results[0].tx_id, Some(1033));
+    assert_eq!(results[0].gas, 736);
+    assert_eq!(
+        results[0].result,
+        PactResult::Success {
+            data: json!("Write succeeded")
+        }
+    );
+    assert_eq!(results[0].events[0].qualified_name(), "coin.TRANSFER");
+    assert_eq!(results[0].events[0].param(1), Some(&json!("k:bob")));
+    assert_eq!(results[0].block_height(), None);
+
+    assert_eq!(results[1].tx_id, None);
+    assert_eq!(
+        results[1].failure().unwrap().error_code(),
+        "pact.account_not_found"
+    );
+
+    let coinbase = payload.coinbase_result().unwrap();
+    assert_eq!(coinbase.req_key, "parent-hash");
+    assert_eq!(coinbase.events[0].param(1), Some(&json!("k:miner")));
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::{c_char, CStr, CString};
+
+use kadena::crypto::{base64url_decode, hash, PactKeypair};
+use kadena::ffi::*;
+use serde_json::{json, Value};
+
+/// Take ownership of a string returned by the library
+fn take(s: *mut c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    let value = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+    unsafe { kadena_string_free(s) };
+    Some(value)
+}
+
+#[test]
+fn test_keys_hashing_and_signing() {
+    let keypair: Value = serde_json::from_str(&take(kadena_keypair_generate()).unwrap()).unwrap();
+    let secret = CString::new(keypair["secretKey"].as_str().unwrap()).unwrap();
+    let restored = PactKeypair::from_secret_key(keypair["secretKey"].as_str().unwrap()).unwrap();
+
+    assert_eq!(
+        take(unsafe { kadena_public_key(secret.as_ptr()) }).unwrap(),
+        keypair["publicKey"].as_str().unwrap()
+    );
+
+    let msg = b"hello";
+    assert_eq!(
+        take(unsafe { kadena_hash(msg.as_ptr(), msg.len()) }).unwrap(),
+        hash(msg)
+    );
+    let signature = take(unsafe { kadena_sign(secret.as_ptr(), msg.as_ptr(), msg.len()) }).unwrap();
+    assert!(restored.verify(msg, &signature).unwrap());
+
+    let digest = CString::new(hash(msg)).unwrap();
+    let signature = take(unsafe { kadena_sign_hash(secret.as_ptr(), digest.as_ptr()) }).unwrap();
+    assert!(restored
+        .verify(&base64url_decode(&hash(msg)).unwrap(), &signature)
+        .unwrap());
+}
+
+#[test]
+fn test_prepare_exec() {
+    let keypair = PactKeypair::generate();
+    let request = json!({
+        "code": "(+ 1 2)",
+        "chainId": "1",
+        "sender": format!("k:{}", keypair.public_key()),
+        "networkId": "testnet04",
+        "gasLimit": 2000,
+        "signers": [{
+            "secretKey": keypair.secret_key(),
+            "caps": [{"name": "coin.GAS", "args": []}],
+        }],
+    });
+    let request = CString::new(request.to_string()).unwrap();
+
+    let cmd: Value =
+        serde_json::from_str(&take(unsafe { kadena_prepare_exec(request.as_ptr()) }).unwrap())
+            .unwrap();
+    let payload: Value = serde_json::from_str(cmd["cmd"].as_str().unwrap()).unwrap();
+    assert_eq!(payload["networkId"], "testnet04");
+    assert_eq!(payload["meta"]["chainId"], "1");
+    assert_eq!(payload["meta"]["gasLimit"], 2000);
+    assert_eq!(payload["signers"][0]["clist"][0]["name"], "coin.GAS");
+
+    let hash = base64url_decode(cmd["hash"].as_str().unwrap()).unwrap();
+    assert!(keypair
+        .verify(&hash, cmd["sigs"][0]["sig"].as_str().unwrap())
+        .unwrap());
+}
+
+#[test]
+fn test_errors_are_reported() {
+    let bad_key = CString::new("not hex").unwrap();
+    assert!(unsafe { kadena_public_key(bad_key.as_ptr()) }.is_null());
+    assert!(take(kadena_last_error()).is_some());
+
+    assert!(unsafe { kadena_prepare_exec(std::ptr::null()) }.is_null());
+    assert_eq!(
+        take(kadena_last_error()).unwrap(),
+        "Null pointer passed as request"
+    );
+
+    let invalid = CString::new("{\"code\": 1}").unwrap();
+    assert!(unsafe { kadena_prepare_exec(invalid.as_ptr()) }.is_null());
+    assert!(take(kadena_last_error())
+        .unwrap()
+        .starts_with("Invalid request"));
+}
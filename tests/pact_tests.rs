@@ -1,3 +1,5 @@
+#![cfg(feature = "pact")]
+
 use kadena::{
     crypto::PactKeypair,
     pact::{cap::Cap, command::Cmd, meta::Meta},
@@ -30,6 +32,55 @@ mod meta_tests {
         assert_eq!(meta.gas_price, 0.00000002);
         assert_eq!(meta.ttl, 7200);
     }
+
+    #[test]
+    fn test_meta_network_profiles() {
+        use kadena::pact::{MetaProfile, NetworkId};
+
+        let mainnet = Meta::for_network(NetworkId::Mainnet, "1", "k:abc123");
+        assert_eq!(mainnet.chain_id, "1");
+        assert_eq!(mainnet.gas_limit, MetaProfile::MAINNET.gas_limit);
+        assert_eq!(mainnet.gas_price, MetaProfile::MAINNET.gas_price);
+
+        let custom = Meta::for_network("my-devnet".into(), "0", "k:abc123");
+        assert_eq!(custom.gas_limit, Meta::new("0", "k:abc123").gas_limit);
+        assert_eq!(
+            MetaProfile::for_network(&"testnet04".parse().unwrap()),
+            MetaProfile::TESTNET
+        );
+
+        assert_eq!(
+            serde_json::to_value(NetworkId::Development).unwrap(),
+            "development"
+        );
+        assert_eq!(String::from(NetworkId::Mainnet), "mainnet01");
+    }
+
+    #[test]
+    fn test_meta_creation_time_skew() {
+        use kadena::pact::{MetaProfile, DEFAULT_CREATION_TIME_SKEW};
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let meta = Meta::new("0", "k:abc123");
+        assert!(meta.creation_time <= now - DEFAULT_CREATION_TIME_SKEW + 1);
+        assert!(meta.creation_time >= now - DEFAULT_CREATION_TIME_SKEW - 1);
+
+        let unskewed = Meta::with_profile(
+            MetaProfile {
+                creation_time_skew: 0,
+                ..MetaProfile::DEFAULT
+            },
+            "0",
+            "k:abc123",
+        );
+        assert!(unskewed.creation_time >= now);
+
+        let lagging = meta.with_creation_time_skew(120);
+        assert!(lagging.creation_time <= now - 119);
+    }
 }
 
 mod cap_tests {
@@ -67,11 +118,13 @@ mod cap_tests {
 
 mod command_tests {
     use super::*;
+    use kadena::crypto::{base64url_decode, CryptoError, Signature, Signer};
+    use kadena::pact::CommandError;
 
     #[test]
     fn test_prepare_exec_cmd() {
         let keypair = PactKeypair::generate();
-        let sender = format!("k:{}", keypair.public_key);
+        let sender = format!("k:{}", keypair.public_key());
 
         let meta = Meta::new("0", &sender);
 
@@ -102,7 +155,7 @@ mod command_tests {
 
         let signer = &cmd_json["signers"][0];
         assert_eq!(signer["scheme"], "ED25519");
-        assert_eq!(signer["pubKey"], keypair.public_key);
+        assert_eq!(signer["pubKey"], keypair.public_key());
 
         // Verify capabilities
         let clist = &signer["clist"];
@@ -111,6 +164,56 @@ mod command_tests {
         assert_eq!(clist[1]["name"], "coin.TRANSFER");
     }
 
+    struct FailingSigner(String);
+
+    impl Signer for FailingSigner {
+        fn public_key(&self) -> &str {
+            &self.0
+        }
+
+        fn sign(&self, _msg: &[u8]) -> Result<Signature, CryptoError> {
+            Err(CryptoError::HsmError("token removed".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_prepare_exec_with_signers() {
+        let keypair = PactKeypair::generate();
+        let signer: &dyn Signer = &keypair;
+        let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+
+        let cmd = Cmd::prepare_exec_with_signers(
+            &[(signer, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            "(+ 1 2)",
+            None,
+            meta,
+            Some("testnet04".to_string()),
+        )
+        .unwrap();
+
+        let hash = base64url_decode(&cmd.hash).unwrap();
+        assert!(keypair.verify(&hash, &cmd.sigs[0].sig).unwrap());
+    }
+
+    #[test]
+    fn test_prepare_exec_with_signers_propagates_errors() {
+        let signer = FailingSigner(PactKeypair::generate().public_key().to_string());
+        let meta = Meta::new("0", &format!("k:{}", signer.0));
+
+        let result = Cmd::prepare_exec_with_signers(
+            &[(&signer as &dyn Signer, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            "(+ 1 2)",
+            None,
+            meta,
+            None,
+        );
+        assert!(matches!(result, Err(CommandError::SigningError(_))));
+    }
+
     // #[test]
     // fn test_prepare_exec_cmd_to_sign() {
     //     let public_key = "5ea2aa347593e8907b0b4de4698a99a57a88dcbc762916688c640ec8efc05f9f";
@@ -143,7 +246,7 @@ mod command_tests {
     #[test]
     fn test_random_nonce_generation() {
         let keypair = PactKeypair::generate();
-        let sender = format!("k:{}", keypair.public_key);
+        let sender = format!("k:{}", keypair.public_key());
         let meta = Meta::new("0", &sender);
         let caps = vec![Cap::new("coin.GAS")];
 
@@ -180,7 +283,7 @@ mod command_tests {
         let keypair1 = PactKeypair::generate();
         let keypair2 = PactKeypair::generate(); // Generate a random second keypair
 
-        let sender = format!("k:{}", keypair1.public_key);
+        let sender = format!("k:{}", keypair1.public_key());
         let meta = Meta::new("0", &sender);
 
         let caps1 = vec![Cap::new("coin.GAS")];
@@ -202,14 +305,14 @@ mod command_tests {
         let cmd_json: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
         let signers = cmd_json["signers"].as_array().unwrap();
         assert_eq!(signers.len(), 2);
-        assert_eq!(signers[0]["pubKey"], keypair1.public_key);
-        assert_eq!(signers[1]["pubKey"], keypair2.public_key);
+        assert_eq!(signers[0]["pubKey"], keypair1.public_key());
+        assert_eq!(signers[1]["pubKey"], keypair2.public_key());
     }
 
     #[test]
     fn test_complex_capabilities() {
         let keypair = PactKeypair::generate();
-        let sender = format!("k:{}", keypair.public_key);
+        let sender = format!("k:{}", keypair.public_key());
 
         // Create a complex capability with nested JSON
         let complex_cap = Cap::new("custom.CAP").add_arg(json!({
@@ -245,56 +348,2000 @@ mod command_tests {
         assert_eq!(complex["args"][0]["nested"]["string"], "value");
         assert_eq!(complex["args"][0]["nested"]["number"], 42.5);
     }
+
+    #[test]
+    fn test_read_keyset_registers_data() {
+        use kadena::pact::KeySet;
+
+        let keypair = PactKeypair::generate();
+        let mut data = serde_json::Value::Null;
+        let keyset = KeySet::keys_any([keypair.public_key()]).read_keyset("owner", &mut data);
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            &format!("(define-keyset \"free.owner\" {})", keyset),
+            Some(data),
+            Meta::new("0", &format!("k:{}", keypair.public_key())),
+            None,
+        )
+        .unwrap();
+
+        let payload = cmd.payload().unwrap();
+        assert_eq!(
+            payload.payload.code(),
+            Some("(define-keyset \"free.owner\" (read-keyset \"owner\"))")
+        );
+        assert_eq!(payload.payload.data()["owner"]["pred"], "keys-any");
+    }
 }
 
-// Integration tests to verify module interactions
-mod integration_tests {
+mod defpact_tests {
     use super::*;
+    use kadena::pact::{CommandError, CommandResult, PactInstance};
 
     #[test]
-    fn test_full_transaction_preparation() {
+    fn test_pact_instance_drives_continuations() {
         let keypair = PactKeypair::generate();
-        let sender = format!("k:{}", keypair.public_key);
+        let sender = format!("k:{}", keypair.public_key());
 
-        // Create metadata
-        let meta = Meta::new("0", &sender)
-            .with_gas_limit(2000)
-            .with_gas_price(0.00000001);
+        let mut instance = PactInstance::from_result(&json!({
+            "result": {
+                "reqKey": "pact-1",
+                "result": {"status": "success"},
+                "continuation": {"pactId": "pact-1", "step": 0, "stepCount": 3, "stepHasRollback": true}
+            }
+        }))
+        .unwrap();
+        assert_eq!(instance.executed_steps, vec![0]);
+        assert_eq!(instance.rollback_continuation().unwrap().step, 0);
 
-        // Create capabilities
-        let caps = vec![Cap::new("coin.GAS"), Cap::transfer(&sender, "Bob", 10.0)];
+        let cmd = instance
+            .prepare_next(
+                &[(&keypair, vec![Cap::new("coin.GAS")])],
+                Some(json!({"buyer": "k:abc"})),
+                None,
+                Meta::new("0", &sender),
+                Some("testnet04".to_string()),
+            )
+            .unwrap();
+        let cmd_json: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
+        assert_eq!(cmd_json["payload"]["cont"]["pactId"], "pact-1");
+        assert_eq!(cmd_json["payload"]["cont"]["step"], 1);
+        assert_eq!(cmd_json["payload"]["cont"]["rollback"], false);
+        assert_eq!(cmd_json["payload"]["cont"]["data"]["buyer"], "k:abc");
 
-        // Create Pact code
-        let pact_code = format!("(coin.transfer \"{}\" \"Bob\" 10.0)", sender);
+        instance
+            .update(&json!({"continuation": {"pactId": "pact-1", "step": 1, "stepCount": 3}}))
+            .unwrap();
+        instance
+            .update(&json!({"continuation": {"pactId": "pact-1", "step": 2, "stepCount": 3}}))
+            .unwrap();
+        assert!(instance.is_complete());
+        assert!(matches!(
+            instance.next_continuation(),
+            Err(CommandError::InvalidContinuation(_))
+        ));
+    }
 
-        // Prepare command
-        let cmd = Cmd::prepare_exec(
-            &[(&keypair, caps)],
+    #[test]
+    fn test_typed_continuation_from_poll_result() {
+        let result = CommandResult::from_value(json!({
+            "reqKey": "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c",
+            "txId": 10,
+            "result": {"status": "success", "data": "Write succeeded"},
+            "gas": 600,
+            "continuation": {
+                "pactId": "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c",
+                "step": 0,
+                "stepCount": 2,
+                "executed": null,
+                "stepHasRollback": false,
+                "continuation": {
+                    "def": "coin.transfer-crosschain",
+                    "args": ["k:a", "k:b", {"keys": ["b"], "pred": "keys-all"}, "3", 1.5]
+                },
+                "yield": {
+                    "data": {"receiver": "k:b", "amount": 1.5},
+                    "provenance": {"targetChainId": "3", "moduleHash": "M1gabakqkEi_1N8dRKt4z5lEv1kuC_nxLTnyDCuZIK0"},
+                    "source": "0"
+                }
+            }
+        }))
+        .unwrap();
+
+        let exec = result.continuation.as_ref().unwrap();
+        assert_eq!(
+            exec.continuation.as_ref().unwrap().def,
+            "coin.transfer-crosschain"
+        );
+        assert_eq!(exec.yield_data.as_ref().unwrap().data["receiver"], "k:b");
+        assert_eq!(exec.target_chain_id(), Some("3"));
+        assert_eq!(exec.next_continuation().unwrap().step, 1);
+
+        let instance = PactInstance::from_command_result(&result).unwrap();
+        assert_eq!(instance.pact_id, exec.pact_id);
+        assert_eq!(instance.next_step(), Some(1));
+    }
+}
+
+mod rotate_tests {
+    use super::*;
+    use kadena::pact::{CommandError, GuardRotation, KeySet};
+
+    #[test]
+    fn test_rotation_command() {
+        let keypair = PactKeypair::generate();
+        let new_key = PactKeypair::generate();
+        let account = format!("k:{}", keypair.public_key());
+        let keyset = KeySet::keys_any([
+            keypair.public_key().to_string(),
+            new_key.public_key().to_string(),
+        ]);
+
+        let cmd = GuardRotation::new(&account, keyset.clone())
+            .prepare(
+                &[&keypair],
+                Meta::new("0", &account),
+                Some("testnet04".to_string()),
+            )
+            .unwrap();
+
+        let cmd_json: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
+        assert_eq!(
+            cmd_json["payload"]["exec"]["code"],
+            format!("(coin.rotate \"{}\" (read-keyset \"ks\"))", account)
+        );
+        assert_eq!(
+            cmd_json["payload"]["exec"]["data"]["ks"],
+            serde_json::to_value(&keyset).unwrap()
+        );
+        let clist = &cmd_json["signers"][0]["clist"];
+        assert_eq!(clist[1]["name"], "coin.ROTATE");
+        assert_eq!(clist[1]["args"][0], account);
+    }
+
+    #[test]
+    fn test_rotation_lockout_check() {
+        let keypair = PactKeypair::generate();
+        let account = format!("k:{}", keypair.public_key());
+        let rotation = GuardRotation::new(&account, KeySet::keys_all(["someone-else"]));
+
+        let result = rotation.prepare(&[&keypair], Meta::new("0", &account), None);
+        assert!(matches!(result, Err(CommandError::UnsatisfiedKeyset(_))));
+
+        let result =
+            rotation
+                .with_lockout_check(false)
+                .prepare(&[&keypair], Meta::new("0", &account), None);
+        assert!(result.is_ok());
+    }
+}
+
+mod lint_tests {
+    use super::*;
+    use kadena::pact::{validate_code, CommandError, LintError};
+
+    #[test]
+    fn test_validate_code_accepts_valid_code() {
+        assert!(validate_code("(coin.transfer \"a\" \"b\" 1.0)").is_ok());
+        assert!(validate_code("(let ((x [1 2])) { \"k\": x }) ; trailing )").is_ok());
+        assert!(validate_code("(format \"{} \\\" ({})\" [1 2])").is_ok());
+    }
+
+    #[test]
+    fn test_validate_code_rejects_invalid_code() {
+        assert_eq!(
+            validate_code("  \n; only a comment"),
+            Err(LintError::EmptyCode)
+        );
+        assert_eq!(
+            validate_code("(+ 1 2))"),
+            Err(LintError::UnexpectedClosing {
+                delimiter: ')',
+                position: 7
+            })
+        );
+        assert_eq!(
+            validate_code("(list [1 2)"),
+            Err(LintError::MismatchedDelimiter {
+                expected: ']',
+                found: ')',
+                position: 10
+            })
+        );
+        assert_eq!(
+            validate_code("(coin.details \"k:abc)"),
+            Err(LintError::UnterminatedString { position: 14 })
+        );
+    }
+
+    #[test]
+    fn test_lint_signers() {
+        use kadena::pact::{
+            lint_signers, CommandPayload, CommandSigner, ContCommand, SignerWarning,
+        };
+
+        let payload = CommandPayload::new(Meta::new("0", "k:alice"))
+            .with_code("(free.dex.swap coin \"k:alice\" 1.0) ; free.nft.MINT".to_string())
+            .add_signer(CommandSigner::new_ed25519(
+                "alice-key",
+                vec![
+                    Cap::new("coin.GAS"),
+                    Cap::transfer("k:alice", "free.dex-pool", 1.0),
+                    Cap::new("free.dex.SWAP"),
+                    Cap::new("free.nft.MINT"),
+                ],
+            ))
+            .add_signer(CommandSigner::new_ed25519("bob-key", Vec::new()));
+
+        let warnings = lint_signers(&payload);
+        assert_eq!(
+            warnings,
+            [
+                SignerWarning::UnusedCapability {
+                    pub_key: "alice-key".to_string(),
+                    cap: "free.nft.MINT".to_string(),
+                },
+                SignerWarning::UnscopedSigner {
+                    pub_key: "bob-key".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "Signer bob-key is unscoped and grants every capability"
+        );
+
+        // The code of continuations is not known, only unscoped signers are reported
+        let cont = payload.with_continuation(ContCommand::new("pid", 1));
+        assert_eq!(lint_signers(&cont).len(), 1);
+    }
+
+    #[test]
+    fn test_prepare_exec_rejects_invalid_code() {
+        let keypair = PactKeypair::generate();
+        let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+
+        let result = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::new("coin.GAS")])],
             Vec::new(),
-            Some("test-nonce"),
-            &pact_code,
+            None,
+            "(+ 1 2",
             None,
             meta,
             Some("testnet04".to_string()),
+        );
+        assert!(matches!(result, Err(CommandError::InvalidCode(_))));
+    }
+}
+
+mod pretty_tests {
+    use kadena::pact::{format_code, CodeFormatter, LintError};
+
+    #[test]
+    fn test_format_code_normalizes_layout() {
+        let messy = "(let ((x   1)\n (y 2))\n\n   (+ x y))  ; sum\n(coin.details   \"k:a  b\")";
+        let formatted = format_code(messy).unwrap();
+        assert_eq!(
+            formatted,
+            "(let ((x 1) (y 2)) (+ x y))\n; sum\n(coin.details \"k:a  b\")"
+        );
+        assert_eq!(format_code(&formatted).unwrap(), formatted);
+        assert_eq!(
+            format_code("(+ 1 2"),
+            Err(LintError::Unclosed {
+                delimiter: '(',
+                position: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_format_code_breaks_long_forms() {
+        let formatter = CodeFormatter::new().with_max_width(32);
+        let code = "(coin.transfer-create \"k:alice\" \"k:bob\" (read-keyset \"ks\") {\"a\": 1, \"b\": [1 2 3]})";
+        let formatted = formatter.format(code).unwrap();
+        assert_eq!(
+            formatted,
+            [
+                "(coin.transfer-create \"k:alice\"",
+                "  \"k:bob\"",
+                "  (read-keyset \"ks\")",
+                "  {\"a\": 1, \"b\": [1 2 3]})",
+            ]
+            .join("\n")
+        );
+        assert!(formatted.lines().all(|line| line.len() <= 32));
+        assert_eq!(formatter.format(&formatted).unwrap(), formatted);
+
+        // Comments inside a form force it onto several lines
+        let commented = formatter.format("(do ; first\n(a) (b))").unwrap();
+        assert_eq!(commented, "(do\n  ; first\n  (a)\n  (b))");
+        assert_eq!(formatter.format(&commented).unwrap(), commented);
+    }
+}
+
+mod template_tests {
+    use super::*;
+    use kadena::pact::{code_template, CommandError, Guard, PactValue};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_template_renders_escaped_values() {
+        let template = code_template(
+            "(let ((x {amount})) (free.app.call {user} {opts} (format \"{name}\" [x]) {guard}))",
+        );
+        assert_eq!(template.placeholders(), ["amount", "user", "opts", "guard"]);
+
+        let code = template
+            .bind("amount", Decimal::new(125, 2))
+            .bind("user", "bob\\\"); (coin.drain")
+            .bind(
+                "opts",
+                PactValue::object([
+                    ("retries", PactValue::from(3)),
+                    ("fast", PactValue::from(true)),
+                ]),
+            )
+            .bind(
+                "guard",
+                Guard::Capability(Cap::new("free.app.OWNER").add_arg("bob")),
+            )
+            .render()
+            .unwrap();
+        assert_eq!(
+            code,
+            r#"(let ((x 1.25)) (free.app.call "bob\\\"); (coin.drain" {"retries": 3, "fast": true} (format "{name}" [x]) (create-capability-guard (free.app.OWNER "bob"))))"#
+        );
+    }
+
+    #[test]
+    fn test_template_rejects_missing_and_unused_bindings() {
+        let template = code_template("(coin.details {account})");
+        assert!(matches!(
+            template.render(),
+            Err(CommandError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            template
+                .clone()
+                .bind("account", "k:a")
+                .bind("acount", "k:a")
+                .render(),
+            Err(CommandError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            template
+                .bind(
+                    "account",
+                    Guard::from(kadena::pact::KeySet::keys_all(["a"]))
+                )
+                .render(),
+            Err(CommandError::InvalidPactValue(_))
+        ));
+    }
+}
+
+mod memo_tests {
+    use super::*;
+    use kadena::pact::{read_memo, with_memo, CommandPayload};
+
+    #[test]
+    fn test_memo_round_trip_through_command() {
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            "(+ 1 2)",
+            Some(with_memo(json!({"amount": 1}), "withdrawal-1001")),
+            Meta::new("0", &sender),
+            Some("testnet04".to_string()),
         )
         .unwrap();
 
-        // Verify complete transaction structure
+        let summary = cmd.summarize().unwrap();
+        assert_eq!(summary.memo.as_deref(), Some("withdrawal-1001"));
+        assert_eq!(summary.sender, sender);
+        assert_eq!(summary.signers, vec![keypair.public_key().to_string()]);
+        assert_eq!(summary.signature_count, 1);
+
         let cmd_json: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
+        assert_eq!(read_memo(&cmd_json), Some("withdrawal-1001"));
+        assert_eq!(cmd_json["payload"]["exec"]["data"]["amount"], 1);
+    }
 
-        // Verify metadata
-        assert_eq!(cmd_json["meta"]["chainId"], "0");
-        assert_eq!(cmd_json["meta"]["sender"], sender);
-        assert_eq!(cmd_json["meta"]["gasLimit"], 2000);
+    #[test]
+    fn test_memo_on_payload_and_history_record() {
+        let payload = CommandPayload::new(Meta::new("0", "k:abc")).with_memo("deposit-7");
+        assert_eq!(payload.memo(), Some("deposit-7"));
 
-        // Verify signer and capabilities
-        let signer = &cmd_json["signers"][0];
-        assert_eq!(signer["pubKey"], keypair.public_key);
-        assert_eq!(signer["clist"][0]["name"], "coin.GAS");
-        assert_eq!(signer["clist"][1]["name"], "coin.TRANSFER");
+        let record = json!({"requestKey": "abc", "data": {"memo": "deposit-7"}});
+        assert_eq!(read_memo(&record), Some("deposit-7"));
+        assert_eq!(read_memo(&json!({"data": {}})), None);
+    }
+}
 
-        // Verify signatures
-        assert!(!cmd.sigs.is_empty());
-        assert!(!cmd.hash.is_empty());
+mod module_upgrade_tests {
+    use super::*;
+    use kadena::pact::ModuleUpgrade;
+
+    #[test]
+    fn test_upgrade_code_pins_module_hash() {
+        let keypair = PactKeypair::generate();
+        let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+        let source = "(module my-module GOV (defcap GOV () true))";
+
+        let upgrade = ModuleUpgrade::new("free.my-module", source, "expected-hash")
+            .with_env_data(json!({"upgrade": true}));
+        let cmd = upgrade
+            .prepare(
+                &[(&keypair, vec![Cap::new("coin.GAS")])],
+                meta,
+                Some("testnet04".to_string()),
+            )
+            .unwrap();
+
+        let cmd_json: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
+        let code = cmd_json["payload"]["exec"]["code"].as_str().unwrap();
+        assert!(code.starts_with(
+            "(enforce (= (at 'hash (describe-module \"free.my-module\")) \"expected-hash\")"
+        ));
+        assert!(code.ends_with(source));
+        assert_eq!(cmd_json["payload"]["exec"]["data"]["upgrade"], true);
+    }
+
+    #[test]
+    fn test_upgrade_pinned_to_description() {
+        use kadena::pact::ModuleDescription;
+
+        let deployed: ModuleDescription = serde_json::from_value(json!({
+            "name": "free.my-module",
+            "hash": "deployed-hash",
+            "interfaces": ["fungible-v2", "free.my-interface"],
+            "code": "(module my-module GOV (defcap GOV () true))",
+            "blessed": ["old-hash"],
+            "keyset": "GOV"
+        }))
+        .unwrap();
+        assert!(deployed.implements("fungible-v2"));
+        assert!(deployed.implements("my-interface"));
+        assert!(!deployed.implements("fungible-xchain-v1"));
+        assert!(deployed.accepts_hash("old-hash"));
+        assert!(!deployed.accepts_hash("other-hash"));
+
+        let upgrade = ModuleUpgrade::pinned_to(&deployed, "(module my-module GOV)");
+        assert!(upgrade
+            .guard_code()
+            .contains("(describe-module \"free.my-module\")) \"deployed-hash\")"));
+    }
+}
+
+mod create_account_tests {
+    use super::*;
+    use kadena::pact::{AccountDetails, CommandError, CreateAccount, Guard, KeySet};
+
+    #[test]
+    fn test_create_k_account() {
+        let payer = PactKeypair::generate();
+        let owner = PactKeypair::generate();
+        let account = format!("k:{}", owner.public_key());
+
+        let cmd = CreateAccount::new(&account, KeySet::keys_all([owner.public_key()]))
+            .prepare(
+                &[(&payer, vec![Cap::new("coin.GAS")])],
+                Meta::new("0", &format!("k:{}", payer.public_key())),
+                Some("testnet04".to_string()),
+            )
+            .unwrap();
+
+        let payload = cmd.payload().unwrap();
+        assert_eq!(
+            payload.payload.code(),
+            Some(format!("(coin.create-account \"{}\" (read-keyset \"ks\"))", account).as_str())
+        );
+        assert_eq!(payload.payload.data()["ks"]["keys"][0], owner.public_key());
+    }
+
+    #[test]
+    fn test_w_principal_is_order_independent() {
+        let keyset = KeySet::keys_2(["b", "a", "c"]);
+        let principal = keyset.principal();
+        assert!(principal.starts_with("w:"));
+        assert!(principal.ends_with(":keys-2"));
+        assert_eq!(principal, KeySet::keys_2(["c", "b", "a"]).principal());
+
+        assert!(CreateAccount::new(&principal, keyset)
+            .check_principal()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_principal_mismatch_is_rejected() {
+        let keyset_ref = CreateAccount::new("r:free.ks", Guard::KeySetRef("free.ks".to_string()));
+        assert!(keyset_ref.check_principal().is_ok());
+        assert_eq!(
+            keyset_ref.code(),
+            "(coin.create-account \"r:free.ks\" (keyset-ref-guard \"free.ks\"))"
+        );
+
+        let capability =
+            CreateAccount::new("k:abc123", Guard::Capability(Cap::new("free.vault.VAULT")));
+        assert!(matches!(
+            capability.check_principal(),
+            Err(CommandError::InvalidPrincipal(_))
+        ));
+
+        // Vanity names are not principals and accept any guard
+        let vanity = CreateAccount::new("alice", Guard::Capability(Cap::new("free.vault.VAULT")));
+        assert!(vanity.check_principal().is_ok());
+
+        // Coin reserves every single character prefix
+        let module = Guard::Module {
+            module: "free.vault".to_string(),
+            name: "bank".to_string(),
+        };
+        assert!(CreateAccount::new("m:free.vault:bank", module.clone())
+            .check_principal()
+            .is_ok());
+        for account in ["m:free.vault:other", "p:abc:bank", "x:free.vault:bank"] {
+            assert!(matches!(
+                CreateAccount::new(account, module.clone()).check_principal(),
+                Err(CommandError::InvalidPrincipal(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_principal() {
+        use kadena::pact::validate_principal;
+
+        let owner = PactKeypair::generate();
+        let account = format!("k:{}", owner.public_key());
+        let guard: Guard =
+            serde_json::from_value(json!({"keys": [owner.public_key()], "pred": "keys-all"}))
+                .unwrap();
+        assert!(validate_principal(&account, &guard).is_ok());
+
+        // A k: account requires its single key under keys-all
+        let any = Guard::from(KeySet::keys_any([owner.public_key()]));
+        assert!(validate_principal(&account, &any).is_err());
+        assert!(validate_principal(&any.principal(), &any).is_ok());
+
+        let multisig = KeySet::keys_2(["a", "b"]);
+        let claimed = multisig.principal().replace(":keys-2", ":keys-any");
+        assert!(matches!(
+            validate_principal(&claimed, &multisig.into()),
+            Err(CommandError::InvalidPrincipal(_))
+        ));
+        assert!(validate_principal("alice", &guard).is_err());
+        assert!(
+            validate_principal("c:abc", &Guard::Capability(Cap::new("free.vault.VAULT"))).is_err()
+        );
+    }
+
+    #[test]
+    fn test_user_guard_accounts() {
+        use kadena::crypto::hash;
+
+        let owner = |name: &str| Guard::User {
+            fun: "free.vault.enforce-owner".to_string(),
+            args: vec![json!(name), json!({"int": 1})],
+        };
+        let account = owner("alice").principal();
+        // The function name, then blake2b-256 of `"alice"{"int":1}`
+        assert_eq!(
+            account,
+            format!(
+                "u:free.vault.enforce-owner:{}",
+                hash(br#""alice"{"int":1}"#)
+            )
+        );
+        assert_ne!(account, owner("bob").principal());
+
+        assert!(CreateAccount::new(&account, owner("alice"))
+            .check_principal()
+            .is_ok());
+        assert!(matches!(
+            CreateAccount::new(&account, owner("mallory")).check_principal(),
+            Err(CommandError::InvalidPrincipal(_))
+        ));
+    }
+
+    #[test]
+    fn test_capability_guard_accounts() {
+        use kadena::pact::Transfer;
+        use rust_decimal::Decimal;
+
+        let vault = Cap::new("free.vault.VAULT")
+            .add_arg("alice")
+            .add_arg(json!({"int": 1}));
+        let account = vault.guard_principal();
+        // blake2b-256 of `free.vault.VAULT"alice"{"int":1}`
+        assert_eq!(account.len(), 2 + 43);
+        assert_ne!(
+            account,
+            Cap::new("free.vault.VAULT")
+                .add_arg("bob")
+                .guard_principal()
+        );
+        let guard = Guard::Capability(vault);
+        assert_eq!(guard.principal(), account);
+
+        let create = CreateAccount::new(&account, guard.clone());
+        assert!(create.check_principal().is_ok());
+        assert_eq!(create.guard.env_data("ks"), json!({}));
+        let squatted = CreateAccount::new(
+            &account,
+            Guard::Capability(Cap::new("free.vault.VAULT").add_arg("mallory")),
+        );
+        assert!(matches!(
+            squatted.check_principal(),
+            Err(CommandError::InvalidPrincipal(_))
+        ));
+
+        let payer = PactKeypair::generate();
+        let meta = || Meta::new("0", &format!("k:{}", payer.public_key()));
+        let sender = format!("k:{}", payer.public_key());
+        let deposit = Transfer::new(&sender, &account, Decimal::TEN).with_receiver_guard(guard);
+        assert!(deposit
+            .code()
+            .ends_with(r#"(create-capability-guard (free.vault.VAULT "alice" {"int":1})) 10.0)"#));
+        assert!(deposit.prepare(&[&payer], meta(), None).is_ok());
+        let misdirected = deposit
+            .clone()
+            .with_receiver_guard(Guard::Capability(Cap::new("free.vault.OTHER")));
+        assert!(matches!(
+            misdirected.prepare(&[&payer], meta(), None),
+            Err(CommandError::InvalidPrincipal(_))
+        ));
+    }
+
+    #[test]
+    fn test_guard_json_encodings() {
+        let keyset = KeySet::keys_all(["abc"]);
+        assert_eq!(
+            Guard::from(keyset).to_value(),
+            json!({"keys": ["abc"], "pred": "keys-all"})
+        );
+        assert_eq!(
+            Guard::KeySetRef("free.ks".to_string()).to_value(),
+            json!({"keysetref": {"ns": "free", "ksn": "ks"}})
+        );
+        assert_eq!(
+            Guard::KeySetRef("admin".to_string()).to_value(),
+            json!({"keysetref": "admin"})
+        );
+        assert_eq!(
+            Guard::Capability(Cap::new("free.vault.VAULT").add_arg("alice")).to_value(),
+            json!({"cgName": "free.vault.VAULT", "cgArgs": ["alice"], "cgPactId": null})
+        );
+
+        let user = Guard::User {
+            fun: "free.vault.enforce-owner".to_string(),
+            args: vec![json!("alice")],
+        };
+        assert_eq!(
+            user.to_value(),
+            json!({"fun": "free.vault.enforce-owner", "args": ["alice"]})
+        );
+        assert_eq!(
+            user.code("ks"),
+            "(create-user-guard (free.vault.enforce-owner \"alice\"))"
+        );
+
+        let module = Guard::Module {
+            module: "free.vault".to_string(),
+            name: "bank".to_string(),
+        };
+        assert_eq!(
+            module.to_value(),
+            json!({"moduleName": {"namespace": "free", "name": "vault"}, "name": "bank"})
+        );
+
+        // Guards are passed to capabilities in their JSON encoding
+        let cap = Cap::new("free.vault.ROTATE").add_arg(module);
+        assert_eq!(cap.args[0]["moduleName"]["name"], "vault");
+    }
+
+    #[test]
+    fn test_guard_round_trip_and_principals() {
+        let guards = [
+            Guard::from(KeySet::keys_any(["a", "b"])),
+            Guard::KeySetRef("free.ks".to_string()),
+            Guard::Capability(Cap::new("free.vault.VAULT").add_arg("alice")),
+            Guard::User {
+                fun: "free.vault.enforce-owner".to_string(),
+                args: vec![json!("alice"), json!(1)],
+            },
+            Guard::Module {
+                module: "free.vault".to_string(),
+                name: "bank".to_string(),
+            },
+            Guard::Pact {
+                pact_id: "pid".to_string(),
+                name: "escrow".to_string(),
+            },
+        ];
+        for guard in &guards {
+            let encoded = serde_json::to_value(guard).unwrap();
+            assert_eq!(serde_json::from_value::<Guard>(encoded).unwrap(), *guard);
+        }
+
+        let principals: Vec<String> = guards.iter().map(Guard::principal).collect();
+        assert!(principals[0].starts_with("w:"));
+        assert_eq!(principals[1], "r:free.ks");
+        assert!(principals[2].starts_with("c:"));
+        assert!(principals[3].starts_with("u:free.vault.enforce-owner:"));
+        assert_eq!(principals[4], "m:free.vault:bank");
+        assert_eq!(principals[5], "p:pid:escrow");
+
+        // Older nodes encode keyset references as a qualified name
+        assert_eq!(
+            Guard::from_value(&json!({"keysetref": "free.ks"})).unwrap(),
+            guards[1]
+        );
+        assert!(matches!(
+            Guard::from_value(&json!({"unknown": true})),
+            Err(CommandError::InvalidGuard(_))
+        ));
+    }
+
+    #[test]
+    fn test_account_details_from_coin_details() {
+        let details: AccountDetails = serde_json::from_value(json!({
+            "account": "m:free.vault:bank",
+            "balance": 3.25,
+            "guard": {"moduleName": {"namespace": "free", "name": "vault"}, "name": "bank"}
+        }))
+        .unwrap();
+        assert_eq!(details.balance.to_string(), "3.25");
+        assert!(details.is_principal());
+
+        let vanity: AccountDetails = serde_json::from_value(json!({
+            "account": "alice",
+            "balance": {"decimal": "1.0"},
+            "guard": {"keysetref": {"ns": "free", "ksn": "alice"}}
+        }))
+        .unwrap();
+        assert!(!vanity.is_principal());
+    }
+
+    #[test]
+    fn test_keyset_snippets() {
+        let keypair = PactKeypair::generate();
+        let keyset = KeySet::keys_all([keypair.public_key()]);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&keyset.to_json_snippet("free.admin-keyset")).unwrap();
+        assert_eq!(json, keyset.to_data("free.admin-keyset"));
+        assert_eq!(json["free.admin-keyset"]["pred"], "keys-all");
+
+        // Hex keys starting with a digit could read as numbers, so they are quoted
+        let yaml = KeySet::keys_any(["0abc", "def"]).to_yaml_snippet("ks");
+        assert_eq!(
+            yaml,
+            "ks:\n  keys:\n    - \"0abc\"\n    - def\n  pred: keys-any\n"
+        );
+        assert_eq!(
+            KeySet::keys_all(Vec::<String>::new()).to_yaml_snippet("no"),
+            "\"no\":\n  keys: []\n  pred: keys-all\n"
+        );
+    }
+}
+
+mod transfer_tests {
+    use super::*;
+    use kadena::pact::{KeySet, Transfer};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_transfer_create() {
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+
+        let transfer = Transfer::new(&sender, "k:def456", Decimal::new(10, 0))
+            .with_receiver_guard(KeySet::keys_all(["def456"]));
+        assert_eq!(
+            transfer.code(),
+            format!(
+                "(coin.transfer-create \"{}\" \"k:def456\" (read-keyset \"ks\") 10.0)",
+                sender
+            )
+        );
+
+        let cmd = transfer
+            .prepare(&[&keypair], Meta::new("0", &sender), None)
+            .unwrap();
+        let payload = cmd.payload().unwrap();
+        assert_eq!(payload.payload.data()["ks"]["keys"][0], "def456");
+        assert_eq!(
+            payload.signers[0].clist[1].args[2],
+            json!({"decimal": "10.0"})
+        );
+    }
+
+    #[test]
+    fn test_token_transfer() {
+        use kadena::pact::{CommandIntent, TokenTransfer};
+
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+        let transfer = TokenTransfer::new_unchecked("free.some-token")
+            .transfer(&sender, "k:def456", Decimal::new(5, 1))
+            .with_receiver_guard(KeySet::keys_all(["def456"]));
+        assert!(transfer
+            .code()
+            .starts_with("(free.some-token.transfer-create "));
+
+        let cmd = transfer
+            .prepare(&[&keypair], Meta::new("0", &sender), None)
+            .unwrap();
+        let payload = cmd.payload().unwrap();
+        assert_eq!(payload.signers[0].clist[0].name, "coin.GAS");
+        assert_eq!(payload.signers[0].clist[1].name, "free.some-token.TRANSFER");
+        let CommandIntent::Transfer(intent) = payload.intent() else {
+            panic!("expected a transfer");
+        };
+        assert_eq!(intent.token, "free.some-token");
+        assert!(intent.capped);
+    }
+}
+
+mod amount_tests {
+    use super::*;
+    use kadena::pact::{Amount, CommandError, PactValue};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_amount_parsing_and_formatting() {
+        for text in ["1.5", "1.5 KDA", " 1.5KDA ", "1.500000000000"] {
+            assert_eq!(Amount::parse(text).unwrap().to_pact(), "1.5");
+        }
+        assert_eq!(Amount::parse("2").unwrap().to_pact(), "2.0");
+        assert_eq!(
+            Amount::parse("0.000000000001").unwrap().to_string(),
+            "0.000000000001 KDA"
+        );
+        for text in ["", "KDA", "-1", "1.5 BTC", "0.0000000000001", "1e3"] {
+            assert!(matches!(
+                Amount::parse(text),
+                Err(CommandError::InvalidAmount(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_amount_arithmetic() {
+        let one = Amount::parse("1").unwrap();
+        let half = Amount::parse("0.5").unwrap();
+        assert_eq!(one.checked_sub(half), Some(half));
+        assert_eq!(half.checked_sub(one), None);
+        assert_eq!(half.checked_add(half), Some(one));
+        assert_eq!(
+            one.checked_mul(Decimal::new(1, 13)),
+            Some(Amount::ZERO),
+            "rounds down to 12 decimals"
+        );
+        assert_eq!(one.checked_mul(Decimal::NEGATIVE_ONE), None);
+        assert_eq!(
+            PactValue::from(half),
+            PactValue::Decimal(Decimal::new(5, 1))
+        );
+    }
+
+    #[test]
+    fn test_fees() {
+        let meta = Meta::new("0", "k:abc123")
+            .with_gas_limit(1500)
+            .with_gas_price(0.00000001);
+        assert_eq!(meta.max_fee().unwrap(), Amount::parse("0.000015").unwrap());
+        assert_eq!(
+            Amount::fee(150_000, 0.000001).unwrap().to_string(),
+            "0.15 KDA"
+        );
+    }
+}
+
+mod batch_transfer_tests {
+    use super::*;
+    use kadena::pact::{BatchTransfer, KeySet, TRANSFER_CREATE_GAS};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_looping_batch() {
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+        let batch = BatchTransfer::new(&sender)
+            .with_module("free.some-token")
+            .with_looping(true)
+            .add("k:bob", Decimal::new(15, 1))
+            .add_create("k:carol", Decimal::ONE, KeySet::keys_all(["carol"]))
+            .add("k:bob", Decimal::new(5, 1));
+        assert_eq!(batch.total(), Decimal::new(3, 0));
+
+        let code = batch.code();
+        assert!(code.starts_with("(map (lambda (t) (free.some-token.transfer "));
+        assert!(code.ends_with("(read-keyset \"ks1\") 1.0)"));
+
+        let cmd = batch
+            .prepare(&[&keypair], Meta::new("0", &sender), None)
+            .unwrap();
+        let payload = cmd.payload().unwrap();
+        let data = payload.payload.data();
+        assert_eq!(data["ks1"]["keys"], json!(["carol"]));
+        assert_eq!(
+            data["transfers"],
+            json!([
+                {"receiver": "k:bob", "amount": {"decimal": "1.5"}},
+                {"receiver": "k:bob", "amount": {"decimal": "0.5"}},
+            ])
+        );
+        let caps = &payload.signers[0].clist;
+        assert_eq!(caps.len(), 3);
+        assert_eq!(caps[1].name, "free.some-token.TRANSFER");
+        assert_eq!(caps[1].args[2], json!({"decimal": "2.0"}));
+    }
+
+    #[test]
+    fn test_split_renames_keysets() {
+        let batch = (0..4).fold(BatchTransfer::new("alice"), |batch, i| {
+            batch.add_create(
+                &format!("k:{}", i),
+                Decimal::ONE,
+                KeySet::keys_all([i.to_string()]),
+            )
+        });
+        let batches = batch.split(batch.estimated_gas() - TRANSFER_CREATE_GAS);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].transfers.len(), 1);
+        assert_eq!(batches[1].env_data()["ks0"]["keys"], json!(["3"]));
+    }
+}
+
+mod gas_station_tests {
+    use super::*;
+    use kadena::pact::{CommandError, ContCommand, GasStation};
+
+    #[test]
+    fn test_station_pays_exec() {
+        let keypair = PactKeypair::generate();
+        let user = format!("k:{}", keypair.public_key());
+        let station = GasStation::new("free.gas-station", "free-gas").with_max_gas_limit(1000);
+
+        let meta = station.meta("0");
+        assert_eq!(meta.gas_limit, 1000);
+        let cmd = station
+            .prepare_exec(
+                &user,
+                &[(&keypair, vec![Cap::new("free.game.PLAY")])],
+                "(free.game.play)",
+                None,
+                Meta::new("0", &user).with_gas_limit(800),
+                None,
+            )
+            .unwrap();
+        let payload = cmd.payload().unwrap();
+        assert_eq!(payload.meta.sender, "free-gas");
+        let gas_payer = &payload.signers[0].clist[0];
+        assert_eq!(gas_payer.name, "free.gas-station.GAS_PAYER");
+        assert_eq!(
+            gas_payer.args,
+            vec![json!(user), json!({"int": 800}), json!(0.00000001)]
+        );
+        assert_eq!(payload.signers[0].clist[1].name, "free.game.PLAY");
+
+        assert!(matches!(
+            station.prepare_exec(&user, &[], "(+ 1 2)", None, Meta::new("0", &user), None),
+            Err(CommandError::InvalidGasStation(_))
+        ));
+    }
+
+    #[test]
+    fn test_xchain_gas_pays_continuations() {
+        let station = GasStation::kadena_xchain_gas();
+        let cont = ContCommand::new("pact-id", 1).with_proof("spv-proof");
+
+        let cmd = station
+            .prepare_cont("k:abc123", &[], cont.clone(), station.meta("1"), None)
+            .unwrap();
+        let payload = cmd.payload().unwrap();
+        assert_eq!(payload.meta.sender, "kadena-xchain-gas");
+        assert!(payload.signers.is_empty());
+
+        let expensive = station.meta("1").with_gas_price(0.000001);
+        assert!(station
+            .prepare_cont("k:abc123", &[], cont, expensive, None)
+            .is_err());
+        assert!(station
+            .prepare_exec("k:abc123", &[], "(+ 1 2)", None, station.meta("1"), None)
+            .is_err());
+    }
+}
+
+mod inspect_tests {
+    use super::*;
+    use kadena::pact::{CommandPayload, CommandSigner};
+    use rust_decimal::Decimal;
+
+    fn proposal() -> CommandPayload {
+        CommandPayload::new(Meta::new("0", "k:alice"))
+            .with_nonce("nonce-1".to_string())
+            .with_network_id("testnet04")
+            .with_code("(coin.transfer \"k:alice\" \"k:bob\" 10.0)".to_string())
+            .add_signer(CommandSigner::new_ed25519(
+                "alice-key",
+                vec![
+                    Cap::new("coin.GAS"),
+                    Cap::transfer("k:alice", "k:bob", 10.0),
+                ],
+            ))
+            .add_signer(CommandSigner::new_ed25519(
+                "bob-key",
+                vec![Cap::with_args(
+                    "coin.TRANSFER_XCHAIN",
+                    vec![
+                        json!("k:bob"),
+                        json!("k:bob"),
+                        json!({"decimal": "0.5"}),
+                        json!("1"),
+                    ],
+                )],
+            ))
+    }
+
+    #[test]
+    fn test_inspect_extracts_caps_and_amounts() {
+        let inspection = proposal().inspect();
+        assert_eq!(inspection.network_id.as_deref(), Some("testnet04"));
+        assert_eq!(inspection.signers.len(), 2);
+        assert_eq!(inspection.signers[1].pub_key, "bob-key");
+        assert_eq!(inspection.amounts.len(), 2);
+        assert_eq!(inspection.amounts[1].cap, "coin.TRANSFER_XCHAIN");
+        assert_eq!(inspection.amounts[1].signer, "bob-key");
+        assert_eq!(inspection.total_amount("coin"), Decimal::new(105, 1));
+        assert_eq!(inspection.total_amount("free.token"), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_diff_lists_changed_fields() {
+        let proposed = proposal();
+        assert!(proposed.diff(&proposed).unwrap().is_empty());
+
+        let mut regenerated = proposal()
+            .with_nonce("nonce-2".to_string())
+            .with_code("(coin.transfer \"k:alice\" \"k:bob\" 12.0)".to_string());
+        regenerated.meta = regenerated.meta.with_gas_limit(5000);
+        regenerated.signers[0].clist[1] = Cap::transfer("k:alice", "k:bob", 12.0);
+        regenerated.signers.swap(0, 1);
+
+        let diff = proposed.diff(&regenerated).unwrap();
+        let paths: Vec<&str> = diff.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "nonce",
+                "meta.gasLimit",
+                "signers.alice-key.clist",
+                "payload.exec.code",
+                "signerOrder"
+            ]
+        );
+        assert_eq!(diff.get("meta.gasLimit").unwrap().after, Some(json!(5000)));
+        assert!(diff.is_material());
+        assert_ne!(
+            proposed.inspect().code_hash,
+            regenerated.inspect().code_hash
+        );
+
+        let renonced = proposal().with_nonce("nonce-3".to_string());
+        assert!(!proposed.diff(&renonced).unwrap().is_material());
+    }
+}
+
+mod intent_tests {
+    use super::*;
+    use kadena::pact::{
+        CommandIntent, CommandPayload, CommandSigner, ContCommand, Guard, TransferKind,
+    };
+    use rust_decimal::Decimal;
+
+    fn payload(code: &str, caps: Vec<Cap>) -> CommandPayload {
+        CommandPayload::new(Meta::new("0", "k:alice"))
+            .with_code(code.to_string())
+            .add_signer(CommandSigner::new_ed25519("alice-key", caps))
+    }
+
+    #[test]
+    fn test_transfers_are_detected() {
+        let transfer = payload(
+            "(coin.transfer \"k:alice\" \"k:bob\" 10.0)",
+            vec![Cap::transfer("k:alice", "k:bob", 5.0)],
+        );
+        let CommandIntent::Transfer(intent) = transfer.intent() else {
+            panic!("expected a transfer");
+        };
+        assert_eq!(intent.kind, TransferKind::Transfer);
+        assert_eq!(intent.amount, Decimal::new(10, 0));
+        // The capability only covers half of the amount
+        assert!(!intent.capped);
+
+        let crosschain = payload(
+            "(free.token.transfer-crosschain \"k:alice\" \"k:bob\" (keyset-ref-guard \"free.bob\") \"2\" (read-decimal \"amount\"))",
+            vec![Cap::with_args(
+                "free.token.TRANSFER_XCHAIN",
+                vec![json!("k:alice"), json!("k:bob"), json!(1.5), json!("2")],
+            )],
+        )
+        .with_env_data(json!({"amount": 1.5}));
+        let CommandIntent::Transfer(intent) = crosschain.intent() else {
+            panic!("expected a transfer");
+        };
+        assert_eq!(intent.kind, TransferKind::CrossChain);
+        assert_eq!(intent.token, "free.token");
+        assert_eq!(intent.target_chain.as_deref(), Some("2"));
+        assert_eq!(
+            intent.receiver_guard,
+            Some(Guard::KeySetRef("free.bob".to_string()))
+        );
+        assert!(intent.capped);
+    }
+
+    #[test]
+    fn test_other_commands_are_classified() {
+        let deployment = payload(
+            "(namespace (read-msg \"ns\"))\n(module vault GOV (defcap GOV () true))\n(create-table accounts)",
+            Vec::new(),
+        )
+        .with_env_data(json!({"ns": "free"}));
+        assert_eq!(
+            deployment.intent(),
+            CommandIntent::Deployment {
+                namespace: Some("free".to_string()),
+                modules: vec!["free.vault".to_string()],
+            }
+        );
+
+        let call = payload(
+            "(coin.transfer \"k:alice\" \"k:bob\" 1.0) (free.dex.swap \"k:alice\")",
+            Vec::new(),
+        );
+        assert_eq!(
+            call.intent(),
+            CommandIntent::ContractCall {
+                functions: vec!["coin.transfer".to_string(), "free.dex.swap".to_string()],
+            }
+        );
+
+        let cont = payload("", Vec::new()).with_continuation(ContCommand::new("pid", 1));
+        assert!(matches!(
+            cont.intent(),
+            CommandIntent::Continuation { step: 1, .. }
+        ));
+    }
+}
+
+mod signing_session_tests {
+    use super::*;
+    use kadena::pact::{CommandError, CommandPayload, CommandSigner, SigningSession};
+
+    fn two_signer_payload(alice: &PactKeypair, bob: &PactKeypair) -> CommandPayload {
+        CommandPayload::new(Meta::new("0", &format!("k:{}", alice.public_key())))
+            .with_code("(free.vault.approve \"proposal-1\")".to_string())
+            .with_nonce("nonce".to_string())
+            .add_signer(CommandSigner::new_ed25519(
+                alice.public_key(),
+                vec![Cap::new("coin.GAS")],
+            ))
+            .add_signer(CommandSigner::new_ed25519(bob.public_key(), Vec::new()))
+    }
+
+    #[test]
+    fn test_session_verifies_with_signer_scheme() {
+        let keypair = PactKeypair::generate();
+        let signer = CommandSigner::new("WebAuthn", keypair.public_key(), Vec::new());
+        let payload = CommandPayload::new(Meta::new("0", "k:abc")).add_signer(signer);
+        let mut session = SigningSession::new(&payload).unwrap();
+        let err = session.sign(&keypair).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported signature scheme: WebAuthn"));
+
+        // Signers without a scheme are ED25519 signers
+        let signer: CommandSigner =
+            serde_json::from_value(json!({"pubKey": keypair.public_key(), "clist": []})).unwrap();
+        assert_eq!(signer.scheme, "ED25519");
+        let payload = CommandPayload::new(Meta::new("0", "k:abc")).add_signer(signer);
+        let mut session = SigningSession::new(&payload).unwrap();
+        session.sign(&keypair).unwrap();
+        assert!(session.is_complete());
+    }
+
+    #[test]
+    fn test_session_resumes_with_collected_signatures() {
+        let alice = PactKeypair::generate();
+        let bob = PactKeypair::generate();
+        let mut session = SigningSession::new(&two_signer_payload(&alice, &bob)).unwrap();
+        assert!(!session.is_complete());
+
+        session.sign(&alice).unwrap();
+        let stored = serde_json::to_value(&session).unwrap();
+        assert!(stored["sigs"][0]["sig"].is_string());
+        assert!(stored["sigs"][1]["sig"].is_null());
+
+        let mut resumed: SigningSession = serde_json::from_value(stored).unwrap();
+        assert_eq!(resumed, session);
+        assert!(matches!(
+            resumed.clone().into_cmd(),
+            Err(CommandError::SigningError(_))
+        ));
+
+        // Signatures made elsewhere are checked before being accepted
+        let bob_sig = bob
+            .sign(&kadena::crypto::base64url_decode(resumed.hash()).unwrap())
+            .unwrap()
+            .to_hex();
+        assert!(resumed.add_signature(alice.public_key(), &bob_sig).is_err());
+        resumed.add_signature(bob.public_key(), &bob_sig).unwrap();
+
+        let cmd = resumed.into_cmd().unwrap();
+        assert_eq!(cmd.sigs[1].sig, bob_sig);
+        assert_eq!(
+            SigningSession::from_cmd(&cmd)
+                .unwrap()
+                .missing_signers()
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_tampered_session_is_rejected() {
+        let alice = PactKeypair::generate();
+        let bob = PactKeypair::generate();
+        let mut session = SigningSession::new(&two_signer_payload(&alice, &bob)).unwrap();
+        session.sign(&alice).unwrap();
+        let stored = serde_json::to_value(&session).unwrap();
+
+        let mut edited = stored.clone();
+        edited["cmd"] = json!(session.cmd().replace("proposal-1", "proposal-2"));
+        assert!(serde_json::from_value::<SigningSession>(edited).is_err());
+
+        let mut forged = stored.clone();
+        forged["sigs"][1]["sig"] = stored["sigs"][0]["sig"].clone();
+        assert!(serde_json::from_value::<SigningSession>(forged).is_err());
+
+        let mut extra = stored;
+        extra["sigs"] = json!([{"sig": null}, {"sig": null}, {"sig": null}]);
+        assert!(serde_json::from_value::<SigningSession>(extra).is_err());
+    }
+
+    #[test]
+    fn test_sig_data_keyed_by_signer() {
+        let alice = PactKeypair::generate();
+        let bob = PactKeypair::generate();
+        let mut session = SigningSession::new(&two_signer_payload(&alice, &bob)).unwrap();
+        session.sign(&bob).unwrap();
+        let bob_sig = serde_json::to_value(&session).unwrap()["sigs"][1]["sig"].clone();
+
+        let listed = json!({
+            "cmd": session.cmd(),
+            "hash": session.hash(),
+            "sigs": [{"pubKey": bob.public_key(), "sig": bob_sig}, {"pubKey": alice.public_key(), "sig": null}],
+        });
+        assert_eq!(SigningSession::parse(&listed.to_string()).unwrap(), session);
+
+        let keyed = json!({
+            "cmd": session.cmd(),
+            "hash": session.hash(),
+            "sigs": {alice.public_key(): null, bob.public_key(): bob_sig},
+        });
+        let parsed: SigningSession = serde_json::from_value(keyed.clone()).unwrap();
+        assert_eq!(parsed.missing_signers(), [alice.public_key()]);
+
+        let mut stranger = keyed;
+        stranger["sigs"] = json!({PactKeypair::generate().public_key(): bob_sig});
+        assert!(matches!(
+            SigningSession::parse(&stranger.to_string()),
+            Err(CommandError::SigningError(_))
+        ));
+    }
+}
+
+mod parse_tests {
+    use super::*;
+    use kadena::crypto::{base64url_decode, hex_to_bin, Signature};
+    use kadena::pact::{CommandPayload, RequestKey, SigningSession};
+
+    /// Every prefix of `input`, cut at character boundaries
+    fn truncations(input: &str) -> impl Iterator<Item = &str> {
+        input.char_indices().map(|(i, _)| &input[..i])
+    }
+
+    #[test]
+    fn test_truncated_inputs_are_errors() {
+        let keypair = PactKeypair::generate();
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::transfer("k:ä", "k:ö", 1.5)])],
+            Vec::new(),
+            None,
+            "(coin.transfer \"k:ä\" \"k:ö\" 1.5)",
+            Some(json!({"memo": "✓"})),
+            Meta::new("0", "k:ä"),
+            Some("testnet04".to_string()),
+        )
+        .unwrap();
+        let sig_data = serde_json::to_string(&cmd).unwrap();
+
+        for input in truncations(&cmd.cmd) {
+            assert!(CommandPayload::parse(input).is_err());
+        }
+        for input in truncations(&sig_data) {
+            assert!(SigningSession::parse(input).is_err());
+        }
+        for input in truncations(&cmd.hash).skip(1) {
+            assert!(RequestKey::parse(input).is_err());
+        }
+        assert!(SigningSession::parse(&sig_data).unwrap().is_complete());
+    }
+
+    #[test]
+    fn test_hostile_inputs_are_errors() {
+        let nested = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(CommandPayload::parse(&nested).is_err());
+        assert!(SigningSession::parse(&nested).is_err());
+
+        let wide = format!("0x{}", "é".repeat(32));
+        assert!(RequestKey::parse(&wide).is_err());
+        assert!(RequestKey::parse(&"ü".repeat(32)).is_err());
+        assert!(hex_to_bin("abc").is_err());
+        assert!(hex_to_bin("zz").is_err());
+        assert!(base64url_decode("a").is_err());
+        assert!(base64url_decode("ab==").is_err());
+        assert!(Signature::from_hex(&"ab".repeat(63)).is_err());
+        assert!(PactKeypair::from_secret_key(&"0".repeat(62)).is_err());
+
+        let payload = json!({
+            "nonce": "n",
+            "meta": {"chainId": "0", "sender": "k:a", "gasLimit": -1, "gasPrice": 1e-8, "ttl": 600, "creationTime": 0},
+            "signers": [],
+            "verifiers": [],
+            "networkId": null,
+            "payload": {"exec": {"code": "(+ 1 2)", "data": {}}},
+        });
+        assert!(CommandPayload::parse(&payload.to_string()).is_err());
+        assert!(CommandPayload::parse(&payload.to_string().replace("-1", "1e400")).is_err());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        use kadena::pact::{validate_code, CommandError};
+
+        let lint = validate_code("(+ 1 2").unwrap_err();
+        assert_eq!(lint.error_code(), "lint.unclosed");
+        assert_eq!(CommandError::from(lint).error_code(), "lint.unclosed");
+
+        let decode = CommandError::from(hex_to_bin("zz").unwrap_err());
+        assert_eq!(decode.error_code(), "crypto.invalid_hex");
+        assert_eq!(
+            CommandPayload::parse("{").unwrap_err().error_code(),
+            "command.serialization"
+        );
+        assert_eq!(
+            RequestKey::parse("abc").unwrap_err().error_code(),
+            "command.invalid_request_key"
+        );
+    }
+}
+
+#[cfg(feature = "cbor")]
+mod cbor_tests {
+    use super::*;
+    use kadena::pact::{CborCodec, CommandError, PactInstance};
+
+    #[test]
+    fn test_cbor_round_trip() {
+        let keypair = PactKeypair::generate();
+        let meta = Meta::new("0", &format!("k:{}", keypair.public_key()));
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            "(+ 1 2)",
+            Some(json!({"key": "value"})),
+            meta,
+            Some("testnet04".to_string()),
+        )
+        .unwrap();
+
+        let bytes = cmd.to_cbor().unwrap();
+        assert!(bytes.len() < serde_json::to_vec(&cmd).unwrap().len());
+
+        let decoded = Cmd::from_cbor(&bytes).unwrap();
+        assert_eq!(decoded.cmd, cmd.cmd);
+        assert_eq!(decoded.sigs[0].sig, cmd.sigs[0].sig);
+    }
+
+    #[test]
+    fn test_cbor_rejects_other_kinds() {
+        let instance = PactInstance::from_result(&json!({
+            "continuation": {"pactId": "abc", "step": 0, "stepCount": 2, "stepHasRollback": true}
+        }))
+        .unwrap();
+
+        let bytes = instance.to_cbor().unwrap();
+        assert_eq!(PactInstance::from_cbor(&bytes).unwrap(), instance);
+        assert!(matches!(
+            Cmd::from_cbor(&bytes),
+            Err(CommandError::BinaryEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_cbor_signing_session() {
+        use kadena::pact::{CommandPayload, CommandSigner, SigningSession};
+
+        let keypair = PactKeypair::generate();
+        let payload = CommandPayload::new(Meta::new("0", &format!("k:{}", keypair.public_key())))
+            .with_code("(+ 1 2)".to_string())
+            .add_signer(CommandSigner::new_ed25519(keypair.public_key(), Vec::new()));
+        let session = SigningSession::new(&payload).unwrap();
+
+        let bytes = session.to_cbor().unwrap();
+        assert_eq!(SigningSession::from_cbor(&bytes).unwrap(), session);
+    }
+}
+
+mod replay_tests {
+    use super::*;
+    use kadena::pact::derive_nonce;
+
+    #[test]
+    fn test_derived_nonces_are_deterministic() {
+        let nonce = derive_nonce("withdrawal", "42");
+        assert_eq!(nonce, derive_nonce("withdrawal", "42"));
+        assert_eq!(nonce.len(), 43);
+        assert_ne!(nonce, derive_nonce("withdrawal", "43"));
+        // Parts cannot be shifted between scope and id
+        assert_ne!(derive_nonce("a:b", "c"), derive_nonce("a", "b:c"));
+
+        let keypair = PactKeypair::generate();
+        let payout = |nonce: &str| {
+            let meta = Meta::new("0", "treasury").with_creation_time(1_700_000_000);
+            Cmd::prepare_exec(
+                &[(&keypair, vec![Cap::new("coin.GAS")])],
+                Vec::new(),
+                Some(nonce),
+                "(coin.transfer \"treasury\" \"bob\" 1.0)",
+                None,
+                meta,
+                Some("mainnet01".to_string()),
+            )
+            .unwrap()
+        };
+        assert_eq!(payout(&nonce).hash, payout(&nonce).hash);
+        assert_ne!(
+            payout(&nonce).hash,
+            payout(&derive_nonce("withdrawal", "43")).hash
+        );
+    }
+}
+
+mod request_key_tests {
+    use super::*;
+    use kadena::pact::{CommandError, RequestKey};
+
+    #[test]
+    fn test_request_key_encodings() {
+        let keypair = PactKeypair::generate();
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            None,
+            "(+ 1 2)",
+            None,
+            Meta::new("0", &format!("k:{}", keypair.public_key())),
+            None,
+        )
+        .unwrap();
+
+        let key = cmd.request_key().unwrap();
+        assert_eq!(key.as_str(), cmd.hash);
+        assert_eq!(key.to_hex().len(), 64);
+
+        let from_hex: RequestKey = format!("0x{}", key.to_hex().to_uppercase())
+            .parse()
+            .unwrap();
+        assert_eq!(from_hex, key);
+        assert_eq!(serde_json::to_value(&from_hex).unwrap(), json!(cmd.hash));
+    }
+
+    #[test]
+    fn test_invalid_request_keys() {
+        assert!(matches!(
+            RequestKey::parse("not-a-key"),
+            Err(CommandError::InvalidRequestKey(_))
+        ));
+        assert!(serde_json::from_value::<RequestKey>(json!("abcd")).is_err());
+    }
+}
+
+mod pact_value_tests {
+    use super::*;
+    use kadena::pact::{CommandError, PactValue};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_object_keeps_field_order() {
+        let json = r#"{"zeta":1,"alpha":{"int":9007199254740993},"mid":[true,{"time":"2024-01-01T00:00:00Z"}]}"#;
+        let value: PactValue = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+
+        let PactValue::Object(fields) = &value else {
+            panic!("expected an object");
+        };
+        let keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["zeta", "alpha", "mid"]);
+        assert_eq!(fields["alpha"], PactValue::Integer(9_007_199_254_740_993));
+
+        // Equality ignores field order, as in Pact
+        let reordered = PactValue::object([
+            ("mid", fields["mid"].clone()),
+            ("alpha", fields["alpha"].clone()),
+            ("zeta", PactValue::from(1)),
+        ]);
+        assert_eq!(reordered, value);
+        assert_ne!(serde_json::to_string(&reordered).unwrap(), json);
+    }
+
+    #[test]
+    fn test_decimal_encodings() {
+        assert_eq!(PactValue::from(Decimal::new(15, 1)).to_value(), json!(1.5));
+        let precise: Decimal = "0.123456789012345678901234567".parse().unwrap();
+        assert_eq!(
+            PactValue::from(precise).to_value(),
+            json!({"decimal": "0.123456789012345678901234567"})
+        );
+        assert_eq!(
+            PactValue::from_value(&json!({"decimal": "0.123456789012345678901234567"})).unwrap(),
+            PactValue::Decimal(precise)
+        );
+        assert!(matches!(
+            PactValue::from_value(&json!(null)),
+            Err(CommandError::InvalidPactValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_env_data_order_survives_round_trip() {
+        let keypair = PactKeypair::generate();
+        let data = PactValue::object([
+            ("receiver", PactValue::from("k:bob")),
+            ("amount", PactValue::from(Decimal::new(25, 1))),
+        ]);
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, vec![Cap::new("coin.GAS")])],
+            Vec::new(),
+            Some("nonce"),
+            "(read-msg \"receiver\")",
+            Some(data.into()),
+            Meta::new("0", &format!("k:{}", keypair.public_key())),
+            None,
+        )
+        .unwrap();
+        assert!(cmd.cmd.contains(r#"{"receiver":"k:bob","amount":2.5}"#));
+
+        let reparsed: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
+        assert_eq!(serde_json::to_string(&reparsed).unwrap(), cmd.cmd);
+    }
+
+    #[test]
+    fn test_modref_as_capability_argument() {
+        let policy = PactValue::modref("free.royalty-policy", ["kip.token-policy-v2"]);
+        let cap = Cap::new("marmalade-v2.ledger.INIT-CALL")
+            .add_arg("t:token")
+            .add_arg(policy.clone());
+        assert_eq!(
+            cap.args[1],
+            json!({"refName": "free.royalty-policy", "refSpec": ["kip.token-policy-v2"]})
+        );
+        assert_eq!(PactValue::from_value(&cap.args[1]).unwrap(), policy);
+
+        // Objects with other fields are not module references
+        let object = PactValue::from_value(&json!({"refName": "coin", "refSpec": [], "x": 1}));
+        assert!(matches!(object, Ok(PactValue::Object(_))));
+    }
+}
+
+// Integration tests to verify module interactions
+mod integration_tests {
+    use super::*;
+
+    #[test]
+    fn test_full_transaction_preparation() {
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+
+        // Create metadata
+        let meta = Meta::new("0", &sender)
+            .with_gas_limit(2000)
+            .with_gas_price(0.00000001);
+
+        // Create capabilities
+        let caps = vec![Cap::new("coin.GAS"), Cap::transfer(&sender, "Bob", 10.0)];
+
+        // Create Pact code
+        let pact_code = format!("(coin.transfer \"{}\" \"Bob\" 10.0)", sender);
+
+        // Prepare command
+        let cmd = Cmd::prepare_exec(
+            &[(&keypair, caps)],
+            Vec::new(),
+            Some("test-nonce"),
+            &pact_code,
+            None,
+            meta,
+            Some("testnet04".to_string()),
+        )
+        .unwrap();
+
+        // Verify complete transaction structure
+        let cmd_json: serde_json::Value = serde_json::from_str(&cmd.cmd).unwrap();
+
+        // Verify metadata
+        assert_eq!(cmd_json["meta"]["chainId"], "0");
+        assert_eq!(cmd_json["meta"]["sender"], sender);
+        assert_eq!(cmd_json["meta"]["gasLimit"], 2000);
+
+        // Verify signer and capabilities
+        let signer = &cmd_json["signers"][0];
+        assert_eq!(signer["pubKey"], keypair.public_key());
+        assert_eq!(signer["clist"][0]["name"], "coin.GAS");
+        assert_eq!(signer["clist"][1]["name"], "coin.TRANSFER");
+
+        // Verify signatures
+        assert!(!cmd.sigs.is_empty());
+        assert!(!cmd.hash.is_empty());
+    }
+}
+
+mod compat_tests {
+    use super::*;
+    use kadena::pact::{builtin_vectors, verify_builtin_vectors, verify_vector, CommandError};
+
+    #[test]
+    fn test_builtin_vectors() {
+        assert!(builtin_vectors().len() >= 4);
+        verify_builtin_vectors().unwrap();
+    }
+
+    #[test]
+    fn test_tampered_vector_is_reported() {
+        let mut vector = builtin_vectors().remove(0);
+        vector.payload["nonce"] = json!("tampered");
+        assert!(matches!(
+            verify_vector(&vector),
+            Err(CommandError::CompatMismatch(message)) if message.starts_with("exec-minimal: cmd")
+        ));
+
+        let mut vector = builtin_vectors().remove(0);
+        vector.signatures[0].sig = "00".repeat(64);
+        assert!(matches!(
+            verify_vector(&vector),
+            Err(CommandError::CompatMismatch(message)) if message.contains("signature")
+        ));
+    }
+
+    #[test]
+    fn test_gas_price_is_formatted_like_javascript() {
+        let cases = [
+            (0.00000001, "1e-8"),
+            (0.000001, "0.000001"),
+            (0.0000025, "0.0000025"),
+            (1.0, "1"),
+            (100.5, "100.5"),
+            (1e21, "1e+21"),
+        ];
+        for (gas_price, expected) in cases {
+            let meta = Meta::new("0", "k:alice").with_gas_price(gas_price);
+            let serialized = serde_json::to_string(&meta).unwrap();
+            assert!(
+                serialized.contains(&format!("\"gasPrice\":{},", expected)),
+                "{}",
+                serialized
+            );
+            let parsed: Meta = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(parsed.gas_price, gas_price);
+        }
+    }
+}
+
+mod fee_policy_tests {
+    use super::*;
+    use kadena::pact::{BatchTransfer, FeePolicy, Transfer};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_builders_enforce_fee_policy() {
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+        let policy = FeePolicy::new()
+            .with_max_gas_price(0.000001)
+            .with_max_fee(Decimal::new(1, 2));
+        let meta = Meta::new("0", &sender).with_gas_limit(2500);
+
+        let transfer = Transfer::new(&sender, "bob", Decimal::ONE).with_fee_policy(policy);
+        assert!(transfer.prepare(&[&keypair], meta.clone(), None).is_ok());
+
+        let pricey = meta.clone().with_gas_price(0.00001);
+        let error = transfer.prepare(&[&keypair], pricey, None).unwrap_err();
+        assert_eq!(error.error_code(), "command.fee_exceeded");
+        assert!(error.to_string().contains("gas price"));
+
+        // 150000 gas at 1e-7 stays under the price limit but costs 0.015 KDA
+        let batch = BatchTransfer::new(&sender)
+            .add("bob", Decimal::ONE)
+            .with_fee_policy(policy);
+        let costly = meta.with_gas_limit(150_000).with_gas_price(0.0000001);
+        let error = batch
+            .prepare(&[&keypair], costly.clone(), None)
+            .unwrap_err();
+        assert!(error.to_string().contains("worst-case fee of 0.015 KDA"));
+        assert!(batch.split(1)[0]
+            .prepare(&[&keypair], costly.clone(), None)
+            .is_err());
+
+        let batch = batch.with_fee_policy(policy.warn_only());
+        assert!(batch.prepare(&[&keypair], costly, None).is_ok());
+    }
+}
+
+mod module_source_tests {
+    use kadena::pact::{strip_comments, ModuleLoader};
+    use std::fs;
+
+    #[test]
+    fn test_loader_resolves_dependencies() {
+        let dir = std::env::temp_dir().join(format!("kadena-module-source-{}", std::process::id()));
+        let interfaces = dir.join("interfaces");
+        fs::create_dir_all(&interfaces).unwrap();
+        fs::write(
+            interfaces.join("ledger-v1.pact"),
+            ";; Ledger interface\n(interface ledger-v1\n  (defun balance:decimal (account:string)))\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("util.pact"),
+            "(module util GOV\n  (defcap GOV () true))\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("token.pact"),
+            ";; @include util.pact\n(module token GOV\n  (implements free.ledger-v1) ; local\n  (implements fungible-v2)\n  (use util)\n  (defcap GOV () true))\n",
+        )
+        .unwrap();
+
+        let loaded = ModuleLoader::new()
+            .with_search_path(&interfaces)
+            .load(dir.join("token.pact"))
+            .unwrap();
+        let names: Vec<_> = loaded
+            .files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["util.pact", "ledger-v1.pact", "token.pact"]);
+        assert!(loaded.source.starts_with("(module util GOV"));
+        assert!(loaded.source.contains(";; Ledger interface"));
+        assert_eq!(loaded.size(), loaded.source.len());
+
+        let stripped = ModuleLoader::new()
+            .with_search_path(&interfaces)
+            .with_strip_comments(true)
+            .load(dir.join("token.pact"))
+            .unwrap();
+        assert!(!stripped.source.contains(';'));
+        assert!(stripped.size() < loaded.size());
+        assert!(stripped.check_size(stripped.size()).is_ok());
+        let error = stripped.check_size(100).unwrap_err();
+        assert_eq!(error.error_code(), "command.module_source");
+
+        // Without the search path the interface is taken to be deployed
+        let loaded = ModuleLoader::new().load(dir.join("token.pact")).unwrap();
+        assert_eq!(loaded.files.len(), 2);
+
+        fs::write(
+            dir.join("util.pact"),
+            ";; @include token.pact\n(module util GOV)\n",
+        )
+        .unwrap();
+        let error = ModuleLoader::new()
+            .load(dir.join("token.pact"))
+            .unwrap_err();
+        assert!(error.to_string().contains("dependency cycle"));
+
+        fs::write(dir.join("util.pact"), "(module util GOV").unwrap();
+        let error = ModuleLoader::new()
+            .load(dir.join("token.pact"))
+            .unwrap_err();
+        assert_eq!(error.error_code(), "lint.unclosed");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_strip_comments_keeps_strings() {
+        let code = "(defun f ()\n  @doc \"Line one;\n\n  line three\"\n  ; comment\n  \"\\\";\")";
+        assert_eq!(
+            strip_comments(code),
+            "(defun f ()\n  @doc \"Line one;\n\n  line three\"\n  \"\\\";\")"
+        );
+    }
+}
+
+mod failure_tests {
+    use super::*;
+    use kadena::pact::{CommandResult, PactFailure};
+
+    fn failed(error: serde_json::Value) -> CommandResult {
+        CommandResult::from_value(json!({
+            "reqKey": "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c",
+            "result": {"status": "failure", "error": error},
+            "gas": 600
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_coin_failures_are_classified() {
+        let cases = [
+            ("Insufficient funds", "pact.insufficient_funds"),
+            ("with-read: row not found: k:bob", "pact.account_not_found"),
+            (
+                "No value found in table coin_coin-table for key: k:bob",
+                "pact.account_not_found",
+            ),
+            (
+                "Keyset failure (keys-all): [6d87fd6e...]",
+                "pact.keyset_failure",
+            ),
+            (
+                "Managed capability not installed: (coin.TRANSFER \"k:a\" \"k:b\" 1.0)",
+                "pact.capability_mismatch",
+            ),
+            (
+                "TRANSFER exceeded for balance 1.0",
+                "pact.capability_mismatch",
+            ),
+            ("Division by 0", "pact.other"),
+        ];
+        for (message, code) in cases {
+            let result = failed(json!({"message": message, "type": "TxFailure"}));
+            let failure = result.failure().unwrap();
+            assert_eq!(failure.error_code(), code, "{}", message);
+            assert_eq!(failure.message(), message);
+        }
+
+        assert_eq!(
+            failed(json!("Insufficient funds")).failure(),
+            Some(PactFailure::InsufficientFunds(
+                "Insufficient funds".to_string()
+            ))
+        );
+        let success = CommandResult::from_value(json!({
+            "reqKey": "TfFIY9uBrR4b9UGbbuk-tyrIZXa10c-mmr5Eg-Eiu4c",
+            "result": {"status": "success", "data": "Write succeeded"}
+        }))
+        .unwrap();
+        assert!(success.failure().is_none());
+    }
+}
+
+mod number_mode_tests {
+    use super::*;
+    use kadena::pact::{CommandPayload, CommandSigner, NumberMode, Transfer};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_number_modes_on_payloads() {
+        let keypair = PactKeypair::generate();
+        let sender = format!("k:{}", keypair.public_key());
+        let transfer = Transfer::new(&sender, "k:bob", Decimal::new(15, 1));
+        let payload = CommandPayload::new(Meta::new("0", &sender))
+            .with_code("(free.app.deposit (read-msg 'amount))")
+            .with_env_data(json!({"amount": 0.30000000000000004, "ids": [1, 9007199254740993u64]}))
+            .add_signer(CommandSigner::new(
+                "ED25519",
+                keypair.public_key(),
+                vec![
+                    Cap::new("coin.GAS"),
+                    transfer.cap(),
+                    Cap::with_args("free.app.DEPOSIT", vec![json!(2.5)]),
+                ],
+            ));
+
+        let mut permissive = payload.clone();
+        NumberMode::Permissive
+            .apply_to_payload(&mut permissive)
+            .unwrap();
+        assert_eq!(permissive.payload.data()["amount"], 0.30000000000000004);
+
+        let mut lenient = payload.clone();
+        NumberMode::Lenient.apply_to_payload(&mut lenient).unwrap();
+        let data = lenient.payload.data();
+        assert_eq!(data["amount"], json!({"decimal": "0.30000000000000004"}));
+        assert_eq!(data["ids"], json!([1, {"int": 9007199254740993u64}]));
+        let caps = &lenient.signers[0].clist;
+        // The transfer builder already uses the precise encoding
+        assert_eq!(caps[1].args, transfer.cap().args);
+        assert_eq!(caps[2].args[0], json!({"decimal": "2.5"}));
+
+        let mut strict = payload.with_env_data(json!({"amount": {"decimal": "0.3"}}));
+        let error = NumberMode::Strict
+            .apply_to_payload(&mut strict)
+            .unwrap_err();
+        assert_eq!(error.error_code(), "command.imprecise_number");
+        assert!(error.to_string().contains("argument 0 of free.app.DEPOSIT"));
+    }
+}
+
+mod rotation_ceremony_tests {
+    use super::*;
+    use kadena::pact::{CommandError, KeyRotation, KeySet};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_rotation_ceremony_checks_both_quorums() {
+        let old: Vec<_> = (0..3).map(|_| PactKeypair::generate()).collect();
+        let new: Vec<_> = (0..2).map(|_| PactKeypair::generate()).collect();
+        let old_keyset = KeySet::keys_2(old.iter().map(|k| k.public_key()));
+        let new_keyset = KeySet::keys_all(new.iter().map(|k| k.public_key()));
+        let rotation = KeyRotation::new(old_keyset.clone(), new_keyset, Decimal::new(250, 0))
+            .with_old_approvers([old[0].public_key(), old[2].public_key()]);
+        let meta = || Meta::new("0", &rotation.old_account());
+
+        let short = rotation.clone().with_new_approvers([new[0].public_key()]);
+        assert!(matches!(
+            short.prepare(meta(), "testnet04"),
+            Err(CommandError::UnsatisfiedKeyset(_))
+        ));
+        let stranger = rotation
+            .clone()
+            .with_old_approvers([old[0].public_key(), new[0].public_key()]);
+        assert!(stranger.prepare(meta(), "testnet04").is_err());
+        let same = KeyRotation::new(old_keyset.clone(), old_keyset, Decimal::ONE);
+        assert!(same.prepare(meta(), "testnet04").is_err());
+
+        let mut ceremony = rotation.prepare(meta(), "testnet04").unwrap();
+        assert!(rotation.new_account().starts_with("w:"));
+        assert_eq!(ceremony.missing_approvals().len(), 4);
+        ceremony.sign(&old[0]).unwrap();
+        ceremony.sign(&new[0]).unwrap();
+        ceremony.sign(&new[1]).unwrap();
+        assert_eq!(ceremony.missing_approvals(), vec![old[2].public_key()]);
+        assert!(ceremony.clone().into_cmd().is_err());
+
+        let sig = old[2]
+            .sign(&kadena::crypto::base64url_decode(ceremony.hash()).unwrap())
+            .unwrap()
+            .to_hex();
+        ceremony.approve(old[2].public_key(), &sig).unwrap();
+        assert!(ceremony.is_approved());
+
+        let payload = ceremony.session().payload().unwrap();
+        let code = payload.payload.code().unwrap();
+        assert!(code.contains("coin.transfer-create"));
+        assert!(code.contains(&rotation.new_account()));
+        // New key holders only grant gas, they cannot move the old funds
+        let new_signer = payload
+            .signers
+            .iter()
+            .find(|s| s.pub_key == new[0].public_key())
+            .unwrap();
+        assert_eq!(new_signer.clist, vec![Cap::new("coin.GAS")]);
+        ceremony.into_cmd().unwrap();
     }
 }
@@ -0,0 +1,115 @@
+#![cfg(feature = "testing")]
+
+use kadena::crypto::PactKeypair;
+use kadena::pact::{Cap, Cmd, Meta, PactResult};
+use kadena::testing::{MockNode, MOCK_BLOCK_HEIGHT};
+use kadena::FetchError;
+use serde_json::json;
+
+fn transfer(keypair: &PactKeypair, amount: f64) -> Cmd {
+    let sender = format!("k:{}", keypair.public_key());
+    Cmd::prepare_exec(
+        &[(
+            keypair,
+            vec![
+                Cap::new("coin.GAS"),
+                Cap::transfer(&sender, "k:receiver", amount),
+            ],
+        )],
+        Vec::new(),
+        None,
+        &format!(
+            "(coin.transfer \"{}\" \"k:receiver\" {:.1})",
+            sender, amount
+        ),
+        None,
+        Meta::new("0", &sender),
+        Some("development".to_string()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_mock_node_round_trip() {
+    let node = MockNode::start().await;
+    node.stub_local(PactResult::Success { data: json!(3) })
+        .await;
+    node.stub_send().await;
+    node.stub_poll(PactResult::Success {
+        data: json!("Write succeeded"),
+    })
+    .await;
+
+    let keypair = PactKeypair::generate();
+    let first = transfer(&keypair, 1.0);
+    let second = transfer(&keypair, 2.0);
+    let client = node.client();
+
+    let local = client.local(&first).await.unwrap();
+    assert_eq!(local["reqKey"], first.hash.as_str());
+    assert_eq!(local["result"]["data"], 3);
+
+    let sent = client.send(&first).await.unwrap();
+    assert_eq!(sent["requestKeys"], json!([first.hash]));
+    client.send(&second).await.unwrap();
+
+    let results = client.poll(&[&first.hash, &second.hash]).await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[&second.hash].block_height(),
+        Some(MOCK_BLOCK_HEIGHT)
+    );
+
+    assert_eq!(node.local_commands().await.len(), 1);
+    let sent: Vec<String> = node
+        .sent_commands()
+        .await
+        .into_iter()
+        .map(|cmd| cmd.hash)
+        .collect();
+    assert_eq!(sent, vec![first.hash.clone(), second.hash.clone()]);
+    node.assert_sent(|payload| {
+        payload.signers[0]
+            .clist
+            .iter()
+            .any(|cap| cap.name == "coin.TRANSFER")
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_mock_node_errors_and_reset() {
+    let node = MockNode::start_with("testnet04", "1").await;
+    node.stub_error("send", 400, "Validation failed").await;
+
+    let cmd = transfer(&PactKeypair::generate(), 1.0);
+    assert!(matches!(
+        node.client().send(&cmd).await,
+        Err(FetchError::ApiError(message)) if message == "Validation failed"
+    ));
+    // Rejected submissions are still recorded
+    assert_eq!(node.sent_commands().await.len(), 1);
+
+    node.reset().await;
+    node.stub_listen(PactResult::Failure {
+        error: json!({"message": "Insufficient funds"}),
+    })
+    .await;
+    let result = node.client().listen(&cmd.hash).await.unwrap();
+    assert!(!result.is_success());
+    assert!(node.sent_commands().await.is_empty());
+}
+
+#[tokio::test]
+#[should_panic(expected = "no matching command")]
+async fn test_assert_sent_reports_mismatch() {
+    let node = MockNode::start().await;
+    node.stub_send().await;
+    node.client()
+        .send(&transfer(&PactKeypair::generate(), 1.0))
+        .await
+        .unwrap();
+
+    node.assert_sent(|payload| payload.meta.chain_id == "5")
+        .await;
+}
@@ -0,0 +1,202 @@
+#![cfg(feature = "wallet")]
+
+use kadena::{
+    crypto::{base64url_decode, PactKeypair, Signer},
+    fetch::ChainwebClient,
+    pact::{Cap, Meta},
+    wallet::{Wallet, WalletAccount, WalletError},
+};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn transfer_payload(wallet: &Wallet, account: &str) -> kadena::pact::CommandPayload {
+    wallet
+        .build(
+            account,
+            vec![Cap::new("coin.GAS")],
+            "(+ 1 2)",
+            None,
+            Meta::new("0", account),
+            Some("testnet04".to_string()),
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_owned_account_signs() {
+    let keypair = PactKeypair::generate();
+    let account = format!("k:{}", keypair.public_key());
+    let mut wallet = Wallet::new();
+    wallet
+        .add(WalletAccount::from_keypair(keypair.clone()))
+        .unwrap();
+
+    let cmd = wallet
+        .sign(&account, &transfer_payload(&wallet, &account), None)
+        .unwrap();
+
+    let hash = base64url_decode(&cmd.hash).unwrap();
+    assert!(keypair.verify(&hash, &cmd.sigs[0].sig).unwrap());
+}
+
+#[test]
+fn test_watch_only_account_routes_to_external_signer() {
+    let keypair = PactKeypair::generate();
+    let account = format!("k:{}", keypair.public_key());
+    let mut wallet = Wallet::new();
+    wallet
+        .add(WalletAccount::watch_public_key(keypair.public_key()))
+        .unwrap();
+
+    let payload = transfer_payload(&wallet, &account);
+    assert!(matches!(
+        wallet.sign(&account, &payload, None),
+        Err(WalletError::WatchOnly(_))
+    ));
+
+    let other = PactKeypair::generate();
+    assert!(matches!(
+        wallet.sign(&account, &payload, Some(&other as &dyn Signer)),
+        Err(WalletError::SignerMismatch { .. })
+    ));
+
+    let cmd = wallet
+        .sign(&account, &payload, Some(&keypair as &dyn Signer))
+        .unwrap();
+    assert_eq!(cmd.sigs.len(), 1);
+}
+
+#[test]
+fn test_duplicate_accounts_are_rejected() {
+    let mut wallet = Wallet::new();
+    wallet.add(WalletAccount::watch_account("alice")).unwrap();
+    assert!(matches!(
+        wallet.add(WalletAccount::watch_account("alice")),
+        Err(WalletError::DuplicateAccount(_))
+    ));
+    assert_eq!(
+        wallet
+            .add(WalletAccount::watch_account("alice"))
+            .unwrap_err()
+            .error_code(),
+        "wallet.duplicate_account"
+    );
+}
+
+#[tokio::test]
+async fn test_refresh_reports_incoming_transfers() {
+    let mock_server = MockServer::start().await;
+    let local = "/chainweb/0.0/testnet04/chain/0/pact/api/v1/local";
+
+    Mock::given(method("POST"))
+        .and(path(local))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"balance": 1.0}}
+        })))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(local))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"status": "success", "data": {"balance": 3.5}}
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = ChainwebClient::new(&mock_server.uri(), "testnet04").with_chain_ids(["0"]);
+    let mut wallet = Wallet::new();
+    wallet.add(WalletAccount::watch_account("alice")).unwrap();
+
+    assert!(wallet.refresh(&client).await.unwrap().is_empty());
+
+    let incoming = wallet.refresh(&client).await.unwrap();
+    assert_eq!(incoming.len(), 1);
+    assert_eq!(incoming[0].account, "alice");
+    assert_eq!(incoming[0].amount.to_string(), "2.5");
+}
+
+mod address_book_tests {
+    use kadena::{
+        pact::Meta,
+        wallet::{AddressBook, Contact, WalletError},
+    };
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_address_book_persistence() {
+        let path =
+            std::env::temp_dir().join(format!("kadena-address-book-{}.json", std::process::id()));
+
+        let mut book = AddressBook::load(&path).unwrap();
+        book.insert(
+            "bob",
+            Contact::new("k:def456")
+                .with_chain_id("2")
+                .with_guard_notes("hardware wallet"),
+        );
+        book.save(&path).unwrap();
+
+        let loaded = AddressBook::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, book);
+        assert_eq!(loaded.get("bob").unwrap().chain_id.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_transfer_to_contact() {
+        let mut book = AddressBook::new();
+        book.insert("alice", Contact::new("k:abc123"));
+        book.insert("bob", Contact::new("k:def456"));
+
+        let meta = Meta::new("0", "k:abc123");
+        let transfer = book
+            .transfer("alice", "bob", Decimal::new(25, 1), &meta)
+            .unwrap();
+        assert_eq!(
+            transfer.code(),
+            "(coin.transfer \"k:abc123\" \"k:def456\" 2.5)"
+        );
+
+        assert!(matches!(
+            book.transfer("alice", "carol", Decimal::ONE, &meta),
+            Err(WalletError::UnknownContact(_))
+        ));
+        assert_eq!(book.resolve("k:raw"), "k:raw");
+    }
+
+    #[test]
+    fn test_transfer_checks_contact_chain() {
+        let mut book = AddressBook::new();
+        book.insert("bob", Contact::new("k:def456").with_chain_id("2"));
+
+        let on_chain = Meta::new("2", "k:abc123");
+        assert!(book
+            .transfer("alice", "bob", Decimal::ONE, &on_chain)
+            .is_ok());
+
+        let err = book
+            .transfer("alice", "bob", Decimal::ONE, &Meta::new("0", "k:abc123"))
+            .unwrap_err();
+        assert_eq!(err.error_code(), "wallet.chain_mismatch");
+        assert!(matches!(
+            err,
+            WalletError::ChainMismatch { expected, found, .. } if expected == "2" && found == "0"
+        ));
+    }
+
+    #[test]
+    fn test_import_merges_contacts() {
+        let mut book = AddressBook::new();
+        book.insert("bob", Contact::new("k:old"));
+
+        let mut other = AddressBook::new();
+        other.insert("bob", Contact::new("k:new"));
+        other.insert("carol", Contact::new("k:carol"));
+
+        book.merge(AddressBook::import_json(&other.export_json().unwrap()).unwrap());
+        assert_eq!(book.get("bob").unwrap().account, "k:new");
+        assert_eq!(book.contacts().count(), 2);
+    }
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "wasm")]
+
+// The bindings are plain Rust functions, so their success paths can be
+// exercised natively; errors need a JavaScript host to build `JsError`s.
+
+use kadena::crypto::{base64url_decode, hash as blake2b_hash, PactKeypair};
+use kadena::wasm::*;
+use serde_json::{json, Value};
+
+#[test]
+fn test_keypair_bindings() {
+    let keypair = WasmKeypair::generate();
+    let restored = WasmKeypair::from_secret_key(&keypair.secret_key()).unwrap();
+    assert_eq!(restored.public_key(), keypair.public_key());
+
+    let signature = keypair.sign(b"hello").unwrap();
+    assert!(restored.verify(b"hello", &signature).unwrap());
+    assert_eq!(hash(b"hello"), blake2b_hash(b"hello"));
+
+    let signature = keypair.sign_hash(&hash(b"hello")).unwrap();
+    assert!(restored
+        .verify(&base64url_decode(&hash(b"hello")).unwrap(), &signature)
+        .unwrap());
+}
+
+#[test]
+fn test_prepare_exec_matches_native_hashing() {
+    let keypair = PactKeypair::generate();
+    let request = json!({
+        "code": "(+ 1 2)",
+        "chainId": "0",
+        "sender": format!("k:{}", keypair.public_key()),
+        "networkId": "mainnet01",
+        "signers": [{"secretKey": keypair.secret_key(), "caps": []}],
+    });
+
+    let cmd: Value = serde_json::from_str(&prepare_exec(&request.to_string()).unwrap()).unwrap();
+    let payload = cmd["cmd"].as_str().unwrap();
+    assert_eq!(cmd["hash"], blake2b_hash(payload.as_bytes()));
+
+    let payload: Value = serde_json::from_str(payload).unwrap();
+    assert_eq!(payload["meta"]["gasLimit"], 2500);
+    let hash = base64url_decode(cmd["hash"].as_str().unwrap()).unwrap();
+    assert!(keypair
+        .verify(&hash, cmd["sigs"][0]["sig"].as_str().unwrap())
+        .unwrap());
+}